@@ -0,0 +1,4 @@
+-- Client-supplied key for idempotent session creation
+ALTER TABLE sessions ADD COLUMN external_id TEXT;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_sessions_external_id ON sessions(external_id);
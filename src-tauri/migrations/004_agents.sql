@@ -0,0 +1,6 @@
+-- Registered agents, so the runtime registry survives restarts
+CREATE TABLE IF NOT EXISTS agents (
+    id TEXT PRIMARY KEY NOT NULL,
+    config TEXT NOT NULL,   -- AgentConfig as JSON
+    created_at TEXT NOT NULL
+);
@@ -0,0 +1,9 @@
+-- Reusable session layouts (panes, agent team, initial blackboard entries)
+CREATE TABLE IF NOT EXISTS session_templates (
+    id TEXT PRIMARY KEY NOT NULL,
+    name TEXT NOT NULL,
+    panes TEXT NOT NULL,        -- Vec<PaneSpec> as JSON
+    agents TEXT NOT NULL,       -- Vec<AgentConfig> as JSON
+    blackboard TEXT NOT NULL,   -- Vec<BlackboardSeed> as JSON
+    created_at TEXT NOT NULL
+);
@@ -0,0 +1,7 @@
+-- Connector metrics snapshots, written on shutdown
+CREATE TABLE IF NOT EXISTS connector_metrics (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    connector TEXT NOT NULL,
+    metrics TEXT NOT NULL,      -- ConnectorMetrics as JSON
+    recorded_at TEXT NOT NULL
+);
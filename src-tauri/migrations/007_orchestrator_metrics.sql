@@ -0,0 +1,7 @@
+-- Orchestrator metrics snapshots, written periodically and on shutdown
+CREATE TABLE IF NOT EXISTS orchestrator_metrics (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    run_id TEXT,
+    metrics TEXT NOT NULL,      -- OrchestratorMetrics as JSON
+    recorded_at TEXT NOT NULL
+);
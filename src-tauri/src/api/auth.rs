@@ -1,9 +1,10 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
 pub struct AuthService {
     tokens: Arc<RwLock<HashSet<String>>>,
+    session_grants: Arc<RwLock<HashMap<String, HashSet<String>>>>,
 }
 
 impl AuthService {
@@ -14,6 +15,7 @@ impl AuthService {
 
         Self {
             tokens: Arc::new(RwLock::new(tokens)),
+            session_grants: Arc::new(RwLock::new(HashMap::new())),
         }
     }
 
@@ -31,12 +33,34 @@ impl AuthService {
     pub async fn remove_token(&self, token: &str) -> Result<(), String> {
         let mut tokens = self.tokens.write().await;
         if tokens.remove(token) {
+            self.session_grants.write().await.remove(token);
             Ok(())
         } else {
             Err("Token not found".to_string())
         }
     }
 
+    /// Restrict a token to the sessions it has been granted.
+    /// Tokens with no grants may access any session.
+    pub async fn grant_session(&self, token: &str, session_id: String) -> Result<(), String> {
+        if !self.validate_token(token).await {
+            return Err("Token not found".to_string());
+        }
+        let mut grants = self.session_grants.write().await;
+        grants.entry(token.to_string()).or_default().insert(session_id);
+        Ok(())
+    }
+
+    pub async fn can_access_session(&self, token: &str, session_id: &str) -> bool {
+        if !self.validate_token(token).await {
+            return false;
+        }
+        let grants = self.session_grants.read().await;
+        grants
+            .get(token)
+            .map_or(true, |sessions| sessions.contains(session_id))
+    }
+
     pub async fn generate_token(&self) -> String {
         let token = format!("token-{}", uuid::Uuid::new_v4());
         self.add_token(token.clone()).await.ok();
@@ -72,6 +96,20 @@ mod tests {
         assert!(!auth.validate_token(&token).await);
     }
 
+    #[tokio::test]
+    async fn test_session_grants() {
+        let auth = AuthService::new();
+        let token = auth.generate_token().await;
+
+        // Unscoped tokens reach every session
+        assert!(auth.can_access_session(&token, "session1").await);
+
+        auth.grant_session(&token, "session1".to_string()).await.unwrap();
+        assert!(auth.can_access_session(&token, "session1").await);
+        assert!(!auth.can_access_session(&token, "session2").await);
+        assert!(!auth.can_access_session("invalid", "session1").await);
+    }
+
     #[tokio::test]
     async fn test_generate_token() {
         let auth = AuthService::new();
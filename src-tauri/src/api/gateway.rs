@@ -2,6 +2,8 @@ use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use serde::{Deserialize, Serialize};
+use super::auth::AuthService;
+use super::rate_limit::{RateLimitConfig, RateLimiter};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ApiConfig {
@@ -9,6 +11,32 @@ pub struct ApiConfig {
     pub port: u16,
     pub enable_cors: bool,
     pub require_auth: bool,
+    /// Origins allowed by CORS (exact match); ignored unless `enable_cors`
+    #[serde(default)]
+    pub cors_allowed_origins: Vec<String>,
+    /// HTTP methods allowed by CORS
+    #[serde(default = "default_cors_methods")]
+    pub cors_allowed_methods: Vec<String>,
+    #[serde(default)]
+    pub cors_allow_credentials: bool,
+    /// Largest accepted request body; larger bodies get `413 Payload Too Large`
+    #[serde(default = "default_max_body_bytes")]
+    pub max_body_bytes: usize,
+    /// Requests taking longer than this get `408 Request Timeout`
+    #[serde(default = "default_request_timeout_ms")]
+    pub request_timeout_ms: u64,
+}
+
+fn default_cors_methods() -> Vec<String> {
+    vec!["GET".to_string(), "POST".to_string()]
+}
+
+fn default_max_body_bytes() -> usize {
+    1024 * 1024
+}
+
+fn default_request_timeout_ms() -> u64 {
+    30_000
 }
 
 impl Default for ApiConfig {
@@ -18,6 +46,11 @@ impl Default for ApiConfig {
             port: 8080,
             enable_cors: false,
             require_auth: true,
+            cors_allowed_origins: Vec::new(),
+            cors_allowed_methods: default_cors_methods(),
+            cors_allow_credentials: false,
+            max_body_bytes: default_max_body_bytes(),
+            request_timeout_ms: default_request_timeout_ms(),
         }
     }
 }
@@ -74,6 +107,8 @@ pub struct ApiGateway {
     sessions: Arc<RwLock<HashMap<String, SessionResponse>>>,
     connections: Arc<RwLock<u32>>,
     request_count: Arc<RwLock<u64>>,
+    auth: Arc<AuthService>,
+    rate_limiter: Arc<RateLimiter>,
 }
 
 impl ApiGateway {
@@ -83,9 +118,33 @@ impl ApiGateway {
             sessions: Arc::new(RwLock::new(HashMap::new())),
             connections: Arc::new(RwLock::new(0)),
             request_count: Arc::new(RwLock::new(0)),
+            auth: Arc::new(AuthService::new()),
+            rate_limiter: Arc::new(RateLimiter::new(RateLimitConfig::default())),
         }
     }
 
+    pub fn with_auth(mut self, auth: Arc<AuthService>) -> Self {
+        self.auth = auth;
+        self
+    }
+
+    pub fn with_rate_limiter(mut self, rate_limiter: Arc<RateLimiter>) -> Self {
+        self.rate_limiter = rate_limiter;
+        self
+    }
+
+    pub fn config(&self) -> &ApiConfig {
+        &self.config
+    }
+
+    pub fn auth(&self) -> Arc<AuthService> {
+        self.auth.clone()
+    }
+
+    pub fn rate_limiter(&self) -> Arc<RateLimiter> {
+        self.rate_limiter.clone()
+    }
+
     pub async fn create_session(&self, request: SessionCreateRequest) -> Result<SessionResponse, String> {
         let mut count = self.request_count.write().await;
         *count += 1;
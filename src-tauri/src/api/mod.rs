@@ -1,8 +1,9 @@
 pub mod gateway;
 pub mod auth;
 pub mod rate_limit;
+pub mod server;
 pub mod websocket;
 
 pub use gateway::ApiGateway;
 pub use auth::AuthService;
-pub use rate_limit::RateLimiter;
+pub use rate_limit::{RateLimitConfig, RateLimitError, RateLimiter};
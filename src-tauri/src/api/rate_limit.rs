@@ -1,9 +1,11 @@
+use crate::clock::{system_clock, Clock};
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::{Duration, SystemTime};
 use tokio::sync::RwLock;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RateLimitConfig {
     pub requests_per_second: u32,
     pub burst_size: u32,
@@ -18,6 +20,18 @@ impl Default for RateLimitConfig {
     }
 }
 
+/// Returned when a client has no tokens left
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("Rate limit exceeded, retry after {retry_after:?}")]
+pub struct RateLimitError {
+    /// Time until the bucket refills by one token
+    pub retry_after: Duration,
+    /// Bucket capacity (burst size)
+    pub limit: u32,
+    /// Tokens left in the bucket
+    pub remaining: u32,
+}
+
 struct ClientState {
     tokens: u32,
     last_refill: SystemTime,
@@ -26,6 +40,7 @@ struct ClientState {
 pub struct RateLimiter {
     config: RateLimitConfig,
     clients: Arc<RwLock<HashMap<String, ClientState>>>,
+    clock: Arc<dyn Clock>,
 }
 
 impl RateLimiter {
@@ -33,13 +48,20 @@ impl RateLimiter {
         Self {
             config,
             clients: Arc::new(RwLock::new(HashMap::new())),
+            clock: system_clock(),
         }
     }
 
-    pub async fn check_rate_limit(&self, client_id: &str) -> Result<(), String> {
+    /// Use `clock` to measure refill time instead of the system clock
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    pub async fn check_rate_limit(&self, client_id: &str) -> Result<(), RateLimitError> {
         let mut clients = self.clients.write().await;
 
-        let now = SystemTime::now();
+        let now = self.clock.now();
 
         let state = clients.entry(client_id.to_string()).or_insert(ClientState {
             tokens: self.config.burst_size,
@@ -60,7 +82,15 @@ impl RateLimiter {
 
         // Check if we have tokens
         if state.tokens == 0 {
-            return Err("Rate limit exceeded".to_string());
+            let retry_after = match self.config.requests_per_second {
+                0 => Duration::MAX,
+                rps => Duration::from_secs_f64(1.0 / rps as f64).saturating_sub(elapsed),
+            };
+            return Err(RateLimitError {
+                retry_after,
+                limit: self.config.burst_size,
+                remaining: 0,
+            });
         }
 
         // Consume a token
@@ -78,6 +108,7 @@ impl RateLimiter {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::clock::MockClock;
 
     #[tokio::test]
     async fn test_rate_limit_allows_requests() {
@@ -105,23 +136,51 @@ mod tests {
         assert!(limiter.check_rate_limit("client1").await.is_err());
     }
 
+    #[tokio::test]
+    async fn test_rate_limit_error_reports_retry_after() {
+        let clock = Arc::new(MockClock::new());
+        let limiter = RateLimiter::new(RateLimitConfig {
+            requests_per_second: 10,
+            burst_size: 3,
+        })
+        .with_clock(clock.clone());
+
+        for _ in 0..3 {
+            limiter.check_rate_limit("client1").await.unwrap();
+        }
+
+        let err = limiter.check_rate_limit("client1").await.unwrap_err();
+        assert_eq!(err.limit, 3);
+        assert_eq!(err.remaining, 0);
+
+        // One token refills every 100ms at 10 req/s
+        assert_eq!(err.retry_after, Duration::from_millis(100));
+
+        clock.advance(Duration::from_millis(40));
+        let err = limiter.check_rate_limit("client1").await.unwrap_err();
+        assert_eq!(err.retry_after, Duration::from_millis(60));
+    }
+
     #[tokio::test]
     async fn test_rate_limit_refills() {
+        let clock = Arc::new(MockClock::new());
         let limiter = RateLimiter::new(RateLimitConfig {
             requests_per_second: 10,
             burst_size: 5,
-        });
+        })
+        .with_clock(clock.clone());
 
         // Exhaust burst
         for _ in 0..5 {
             limiter.check_rate_limit("client1").await.ok();
         }
+        assert!(limiter.check_rate_limit("client1").await.is_err());
 
-        // Wait for refill
-        tokio::time::sleep(Duration::from_millis(200)).await;
-
-        // Should allow more requests now
+        // 200ms refills exactly two tokens at 10 req/s
+        clock.advance(Duration::from_millis(200));
         assert!(limiter.check_rate_limit("client1").await.is_ok());
+        assert!(limiter.check_rate_limit("client1").await.is_ok());
+        assert!(limiter.check_rate_limit("client1").await.is_err());
     }
 
     #[tokio::test]
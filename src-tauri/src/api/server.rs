@@ -0,0 +1,301 @@
+use super::auth::AuthService;
+use super::gateway::{ApiGateway, CommandRequest, SessionCreateRequest};
+use super::rate_limit::RateLimiter;
+use axum::extract::{ConnectInfo, DefaultBodyLimit, Path, Request, State};
+use super::gateway::ApiConfig;
+use axum::http::{header, HeaderValue, Method, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+use tower_http::cors::{AllowOrigin, CorsLayer};
+use tower_http::timeout::TimeoutLayer;
+use tracing::{info, warn};
+
+/// Shared state for HTTP handlers
+#[derive(Clone)]
+struct ServerState {
+    gateway: Arc<ApiGateway>,
+    auth: Arc<AuthService>,
+    rate_limiter: Arc<RateLimiter>,
+}
+
+/// JSON error body with a status code
+struct ApiError(StatusCode, String);
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.0, Json(serde_json::json!({ "error": self.1 }))).into_response()
+    }
+}
+
+/// A running API server
+pub struct ServerHandle {
+    /// Address the server is bound to
+    pub addr: SocketAddr,
+    shutdown: oneshot::Sender<()>,
+    task: JoinHandle<std::io::Result<()>>,
+}
+
+impl ServerHandle {
+    /// Stop accepting connections, wait for in-flight requests to finish,
+    /// and release the listening socket
+    pub async fn shutdown(self) -> std::io::Result<()> {
+        let _ = self.shutdown.send(());
+        self.task
+            .await
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?
+    }
+}
+
+impl ApiGateway {
+    /// Bind `host:port` and serve the gateway's routes in a background task
+    pub async fn serve(self) -> std::io::Result<ServerHandle> {
+        let config = self.config().clone();
+        let listener = TcpListener::bind((config.host.as_str(), config.port)).await?;
+        spawn_server(listener, router(Arc::new(self)))
+    }
+}
+
+fn spawn_server(listener: TcpListener, app: Router) -> std::io::Result<ServerHandle> {
+    let addr = listener.local_addr()?;
+    let (shutdown, signal) = oneshot::channel::<()>();
+
+    info!("API gateway listening on {}", addr);
+    let task = tokio::spawn(async move {
+        axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
+            .with_graceful_shutdown(async {
+                let _ = signal.await;
+                info!("API gateway shutting down");
+            })
+            .await
+    });
+
+    Ok(ServerHandle { addr, shutdown, task })
+}
+
+/// Build the HTTP router for a gateway
+fn router(gateway: Arc<ApiGateway>) -> Router {
+    let config = gateway.config().clone();
+    let state = ServerState {
+        auth: gateway.auth(),
+        rate_limiter: gateway.rate_limiter(),
+        gateway,
+    };
+
+    let mut app = Router::new()
+        .route("/sessions", post(create_session).get(list_sessions))
+        .route("/sessions/:id", get(get_session))
+        .route("/commands", post(execute_command))
+        .route("/usage", get(get_usage))
+        .route_layer(middleware::from_fn_with_state(state.clone(), rate_limit));
+
+    if config.require_auth {
+        app = app.route_layer(middleware::from_fn_with_state(state.clone(), require_auth));
+    }
+
+    app = apply_limits(app, &config);
+
+    if config.enable_cors {
+        app = app.layer(cors_layer(&config));
+    }
+
+    app.with_state(state)
+}
+
+/// Cap request body size and total handling time
+fn apply_limits<S>(router: Router<S>, config: &ApiConfig) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    router
+        .layer(TimeoutLayer::new(Duration::from_millis(config.request_timeout_ms)))
+        .layer(DefaultBodyLimit::max(config.max_body_bytes))
+}
+
+/// CORS policy from config; unparseable origins or methods are skipped with a warning
+fn cors_layer(config: &ApiConfig) -> CorsLayer {
+    let origins: Vec<HeaderValue> = config
+        .cors_allowed_origins
+        .iter()
+        .filter_map(|origin| {
+            HeaderValue::from_str(origin)
+                .map_err(|_| warn!("Ignoring invalid CORS origin: {}", origin))
+                .ok()
+        })
+        .collect();
+    let methods: Vec<Method> = config
+        .cors_allowed_methods
+        .iter()
+        .filter_map(|method| {
+            Method::from_bytes(method.to_uppercase().as_bytes())
+                .map_err(|_| warn!("Ignoring invalid CORS method: {}", method))
+                .ok()
+        })
+        .collect();
+
+    CorsLayer::new()
+        .allow_origin(AllowOrigin::list(origins))
+        .allow_methods(methods)
+        .allow_headers([header::AUTHORIZATION, header::CONTENT_TYPE])
+        .allow_credentials(config.cors_allow_credentials)
+}
+
+fn bearer_token(request: &Request) -> Option<&str> {
+    request
+        .headers()
+        .get(header::AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")
+}
+
+async fn require_auth(
+    State(state): State<ServerState>,
+    request: Request,
+    next: Next,
+) -> Result<Response, ApiError> {
+    let token = bearer_token(&request).map(str::to_string);
+    let authorized = match token {
+        Some(token) => state.auth.validate_token(&token).await,
+        None => false,
+    };
+
+    if authorized {
+        Ok(next.run(request).await)
+    } else {
+        Err(ApiError(StatusCode::UNAUTHORIZED, "Missing or invalid token".to_string()))
+    }
+}
+
+async fn rate_limit(
+    State(state): State<ServerState>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let client_id = bearer_token(&request)
+        .map(str::to_string)
+        .unwrap_or_else(|| peer.ip().to_string());
+
+    match state.rate_limiter.check_rate_limit(&client_id).await {
+        Ok(()) => next.run(request).await,
+        Err(e) => {
+            warn!(client_id = %client_id, "Rate limit exceeded");
+            let retry_after = e.retry_after.as_secs_f64().ceil() as u64;
+            let mut response = ApiError(StatusCode::TOO_MANY_REQUESTS, e.to_string()).into_response();
+            if let Ok(value) = HeaderValue::from_str(&retry_after.to_string()) {
+                response.headers_mut().insert(header::RETRY_AFTER, value);
+            }
+            response
+        }
+    }
+}
+
+async fn create_session(
+    State(state): State<ServerState>,
+    Json(request): Json<SessionCreateRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    state
+        .gateway
+        .create_session(request)
+        .await
+        .map(|session| (StatusCode::CREATED, Json(session)))
+        .map_err(|e| ApiError(StatusCode::INTERNAL_SERVER_ERROR, e))
+}
+
+async fn list_sessions(State(state): State<ServerState>) -> Result<impl IntoResponse, ApiError> {
+    state
+        .gateway
+        .list_sessions()
+        .await
+        .map(Json)
+        .map_err(|e| ApiError(StatusCode::INTERNAL_SERVER_ERROR, e))
+}
+
+async fn get_session(
+    State(state): State<ServerState>,
+    Path(id): Path<String>,
+) -> Result<impl IntoResponse, ApiError> {
+    state
+        .gateway
+        .get_session(&id)
+        .await
+        .map(Json)
+        .map_err(|e| ApiError(StatusCode::NOT_FOUND, e))
+}
+
+async fn execute_command(
+    State(state): State<ServerState>,
+    Json(request): Json<CommandRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    state
+        .gateway
+        .execute_command(request)
+        .await
+        .map(Json)
+        .map_err(|e| ApiError(StatusCode::INTERNAL_SERVER_ERROR, e))
+}
+
+async fn get_usage(State(state): State<ServerState>) -> Result<impl IntoResponse, ApiError> {
+    state
+        .gateway
+        .get_usage()
+        .await
+        .map(Json)
+        .map_err(|e| ApiError(StatusCode::INTERNAL_SERVER_ERROR, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn slow_router(delay: Duration) -> Router {
+        Router::new().route(
+            "/slow",
+            get(move || async move {
+                tokio::time::sleep(delay).await;
+                "done"
+            }),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_slow_handler_times_out() {
+        let config = ApiConfig {
+            request_timeout_ms: 50,
+            ..ApiConfig::default()
+        };
+        let app = apply_limits(slow_router(Duration::from_millis(500)), &config);
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let server = spawn_server(listener, app).unwrap();
+
+        let response = reqwest::get(format!("http://{}/slow", server.addr)).await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::REQUEST_TIMEOUT);
+
+        server.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_graceful_shutdown_drains_in_flight_requests() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let server = spawn_server(listener, slow_router(Duration::from_millis(200))).unwrap();
+        let addr = server.addr;
+
+        let in_flight = tokio::spawn(async move {
+            reqwest::get(format!("http://{}/slow", addr)).await?.text().await
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        server.shutdown().await.unwrap();
+
+        assert_eq!(in_flight.await.unwrap().unwrap(), "done");
+        assert!(TcpListener::bind(addr).await.is_ok());
+    }
+}
@@ -1,32 +1,175 @@
+use super::auth::AuthService;
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use std::time::{Duration, SystemTime};
+use tokio::sync::{mpsc, RwLock};
+use tokio::task::JoinHandle;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Default time a connection may go without a pong before it is swept
+pub const DEFAULT_IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct WebSocketMessage {
     pub message_type: String,
     pub data: serde_json::Value,
 }
 
+/// Frame queued for the socket task that owns a connection
+#[derive(Debug, Clone, PartialEq)]
+pub enum Message {
+    Text(WebSocketMessage),
+    Ping,
+    Close,
+}
+
 #[derive(Debug, Clone)]
 pub struct WebSocketConnection {
     pub id: String,
     pub session_id: Option<String>,
     pub connected_at: std::time::SystemTime,
+    /// Last time the client proved it was alive (connect or pong)
+    pub last_seen: std::time::SystemTime,
+    /// Event types this connection wants; empty means every type
+    pub subscribed_types: HashSet<String>,
+    /// Frames for the socket task; `None` until a socket is attached
+    pub outbox: Option<mpsc::UnboundedSender<Message>>,
+}
+
+impl WebSocketConnection {
+    pub fn new(id: String, session_id: Option<String>) -> Self {
+        let now = SystemTime::now();
+        Self {
+            id,
+            session_id,
+            connected_at: now,
+            last_seen: now,
+            subscribed_types: HashSet::new(),
+            outbox: None,
+        }
+    }
+
+    /// Queue frames for this connection on `outbox`
+    pub fn with_outbox(mut self, outbox: mpsc::UnboundedSender<Message>) -> Self {
+        self.outbox = Some(outbox);
+        self
+    }
+
+    /// Queue `message` for the socket, returning false if none is attached or it hung up
+    pub fn send(&self, message: Message) -> bool {
+        self.outbox
+            .as_ref()
+            .is_some_and(|outbox| outbox.send(message).is_ok())
+    }
+
+    pub fn with_event_types<I, S>(mut self, types: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.subscribed_types = types.into_iter().map(Into::into).collect();
+        self
+    }
+
+    pub fn is_subscribed_to(&self, message_type: &str) -> bool {
+        self.subscribed_types.is_empty() || self.subscribed_types.contains(message_type)
+    }
 }
 
 pub struct WebSocketManager {
     connections: Arc<RwLock<Vec<WebSocketConnection>>>,
+    idle_timeout: Duration,
 }
 
 impl WebSocketManager {
     pub fn new() -> Self {
         Self {
             connections: Arc::new(RwLock::new(Vec::new())),
+            idle_timeout: DEFAULT_IDLE_TIMEOUT,
         }
     }
 
-    pub async fn add_connection(&self, connection: WebSocketConnection) {
+    pub fn with_idle_timeout(mut self, idle_timeout: Duration) -> Self {
+        self.idle_timeout = idle_timeout;
+        self
+    }
+
+    /// Mark a connection as alive after a pong (or any client frame)
+    pub async fn record_pong(&self, connection_id: &str) -> bool {
+        let mut connections = self.connections.write().await;
+        match connections.iter_mut().find(|c| c.id == connection_id) {
+            Some(connection) => {
+                connection.last_seen = SystemTime::now();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Drop connections not seen within the idle timeout, returning how many were removed
+    pub async fn sweep_idle(&self) -> usize {
+        let now = SystemTime::now();
+        let mut connections = self.connections.write().await;
+        let before = connections.len();
+        connections.retain(|c| {
+            now.duration_since(c.last_seen)
+                .map(|idle| idle <= self.idle_timeout)
+                .unwrap_or(true)
+        });
+
+        let removed = before - connections.len();
+        if removed > 0 {
+            tracing::info!(removed, "Swept idle WebSocket connections");
+        }
+        removed
+    }
+
+    /// Every `interval`, sweep connections that stopped answering and ping the rest
+    pub fn spawn_ping_loop(self: Arc<Self>, interval: Duration) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+                self.sweep_idle().await;
+                let pinged = self.ping_all().await;
+                tracing::debug!(pinged, "Pinged WebSocket connections");
+            }
+        })
+    }
+
+    /// Queue a ping frame on every connection, returning how many took it
+    ///
+    /// `record_pong` is called when the client answers.
+    pub async fn ping_all(&self) -> usize {
+        let connections = self.connections.read().await;
+        connections
+            .iter()
+            .filter(|connection| connection.send(Message::Ping))
+            .count()
+    }
+
+    /// Handshake: validate the client's token and its claim on the
+    /// connection's session before registering it
+    pub async fn connect(
+        &self,
+        auth: &AuthService,
+        token: &str,
+        connection: WebSocketConnection,
+    ) -> Result<(), String> {
+        if !auth.validate_token(token).await {
+            return Err("Invalid auth token".to_string());
+        }
+
+        if let Some(session_id) = connection.session_id.as_deref() {
+            if !auth.can_access_session(token, session_id).await {
+                return Err(format!("Token may not access session: {}", session_id));
+            }
+        }
+
+        self.add_connection(connection).await;
+        Ok(())
+    }
+
+    pub(crate) async fn add_connection(&self, connection: WebSocketConnection) {
         let mut connections = self.connections.write().await;
         connections.push(connection);
     }
@@ -36,17 +179,35 @@ impl WebSocketManager {
         connections.retain(|c| c.id != connection_id);
     }
 
+    /// Send a close frame to every connection and drop it, returning how many were open
+    pub async fn close_all(&self) -> usize {
+        let mut connections = self.connections.write().await;
+        for connection in connections.iter() {
+            connection.send(Message::Close);
+        }
+        let closed = connections.len();
+        connections.clear();
+        closed
+    }
+
     pub async fn get_connection_count(&self) -> usize {
         let connections = self.connections.read().await;
         connections.len()
     }
 
-    pub async fn broadcast_to_session(&self, session_id: &str, message: WebSocketMessage) -> Result<usize, String> {
+    /// Connection IDs in a session subscribed to `message_type`
+    pub async fn recipients(&self, session_id: &str, message_type: &str) -> Vec<String> {
         let connections = self.connections.read().await;
-        let matching: Vec<_> = connections
+        connections
             .iter()
             .filter(|c| c.session_id.as_deref() == Some(session_id))
-            .collect();
+            .filter(|c| c.is_subscribed_to(message_type))
+            .map(|c| c.id.clone())
+            .collect()
+    }
+
+    pub async fn broadcast_to_session(&self, session_id: &str, message: WebSocketMessage) -> Result<usize, String> {
+        let matching = self.recipients(session_id, &message.message_type).await;
 
         let count = matching.len();
 
@@ -64,11 +225,7 @@ mod tests {
     async fn test_connection_management() {
         let manager = WebSocketManager::new();
 
-        let conn1 = WebSocketConnection {
-            id: "conn1".to_string(),
-            session_id: Some("session1".to_string()),
-            connected_at: std::time::SystemTime::now(),
-        };
+        let conn1 = WebSocketConnection::new("conn1".to_string(), Some("session1".to_string()));
 
         manager.add_connection(conn1).await;
 
@@ -79,20 +236,107 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn test_broadcast_to_session() {
+    async fn test_close_all_sends_close_frame() {
         let manager = WebSocketManager::new();
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        manager
+            .add_connection(WebSocketConnection::new("conn1".to_string(), None).with_outbox(tx))
+            .await;
+        manager
+            .add_connection(WebSocketConnection::new("detached".to_string(), None))
+            .await;
+
+        assert_eq!(manager.close_all().await, 2);
+        assert_eq!(manager.get_connection_count().await, 0);
+        assert_eq!(rx.recv().await, Some(Message::Close));
+        assert_eq!(rx.recv().await, None);
+    }
 
-        let conn1 = WebSocketConnection {
-            id: "conn1".to_string(),
-            session_id: Some("session1".to_string()),
-            connected_at: std::time::SystemTime::now(),
+    #[tokio::test]
+    async fn test_ping_loop_pings_live_connections() {
+        let manager = Arc::new(WebSocketManager::new());
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        manager
+            .add_connection(WebSocketConnection::new("conn1".to_string(), None).with_outbox(tx))
+            .await;
+
+        let ping_loop = manager.clone().spawn_ping_loop(Duration::from_millis(10));
+        assert_eq!(rx.recv().await, Some(Message::Ping));
+        ping_loop.abort();
+    }
+
+    #[tokio::test]
+    async fn test_connect_requires_auth() {
+        let manager = WebSocketManager::new();
+        let auth = AuthService::new();
+        let token = auth.generate_token().await;
+        auth.grant_session(&token, "session1".to_string()).await.unwrap();
+
+        let conn = |id: &str, session: &str| {
+            WebSocketConnection::new(id.to_string(), Some(session.to_string()))
         };
 
-        let conn2 = WebSocketConnection {
-            id: "conn2".to_string(),
-            session_id: Some("session1".to_string()),
-            connected_at: std::time::SystemTime::now(),
+        assert!(manager.connect(&auth, "bogus", conn("conn1", "session1")).await.is_err());
+        assert!(manager.connect(&auth, &token, conn("conn2", "session2")).await.is_err());
+        assert_eq!(manager.get_connection_count().await, 0);
+
+        manager.connect(&auth, &token, conn("conn3", "session1")).await.unwrap();
+        assert_eq!(manager.get_connection_count().await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_sweep_idle_connections() {
+        let manager = WebSocketManager::new().with_idle_timeout(Duration::from_secs(30));
+
+        // Simulate a client that went quiet past the deadline
+        let mut stale = WebSocketConnection::new("stale".to_string(), None);
+        stale.last_seen = SystemTime::now() - Duration::from_secs(31);
+        manager.add_connection(stale).await;
+        manager
+            .add_connection(WebSocketConnection::new("live".to_string(), None))
+            .await;
+
+        assert_eq!(manager.sweep_idle().await, 1);
+        assert_eq!(manager.get_connection_count().await, 1);
+        assert!(manager.record_pong("live").await);
+        assert!(!manager.record_pong("stale").await);
+    }
+
+    #[tokio::test]
+    async fn test_event_type_filtering() {
+        let manager = WebSocketManager::new();
+        let session = Some("session1".to_string());
+
+        manager
+            .add_connection(
+                WebSocketConnection::new("errors".to_string(), session.clone())
+                    .with_event_types(["Error"]),
+            )
+            .await;
+        manager
+            .add_connection(
+                WebSocketConnection::new("tasks".to_string(), session.clone())
+                    .with_event_types(["TaskCompleted"]),
+            )
+            .await;
+
+        assert_eq!(manager.recipients("session1", "Error").await, vec!["errors"]);
+        assert_eq!(manager.recipients("session1", "TaskCompleted").await, vec!["tasks"]);
+
+        let progress = WebSocketMessage {
+            message_type: "Progress".to_string(),
+            data: serde_json::Value::Null,
         };
+        assert_eq!(manager.broadcast_to_session("session1", progress).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_broadcast_to_session() {
+        let manager = WebSocketManager::new();
+
+        let conn1 = WebSocketConnection::new("conn1".to_string(), Some("session1".to_string()));
+
+        let conn2 = WebSocketConnection::new("conn2".to_string(), Some("session1".to_string()));
 
         manager.add_connection(conn1).await;
         manager.add_connection(conn2).await;
@@ -0,0 +1,74 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+/// Source of the current time for TTLs, rate limits and other time-based logic
+pub trait Clock: Send + Sync {
+  fn now(&self) -> SystemTime;
+}
+
+/// Wall-clock time
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+  fn now(&self) -> SystemTime {
+    SystemTime::now()
+  }
+}
+
+/// The clock used when none is given
+pub fn system_clock() -> Arc<dyn Clock> {
+  Arc::new(SystemClock)
+}
+
+/// Clock that only moves when told to, for deterministic tests
+///
+/// Starts at the current wall-clock time so entries stamped with real
+/// timestamps (e.g. `BlackboardEntry::new`) line up with it.
+#[derive(Debug)]
+pub struct MockClock {
+  now: Mutex<SystemTime>,
+}
+
+impl MockClock {
+  pub fn new() -> Self {
+    Self::starting_at(SystemTime::now())
+  }
+
+  pub fn starting_at(now: SystemTime) -> Self {
+    Self { now: Mutex::new(now) }
+  }
+
+  /// Move the clock forward by `by`
+  pub fn advance(&self, by: Duration) {
+    *self.now.lock().unwrap() += by;
+  }
+}
+
+impl Default for MockClock {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl Clock for MockClock {
+  fn now(&self) -> SystemTime {
+    *self.now.lock().unwrap()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_mock_clock_only_moves_when_advanced() {
+    let start = SystemTime::UNIX_EPOCH;
+    let clock = MockClock::starting_at(start);
+    assert_eq!(clock.now(), start);
+    assert_eq!(clock.now(), start);
+
+    clock.advance(Duration::from_secs(5));
+    assert_eq!(clock.now(), start + Duration::from_secs(5));
+  }
+}
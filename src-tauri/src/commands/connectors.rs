@@ -1,17 +1,25 @@
 use crate::connectors::claude_code::ClaudeCodeConnector;
 use crate::connectors::codex_cli::{CodexCliConnector, GptModel};
 use crate::connectors::ollama::{OllamaConfig, OllamaConnector};
-use crate::connectors::types::{ConnectorConfig, ConnectorHealth, ConnectorMetrics};
+use crate::connectors::Connector;
+use crate::connectors::stream::{forward_stream, CONNECTOR_MESSAGE_EVENT};
+use crate::connectors::types::{
+    ConnectorConfig, ConnectorHealth, ConnectorMessage, ConnectorMetrics, ModelInfo,
+};
 use serde::{Deserialize, Serialize};
-use tauri::State;
+use tauri::{Manager, State};
+use std::collections::HashMap;
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use std::time::Instant;
+use tokio::sync::{mpsc, Mutex};
 
 /// Shared connector state
+#[derive(Clone)]
 pub struct ConnectorState {
     pub claude_code: Arc<Mutex<Option<ClaudeCodeConnector>>>,
     pub codex_cli: Arc<Mutex<Option<CodexCliConnector>>>,
     pub ollama: Arc<Mutex<Option<OllamaConnector>>>,
+    pub streams: Arc<Mutex<HashMap<String, tauri::async_runtime::JoinHandle<()>>>>,
 }
 
 impl ConnectorState {
@@ -20,8 +28,151 @@ impl ConnectorState {
             claude_code: Arc::new(Mutex::new(None)),
             codex_cli: Arc::new(Mutex::new(None)),
             ollama: Arc::new(Mutex::new(None)),
+            streams: Arc::new(Mutex::new(HashMap::new())),
         }
     }
+
+    /// Create a CLI connector from `config`, replacing any existing instance
+    ///
+    /// The new connector is built before the lock is taken, so a replaced
+    /// instance never leaves the slot empty. The old one is dropped at the end
+    /// of the swap, while the lock is still held. Returns whether an instance
+    /// was replaced.
+    pub async fn install(&self, connector_type: &str, config: ConnectorConfig) -> Result<bool, String> {
+        let previous = match connector_type {
+            "claude_code" => {
+                let connector = ClaudeCodeConnector::new(config);
+                self.claude_code.lock().await.replace(connector).is_some()
+            }
+            "codex_cli" => {
+                let connector = CodexCliConnector::new(config);
+                self.codex_cli.lock().await.replace(connector).is_some()
+            }
+            _ => return Err(format!("Unknown connector type: {}", connector_type)),
+        };
+        Ok(previous)
+    }
+
+    /// Create the Ollama connector from `config`, replacing any existing instance
+    pub async fn install_ollama(&self, config: OllamaConfig) -> bool {
+        let connector = OllamaConnector::new(config);
+        self.ollama.lock().await.replace(connector).is_some()
+    }
+
+    /// Every initialized connector, sharing metrics with the installed instance
+    pub async fn installed(&self) -> Vec<Arc<dyn Connector>> {
+        let mut connectors: Vec<Arc<dyn Connector>> = Vec::new();
+        if let Some(connector) = self.claude_code.lock().await.clone() {
+            connectors.push(Arc::new(connector));
+        }
+        if let Some(connector) = self.codex_cli.lock().await.clone() {
+            connectors.push(Arc::new(connector));
+        }
+        if let Some(connector) = self.ollama.lock().await.clone() {
+            connectors.push(Arc::new(connector));
+        }
+        connectors
+    }
+
+    /// Send `prompt` to an initialized connector and return its output stream
+    async fn open_stream(
+        &self,
+        connector_type: &str,
+        prompt: &str,
+    ) -> Result<mpsc::Receiver<ConnectorMessage>, String> {
+        match connector_type {
+            "claude_code" => {
+                let guard = self.claude_code.lock().await;
+                let connector = guard.as_ref().ok_or("Connector not initialized")?;
+                connector.execute(prompt).await
+                    .map_err(|e| format!("Failed to execute: {}", e))
+            }
+            "codex_cli" => {
+                let guard = self.codex_cli.lock().await;
+                let connector = guard.as_ref().ok_or("Connector not initialized")?;
+                connector.execute(prompt).await
+                    .map_err(|e| format!("Failed to execute: {}", e))
+            }
+            "ollama" => {
+                let guard = self.ollama.lock().await;
+                let connector = guard.as_ref().ok_or("Connector not initialized")?;
+                connector.chat(prompt).await
+                    .map_err(|e| format!("Failed to execute: {}", e))
+            }
+            _ => Err(format!("Unknown connector type: {}", connector_type)),
+        }
+    }
+
+    /// Run `prompt` through a connector and collect the whole stream
+    ///
+    /// Failures, including a connector that isn't initialized, are reported
+    /// in the result rather than as an error.
+    pub async fn test_connector(&self, connector_type: &str, prompt: &str) -> ConnectorTestResult {
+        let start = Instant::now();
+        let mut content = String::new();
+        let mut tokens = 0;
+        let mut error = None;
+
+        match self.open_stream(connector_type, prompt).await {
+            Ok(mut rx) => {
+                while let Some(message) = rx.recv().await {
+                    match message {
+                        ConnectorMessage::Content { content: chunk } => content.push_str(&chunk),
+                        ConnectorMessage::Usage { input_tokens, output_tokens } => {
+                            tokens += input_tokens + output_tokens;
+                        }
+                        ConnectorMessage::Error { message } => {
+                            error.get_or_insert(message);
+                        }
+                        ConnectorMessage::ToolCall { .. }
+                        | ConnectorMessage::SystemNotice { .. }
+                        | ConnectorMessage::Done => {}
+                    }
+                }
+            }
+            Err(e) => error = Some(e),
+        }
+
+        ConnectorTestResult {
+            success: error.is_none(),
+            content,
+            tokens,
+            elapsed_ms: start.elapsed().as_millis() as u64,
+            error,
+        }
+    }
+
+    /// Models a connector can run: the fixed GPT list for Codex, pulled models for Ollama
+    pub async fn available_models(&self, connector_type: &str) -> Result<Vec<ModelInfo>, String> {
+        match connector_type {
+            "codex_cli" => Ok(GptModel::ALL.iter().map(GptModel::info).collect()),
+            "ollama" => {
+                let guard = self.ollama.lock().await;
+                let connector = guard
+                    .as_ref()
+                    .ok_or_else(|| "Ollama connector not initialized".to_string())?;
+                connector
+                    .available_models()
+                    .await
+                    .map_err(|e| format!("Failed to list models: {}", e))
+            }
+            _ => Err(format!("No model list for connector: {}", connector_type)),
+        }
+    }
+}
+
+/// Outcome of a `test_connector` round trip
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConnectorTestResult {
+    /// The stream opened and finished without an error message
+    pub success: bool,
+    /// Every content chunk, concatenated
+    pub content: String,
+    /// Input plus output tokens from `Usage` messages
+    pub tokens: u64,
+    pub elapsed_ms: u64,
+    /// First error, from starting the connector or from the stream
+    pub error: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -36,24 +187,21 @@ pub struct InitOllamaRequest {
 }
 
 /// Initialize a connector
+///
+/// Calling this again replaces the existing instance with one built from the
+/// new config.
 #[tauri::command]
 pub async fn init_connector(
     request: InitConnectorRequest,
     state: State<'_, ConnectorState>,
 ) -> Result<String, String> {
-    match request.connector_type.as_str() {
-        "claude_code" => {
-            let connector = ClaudeCodeConnector::new(request.config);
-            *state.claude_code.lock().await = Some(connector);
-            Ok("Claude Code connector initialized".to_string())
-        }
-        "codex_cli" => {
-            let connector = CodexCliConnector::new(request.config);
-            *state.codex_cli.lock().await = Some(connector);
-            Ok("Codex CLI connector initialized".to_string())
-        }
-        _ => Err(format!("Unknown connector type: {}", request.connector_type)),
-    }
+    let label = match request.connector_type.as_str() {
+        "claude_code" => "Claude Code",
+        "codex_cli" => "Codex CLI",
+        _ => return Err(format!("Unknown connector type: {}", request.connector_type)),
+    };
+    state.install(&request.connector_type, request.config).await?;
+    Ok(format!("{} connector initialized", label))
 }
 
 /// Initialize Ollama connector (uses different config)
@@ -62,11 +210,67 @@ pub async fn init_ollama(
     request: InitOllamaRequest,
     state: State<'_, ConnectorState>,
 ) -> Result<String, String> {
-    let connector = OllamaConnector::new(request.config);
-    *state.ollama.lock().await = Some(connector);
+    state.install_ollama(request.config).await;
     Ok("Ollama connector initialized".to_string())
 }
 
+/// Replace a connector with a fresh instance built from `config`
+///
+/// `config` is an `OllamaConfig` for `ollama` and a `ConnectorConfig`
+/// otherwise. Use this to recover a connector stuck in a bad state; its
+/// metrics and health start over. Works whether or not it was initialized.
+#[tauri::command]
+pub async fn reinit_connector(
+    connector_type: String,
+    config: serde_json::Value,
+    state: State<'_, ConnectorState>,
+) -> Result<bool, String> {
+    if connector_type == "ollama" {
+        let config: OllamaConfig = serde_json::from_value(config)
+            .map_err(|e| format!("Invalid Ollama config: {}", e))?;
+        return Ok(state.install_ollama(config).await);
+    }
+
+    let config: ConnectorConfig = serde_json::from_value(config)
+        .map_err(|e| format!("Invalid connector config: {}", e))?;
+    state.install(&connector_type, config).await
+}
+
+/// Warm up an initialized connector before its first real prompt
+///
+/// CLI connectors run a version probe and Ollama runs a health check; either
+/// way the result is reflected in `get_connector_health`.
+#[tauri::command]
+pub async fn warmup_connector(
+    connector_type: String,
+    state: State<'_, ConnectorState>,
+) -> Result<ConnectorHealth, String> {
+    match connector_type.as_str() {
+        "claude_code" => {
+            let guard = state.claude_code.lock().await;
+            let connector = guard.as_ref().ok_or("Connector not initialized")?;
+            connector.warmup().await
+                .map_err(|e| format!("Warmup failed: {}", e))?;
+            Ok(connector.health().await)
+        }
+        "codex_cli" => {
+            let guard = state.codex_cli.lock().await;
+            let connector = guard.as_ref().ok_or("Connector not initialized")?;
+            connector.warmup().await
+                .map_err(|e| format!("Warmup failed: {}", e))?;
+            Ok(connector.health().await)
+        }
+        "ollama" => {
+            let guard = state.ollama.lock().await;
+            let connector = guard.as_ref().ok_or("Connector not initialized")?;
+            connector.warmup().await
+                .map_err(|e| format!("Warmup failed: {}", e))?;
+            Ok(connector.health().await)
+        }
+        _ => Err(format!("Unknown connector type: {}", connector_type)),
+    }
+}
+
 /// Get connector health
 #[tauri::command]
 pub async fn get_connector_health(
@@ -137,6 +341,90 @@ pub async fn get_connector_metrics(
     }
 }
 
+/// Zero one connector's metrics, leaving the others untouched
+#[tauri::command]
+pub async fn reset_connector_metrics(
+    connector_type: String,
+    state: State<'_, ConnectorState>,
+) -> Result<(), String> {
+    match connector_type.as_str() {
+        "claude_code" => {
+            let guard = state.claude_code.lock().await;
+            let connector = guard.as_ref().ok_or("Connector not initialized")?;
+            connector.reset_metrics().await;
+        }
+        "codex_cli" => {
+            let guard = state.codex_cli.lock().await;
+            let connector = guard.as_ref().ok_or("Connector not initialized")?;
+            connector.reset_metrics().await;
+        }
+        "ollama" => {
+            let guard = state.ollama.lock().await;
+            let connector = guard.as_ref().ok_or("Connector not initialized")?;
+            connector.reset_metrics().await;
+        }
+        _ => return Err(format!("Unknown connector type: {}", connector_type)),
+    }
+    Ok(())
+}
+
+/// Run a prompt on a connector and emit its output as `connector://message` events
+///
+/// Returns a stream ID that can be passed to `cancel_connector_stream`.
+#[tauri::command]
+pub async fn execute_connector(
+    connector_type: String,
+    prompt: String,
+    session_id: Option<String>,
+    app: tauri::AppHandle,
+    state: State<'_, ConnectorState>,
+) -> Result<String, String> {
+    let rx = state.open_stream(&connector_type, &prompt).await?;
+
+    let stream_id = uuid::Uuid::new_v4().to_string();
+    let streams = state.streams.clone();
+    let task_stream_id = stream_id.clone();
+
+    // Hold the map lock until the handle is stored so a short stream can't
+    // finish and deregister itself first
+    let mut active = state.streams.lock().await;
+    let handle = tauri::async_runtime::spawn(async move {
+        forward_stream(&task_stream_id, session_id.as_deref(), rx, |event| {
+            app.emit_all(CONNECTOR_MESSAGE_EVENT, event).is_ok()
+        })
+        .await;
+        streams.lock().await.remove(&task_stream_id);
+    });
+    active.insert(stream_id.clone(), handle);
+
+    Ok(stream_id)
+}
+
+/// Send a prompt and wait for the full response, to check a connector streams end to end
+#[tauri::command]
+pub async fn test_connector(
+    connector_type: String,
+    prompt: String,
+    state: State<'_, ConnectorState>,
+) -> Result<ConnectorTestResult, String> {
+    Ok(state.test_connector(&connector_type, &prompt).await)
+}
+
+/// Stop forwarding a connector stream started by `execute_connector`
+#[tauri::command]
+pub async fn cancel_connector_stream(
+    stream_id: String,
+    state: State<'_, ConnectorState>,
+) -> Result<bool, String> {
+    match state.streams.lock().await.remove(&stream_id) {
+        Some(handle) => {
+            handle.abort();
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
 /// Check Ollama health (runs actual health check)
 #[tauri::command]
 pub async fn check_ollama_health(
@@ -165,6 +453,15 @@ pub async fn list_ollama_models(
     }
 }
 
+/// List the models a connector offers, for the model switcher
+#[tauri::command]
+pub async fn get_available_models(
+    connector_type: String,
+    state: State<'_, ConnectorState>,
+) -> Result<Vec<ModelInfo>, String> {
+    state.available_models(&connector_type).await
+}
+
 /// Switch model for Codex CLI connector
 #[tauri::command]
 pub async fn switch_codex_model(
@@ -186,3 +483,90 @@ pub async fn switch_codex_model(
         Err("Codex CLI connector not initialized".to_string())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_reinit_replaces_connector_config() {
+        let state = ConnectorState::new();
+
+        let first = ConnectorConfig {
+            cli_path: "claude-old".to_string(),
+            ..Default::default()
+        };
+        assert!(!state.install("claude_code", first).await.unwrap());
+
+        let second = ConnectorConfig {
+            cli_path: "claude-new".to_string(),
+            ..Default::default()
+        };
+        assert!(state.install("claude_code", second).await.unwrap());
+
+        let guard = state.claude_code.lock().await;
+        assert_eq!(guard.as_ref().unwrap().config().cli_path, "claude-new");
+        drop(guard);
+
+        assert!(state.install("unknown", ConnectorConfig::default()).await.is_err());
+        assert!(state.codex_cli.lock().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_available_models_per_connector() {
+        let state = ConnectorState::new();
+
+        // Codex models are static and need no running connector
+        let codex = state.available_models("codex_cli").await.unwrap();
+        let ids: Vec<&str> = codex.iter().map(|m| m.id.as_str()).collect();
+        assert_eq!(ids, vec!["gpt-5", "gpt-5-codex", "gpt-4"]);
+        assert!(codex.iter().all(|m| m.available));
+
+        assert!(state.available_models("ollama").await.is_err());
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/tags"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "models": [
+                    {"name": "codellama:7b", "size": 1200000},
+                    {"name": "nomic-embed-text", "size": 500000}
+                ]
+            })))
+            .mount(&server)
+            .await;
+        let address = server.address();
+        state
+            .install_ollama(OllamaConfig {
+                host: format!("http://{}", address.ip()),
+                port: address.port(),
+                chat_model: "llama2".to_string(),
+                ..Default::default()
+            })
+            .await;
+
+        let ollama = state.available_models("ollama").await.unwrap();
+        assert_eq!(
+            ollama,
+            vec![
+                ModelInfo {
+                    id: "codellama:7b".to_string(),
+                    display_name: "codellama:7b".to_string(),
+                    available: true,
+                },
+                ModelInfo {
+                    id: "nomic-embed-text".to_string(),
+                    display_name: "nomic-embed-text".to_string(),
+                    available: true,
+                },
+                ModelInfo {
+                    id: "llama2".to_string(),
+                    display_name: "llama2".to_string(),
+                    available: false,
+                },
+            ]
+        );
+    }
+}
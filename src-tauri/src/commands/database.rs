@@ -0,0 +1,33 @@
+use crate::db::{Database, PoolStats};
+use std::sync::Arc;
+use tauri::State;
+
+/// Database state
+pub struct DatabaseState {
+    pub db: Arc<Database>,
+}
+
+impl DatabaseState {
+    pub fn new(db: Arc<Database>) -> Self {
+        Self { db }
+    }
+}
+
+/// Get connection pool usage
+#[tauri::command]
+pub async fn get_pool_stats(state: State<'_, DatabaseState>) -> Result<PoolStats, String> {
+    Ok(state.db.pool_stats())
+}
+
+/// Vacuum and analyze the database
+#[tauri::command]
+pub async fn run_db_maintenance(state: State<'_, DatabaseState>) -> Result<(), String> {
+    state.db
+        .vacuum()
+        .await
+        .map_err(|e| format!("Failed to vacuum database: {}", e))?;
+    state.db
+        .analyze()
+        .await
+        .map_err(|e| format!("Failed to analyze database: {}", e))
+}
@@ -0,0 +1,36 @@
+use super::validation::parse_id;
+use crate::memory::{AgentMemorySnapshot, MemoryManager};
+use std::sync::Arc;
+use tauri::State;
+
+/// Default blackboard capacity for the shared memory manager
+pub const DEFAULT_BLACKBOARD_CAPACITY: usize = 1000;
+
+/// Memory state
+pub struct MemoryState {
+    pub manager: Arc<MemoryManager>,
+}
+
+impl MemoryState {
+    pub fn new() -> Self {
+        Self {
+            manager: Arc::new(MemoryManager::new(DEFAULT_BLACKBOARD_CAPACITY)),
+        }
+    }
+
+    /// Serve an existing manager, e.g. the one `RuntimeState` gives its orchestrators
+    pub fn with_manager(manager: Arc<MemoryManager>) -> Self {
+        Self { manager }
+    }
+}
+
+/// Dump an agent's full memory context for debugging
+#[tauri::command]
+pub async fn dump_agent_memory(
+    agent_id: String,
+    state: State<'_, MemoryState>,
+) -> Result<Option<AgentMemorySnapshot>, String> {
+    let agent_id = parse_id("agent", &agent_id).map_err(|e| e.to_string())?;
+
+    Ok(state.manager.snapshot(agent_id).await)
+}
@@ -1,3 +1,7 @@
 pub mod connectors;
+pub mod database;
+pub mod memory;
 pub mod runtime;
 pub mod session;
+pub mod system;
+pub mod validation;
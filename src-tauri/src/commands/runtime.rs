@@ -1,8 +1,18 @@
+use super::memory::DEFAULT_BLACKBOARD_CAPACITY;
+use super::session::SessionState;
+use super::validation::parse_id;
+use crate::api::{RateLimitConfig, RateLimiter};
+use crate::config::AppConfig;
+use crate::connectors::ConnectorConfig;
+use crate::memory::MemoryManager;
 use crate::runtime::{
-    AgentConfig, AgentId, AgentMetadata, AgentRegistry, LoopGuard, MessageBus, Orchestrator,
-    OrchestratorMetrics, StopReason,
+    AgentConfig, AgentId, AgentMetadata, AgentRegistry, AgentStatusSummary, AgentTopology,
+    ExecutionOrder, LoopGuard, MessageBus, Orchestrator, OrchestratorMetrics, OversizePolicy, RunRecord,
+    SessionBudget,
 };
 use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use std::collections::HashMap;
 use std::sync::Arc;
 use tauri::State;
 use tokio::sync::Mutex;
@@ -12,6 +22,8 @@ pub struct RuntimeState {
     pub registry: Arc<AgentRegistry>,
     pub message_bus: Arc<MessageBus>,
     pub orchestrator: Arc<Mutex<Option<Arc<Orchestrator>>>>,
+    /// Memory the orchestrator records agent work in; share it with `MemoryState`
+    pub memory: Arc<MemoryManager>,
 }
 
 impl RuntimeState {
@@ -23,8 +35,44 @@ impl RuntimeState {
             registry,
             message_bus,
             orchestrator: Arc::new(Mutex::new(None)),
+            memory: Arc::new(MemoryManager::new(DEFAULT_BLACKBOARD_CAPACITY)),
         }
     }
+
+    /// Create runtime state with bus limits (queue depth, payload and message size) taken from the app config
+    pub fn from_config(config: &AppConfig) -> Self {
+        let mut message_bus = MessageBus::new().with_max_payload_bytes(config.max_metadata_bytes);
+        if let Some(depth) = config.max_queue_depth {
+            message_bus = message_bus.with_max_queue_depth(depth);
+        }
+        if let Some(bytes) = config.max_message_bytes {
+            message_bus = message_bus.with_max_message_bytes(bytes, OversizePolicy::Reject);
+        }
+
+        Self {
+            registry: Arc::new(AgentRegistry::new()),
+            message_bus: Arc::new(message_bus),
+            orchestrator: Arc::new(Mutex::new(None)),
+            memory: Arc::new(MemoryManager::new(DEFAULT_BLACKBOARD_CAPACITY)),
+        }
+    }
+
+    /// Create runtime state whose agent registry is stored in the database
+    ///
+    /// Agents saved by a previous run are reloaded and given fresh mailboxes.
+    pub async fn from_config_persistent(
+        config: &AppConfig,
+        pool: SqlitePool,
+    ) -> Result<Self, String> {
+        let mut state = Self::from_config(config);
+        let registry = AgentRegistry::new_persistent(pool).await?;
+
+        let agent_ids: Vec<AgentId> = registry.list_agents().await.iter().map(|a| a.id).collect();
+        state.message_bus.create_mailboxes(&agent_ids).await;
+        state.registry = Arc::new(registry);
+
+        Ok(state)
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -43,7 +91,11 @@ pub async fn register_agent(
     request: RegisterAgentRequest,
     state: State<'_, RuntimeState>,
 ) -> Result<RegisterAgentResponse, String> {
-    let agent_id = state.registry.register(request.config).await;
+    let agent_id = state
+        .registry
+        .register(request.config)
+        .await
+        .map_err(|e| format!("Failed to register agent: {}", e))?;
 
     // Create mailbox for the agent
     state.message_bus.create_mailbox(agent_id).await;
@@ -53,15 +105,30 @@ pub async fn register_agent(
     })
 }
 
+/// Register a whole team in one call; nobody is registered if any config is invalid
+#[tauri::command]
+pub async fn register_agents(
+    configs: Vec<AgentConfig>,
+    state: State<'_, RuntimeState>,
+) -> Result<Vec<String>, String> {
+    let agent_ids = state
+        .registry
+        .register_all(configs)
+        .await
+        .map_err(|e| format!("Failed to register agents: {}", e))?;
+
+    state.message_bus.create_mailboxes(&agent_ids).await;
+
+    Ok(agent_ids.iter().map(|id| id.to_string()).collect())
+}
+
 /// Unregister an agent
 #[tauri::command]
 pub async fn unregister_agent(
     agent_id: String,
     state: State<'_, RuntimeState>,
 ) -> Result<bool, String> {
-    let agent_id = agent_id
-        .parse::<uuid::Uuid>()
-        .map_err(|e| format!("Invalid agent ID: {}", e))?;
+    let agent_id = parse_id("agent", &agent_id).map_err(|e| e.to_string())?;
 
     let removed = state.registry.unregister(agent_id).await;
     if removed {
@@ -83,9 +150,7 @@ pub async fn get_agent_metadata(
     agent_id: String,
     state: State<'_, RuntimeState>,
 ) -> Result<Option<AgentMetadata>, String> {
-    let agent_id = agent_id
-        .parse::<uuid::Uuid>()
-        .map_err(|e| format!("Invalid agent ID: {}", e))?;
+    let agent_id = parse_id("agent", &agent_id).map_err(|e| e.to_string())?;
 
     Ok(state.registry.get_metadata(agent_id).await)
 }
@@ -95,6 +160,26 @@ pub struct CreateOrchestratorRequest {
     pub max_iterations: Option<u32>,
     pub max_messages_per_agent: Option<u32>,
     pub max_execution_time_ms: Option<u64>,
+    pub max_iteration_time_ms: Option<u64>,
+    pub tick_interval_ms: Option<u64>,
+    /// Session whose progress timeline should record the run
+    #[serde(default)]
+    pub session_id: Option<String>,
+    /// Run-wide token/cost limit
+    #[serde(default)]
+    pub budget: Option<SessionBudget>,
+    /// Agents processed concurrently per iteration (default 1, sequential)
+    #[serde(default)]
+    pub max_parallel_agents: Option<usize>,
+    /// CLI connector configs keyed by agent ID or by `session_id`
+    #[serde(default)]
+    pub connector_overrides: HashMap<String, ConnectorConfig>,
+    /// Dispatch rate allowed to each agent (unset means unlimited)
+    #[serde(default)]
+    pub agent_rate_limit: Option<RateLimitConfig>,
+    /// Order agents run in each iteration (default: registration order)
+    #[serde(default)]
+    pub execution_order: Option<ExecutionOrder>,
 }
 
 /// Create and initialize the orchestrator
@@ -102,6 +187,7 @@ pub struct CreateOrchestratorRequest {
 pub async fn create_orchestrator(
     request: CreateOrchestratorRequest,
     state: State<'_, RuntimeState>,
+    sessions: State<'_, SessionState>,
 ) -> Result<String, String> {
     let mut loop_guard = LoopGuard::default();
 
@@ -114,13 +200,35 @@ pub async fn create_orchestrator(
     if let Some(max_time) = request.max_execution_time_ms {
         loop_guard.max_execution_time_ms = max_time;
     }
+    loop_guard.max_iteration_time_ms = request.max_iteration_time_ms;
 
-    let orchestrator = Arc::new(
-        Orchestrator::new(state.registry.clone(), state.message_bus.clone())
-            .with_loop_guard(loop_guard),
-    );
+    let mut orchestrator = Orchestrator::new(state.registry.clone(), state.message_bus.clone())
+        .with_loop_guard(loop_guard)
+        .with_memory(state.memory.clone());
+    if let Some(tick_ms) = request.tick_interval_ms {
+        orchestrator = orchestrator.with_tick_interval_ms(tick_ms);
+    }
+    if let Some(budget) = request.budget {
+        orchestrator = orchestrator.with_budget(budget);
+    }
+    if let Some(limit) = request.max_parallel_agents {
+        orchestrator = orchestrator.with_max_parallel_agents(limit);
+    }
+    for (key, config) in request.connector_overrides {
+        orchestrator = orchestrator.with_connector_override(key, config);
+    }
+    if let Some(order) = request.execution_order {
+        orchestrator = orchestrator.with_execution_order(order);
+    }
+    if let Some(rate_limit) = request.agent_rate_limit {
+        orchestrator = orchestrator.with_agent_rate_limit(Arc::new(RateLimiter::new(rate_limit)));
+    }
+    if let Some(session_id) = request.session_id {
+        parse_id("session", &session_id).map_err(|e| e.to_string())?;
+        orchestrator = orchestrator.with_session(sessions.service.clone(), session_id);
+    }
 
-    *state.orchestrator.lock().await = Some(orchestrator);
+    *state.orchestrator.lock().await = Some(Arc::new(orchestrator));
 
     Ok("Orchestrator created".to_string())
 }
@@ -147,21 +255,8 @@ pub async fn start_orchestrator(
         .await
         .map_err(|e| format!("Orchestrator error: {}", e))?;
 
-    let reason_str = match stop_reason {
-        StopReason::Completed => "completed".to_string(),
-        StopReason::MaxIterations => "max_iterations".to_string(),
-        StopReason::MaxMessagesPerAgent { agent_id, count } => {
-            format!("max_messages_per_agent:{}:{}", agent_id, count)
-        }
-        StopReason::MaxExecutionTime => "max_execution_time".to_string(),
-        StopReason::AgentError { agent_id, error } => {
-            format!("agent_error:{}:{}", agent_id, error)
-        }
-        StopReason::ManualStop => "manual_stop".to_string(),
-    };
-
     Ok(StartOrchestratorResponse {
-        stop_reason: reason_str,
+        stop_reason: stop_reason.to_string(),
     })
 }
 
@@ -179,6 +274,36 @@ pub async fn stop_orchestrator(state: State<'_, RuntimeState>) -> Result<String,
     Ok("Orchestrator stopped".to_string())
 }
 
+/// Pause the orchestrator; queued messages wait until it is resumed
+#[tauri::command]
+pub async fn pause_orchestrator(state: State<'_, RuntimeState>) -> Result<String, String> {
+    let handle = state
+        .orchestrator
+        .lock()
+        .await
+        .as_ref()
+        .map(|o| o.handle())
+        .ok_or_else(|| "Orchestrator not created".to_string())?;
+
+    handle.pause().await;
+    Ok("Orchestrator paused".to_string())
+}
+
+/// Resume a paused orchestrator
+#[tauri::command]
+pub async fn resume_orchestrator(state: State<'_, RuntimeState>) -> Result<String, String> {
+    let handle = state
+        .orchestrator
+        .lock()
+        .await
+        .as_ref()
+        .map(|o| o.handle())
+        .ok_or_else(|| "Orchestrator not created".to_string())?;
+
+    handle.resume().await;
+    Ok("Orchestrator resumed".to_string())
+}
+
 /// Get orchestrator metrics
 #[tauri::command]
 pub async fn get_orchestrator_metrics(
@@ -194,8 +319,60 @@ pub async fn get_orchestrator_metrics(
     Ok(orchestrator.metrics().await)
 }
 
+/// Zero one agent's message count in the current orchestrator metrics
+#[tauri::command]
+pub async fn reset_agent_metrics(
+    agent_id: String,
+    state: State<'_, RuntimeState>,
+) -> Result<(), String> {
+    let agent_id = parse_id("agent", &agent_id).map_err(|e| e.to_string())?;
+
+    let orchestrator = state
+        .orchestrator
+        .lock()
+        .await
+        .clone()
+        .ok_or_else(|| "Orchestrator not created".to_string())?;
+
+    orchestrator.reset_agent_metrics(agent_id).await;
+    Ok(())
+}
+
+/// Get the most recent orchestrator runs, oldest first
+#[tauri::command]
+pub async fn get_run_history(state: State<'_, RuntimeState>) -> Result<Vec<RunRecord>, String> {
+    let orchestrator = state
+        .orchestrator
+        .lock()
+        .await
+        .clone()
+        .ok_or_else(|| "Orchestrator not created".to_string())?;
+
+    Ok(orchestrator.get_run_history().await)
+}
+
 /// Get message bus queue depth
 #[tauri::command]
 pub async fn get_queue_depth(state: State<'_, RuntimeState>) -> Result<usize, String> {
     Ok(state.message_bus.queue_depth().await)
 }
+
+/// Get every agent's status, queue length and processed-message count in one call
+#[tauri::command]
+pub async fn get_agents_status(
+    state: State<'_, RuntimeState>,
+) -> Result<Vec<AgentStatusSummary>, String> {
+    let orchestrator = state.orchestrator.lock().await.clone();
+    let metrics = match orchestrator {
+        Some(orchestrator) => Some(orchestrator.metrics().await),
+        None => None,
+    };
+
+    Ok(AgentStatusSummary::collect(&state.registry, &state.message_bus, metrics.as_ref()).await)
+}
+
+/// Get the agent graph: agents with status and queue depth, plus observed message edges
+#[tauri::command]
+pub async fn get_agent_topology(state: State<'_, RuntimeState>) -> Result<AgentTopology, String> {
+    Ok(AgentTopology::capture(&state.registry, &state.message_bus).await)
+}
@@ -1,27 +1,44 @@
+use super::memory::MemoryState;
+use super::runtime::RuntimeState;
+use super::validation::{parse_id, parse_session_status, validate_name};
+use crate::error::AppResult;
 use crate::session::{
     SessionService, Session, Pane, Message, Block, Attachment, ProgressEvent,
-    MessageType, MessageRole, BlockType,
+    MessageType, MessageRole, BlockType, SessionTemplate, TemplateInstance,
 };
 use serde::{Deserialize, Serialize};
 use tauri::State;
+use std::collections::HashMap;
 use std::sync::Arc;
+use tokio::sync::Mutex;
 
 /// Shared session service state
 pub struct SessionState {
     pub service: Arc<SessionService>,
+    /// Message forwarding tasks started by `subscribe_session_messages`, by subscription ID
+    pub subscriptions: Arc<Mutex<HashMap<String, tauri::async_runtime::JoinHandle<()>>>>,
 }
 
 impl SessionState {
     pub fn new(service: SessionService) -> Self {
         Self {
             service: Arc::new(service),
+            subscriptions: Arc::new(Mutex::new(HashMap::new())),
         }
     }
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SessionSubscription {
+    pub subscription_id: String,
+    pub event: String,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CreateSessionRequest {
     pub name: String,
+    #[serde(default)]
+    pub external_id: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -31,6 +48,21 @@ pub struct CreatePaneRequest {
     pub position: i32,
 }
 
+impl CreateSessionRequest {
+    /// Check the request before it reaches the database
+    pub fn validate(&self) -> AppResult<()> {
+        validate_name("Session name", &self.name)
+    }
+}
+
+impl CreatePaneRequest {
+    /// Check the request before it reaches the database
+    pub fn validate(&self) -> AppResult<()> {
+        parse_id("session", &self.session_id)?;
+        validate_name("Pane name", &self.name)
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct AddMessageRequest {
     pub session_id: String,
@@ -51,6 +83,31 @@ pub struct CreateBlockRequest {
     pub sequence_number: i32,
 }
 
+impl AddMessageRequest {
+    /// Check the request before it reaches the database
+    pub fn validate(&self) -> AppResult<()> {
+        parse_id("session", &self.session_id)?;
+        if let Some(pane_id) = &self.pane_id {
+            parse_id("pane", pane_id)?;
+        }
+        if let Some(parent_id) = &self.parent_id {
+            parse_id("message", parent_id)?;
+        }
+        Ok(())
+    }
+}
+
+impl CreateBlockRequest {
+    /// Check the request before it reaches the database
+    pub fn validate(&self) -> AppResult<()> {
+        parse_id("session", &self.session_id)?;
+        if let Some(pane_id) = &self.pane_id {
+            parse_id("pane", pane_id)?;
+        }
+        Ok(())
+    }
+}
+
 // ===== Session commands =====
 
 /// Create a new session
@@ -59,8 +116,10 @@ pub async fn create_session(
     request: CreateSessionRequest,
     state: State<'_, SessionState>,
 ) -> Result<Session, String> {
+    request.validate().map_err(|e| e.to_string())?;
+
     state.service
-        .create_session(request.name)
+        .create_session_with_external_id(request.name, request.external_id)
         .await
         .map_err(|e| format!("Failed to create session: {}", e))
 }
@@ -71,6 +130,8 @@ pub async fn get_session(
     session_id: String,
     state: State<'_, SessionState>,
 ) -> Result<Option<Session>, String> {
+    parse_id("session", &session_id).map_err(|e| e.to_string())?;
+
     state.service
         .get_session(&session_id)
         .await
@@ -88,6 +149,17 @@ pub async fn list_sessions(
         .map_err(|e| format!("Failed to list sessions: {}", e))
 }
 
+/// List sessions with the most recently active first
+#[tauri::command]
+pub async fn list_sessions_by_activity(
+    state: State<'_, SessionState>,
+) -> Result<Vec<Session>, String> {
+    state.service
+        .list_sessions_by_activity()
+        .await
+        .map_err(|e| format!("Failed to list sessions: {}", e))
+}
+
 /// Update session status
 #[tauri::command]
 pub async fn update_session_status(
@@ -95,15 +167,9 @@ pub async fn update_session_status(
     status: String,
     state: State<'_, SessionState>,
 ) -> Result<(), String> {
-    use crate::session::SessionStatus;
+    parse_id("session", &session_id).map_err(|e| e.to_string())?;
 
-    let session_status = match status.to_lowercase().as_str() {
-        "active" => SessionStatus::Active,
-        "paused" => SessionStatus::Paused,
-        "completed" => SessionStatus::Completed,
-        "archived" => SessionStatus::Archived,
-        _ => return Err(format!("Invalid status: {}", status)),
-    };
+    let session_status = parse_session_status(&status).map_err(|e| e.to_string())?;
 
     state.service
         .update_session_status(&session_id, session_status)
@@ -117,12 +183,128 @@ pub async fn delete_session(
     session_id: String,
     state: State<'_, SessionState>,
 ) -> Result<(), String> {
+    parse_id("session", &session_id).map_err(|e| e.to_string())?;
+
     state.service
         .delete_session(&session_id)
         .await
         .map_err(|e| format!("Failed to delete session: {}", e))
 }
 
+// ===== Template commands =====
+
+/// Save a session template
+#[tauri::command]
+pub async fn save_session_template(
+    template: SessionTemplate,
+    state: State<'_, SessionState>,
+) -> Result<SessionTemplate, String> {
+    validate_name("Template name", &template.name).map_err(|e| e.to_string())?;
+    for pane in &template.panes {
+        validate_name("Pane name", &pane.name).map_err(|e| e.to_string())?;
+    }
+
+    state.service
+        .save_template(template)
+        .await
+        .map_err(|e| format!("Failed to save session template: {}", e))
+}
+
+/// List session templates
+#[tauri::command]
+pub async fn list_session_templates(
+    state: State<'_, SessionState>,
+) -> Result<Vec<SessionTemplate>, String> {
+    state.service
+        .list_templates()
+        .await
+        .map_err(|e| format!("Failed to list session templates: {}", e))
+}
+
+/// Delete session template
+#[tauri::command]
+pub async fn delete_session_template(
+    template_id: String,
+    state: State<'_, SessionState>,
+) -> Result<(), String> {
+    parse_id("template", &template_id).map_err(|e| e.to_string())?;
+
+    state.service
+        .delete_template(&template_id)
+        .await
+        .map_err(|e| format!("Failed to delete session template: {}", e))
+}
+
+/// Spawn a session from a template: its panes, agent team and blackboard seeds
+///
+/// If any step fails the earlier ones are undone, so a failed call leaves
+/// nothing behind.
+#[tauri::command]
+pub async fn create_session_from_template(
+    template_id: String,
+    name: String,
+    state: State<'_, SessionState>,
+    runtime: State<'_, RuntimeState>,
+    memory: State<'_, MemoryState>,
+) -> Result<TemplateInstance, String> {
+    parse_id("template", &template_id).map_err(|e| e.to_string())?;
+    validate_name("Session name", &name).map_err(|e| e.to_string())?;
+
+    instantiate_template(&state.service, &runtime, &memory, &template_id, name).await
+}
+
+/// Create a template's session, register its agents and seed its blackboard,
+/// rolling back whatever was done if a later step fails
+pub async fn instantiate_template(
+    service: &SessionService,
+    runtime: &RuntimeState,
+    memory: &MemoryState,
+    template_id: &str,
+    name: String,
+) -> Result<TemplateInstance, String> {
+    let instance = service
+        .create_session_from_template(template_id, name)
+        .await
+        .map_err(|e| format!("Failed to create session from template: {}", e))?;
+
+    let mut agent_ids = Vec::new();
+    let mut seeded = Vec::new();
+    let result = async {
+        agent_ids = runtime.registry
+            .register_all(instance.agents.clone())
+            .await
+            .map_err(|e| format!("Failed to register template agents: {}", e))?;
+        runtime.message_bus.create_mailboxes(&agent_ids).await;
+
+        for seed in &instance.blackboard {
+            let key = instance.blackboard_key(&seed.key);
+            memory.manager
+                .add_to_blackboard(key.clone(), seed.value.clone(), false)
+                .await
+                .map_err(|e| format!("Failed to seed blackboard: {}", e))?;
+            seeded.push(key);
+        }
+        Ok::<_, String>(())
+    }
+    .await;
+
+    if let Err(e) = result {
+        for key in &seeded {
+            memory.manager.remove_from_blackboard(key).await;
+        }
+        for agent_id in &agent_ids {
+            runtime.message_bus.remove_mailbox(*agent_id).await;
+            runtime.registry.unregister(*agent_id).await;
+        }
+        if let Err(cleanup) = service.delete_session(&instance.session.id).await {
+            tracing::warn!(session_id = %instance.session.id, "Failed to remove session: {}", cleanup);
+        }
+        return Err(e);
+    }
+
+    Ok(instance)
+}
+
 // ===== Pane commands =====
 
 /// Create a pane
@@ -131,6 +313,8 @@ pub async fn create_pane(
     request: CreatePaneRequest,
     state: State<'_, SessionState>,
 ) -> Result<Pane, String> {
+    request.validate().map_err(|e| e.to_string())?;
+
     state.service
         .create_pane(request.session_id, request.name, request.position)
         .await
@@ -143,6 +327,8 @@ pub async fn list_panes(
     session_id: String,
     state: State<'_, SessionState>,
 ) -> Result<Vec<Pane>, String> {
+    parse_id("session", &session_id).map_err(|e| e.to_string())?;
+
     state.service
         .list_panes(&session_id)
         .await
@@ -155,12 +341,77 @@ pub async fn delete_pane(
     pane_id: String,
     state: State<'_, SessionState>,
 ) -> Result<(), String> {
+    parse_id("pane", &pane_id).map_err(|e| e.to_string())?;
+
     state.service
         .delete_pane(&pane_id)
         .await
         .map_err(|e| format!("Failed to delete pane: {}", e))
 }
 
+/// Put a session's panes in the given order
+#[tauri::command]
+pub async fn reorder_panes(
+    session_id: String,
+    pane_ids: Vec<String>,
+    state: State<'_, SessionState>,
+) -> Result<Vec<Pane>, String> {
+    parse_id("session", &session_id).map_err(|e| e.to_string())?;
+    for pane_id in &pane_ids {
+        parse_id("pane", pane_id).map_err(|e| e.to_string())?;
+    }
+
+    state.service
+        .reorder_panes(&session_id, &pane_ids)
+        .await
+        .map_err(|e| format!("Failed to reorder panes: {}", e))
+}
+
+/// Move one pane to a new position, shifting the others
+#[tauri::command]
+pub async fn set_pane_position(
+    pane_id: String,
+    position: i32,
+    state: State<'_, SessionState>,
+) -> Result<Vec<Pane>, String> {
+    parse_id("pane", &pane_id).map_err(|e| e.to_string())?;
+
+    state.service
+        .set_pane_position(&pane_id, position)
+        .await
+        .map_err(|e| format!("Failed to set pane position: {}", e))
+}
+
+/// Make one pane the session's only active pane
+#[tauri::command]
+pub async fn set_active_pane(
+    session_id: String,
+    pane_id: String,
+    state: State<'_, SessionState>,
+) -> Result<(), String> {
+    parse_id("session", &session_id).map_err(|e| e.to_string())?;
+    parse_id("pane", &pane_id).map_err(|e| e.to_string())?;
+
+    state.service
+        .set_active_pane(&session_id, &pane_id)
+        .await
+        .map_err(|e| format!("Failed to set active pane: {}", e))
+}
+
+/// Get the session's active pane
+#[tauri::command]
+pub async fn get_active_pane(
+    session_id: String,
+    state: State<'_, SessionState>,
+) -> Result<Option<Pane>, String> {
+    parse_id("session", &session_id).map_err(|e| e.to_string())?;
+
+    state.service
+        .get_active_pane(&session_id)
+        .await
+        .map_err(|e| format!("Failed to get active pane: {}", e))
+}
+
 // ===== Message commands =====
 
 /// Add a message
@@ -169,7 +420,9 @@ pub async fn add_message(
     request: AddMessageRequest,
     state: State<'_, SessionState>,
 ) -> Result<Message, String> {
-    let message = Message::new(
+    request.validate().map_err(|e| e.to_string())?;
+
+    let mut message = Message::new(
         request.session_id,
         request.pane_id,
         MessageType::from_str(&request.message_type),
@@ -177,6 +430,7 @@ pub async fn add_message(
         request.content,
         request.sequence_number,
     );
+    message.parent_id = request.parent_id;
 
     state.service
         .add_message(message)
@@ -184,24 +438,106 @@ pub async fn add_message(
         .map_err(|e| format!("Failed to add message: {}", e))
 }
 
+/// Stream new messages for a session to the calling window
+///
+/// Each message is emitted as a `session-message:<session_id>` event until
+/// `unsubscribe_session_messages` is called or the window goes away.
+#[tauri::command]
+pub async fn subscribe_session_messages(
+    session_id: String,
+    window: tauri::Window,
+    state: State<'_, SessionState>,
+) -> Result<SessionSubscription, String> {
+    use tokio::sync::broadcast::error::RecvError;
+
+    parse_id("session", &session_id).map_err(|e| e.to_string())?;
+
+    let event = format!("session-message:{}", session_id);
+    let mut rx = state.service.subscribe_messages();
+    let event_name = event.clone();
+    let subscription_id = uuid::Uuid::new_v4().to_string();
+    let subscriptions = state.subscriptions.clone();
+    let task_subscription_id = subscription_id.clone();
+
+    // Hold the map lock until the handle is stored so a task that ends at
+    // once can't deregister itself first
+    let mut active = state.subscriptions.lock().await;
+    let handle = tauri::async_runtime::spawn(async move {
+        loop {
+            match rx.recv().await {
+                Ok(message) if message.session_id == session_id => {
+                    if window.emit(&event_name, &message).is_err() {
+                        break;
+                    }
+                }
+                Ok(_) => {}
+                Err(RecvError::Lagged(skipped)) => {
+                    tracing::warn!(session_id = %session_id, skipped, "Message subscriber lagged");
+                }
+                Err(RecvError::Closed) => break,
+            }
+        }
+        subscriptions.lock().await.remove(&task_subscription_id);
+    });
+    active.insert(subscription_id.clone(), handle);
+
+    Ok(SessionSubscription {
+        subscription_id,
+        event,
+    })
+}
+
+/// Stop a message subscription started by `subscribe_session_messages`
+#[tauri::command]
+pub async fn unsubscribe_session_messages(
+    subscription_id: String,
+    state: State<'_, SessionState>,
+) -> Result<bool, String> {
+    match state.subscriptions.lock().await.remove(&subscription_id) {
+        Some(handle) => {
+            handle.abort();
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
 /// Get messages for a session
 #[tauri::command]
 pub async fn get_messages(
     session_id: String,
     state: State<'_, SessionState>,
 ) -> Result<Vec<Message>, String> {
+    parse_id("session", &session_id).map_err(|e| e.to_string())?;
+
     state.service
         .get_messages(&session_id)
         .await
         .map_err(|e| format!("Failed to get messages: {}", e))
 }
 
+/// Get the reply thread containing a message
+#[tauri::command]
+pub async fn get_thread(
+    message_id: String,
+    state: State<'_, SessionState>,
+) -> Result<Vec<Message>, String> {
+    parse_id("message", &message_id).map_err(|e| e.to_string())?;
+
+    state.service
+        .get_thread(&message_id)
+        .await
+        .map_err(|e| format!("Failed to get thread: {}", e))
+}
+
 /// Get messages for a pane
 #[tauri::command]
 pub async fn get_pane_messages(
     pane_id: String,
     state: State<'_, SessionState>,
 ) -> Result<Vec<Message>, String> {
+    parse_id("pane", &pane_id).map_err(|e| e.to_string())?;
+
     state.service
         .get_pane_messages(&pane_id)
         .await
@@ -214,6 +550,8 @@ pub async fn get_next_sequence_number(
     session_id: String,
     state: State<'_, SessionState>,
 ) -> Result<i32, String> {
+    parse_id("session", &session_id).map_err(|e| e.to_string())?;
+
     state.service
         .get_next_sequence_number(&session_id)
         .await
@@ -228,6 +566,8 @@ pub async fn create_block(
     request: CreateBlockRequest,
     state: State<'_, SessionState>,
 ) -> Result<Block, String> {
+    request.validate().map_err(|e| e.to_string())?;
+
     let block = Block::new(
         request.session_id,
         request.pane_id,
@@ -248,6 +588,8 @@ pub async fn get_blocks(
     session_id: String,
     state: State<'_, SessionState>,
 ) -> Result<Vec<Block>, String> {
+    parse_id("session", &session_id).map_err(|e| e.to_string())?;
+
     state.service
         .get_blocks(&session_id)
         .await
@@ -260,6 +602,8 @@ pub async fn toggle_bookmark(
     block_id: String,
     state: State<'_, SessionState>,
 ) -> Result<(), String> {
+    parse_id("block", &block_id).map_err(|e| e.to_string())?;
+
     state.service
         .toggle_bookmark(&block_id)
         .await
@@ -272,6 +616,8 @@ pub async fn assemble_blocks(
     session_id: String,
     state: State<'_, SessionState>,
 ) -> Result<Vec<Block>, String> {
+    parse_id("session", &session_id).map_err(|e| e.to_string())?;
+
     state.service
         .assemble_blocks(&session_id)
         .await
@@ -286,6 +632,8 @@ pub async fn get_block_attachments(
     block_id: String,
     state: State<'_, SessionState>,
 ) -> Result<Vec<Attachment>, String> {
+    parse_id("block", &block_id).map_err(|e| e.to_string())?;
+
     state.service
         .get_block_attachments(&block_id)
         .await
@@ -300,8 +648,60 @@ pub async fn get_progress_timeline(
     session_id: String,
     state: State<'_, SessionState>,
 ) -> Result<Vec<ProgressEvent>, String> {
+    parse_id("session", &session_id).map_err(|e| e.to_string())?;
+
     state.service
         .get_progress_timeline(&session_id)
         .await
         .map_err(|e| format!("Failed to get progress timeline: {}", e))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::AppError;
+
+    #[test]
+    fn test_create_session_request_validation() {
+        let request = |name: &str| CreateSessionRequest {
+            name: name.to_string(),
+            external_id: None,
+        };
+
+        assert!(matches!(request("").validate(), Err(AppError::Validation(_))));
+        assert!(matches!(request(&"x".repeat(1000)).validate(), Err(AppError::Validation(_))));
+        assert!(request("My session").validate().is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_failed_template_instance_is_rolled_back() {
+        use crate::db::Database;
+        use crate::runtime::{AgentConfig, AgentRole};
+        use tempfile::NamedTempFile;
+
+        let temp_file = NamedTempFile::new().unwrap();
+        let db = Database::init(temp_file.path()).await.unwrap();
+        let service = SessionService::new(db.pool().clone());
+        let runtime = RuntimeState::new();
+        let memory = MemoryState::new();
+
+        // The second agent fails validation once the session already exists
+        let mut broken = AgentConfig::new("broken".to_string(), AgentRole::Worker, "claude_code".to_string());
+        broken.timeout_ms = 0;
+        let template = SessionTemplate::new("team".to_string())
+            .with_pane("main")
+            .with_agent(AgentConfig::new("lead".to_string(), AgentRole::Coordinator, "claude_code".to_string()))
+            .with_agent(broken)
+            .with_blackboard_entry("goal", "ship it");
+        let template = service.save_template(template).await.unwrap();
+
+        let err = instantiate_template(&service, &runtime, &memory, &template.id, "team-1".to_string())
+            .await
+            .unwrap_err();
+        assert!(err.contains("zero timeout"), "got {}", err);
+
+        assert!(service.list_sessions().await.unwrap().is_empty());
+        assert_eq!(runtime.registry.count().await, 0);
+        assert!(memory.manager.export_blackboard().await.is_empty());
+    }
+}
@@ -0,0 +1,76 @@
+use crate::config::AppConfig;
+use serde::Serialize;
+use tauri::State;
+
+/// Connector backends every build ships with; none are behind cargo features
+const BUILTIN_CONNECTORS: [&str; 3] = ["claude_code", "codex_cli", "ollama"];
+
+/// What the running backend is and can do
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BackendInfo {
+    pub version: String,
+    pub schema_version: u32,
+    /// Enabled cargo features plus the built-in connectors, e.g. `gui`, `connector:ollama`
+    pub features: Vec<String>,
+    /// Target OS and architecture, e.g. `linux-x86_64`
+    pub platform: String,
+}
+
+impl BackendInfo {
+    /// `schema_version` is the migration the opened database is at, see `Database::schema_version`
+    pub fn from_config(config: &AppConfig, schema_version: u32) -> Self {
+        let mut features = Vec::new();
+        if cfg!(feature = "gui") {
+            features.push("gui".to_string());
+        }
+        features.extend(BUILTIN_CONNECTORS.iter().map(|name| format!("connector:{}", name)));
+
+        Self {
+            version: config.version.clone(),
+            schema_version,
+            features,
+            platform: format!("{}-{}", std::env::consts::OS, std::env::consts::ARCH),
+        }
+    }
+}
+
+/// Backend description, fixed at startup
+pub struct SystemState {
+    pub info: BackendInfo,
+}
+
+impl SystemState {
+    pub fn new(config: &AppConfig, schema_version: u32) -> Self {
+        Self {
+            info: BackendInfo::from_config(config, schema_version),
+        }
+    }
+}
+
+/// Describe the running backend
+#[tauri::command]
+pub async fn get_backend_info(state: State<'_, SystemState>) -> Result<BackendInfo, String> {
+    Ok(state.info.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::db::Database;
+    use tempfile::NamedTempFile;
+
+    #[tokio::test]
+    async fn test_backend_info_reports_config_version() {
+        let temp_file = NamedTempFile::new().unwrap();
+        let db = Database::init(temp_file.path()).await.unwrap();
+        let schema_version = db.schema_version().await.unwrap();
+
+        let info = SystemState::new(&AppConfig::default(), schema_version).info;
+        assert_eq!(info.version, AppConfig::default().version);
+        assert!(info.schema_version > 0);
+        assert_eq!(info.schema_version, schema_version);
+        assert!(info.features.contains(&"connector:ollama".to_string()));
+        assert!(info.platform.starts_with(std::env::consts::OS));
+    }
+}
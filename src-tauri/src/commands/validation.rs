@@ -0,0 +1,87 @@
+use crate::error::{AppError, AppResult};
+use crate::session::SessionStatus;
+use uuid::Uuid;
+
+/// Longest name accepted for sessions and panes, in characters
+pub const MAX_NAME_LENGTH: usize = 256;
+
+/// Parse an ID taken from a command argument, so malformed IDs never reach SQL
+///
+/// `kind` names the ID in the error, e.g. "session" or "agent".
+pub fn parse_id(kind: &str, id: &str) -> AppResult<Uuid> {
+    id.parse::<Uuid>()
+        .map_err(|e| AppError::Validation(format!("Invalid {} ID '{}': {}", kind, id, e)))
+}
+
+/// Reject empty, whitespace-only and oversized names
+pub fn validate_name(field: &str, name: &str) -> AppResult<()> {
+    if name.trim().is_empty() {
+        return Err(AppError::Validation(format!("{} must not be empty", field)));
+    }
+
+    let length = name.chars().count();
+    if length > MAX_NAME_LENGTH {
+        return Err(AppError::Validation(format!(
+            "{} is {} characters long (max {})",
+            field, length, MAX_NAME_LENGTH
+        )));
+    }
+
+    Ok(())
+}
+
+/// Parse a session status, rejecting anything that isn't a known status
+pub fn parse_session_status(status: &str) -> AppResult<SessionStatus> {
+    match status.to_lowercase().as_str() {
+        "active" => Ok(SessionStatus::Active),
+        "paused" => Ok(SessionStatus::Paused),
+        "completed" => Ok(SessionStatus::Completed),
+        "archived" => Ok(SessionStatus::Archived),
+        _ => Err(AppError::Validation(format!("Invalid status: {}", status))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorCode;
+
+    #[test]
+    fn test_parse_id() {
+        let id = Uuid::new_v4();
+        assert_eq!(parse_id("session", &id.to_string()).unwrap(), id);
+
+        let err = parse_id("session", "1; DROP TABLE sessions").unwrap_err();
+        assert_eq!(err.code(), ErrorCode::Validation);
+        assert!(err.to_string().starts_with("Validation error: Invalid session ID"));
+    }
+
+    #[test]
+    fn test_validate_name_rejects_empty_and_whitespace() {
+        assert!(matches!(validate_name("Session name", ""), Err(AppError::Validation(_))));
+        assert!(matches!(validate_name("Session name", " \t\n"), Err(AppError::Validation(_))));
+    }
+
+    #[test]
+    fn test_validate_name_length_limit() {
+        let at_limit = "a".repeat(MAX_NAME_LENGTH);
+        assert!(validate_name("Session name", &at_limit).is_ok());
+
+        let oversized = "a".repeat(MAX_NAME_LENGTH + 1);
+        let err = validate_name("Session name", &oversized).unwrap_err();
+        assert!(matches!(err, AppError::Validation(_)));
+        assert!(err.to_string().contains("max 256"));
+    }
+
+    #[test]
+    fn test_validate_name_accepts_normal_names() {
+        assert!(validate_name("Session name", "Refactor auth").is_ok());
+        assert!(validate_name("Pane name", "  padded  ").is_ok());
+    }
+
+    #[test]
+    fn test_parse_session_status() {
+        assert_eq!(parse_session_status("Paused").unwrap(), SessionStatus::Paused);
+        assert!(matches!(parse_session_status("done"), Err(AppError::Validation(_))));
+    }
+}
@@ -1,12 +1,61 @@
 use serde::{Deserialize, Serialize};
 use std::path::Path;
 
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+  #[default]
+  Text,
+  Json,
+}
+
+/// SQLite pragmas applied to every pooled connection
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct DatabaseConfig {
+  pub journal_mode: String,
+  pub synchronous: String,
+  pub busy_timeout_ms: u64,
+  pub foreign_keys: bool,
+}
+
+impl Default for DatabaseConfig {
+  fn default() -> Self {
+    Self {
+      journal_mode: "wal".to_string(),
+      synchronous: "normal".to_string(),
+      busy_timeout_ms: 5000,
+      foreign_keys: true,
+    }
+  }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct AppConfig {
   pub app_name: String,
   pub version: String,
   pub db_pool_size: u32,
   pub log_level: String,
+  #[serde(default)]
+  pub log_format: LogFormat,
+  /// Per-agent mailbox depth at which the message bus applies back-pressure
+  #[serde(default)]
+  pub max_queue_depth: Option<usize>,
+  /// Largest agent message content the bus accepts, in bytes (unset means unbounded)
+  #[serde(default)]
+  pub max_message_bytes: Option<usize>,
+  /// Largest JSON metadata, event data or message payload accepted, in bytes
+  #[serde(default = "default_max_metadata_bytes")]
+  pub max_metadata_bytes: usize,
+  #[serde(default)]
+  pub database: DatabaseConfig,
+}
+
+/// Metadata size cap used when the config doesn't set one
+pub const DEFAULT_MAX_METADATA_BYTES: usize = 64 * 1024;
+
+fn default_max_metadata_bytes() -> usize {
+  DEFAULT_MAX_METADATA_BYTES
 }
 
 impl Default for AppConfig {
@@ -16,6 +65,11 @@ impl Default for AppConfig {
       version: "0.0.1".to_string(),
       db_pool_size: 5,
       log_level: "info".to_string(),
+      log_format: LogFormat::Text,
+      max_queue_depth: None,
+      max_message_bytes: None,
+      max_metadata_bytes: DEFAULT_MAX_METADATA_BYTES,
+      database: DatabaseConfig::default(),
     }
   }
 }
@@ -64,4 +118,19 @@ mod tests {
     let loaded_config = AppConfig::load(&config_path).unwrap();
     assert_eq!(original_config.app_name, loaded_config.app_name);
   }
+
+  #[test]
+  fn test_log_format_defaults_to_text() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("config.json");
+
+    fs::write(
+      &config_path,
+      r#"{"app_name":"Agent Manager","version":"0.0.1","db_pool_size":5,"log_level":"info"}"#,
+    ).unwrap();
+
+    let loaded_config = AppConfig::load(&config_path).unwrap();
+    assert_eq!(loaded_config.log_format, LogFormat::Text);
+    assert_eq!(loaded_config.database, DatabaseConfig::default());
+  }
 }
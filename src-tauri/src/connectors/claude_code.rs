@@ -1,18 +1,29 @@
-use super::types::{ConnectorConfig, ConnectorHealth, ConnectorMessage, ConnectorMetrics};
+use super::context::{default_token_counter, fit_prompt, ContextLimitExceeded, TokenCounter};
+use super::error::ConnectorError;
+use super::health::{HealthThresholds, HealthTracker};
+use super::line_buffer::JsonLineBuffer;
+use super::preflight::probe_cli_version;
+use super::types::{
+    is_cli_notice, ConnectorConfig, ConnectorHealth, ConnectorMessage, ConnectorMetrics,
+};
 use serde::{Deserialize, Serialize};
 use std::process::Stdio;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::io::{AsyncBufReadExt, BufReader};
 use tokio::process::{Child, Command};
 use tokio::sync::{mpsc, Mutex};
 use tokio::time::timeout;
+use tracing::instrument;
 
 /// Errors specific to Claude Code connector
 #[derive(Debug, thiserror::Error)]
 pub enum ClaudeCodeError {
     #[error("Failed to spawn CLI process: {0}")]
     SpawnError(String),
+    #[error("CLI binary not found: {0}")]
+    NotFound(String),
     #[error("Process terminated unexpectedly: {0}")]
     ProcessTerminated(String),
     #[error("Timeout waiting for response")]
@@ -23,15 +34,44 @@ pub enum ClaudeCodeError {
     IoError(#[from] std::io::Error),
     #[error("Max retries exceeded")]
     MaxRetriesExceeded,
+    #[error("{0}")]
+    ContextLimit(#[from] ContextLimitExceeded),
 }
 
 pub type Result<T> = std::result::Result<T, ClaudeCodeError>;
 
+impl ClaudeCodeError {
+    /// Whether the retry loop should try again after this error
+    pub fn is_retryable(&self) -> bool {
+        ConnectorError::from(self).is_retryable()
+    }
+}
+
+impl From<&ClaudeCodeError> for ConnectorError {
+    fn from(error: &ClaudeCodeError) -> Self {
+        match error {
+            ClaudeCodeError::SpawnError(reason) => ConnectorError::Spawn(reason.clone()),
+            ClaudeCodeError::NotFound(path) => ConnectorError::NotFound(path.clone()),
+            ClaudeCodeError::Timeout => ConnectorError::Timeout(error.to_string()),
+            ClaudeCodeError::ContextLimit(e) => ConnectorError::Rejected(e.to_string()),
+            ClaudeCodeError::ProcessTerminated(_)
+            | ClaudeCodeError::ParseError(_)
+            | ClaudeCodeError::IoError(_)
+            | ClaudeCodeError::MaxRetriesExceeded => ConnectorError::Transient(error.to_string()),
+        }
+    }
+}
+
 /// Claude Code CLI connector
+///
+/// Clones share metrics and health.
+#[derive(Clone)]
 pub struct ClaudeCodeConnector {
     config: ConnectorConfig,
     metrics: Arc<Mutex<ConnectorMetrics>>,
-    health: Arc<Mutex<ConnectorHealth>>,
+    health: Arc<Mutex<HealthTracker>>,
+    /// Sizes prompts against `max_context_tokens`
+    token_counter: Arc<dyn TokenCounter>,
 }
 
 impl ClaudeCodeConnector {
@@ -40,13 +80,45 @@ impl ClaudeCodeConnector {
         Self {
             config,
             metrics: Arc::new(Mutex::new(ConnectorMetrics::default())),
-            health: Arc::new(Mutex::new(ConnectorHealth::Healthy)),
+            health: Arc::new(Mutex::new(HealthTracker::default())),
+            token_counter: default_token_counter(),
+        }
+    }
+
+    /// Count prompt tokens with the model's tokenizer instead of the estimate
+    pub fn with_token_counter(mut self, counter: Arc<dyn TokenCounter>) -> Self {
+        self.token_counter = counter;
+        self
+    }
+
+    /// Require runs of failures or successes before health changes
+    pub fn with_health_thresholds(mut self, thresholds: HealthThresholds) -> Self {
+        self.health = Arc::new(Mutex::new(HealthTracker::new(thresholds)));
+        self
+    }
+
+    /// Configuration this connector was created with
+    pub fn config(&self) -> &ConnectorConfig {
+        &self.config
+    }
+
+    /// Model picked with a `--model` flag, if any; otherwise the CLI's default applies
+    pub fn model(&self) -> Option<String> {
+        let mut flags = self.config.flags.iter();
+        while let Some(flag) = flags.next() {
+            if flag == "--model" {
+                return flags.next().cloned();
+            }
+            if let Some(model) = flag.strip_prefix("--model=") {
+                return Some(model.to_string());
+            }
         }
+        None
     }
 
     /// Get current health status
     pub async fn health(&self) -> ConnectorHealth {
-        self.health.lock().await.clone()
+        self.health.lock().await.status()
     }
 
     /// Get current metrics
@@ -54,53 +126,152 @@ impl ClaudeCodeConnector {
         self.metrics.lock().await.clone()
     }
 
+    /// Zero the counters without touching health or config
+    pub async fn reset_metrics(&self) {
+        *self.metrics.lock().await = ConnectorMetrics::default();
+    }
+
+    /// Validate the CLI binary before the first real prompt
+    ///
+    /// Runs a version probe and records the outcome in the connector's
+    /// health, so a missing or broken binary is reported up front.
+    pub async fn warmup(&self) -> Result<String> {
+        match probe_cli_version(&self.config).await {
+            Ok(version) => {
+                self.update_health(ConnectorHealth::Healthy).await;
+                Ok(version)
+            }
+            Err(reason) => {
+                self.update_health(ConnectorHealth::Unhealthy {
+                    reason: format!("Warmup failed: {}", reason),
+                }).await;
+                Err(ClaudeCodeError::SpawnError(reason))
+            }
+        }
+    }
+
     /// Execute a prompt and stream responses
+    ///
+    /// The CLI is spawned before this returns, so a missing binary or a
+    /// rejected prompt fails the call itself. The process is then driven in a
+    /// background task while the caller reads, so output never has to fit in
+    /// the channel; later failures end the stream with an `Error` message.
+    #[instrument(skip_all, fields(connector = "claude_code", prompt_len = prompt.len()))]
     pub async fn execute(
         &self,
         prompt: &str,
     ) -> Result<mpsc::Receiver<ConnectorMessage>> {
-        let (tx, rx) = mpsc::channel(100);
+        let prompt = fit_prompt(
+            prompt,
+            self.config.max_context_tokens,
+            self.config.context_policy,
+            self.token_counter.as_ref(),
+        )?
+        .into_owned();
+        let (tx, rx) = mpsc::channel(self.config.channel_capacity.max(1));
 
         let mut retries = 0;
-        let max_retries = self.config.max_retries;
-
-        loop {
-            match self.try_execute(prompt, tx.clone()).await {
-                Ok(_) => {
-                    self.update_metrics(true).await;
-                    self.update_health(ConnectorHealth::Healthy).await;
-                    break Ok(rx);
-                }
+        let child = loop {
+            match self.spawn_process(&prompt).await {
+                Ok(child) => break child,
                 Err(e) => {
                     retries += 1;
-                    self.update_metrics(false).await;
+                    self.retry_or_give_up(e, retries, false).await?;
+                }
+            }
+        };
 
-                    if retries >= max_retries {
-                        self.update_health(ConnectorHealth::Unhealthy {
-                            reason: format!("Max retries exceeded: {}", e),
-                        }).await;
-                        return Err(ClaudeCodeError::MaxRetriesExceeded);
-                    }
+        let connector = self.clone();
+        tokio::spawn(async move {
+            connector.drive(child, &prompt, tx, retries).await;
+        });
+
+        Ok(rx)
+    }
 
-                    // Exponential backoff
-                    let backoff = Duration::from_millis(100 * 2_u64.pow(retries - 1));
-                    tokio::time::sleep(backoff).await;
+    /// Run attempts until one succeeds or retries run out, ending the stream
+    /// with `Done` or an `Error`
+    async fn drive(
+        &self,
+        child: Child,
+        prompt: &str,
+        tx: mpsc::Sender<ConnectorMessage>,
+        mut retries: u32,
+    ) {
+        let mut next = Some(child);
+        loop {
+            let sent = Arc::new(AtomicBool::new(false));
+            let result = match next.take() {
+                Some(child) => self.try_execute(child, &tx, &sent).await,
+                None => match self.spawn_process(prompt).await {
+                    Ok(child) => self.try_execute(child, &tx, &sent).await,
+                    Err(e) => Err(e),
+                },
+            };
+
+            let e = match result {
+                Ok(()) => {
+                    self.update_metrics(true).await;
+                    self.health.lock().await.record_success();
+                    let _ = tx.send(ConnectorMessage::Done).await;
+                    return;
                 }
+                Err(e) => e,
+            };
+
+            retries += 1;
+            if let Err(e) = self.retry_or_give_up(e, retries, sent.load(Ordering::Relaxed)).await {
+                let _ = tx.send(ConnectorMessage::Error {
+                    message: e.to_string(),
+                }).await;
+                return;
             }
         }
     }
 
-    /// Single execution attempt
+    /// Count a failed attempt, then wait out the backoff if another attempt is
+    /// allowed, or return the error to give up with
+    async fn retry_or_give_up(
+        &self,
+        e: ClaudeCodeError,
+        retries: u32,
+        output_sent: bool,
+    ) -> Result<()> {
+        self.update_metrics(false).await;
+
+        // Retrying can't fix a missing binary or a rejected prompt, and would
+        // repeat output the reader already has
+        if output_sent || !e.is_retryable() {
+            self.health.lock().await.record_failure(ConnectorHealth::Unhealthy {
+                reason: e.to_string(),
+            });
+            return Err(e);
+        }
+
+        if retries >= self.config.max_retries {
+            self.health.lock().await.record_failure(ConnectorHealth::Unhealthy {
+                reason: format!("Max retries exceeded: {}", e),
+            });
+            return Err(ClaudeCodeError::MaxRetriesExceeded);
+        }
+
+        // Exponential backoff
+        let backoff = Duration::from_millis(100 * 2_u64.pow(retries - 1));
+        tokio::time::sleep(backoff).await;
+        Ok(())
+    }
+
+    /// Single execution attempt on a spawned process
+    ///
+    /// `sent` is set once any of the attempt's output has reached the reader.
     async fn try_execute(
         &self,
-        prompt: &str,
-        tx: mpsc::Sender<ConnectorMessage>,
+        mut child: Child,
+        tx: &mpsc::Sender<ConnectorMessage>,
+        sent: &Arc<AtomicBool>,
     ) -> Result<()> {
         let start = Instant::now();
 
-        // Spawn the CLI process
-        let mut child = self.spawn_process(prompt).await?;
-
         // Stream stdout and stderr
         let stdout = child.stdout.take().ok_or_else(|| {
             ClaudeCodeError::SpawnError("Failed to capture stdout".to_string())
@@ -111,32 +282,29 @@ impl ClaudeCodeConnector {
         })?;
 
         // Spawn tasks to read stdout and stderr
-        let tx_stdout = tx.clone();
-        let stdout_task = tokio::spawn(async move {
-            Self::stream_output(stdout, tx_stdout).await
-        });
-
-        let tx_stderr = tx.clone();
-        let stderr_task = tokio::spawn(async move {
-            Self::stream_errors(stderr, tx_stderr).await
-        });
+        let stdout_task = tokio::spawn(Self::stream_output(stdout, tx.clone(), sent.clone()));
+        let stderr_task = tokio::spawn(Self::stream_errors(stderr, tx.clone(), sent.clone()));
 
         // Wait for process to complete with optional timeout
-        let wait_future = child.wait();
         let result = if let Some(timeout_ms) = self.config.timeout_ms {
-            timeout(Duration::from_millis(timeout_ms), wait_future)
-                .await
-                .map_err(|_| ClaudeCodeError::Timeout)?
+            match timeout(Duration::from_millis(timeout_ms), child.wait()).await {
+                Ok(result) => result,
+                Err(_) => {
+                    // Children of the CLI may still hold its pipes open, so stop
+                    // reading rather than wait for EOF
+                    let _ = child.kill().await;
+                    stdout_task.abort();
+                    stderr_task.abort();
+                    return Err(ClaudeCodeError::Timeout);
+                }
+            }
         } else {
-            wait_future.await
+            child.wait().await
         };
 
         // Wait for streaming tasks to complete
         let _ = tokio::join!(stdout_task, stderr_task);
 
-        // Send done message
-        let _ = tx.send(ConnectorMessage::Done).await;
-
         // Check exit status
         match result {
             Ok(status) if status.success() => {
@@ -174,19 +342,34 @@ impl ClaudeCodeConnector {
             .stdin(Stdio::null());
 
         // Spawn
-        cmd.spawn()
-            .map_err(|e| ClaudeCodeError::SpawnError(e.to_string()))
+        cmd.spawn().map_err(|e| match ConnectorError::from_spawn(&e, &self.config.cli_path) {
+            ConnectorError::NotFound(path) => ClaudeCodeError::NotFound(path),
+            _ => ClaudeCodeError::SpawnError(e.to_string()),
+        })
     }
 
     /// Stream and parse stdout
     async fn stream_output<R: tokio::io::AsyncRead + Unpin>(
         reader: R,
         tx: mpsc::Sender<ConnectorMessage>,
+        sent: Arc<AtomicBool>,
     ) {
         let mut lines = BufReader::new(reader).lines();
+        let mut buffer = JsonLineBuffer::new();
 
         while let Ok(Some(line)) = lines.next_line().await {
-            if let Some(msg) = Self::parse_output_line(&line) {
+            for record in buffer.push(line) {
+                if let Some(msg) = Self::parse_output_line(&record) {
+                    sent.store(true, Ordering::Relaxed);
+                    let _ = tx.send(msg).await;
+                }
+            }
+        }
+
+        // Whatever never completed is passed through as-is
+        for record in buffer.flush() {
+            if let Some(msg) = Self::parse_output_line(&record) {
+                sent.store(true, Ordering::Relaxed);
                 let _ = tx.send(msg).await;
             }
         }
@@ -196,10 +379,12 @@ impl ClaudeCodeConnector {
     async fn stream_errors<R: tokio::io::AsyncRead + Unpin>(
         reader: R,
         tx: mpsc::Sender<ConnectorMessage>,
+        sent: Arc<AtomicBool>,
     ) {
         let mut lines = BufReader::new(reader).lines();
 
         while let Ok(Some(line)) = lines.next_line().await {
+            sent.store(true, Ordering::Relaxed);
             let _ = tx.send(ConnectorMessage::Error {
                 message: line,
             }).await;
@@ -210,7 +395,7 @@ impl ClaudeCodeConnector {
     fn parse_output_line(line: &str) -> Option<ConnectorMessage> {
         // Try to parse as JSON first (for structured output)
         if let Ok(msg) = serde_json::from_str::<ConnectorMessage>(line) {
-            return Some(msg);
+            return Some(msg.with_structured_args());
         }
 
         // Check for usage patterns
@@ -220,6 +405,13 @@ impl ClaudeCodeConnector {
             }
         }
 
+        // Banners and spinners aren't part of the answer
+        if is_cli_notice(line) {
+            return Some(ConnectorMessage::SystemNotice {
+                text: line.trim().to_string(),
+            });
+        }
+
         // Default: treat as content
         if !line.trim().is_empty() {
             Some(ConnectorMessage::Content {
@@ -286,7 +478,7 @@ impl ClaudeCodeConnector {
 
     /// Update health status
     async fn update_health(&self, health: ConnectorHealth) {
-        *self.health.lock().await = health;
+        self.health.lock().await.set(health);
     }
 
     /// Update token usage in metrics
@@ -341,6 +533,36 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_banner_line_is_a_system_notice() {
+        assert_eq!(
+            ClaudeCodeConnector::parse_output_line("Starting Claude Code..."),
+            Some(ConnectorMessage::SystemNotice {
+                text: "Starting Claude Code...".to_string(),
+            })
+        );
+        assert_eq!(
+            ClaudeCodeConnector::parse_output_line("The bug is an off-by-one in the loop."),
+            Some(ConnectorMessage::Content {
+                content: "The bug is an off-by-one in the loop.".to_string(),
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_stream_output_reassembles_split_json() {
+        let (tx, mut rx) = mpsc::channel(10);
+        let output: &[u8] = b"{\"type\":\"content\",\n\"content\":\"Hello world\"}\n";
+
+        ClaudeCodeConnector::stream_output(output, tx, Arc::default()).await;
+
+        let msg = rx.recv().await.unwrap();
+        assert_eq!(msg, ConnectorMessage::Content {
+            content: "Hello world".to_string(),
+        });
+        assert!(rx.recv().await.is_none());
+    }
+
     #[tokio::test]
     async fn test_connector_creation() {
         let config = ConnectorConfig::default();
@@ -364,4 +586,62 @@ mod tests {
         assert_eq!(metrics.total_input_tokens, 100);
         assert_eq!(metrics.total_output_tokens, 50);
     }
+
+    #[tokio::test]
+    async fn test_reset_metrics() {
+        let connector = ClaudeCodeConnector::new(ConnectorConfig::default());
+        connector.record_usage(100, 50).await;
+        connector.update_metrics(false).await;
+
+        connector.reset_metrics().await;
+
+        let metrics = connector.metrics().await;
+        assert_eq!(metrics.total_input_tokens, 0);
+        assert_eq!(metrics.error_count, 0);
+        assert_eq!(metrics.spawn_count, 0);
+    }
+
+    #[test]
+    fn test_parse_tool_call_args() {
+        let line = r#"{"type":"tool_call","name":"edit","args":{"path":"a.rs","line":3}}"#;
+        let msg = ClaudeCodeConnector::parse_output_line(line);
+
+        if let Some(ConnectorMessage::ToolCall { name, args }) = msg {
+            assert_eq!(name, "edit");
+            assert_eq!(args["path"], "a.rs");
+            assert_eq!(args["line"], 3);
+        } else {
+            panic!("Expected ToolCall message");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_warmup_missing_binary_marks_unhealthy() {
+        let connector = ClaudeCodeConnector::new(ConnectorConfig {
+            cli_path: "/nonexistent/claude".to_string(),
+            ..ConnectorConfig::default()
+        });
+
+        let result = connector.warmup().await;
+        assert!(matches!(result, Err(ClaudeCodeError::SpawnError(_))));
+        assert!(matches!(connector.health().await, ConnectorHealth::Unhealthy { .. }));
+
+        // Nothing was executed, so no spawns should be counted
+        assert_eq!(connector.metrics().await.spawn_count, 0);
+    }
+
+    #[test]
+    fn test_model_read_from_flags() {
+        let model_of = |flags: &[&str]| {
+            ClaudeCodeConnector::new(ConnectorConfig {
+                flags: flags.iter().map(|f| f.to_string()).collect(),
+                ..ConnectorConfig::default()
+            })
+            .model()
+        };
+
+        assert_eq!(model_of(&["--model", "opus"]), Some("opus".to_string()));
+        assert_eq!(model_of(&["--verbose", "--model=sonnet"]), Some("sonnet".to_string()));
+        assert_eq!(model_of(&["--verbose"]), None);
+    }
 }
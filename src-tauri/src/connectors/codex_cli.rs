@@ -1,18 +1,29 @@
-use super::types::{ConnectorConfig, ConnectorHealth, ConnectorMessage, ConnectorMetrics};
+use super::context::{default_token_counter, fit_prompt, ContextLimitExceeded, TokenCounter};
+use super::error::ConnectorError;
+use super::health::{HealthThresholds, HealthTracker};
+use super::line_buffer::JsonLineBuffer;
+use super::preflight::probe_cli_version;
+use super::types::{
+    is_cli_notice, ConnectorConfig, ConnectorHealth, ConnectorMessage, ConnectorMetrics, ModelInfo,
+};
 use serde::{Deserialize, Serialize};
 use std::process::Stdio;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 use tokio::process::{Child, Command};
 use tokio::sync::{mpsc, Mutex};
 use tokio::time::timeout;
+use tracing::instrument;
 
 /// Errors specific to Codex CLI connector
 #[derive(Debug, thiserror::Error)]
 pub enum CodexCliError {
     #[error("Failed to spawn CLI process: {0}")]
     SpawnError(String),
+    #[error("CLI binary not found: {0}")]
+    NotFound(String),
     #[error("Process terminated unexpectedly: {0}")]
     ProcessTerminated(String),
     #[error("Timeout waiting for response")]
@@ -23,12 +34,37 @@ pub enum CodexCliError {
     IoError(#[from] std::io::Error),
     #[error("Max retries exceeded")]
     MaxRetriesExceeded,
+    #[error("{0}")]
+    ContextLimit(#[from] ContextLimitExceeded),
     #[error("Model switch failed: {0}")]
     ModelSwitchError(String),
 }
 
 pub type Result<T> = std::result::Result<T, CodexCliError>;
 
+impl CodexCliError {
+    /// Whether the retry loop should try again after this error
+    pub fn is_retryable(&self) -> bool {
+        ConnectorError::from(self).is_retryable()
+    }
+}
+
+impl From<&CodexCliError> for ConnectorError {
+    fn from(error: &CodexCliError) -> Self {
+        match error {
+            CodexCliError::SpawnError(reason) => ConnectorError::Spawn(reason.clone()),
+            CodexCliError::NotFound(path) => ConnectorError::NotFound(path.clone()),
+            CodexCliError::Timeout => ConnectorError::Timeout(error.to_string()),
+            CodexCliError::ContextLimit(e) => ConnectorError::Rejected(e.to_string()),
+            CodexCliError::ModelSwitchError(reason) => ConnectorError::Rejected(reason.clone()),
+            CodexCliError::ProcessTerminated(_)
+            | CodexCliError::ParseError(_)
+            | CodexCliError::IoError(_)
+            | CodexCliError::MaxRetriesExceeded => ConnectorError::Transient(error.to_string()),
+        }
+    }
+}
+
 /// Supported GPT models
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum GptModel {
@@ -41,6 +77,9 @@ pub enum GptModel {
 }
 
 impl GptModel {
+    /// Every supported model, in picker order
+    pub const ALL: [GptModel; 3] = [GptModel::Gpt5, GptModel::Gpt5Codex, GptModel::Gpt4];
+
     pub fn as_str(&self) -> &str {
         match self {
             GptModel::Gpt5 => "gpt-5",
@@ -48,14 +87,35 @@ impl GptModel {
             GptModel::Gpt4 => "gpt-4",
         }
     }
+
+    pub fn display_name(&self) -> &str {
+        match self {
+            GptModel::Gpt5 => "GPT-5",
+            GptModel::Gpt5Codex => "GPT-5-Codex",
+            GptModel::Gpt4 => "GPT-4",
+        }
+    }
+
+    pub fn info(&self) -> ModelInfo {
+        ModelInfo {
+            id: self.as_str().to_string(),
+            display_name: self.display_name().to_string(),
+            available: true,
+        }
+    }
 }
 
 /// Codex CLI connector for GPT-5 and GPT-5-Codex
+///
+/// Clones share the current model, metrics and health.
+#[derive(Clone)]
 pub struct CodexCliConnector {
     config: ConnectorConfig,
     current_model: Arc<Mutex<GptModel>>,
     metrics: Arc<Mutex<ConnectorMetrics>>,
-    health: Arc<Mutex<ConnectorHealth>>,
+    health: Arc<Mutex<HealthTracker>>,
+    /// Sizes prompts against `max_context_tokens`
+    token_counter: Arc<dyn TokenCounter>,
 }
 
 impl CodexCliConnector {
@@ -70,13 +130,31 @@ impl CodexCliConnector {
             config,
             current_model: Arc::new(Mutex::new(model)),
             metrics: Arc::new(Mutex::new(ConnectorMetrics::default())),
-            health: Arc::new(Mutex::new(ConnectorHealth::Healthy)),
+            health: Arc::new(Mutex::new(HealthTracker::default())),
+            token_counter: default_token_counter(),
         }
     }
 
+    /// Count prompt tokens with the model's tokenizer instead of the estimate
+    pub fn with_token_counter(mut self, counter: Arc<dyn TokenCounter>) -> Self {
+        self.token_counter = counter;
+        self
+    }
+
+    /// Require runs of failures or successes before health changes
+    pub fn with_health_thresholds(mut self, thresholds: HealthThresholds) -> Self {
+        self.health = Arc::new(Mutex::new(HealthTracker::new(thresholds)));
+        self
+    }
+
+    /// Configuration this connector was created with
+    pub fn config(&self) -> &ConnectorConfig {
+        &self.config
+    }
+
     /// Get current health status
     pub async fn health(&self) -> ConnectorHealth {
-        self.health.lock().await.clone()
+        self.health.lock().await.status()
     }
 
     /// Get current metrics
@@ -84,6 +162,11 @@ impl CodexCliConnector {
         self.metrics.lock().await.clone()
     }
 
+    /// Zero the counters without touching health or config
+    pub async fn reset_metrics(&self) {
+        *self.metrics.lock().await = ConnectorMetrics::default();
+    }
+
     /// Get current model
     pub async fn current_model(&self) -> GptModel {
         self.current_model.lock().await.clone()
@@ -95,53 +178,148 @@ impl CodexCliConnector {
         Ok(())
     }
 
+    /// Validate the CLI binary before the first real prompt
+    ///
+    /// Runs a version probe and records the outcome in the connector's
+    /// health, so a missing or broken binary is reported up front.
+    pub async fn warmup(&self) -> Result<String> {
+        match probe_cli_version(&self.config).await {
+            Ok(version) => {
+                self.update_health(ConnectorHealth::Healthy).await;
+                Ok(version)
+            }
+            Err(reason) => {
+                self.update_health(ConnectorHealth::Unhealthy {
+                    reason: format!("Warmup failed: {}", reason),
+                }).await;
+                Err(CodexCliError::SpawnError(reason))
+            }
+        }
+    }
+
     /// Execute a prompt and stream responses
+    ///
+    /// The CLI is spawned before this returns, so a missing binary or a
+    /// rejected prompt fails the call itself. The process is then driven in a
+    /// background task while the caller reads, so output never has to fit in
+    /// the channel; later failures end the stream with an `Error` message.
+    #[instrument(skip_all, fields(connector = "codex_cli", prompt_len = prompt.len()))]
     pub async fn execute(
         &self,
         prompt: &str,
     ) -> Result<mpsc::Receiver<ConnectorMessage>> {
-        let (tx, rx) = mpsc::channel(100);
+        let prompt = fit_prompt(
+            prompt,
+            self.config.max_context_tokens,
+            self.config.context_policy,
+            self.token_counter.as_ref(),
+        )?
+        .into_owned();
+        let (tx, rx) = mpsc::channel(self.config.channel_capacity.max(1));
 
         let mut retries = 0;
-        let max_retries = self.config.max_retries;
-
-        loop {
-            match self.try_execute(prompt, tx.clone()).await {
-                Ok(_) => {
-                    self.update_metrics(true).await;
-                    self.update_health(ConnectorHealth::Healthy).await;
-                    break Ok(rx);
-                }
+        let child = loop {
+            match self.spawn_process().await {
+                Ok(child) => break child,
                 Err(e) => {
                     retries += 1;
-                    self.update_metrics(false).await;
+                    self.retry_or_give_up(e, retries, false).await?;
+                }
+            }
+        };
 
-                    if retries >= max_retries {
-                        self.update_health(ConnectorHealth::Unhealthy {
-                            reason: format!("Max retries exceeded: {}", e),
-                        }).await;
-                        return Err(CodexCliError::MaxRetriesExceeded);
-                    }
+        let connector = self.clone();
+        tokio::spawn(async move {
+            connector.drive(child, &prompt, tx, retries).await;
+        });
+
+        Ok(rx)
+    }
 
-                    // Exponential backoff
-                    let backoff = Duration::from_millis(100 * 2_u64.pow(retries - 1));
-                    tokio::time::sleep(backoff).await;
+    /// Run attempts until one succeeds or retries run out, ending the stream
+    /// with `Done` or an `Error`
+    async fn drive(
+        &self,
+        child: Child,
+        prompt: &str,
+        tx: mpsc::Sender<ConnectorMessage>,
+        mut retries: u32,
+    ) {
+        let mut next = Some(child);
+        loop {
+            let sent = Arc::new(AtomicBool::new(false));
+            let result = match next.take() {
+                Some(child) => self.try_execute(child, prompt, &tx, &sent).await,
+                None => match self.spawn_process().await {
+                    Ok(child) => self.try_execute(child, prompt, &tx, &sent).await,
+                    Err(e) => Err(e),
+                },
+            };
+
+            let e = match result {
+                Ok(()) => {
+                    self.update_metrics(true).await;
+                    self.health.lock().await.record_success();
+                    let _ = tx.send(ConnectorMessage::Done).await;
+                    return;
                 }
+                Err(e) => e,
+            };
+
+            retries += 1;
+            if let Err(e) = self.retry_or_give_up(e, retries, sent.load(Ordering::Relaxed)).await {
+                let _ = tx.send(ConnectorMessage::Error {
+                    message: e.to_string(),
+                }).await;
+                return;
             }
         }
     }
 
-    /// Single execution attempt
+    /// Count a failed attempt, then wait out the backoff if another attempt is
+    /// allowed, or return the error to give up with
+    async fn retry_or_give_up(
+        &self,
+        e: CodexCliError,
+        retries: u32,
+        output_sent: bool,
+    ) -> Result<()> {
+        self.update_metrics(false).await;
+
+        // Retrying can't fix a missing binary or a rejected prompt, and would
+        // repeat output the reader already has
+        if output_sent || !e.is_retryable() {
+            self.health.lock().await.record_failure(ConnectorHealth::Unhealthy {
+                reason: e.to_string(),
+            });
+            return Err(e);
+        }
+
+        if retries >= self.config.max_retries {
+            self.health.lock().await.record_failure(ConnectorHealth::Unhealthy {
+                reason: format!("Max retries exceeded: {}", e),
+            });
+            return Err(CodexCliError::MaxRetriesExceeded);
+        }
+
+        // Exponential backoff
+        let backoff = Duration::from_millis(100 * 2_u64.pow(retries - 1));
+        tokio::time::sleep(backoff).await;
+        Ok(())
+    }
+
+    /// Single execution attempt on a spawned process
+    ///
+    /// `sent` is set once any of the attempt's output has reached the reader.
     async fn try_execute(
         &self,
+        mut child: Child,
         prompt: &str,
-        tx: mpsc::Sender<ConnectorMessage>,
+        tx: &mpsc::Sender<ConnectorMessage>,
+        sent: &Arc<AtomicBool>,
     ) -> Result<()> {
         let start = Instant::now();
 
-        // Spawn the CLI process
-        let mut child = self.spawn_process().await?;
-
         // Get stdin to send commands
         let mut stdin = child.stdin.take().ok_or_else(|| {
             CodexCliError::SpawnError("Failed to capture stdin".to_string())
@@ -168,32 +346,29 @@ impl CodexCliConnector {
         })?;
 
         // Spawn tasks to read stdout and stderr
-        let tx_stdout = tx.clone();
-        let stdout_task = tokio::spawn(async move {
-            Self::stream_output(stdout, tx_stdout).await
-        });
-
-        let tx_stderr = tx.clone();
-        let stderr_task = tokio::spawn(async move {
-            Self::stream_errors(stderr, tx_stderr).await
-        });
+        let stdout_task = tokio::spawn(Self::stream_output(stdout, tx.clone(), sent.clone()));
+        let stderr_task = tokio::spawn(Self::stream_errors(stderr, tx.clone(), sent.clone()));
 
         // Wait for process to complete with optional timeout
-        let wait_future = child.wait();
         let result = if let Some(timeout_ms) = self.config.timeout_ms {
-            timeout(Duration::from_millis(timeout_ms), wait_future)
-                .await
-                .map_err(|_| CodexCliError::Timeout)?
+            match timeout(Duration::from_millis(timeout_ms), child.wait()).await {
+                Ok(result) => result,
+                Err(_) => {
+                    // Children of the CLI may still hold its pipes open, so stop
+                    // reading rather than wait for EOF
+                    let _ = child.kill().await;
+                    stdout_task.abort();
+                    stderr_task.abort();
+                    return Err(CodexCliError::Timeout);
+                }
+            }
         } else {
-            wait_future.await
+            child.wait().await
         };
 
         // Wait for streaming tasks to complete
         let _ = tokio::join!(stdout_task, stderr_task);
 
-        // Send done message
-        let _ = tx.send(ConnectorMessage::Done).await;
-
         // Check exit status
         match result {
             Ok(status) if status.success() => {
@@ -228,19 +403,34 @@ impl CodexCliConnector {
             .stdin(Stdio::piped());
 
         // Spawn
-        cmd.spawn()
-            .map_err(|e| CodexCliError::SpawnError(e.to_string()))
+        cmd.spawn().map_err(|e| match ConnectorError::from_spawn(&e, &self.config.cli_path) {
+            ConnectorError::NotFound(path) => CodexCliError::NotFound(path),
+            _ => CodexCliError::SpawnError(e.to_string()),
+        })
     }
 
     /// Stream and parse stdout
     async fn stream_output<R: tokio::io::AsyncRead + Unpin>(
         reader: R,
         tx: mpsc::Sender<ConnectorMessage>,
+        sent: Arc<AtomicBool>,
     ) {
         let mut lines = BufReader::new(reader).lines();
+        let mut buffer = JsonLineBuffer::new();
 
         while let Ok(Some(line)) = lines.next_line().await {
-            if let Some(msg) = Self::parse_output_line(&line) {
+            for record in buffer.push(line) {
+                if let Some(msg) = Self::parse_output_line(&record) {
+                    sent.store(true, Ordering::Relaxed);
+                    let _ = tx.send(msg).await;
+                }
+            }
+        }
+
+        // Whatever never completed is passed through as-is
+        for record in buffer.flush() {
+            if let Some(msg) = Self::parse_output_line(&record) {
+                sent.store(true, Ordering::Relaxed);
                 let _ = tx.send(msg).await;
             }
         }
@@ -250,10 +440,12 @@ impl CodexCliConnector {
     async fn stream_errors<R: tokio::io::AsyncRead + Unpin>(
         reader: R,
         tx: mpsc::Sender<ConnectorMessage>,
+        sent: Arc<AtomicBool>,
     ) {
         let mut lines = BufReader::new(reader).lines();
 
         while let Ok(Some(line)) = lines.next_line().await {
+            sent.store(true, Ordering::Relaxed);
             let _ = tx.send(ConnectorMessage::Error {
                 message: line,
             }).await;
@@ -264,7 +456,7 @@ impl CodexCliConnector {
     fn parse_output_line(line: &str) -> Option<ConnectorMessage> {
         // Try to parse as JSON first (for structured output)
         if let Ok(msg) = serde_json::from_str::<ConnectorMessage>(line) {
-            return Some(msg);
+            return Some(msg.with_structured_args());
         }
 
         // Check for usage object from OpenAI format
@@ -281,6 +473,13 @@ impl CodexCliConnector {
             }
         }
 
+        // Banners and spinners aren't part of the answer
+        if is_cli_notice(line) {
+            return Some(ConnectorMessage::SystemNotice {
+                text: line.trim().to_string(),
+            });
+        }
+
         // Default: treat as content
         if !line.trim().is_empty() && !line.starts_with('/') {
             Some(ConnectorMessage::Content {
@@ -368,7 +567,7 @@ impl CodexCliConnector {
 
     /// Update health status
     async fn update_health(&self, health: ConnectorHealth) {
-        *self.health.lock().await = health;
+        self.health.lock().await.set(health);
     }
 
     /// Update token usage in metrics
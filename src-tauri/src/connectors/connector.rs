@@ -0,0 +1,148 @@
+use super::claude_code::ClaudeCodeConnector;
+use super::codex_cli::CodexCliConnector;
+use super::ollama::OllamaConnector;
+use super::types::{ConnectorConfig, ConnectorHealth, ConnectorMessage, ConnectorMetrics};
+use async_trait::async_trait;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+
+/// Common interface over the CLI and HTTP connectors
+///
+/// Errors are flattened to strings so connectors with different error types
+/// can be composed (e.g. in a fallback chain).
+#[async_trait]
+pub trait Connector: Send + Sync {
+    /// Connector identifier, e.g. `"claude_code"`
+    fn name(&self) -> &str;
+
+    /// Run a prompt and stream the responses
+    async fn execute(&self, prompt: &str) -> Result<mpsc::Receiver<ConnectorMessage>, String>;
+
+    /// Current health status
+    async fn health(&self) -> ConnectorHealth;
+
+    /// Current metrics
+    async fn metrics(&self) -> ConnectorMetrics;
+
+    /// Model prompts are sent to, if the connector chooses it
+    async fn model(&self) -> Option<String> {
+        None
+    }
+}
+
+/// Build a CLI connector of `connector_type` (`claude_code` or `codex_cli`) from `config`
+pub fn cli_connector(
+    connector_type: &str,
+    config: ConnectorConfig,
+) -> Result<Arc<dyn Connector>, String> {
+    match connector_type {
+        "claude_code" => Ok(Arc::new(ClaudeCodeConnector::new(config))),
+        "codex_cli" => Ok(Arc::new(CodexCliConnector::new(config))),
+        _ => Err(format!("Unknown CLI connector type: {}", connector_type)),
+    }
+}
+
+/// Source of embeddings for semantic recall, independent of any chat connector
+#[async_trait]
+pub trait EmbeddingConnector: Send + Sync {
+    /// Model the embeddings come from
+    fn embedding_model(&self) -> &str;
+
+    /// Embed `text`
+    async fn embed(&self, text: &str) -> Result<Vec<f32>, String>;
+
+    /// Fail unless `embedding_model` can be used, e.g. because it isn't pulled
+    async fn ensure_embedding_model(&self) -> Result<(), String>;
+}
+
+#[async_trait]
+impl Connector for ClaudeCodeConnector {
+    fn name(&self) -> &str {
+        "claude_code"
+    }
+
+    async fn execute(&self, prompt: &str) -> Result<mpsc::Receiver<ConnectorMessage>, String> {
+        ClaudeCodeConnector::execute(self, prompt)
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    async fn health(&self) -> ConnectorHealth {
+        ClaudeCodeConnector::health(self).await
+    }
+
+    async fn metrics(&self) -> ConnectorMetrics {
+        ClaudeCodeConnector::metrics(self).await
+    }
+
+    async fn model(&self) -> Option<String> {
+        ClaudeCodeConnector::model(self)
+    }
+}
+
+#[async_trait]
+impl Connector for CodexCliConnector {
+    fn name(&self) -> &str {
+        "codex_cli"
+    }
+
+    async fn execute(&self, prompt: &str) -> Result<mpsc::Receiver<ConnectorMessage>, String> {
+        CodexCliConnector::execute(self, prompt)
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    async fn health(&self) -> ConnectorHealth {
+        CodexCliConnector::health(self).await
+    }
+
+    async fn metrics(&self) -> ConnectorMetrics {
+        CodexCliConnector::metrics(self).await
+    }
+
+    async fn model(&self) -> Option<String> {
+        Some(self.current_model().await.as_str().to_string())
+    }
+}
+
+#[async_trait]
+impl Connector for OllamaConnector {
+    fn name(&self) -> &str {
+        "ollama"
+    }
+
+    async fn execute(&self, prompt: &str) -> Result<mpsc::Receiver<ConnectorMessage>, String> {
+        self.chat(prompt).await.map_err(|e| e.to_string())
+    }
+
+    async fn health(&self) -> ConnectorHealth {
+        OllamaConnector::health(self).await
+    }
+
+    async fn metrics(&self) -> ConnectorMetrics {
+        OllamaConnector::metrics(self).await
+    }
+
+    async fn model(&self) -> Option<String> {
+        Some(self.config().chat_model.clone())
+    }
+}
+
+#[async_trait]
+impl EmbeddingConnector for OllamaConnector {
+    fn embedding_model(&self) -> &str {
+        &self.config().embedding_model
+    }
+
+    async fn embed(&self, text: &str) -> Result<Vec<f32>, String> {
+        OllamaConnector::embed(self, text)
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    async fn ensure_embedding_model(&self) -> Result<(), String> {
+        self.ensure_model_available(&self.config().embedding_model)
+            .await
+            .map_err(|e| e.to_string())
+    }
+}
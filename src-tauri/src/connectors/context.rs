@@ -0,0 +1,122 @@
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::sync::Arc;
+
+/// Counts tokens the way a model's tokenizer would, for context-limit checks
+pub trait TokenCounter: Send + Sync {
+    fn count(&self, text: &str) -> usize;
+}
+
+/// Rough count of one token per four characters, the usual rule of thumb
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ApproxTokenCounter;
+
+impl TokenCounter for ApproxTokenCounter {
+    fn count(&self, text: &str) -> usize {
+        (text.chars().count() + 3) / 4
+    }
+}
+
+/// The counter used when none is injected
+pub fn default_token_counter() -> Arc<dyn TokenCounter> {
+    Arc::new(ApproxTokenCounter)
+}
+
+/// What to do with a prompt over the connector's `max_context_tokens`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ContextPolicy {
+    /// Fail before anything is sent
+    #[default]
+    Reject,
+    /// Drop the start of the prompt, keeping the most recent text
+    TruncateHead,
+    /// Drop the end of the prompt
+    TruncateTail,
+}
+
+/// A prompt that doesn't fit and wasn't allowed to be truncated
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("Prompt is {tokens} tokens, over the {limit} token context limit")]
+pub struct ContextLimitExceeded {
+    pub tokens: usize,
+    pub limit: usize,
+}
+
+/// Apply `policy` so `prompt` fits in `limit` tokens (no limit leaves it as is)
+///
+/// Truncation cuts on character boundaries and keeps the longest slice the
+/// counter puts at or under the limit.
+pub fn fit_prompt<'a>(
+    prompt: &'a str,
+    limit: Option<usize>,
+    policy: ContextPolicy,
+    counter: &dyn TokenCounter,
+) -> Result<Cow<'a, str>, ContextLimitExceeded> {
+    let Some(limit) = limit else {
+        return Ok(Cow::Borrowed(prompt));
+    };
+    let tokens = counter.count(prompt);
+    if tokens <= limit {
+        return Ok(Cow::Borrowed(prompt));
+    }
+
+    let mut cuts: Vec<usize> = prompt.char_indices().map(|(i, _)| i).collect();
+    cuts.push(prompt.len());
+
+    let fitted = match policy {
+        ContextPolicy::Reject => return Err(ContextLimitExceeded { tokens, limit }),
+        ContextPolicy::TruncateTail => {
+            // Longest prefix that fits
+            let keep = cuts.partition_point(|&end| counter.count(&prompt[..end]) <= limit);
+            &prompt[..cuts[keep.saturating_sub(1)]]
+        }
+        ContextPolicy::TruncateHead => {
+            // Longest suffix that fits
+            let skip = cuts.partition_point(|&start| counter.count(&prompt[start..]) > limit);
+            &prompt[cuts[skip.min(cuts.len() - 1)]..]
+        }
+    };
+
+    tracing::warn!(
+        tokens,
+        limit,
+        ?policy,
+        "Prompt over context limit, truncated to {} tokens",
+        counter.count(fitted)
+    );
+    Ok(Cow::Owned(fitted.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fit_prompt_policies() {
+        let counter = ApproxTokenCounter;
+        let prompt = "first part of the prompt ... last part of the prompt";
+        assert_eq!(counter.count(prompt), 13);
+
+        let rejected = fit_prompt(prompt, Some(5), ContextPolicy::Reject, &counter);
+        assert_eq!(rejected, Err(ContextLimitExceeded { tokens: 13, limit: 5 }));
+
+        let tail = fit_prompt(prompt, Some(5), ContextPolicy::TruncateTail, &counter).unwrap();
+        assert!(counter.count(&tail) <= 5);
+        assert!(tail.starts_with("first part"));
+
+        let head = fit_prompt(prompt, Some(5), ContextPolicy::TruncateHead, &counter).unwrap();
+        assert!(counter.count(&head) <= 5);
+        assert!(head.ends_with("of the prompt"));
+
+        // Prompts under the limit, or with no limit, pass through untouched
+        assert!(matches!(
+            fit_prompt(prompt, Some(100), ContextPolicy::Reject, &counter),
+            Ok(Cow::Borrowed(_))
+        ));
+        assert!(matches!(
+            fit_prompt(prompt, None, ContextPolicy::Reject, &counter),
+            Ok(Cow::Borrowed(_))
+        ));
+    }
+}
@@ -0,0 +1,74 @@
+/// Connector failure sorted by whether trying again could help
+///
+/// Each connector keeps its own error type; converting it into this one lets
+/// retry loops and callers make the same call everywhere.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ConnectorError {
+    /// The CLI binary or model doesn't exist
+    #[error("Not found: {0}")]
+    NotFound(String),
+    /// The process couldn't be started
+    #[error("Failed to spawn: {0}")]
+    Spawn(String),
+    /// The request itself is bad, e.g. HTTP 4xx or over the context limit
+    #[error("Request rejected: {0}")]
+    Rejected(String),
+    /// No answer in time
+    #[error("Timed out: {0}")]
+    Timeout(String),
+    /// The server failed, e.g. HTTP 5xx
+    #[error("Server error: {0}")]
+    Server(String),
+    /// IO errors, dropped connections and crashed processes
+    #[error("{0}")]
+    Transient(String),
+}
+
+impl ConnectorError {
+    /// Classify an unsuccessful HTTP status
+    pub fn from_status(status: u16, message: impl Into<String>) -> Self {
+        let message = message.into();
+        match status {
+            404 => ConnectorError::NotFound(message),
+            400..=499 => ConnectorError::Rejected(message),
+            500..=599 => ConnectorError::Server(message),
+            _ => ConnectorError::Transient(message),
+        }
+    }
+
+    /// Classify a failure to start a process
+    pub fn from_spawn(error: &std::io::Error, program: &str) -> Self {
+        match error.kind() {
+            std::io::ErrorKind::NotFound => ConnectorError::NotFound(program.to_string()),
+            _ => ConnectorError::Spawn(format!("{}: {}", program, error)),
+        }
+    }
+
+    /// Whether the same request might succeed if sent again
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            ConnectorError::Timeout(_) | ConnectorError::Server(_) | ConnectorError::Transient(_)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retryability() {
+        assert!(!ConnectorError::from_status(400, "bad request").is_retryable());
+        assert!(!ConnectorError::from_status(404, "no such model").is_retryable());
+        assert!(ConnectorError::from_status(503, "overloaded").is_retryable());
+
+        let missing = std::io::Error::from(std::io::ErrorKind::NotFound);
+        assert_eq!(
+            ConnectorError::from_spawn(&missing, "claude"),
+            ConnectorError::NotFound("claude".to_string())
+        );
+        assert!(!ConnectorError::from_spawn(&missing, "claude").is_retryable());
+        assert!(ConnectorError::Timeout("no response".to_string()).is_retryable());
+    }
+}
@@ -0,0 +1,291 @@
+use super::connector::Connector;
+use super::types::{ConnectorHealth, ConnectorMessage, ConnectorMetrics};
+use async_trait::async_trait;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use tokio::sync::{mpsc, oneshot};
+
+/// Session key used for requests that don't name a session
+const DEFAULT_SESSION: &str = "";
+
+/// Waiting requests, grouped by session and served round-robin
+#[derive(Default)]
+struct SchedulerState {
+    in_flight: usize,
+    waiting: HashMap<String, VecDeque<oneshot::Sender<()>>>,
+    /// Sessions with waiting requests, in the order they get their next turn
+    turns: VecDeque<String>,
+}
+
+impl SchedulerState {
+    fn enqueue(&mut self, session_id: &str, waiter: oneshot::Sender<()>) {
+        let queue = self.waiting.entry(session_id.to_string()).or_default();
+        if queue.is_empty() {
+            self.turns.push_back(session_id.to_string());
+        }
+        queue.push_back(waiter);
+    }
+
+    /// Hand a freed slot to the next session in turn, or release it if nobody waits
+    fn release(&mut self) {
+        while let Some(session_id) = self.turns.pop_front() {
+            let Some(queue) = self.waiting.get_mut(&session_id) else {
+                continue;
+            };
+            let waiter = queue.pop_front();
+            if queue.is_empty() {
+                self.waiting.remove(&session_id);
+            } else {
+                self.turns.push_back(session_id);
+            }
+
+            // A waiter that gave up is skipped; the slot goes to the next one
+            if let Some(waiter) = waiter {
+                if waiter.send(()).is_ok() {
+                    return;
+                }
+            }
+        }
+        self.in_flight -= 1;
+    }
+
+    fn queued(&self) -> usize {
+        self.waiting.values().map(VecDeque::len).sum()
+    }
+}
+
+/// Releases a scheduler slot when the request's stream ends
+struct SlotGuard {
+    state: Arc<Mutex<SchedulerState>>,
+}
+
+impl Drop for SlotGuard {
+    fn drop(&mut self) {
+        self.state.lock().unwrap().release();
+    }
+}
+
+/// A queued request's place in line
+///
+/// If the request stops waiting after a slot was handed to it, dropping this
+/// passes the slot on instead of leaking it.
+struct Waiter {
+    rx: oneshot::Receiver<()>,
+    state: Arc<Mutex<SchedulerState>>,
+}
+
+impl Drop for Waiter {
+    fn drop(&mut self) {
+        // Refuse further hand-offs, then pass on one that already arrived
+        self.rx.close();
+        if self.rx.try_recv().is_ok() {
+            self.state.lock().unwrap().release();
+        }
+    }
+}
+
+/// Limits concurrent requests to a shared connector and interleaves waiting
+/// requests round-robin by session, so one chatty session can't starve others
+pub struct FairScheduler {
+    inner: Arc<dyn Connector>,
+    max_concurrent: usize,
+    // std Mutex: slots are released from `Drop`, which can't await
+    state: Arc<Mutex<SchedulerState>>,
+}
+
+impl FairScheduler {
+    /// Wrap `inner`, allowing at most `max_concurrent` requests at once
+    pub fn new(inner: Arc<dyn Connector>, max_concurrent: usize) -> Self {
+        Self {
+            inner,
+            max_concurrent: max_concurrent.max(1),
+            state: Arc::new(Mutex::new(SchedulerState::default())),
+        }
+    }
+
+    /// Number of requests currently running
+    pub fn in_flight(&self) -> usize {
+        self.state.lock().unwrap().in_flight
+    }
+
+    /// Number of requests waiting for a slot
+    pub fn queued(&self) -> usize {
+        self.state.lock().unwrap().queued()
+    }
+
+    /// Run a prompt on behalf of `session_id` once it gets a fair turn
+    ///
+    /// The slot is held until the returned stream finishes or is dropped.
+    pub async fn execute_for(
+        &self,
+        session_id: &str,
+        prompt: &str,
+    ) -> Result<mpsc::Receiver<ConnectorMessage>, String> {
+        let guard = self.acquire(session_id).await?;
+        let mut inner_rx = self.inner.execute(prompt).await?;
+
+        let (tx, rx) = mpsc::channel(100);
+        tokio::spawn(async move {
+            let _guard = guard;
+            while let Some(msg) = inner_rx.recv().await {
+                if tx.send(msg).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(rx)
+    }
+
+    /// Wait for a free slot, queueing behind other sessions if needed
+    async fn acquire(&self, session_id: &str) -> Result<SlotGuard, String> {
+        let waiter = {
+            let mut state = self.state.lock().unwrap();
+            if state.in_flight < self.max_concurrent && state.turns.is_empty() {
+                state.in_flight += 1;
+                None
+            } else {
+                let (tx, rx) = oneshot::channel();
+                state.enqueue(session_id, tx);
+                Some(Waiter {
+                    rx,
+                    state: self.state.clone(),
+                })
+            }
+        };
+
+        if let Some(mut waiter) = waiter {
+            (&mut waiter.rx)
+                .await
+                .map_err(|_| "Scheduler dropped queued request".to_string())?;
+        }
+
+        Ok(SlotGuard {
+            state: self.state.clone(),
+        })
+    }
+}
+
+#[async_trait]
+impl Connector for FairScheduler {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    async fn execute(&self, prompt: &str) -> Result<mpsc::Receiver<ConnectorMessage>, String> {
+        self.execute_for(DEFAULT_SESSION, prompt).await
+    }
+
+    async fn health(&self) -> ConnectorHealth {
+        self.inner.health().await
+    }
+
+    async fn metrics(&self) -> ConnectorMetrics {
+        self.inner.metrics().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    /// Records prompts in the order they start and holds each stream open briefly
+    struct RecordingConnector {
+        started: Arc<Mutex<Vec<String>>>,
+    }
+
+    #[async_trait]
+    impl Connector for RecordingConnector {
+        fn name(&self) -> &str {
+            "recording"
+        }
+
+        async fn execute(&self, prompt: &str) -> Result<mpsc::Receiver<ConnectorMessage>, String> {
+            self.started.lock().unwrap().push(prompt.to_string());
+            let (tx, rx) = mpsc::channel(1);
+            tokio::spawn(async move {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                let _ = tx.send(ConnectorMessage::Done).await;
+            });
+            Ok(rx)
+        }
+
+        async fn health(&self) -> ConnectorHealth {
+            ConnectorHealth::Healthy
+        }
+
+        async fn metrics(&self) -> ConnectorMetrics {
+            ConnectorMetrics::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_bursts_from_two_sessions_are_interleaved() {
+        let started = Arc::new(Mutex::new(Vec::new()));
+        let scheduler = Arc::new(FairScheduler::new(
+            Arc::new(RecordingConnector {
+                started: started.clone(),
+            }),
+            1,
+        ));
+
+        // Session A bursts first, then session B
+        let mut tasks = Vec::new();
+        for (session, count) in [("a", 4), ("b", 4)] {
+            for i in 0..count {
+                let scheduler = scheduler.clone();
+                tasks.push(tokio::spawn(async move {
+                    let mut rx = scheduler
+                        .execute_for(session, &format!("{}{}", session, i))
+                        .await
+                        .unwrap();
+                    while rx.recv().await.is_some() {}
+                }));
+            }
+        }
+        for task in tasks {
+            task.await.unwrap();
+        }
+
+        let order: Vec<String> = started.lock().unwrap().clone();
+        assert_eq!(order.len(), 8);
+
+        // Session B gets a turn long before session A has drained
+        let first_b = order.iter().position(|p| p.starts_with('b')).unwrap();
+        assert!(first_b <= 2, "order was {:?}", order);
+
+        // No session runs more than twice in a row
+        let runs_of_three = order
+            .windows(3)
+            .filter(|w| w.iter().all(|p| p[..1] == w[0][..1]))
+            .count();
+        assert_eq!(runs_of_three, 0, "order was {:?}", order);
+
+        assert_eq!(scheduler.in_flight(), 0);
+        assert_eq!(scheduler.queued(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_slot_handed_to_abandoned_waiter_is_released() {
+        let scheduler = FairScheduler::new(
+            Arc::new(RecordingConnector {
+                started: Arc::new(Mutex::new(Vec::new())),
+            }),
+            1,
+        );
+
+        let held = scheduler.acquire("a").await.unwrap();
+        let mut waiting = Box::pin(scheduler.acquire("b"));
+        assert!(futures::poll!(&mut waiting).is_pending());
+        assert_eq!(scheduler.queued(), 1);
+
+        // The slot is handed to "b", which gives up before it ever wakes
+        drop(held);
+        assert_eq!(scheduler.in_flight(), 1);
+        drop(waiting);
+
+        assert_eq!(scheduler.in_flight(), 0);
+        assert_eq!(scheduler.queued(), 0);
+    }
+}
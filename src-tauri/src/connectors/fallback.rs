@@ -0,0 +1,326 @@
+use super::connector::Connector;
+use super::types::{ConnectorHealth, ConnectorMessage, ConnectorMetrics, DEFAULT_CHANNEL_CAPACITY};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::sync::{mpsc, Mutex};
+use tracing::warn;
+
+/// Which connectors in a fallback chain served or failed requests
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FallbackMetrics {
+    /// Successful requests per connector name
+    pub served_by: HashMap<String, u64>,
+    /// Failed attempts per connector name
+    pub failures: HashMap<String, u64>,
+    /// Connector that served the most recent successful request
+    pub last_served_by: Option<String>,
+}
+
+/// Tries an ordered list of connectors until one accepts the prompt
+///
+/// Unhealthy connectors are tried only after the rest, and a connector whose
+/// stream opens with an error counts as failed, so the next one is tried.
+pub struct FallbackConnector {
+    connectors: Vec<Box<dyn Connector>>,
+    metrics: Arc<Mutex<ConnectorMetrics>>,
+    fallback_metrics: Arc<Mutex<FallbackMetrics>>,
+}
+
+impl FallbackConnector {
+    /// Create a chain that tries `connectors` in order
+    pub fn new(connectors: Vec<Box<dyn Connector>>) -> Self {
+        Self {
+            connectors,
+            metrics: Arc::new(Mutex::new(ConnectorMetrics::default())),
+            fallback_metrics: Arc::new(Mutex::new(FallbackMetrics::default())),
+        }
+    }
+
+    /// Names of the wrapped connectors, in the order they are tried
+    pub fn connector_names(&self) -> Vec<String> {
+        self.connectors.iter().map(|c| c.name().to_string()).collect()
+    }
+
+    /// Get per-connector success and failure counts
+    pub async fn fallback_metrics(&self) -> FallbackMetrics {
+        self.fallback_metrics.lock().await.clone()
+    }
+
+    /// Wrapped connectors in the order to try them: configured order, except
+    /// that unhealthy ones go last so they are only a last resort
+    async fn in_try_order(&self) -> Vec<&dyn Connector> {
+        let mut ready = Vec::new();
+        let mut unhealthy = Vec::new();
+        for connector in &self.connectors {
+            match connector.health().await {
+                ConnectorHealth::Unhealthy { .. } => unhealthy.push(connector.as_ref()),
+                _ => ready.push(connector.as_ref()),
+            }
+        }
+        ready.extend(unhealthy);
+        ready
+    }
+
+    /// Start `connector` on `prompt`, counting a stream that opens with an
+    /// `Error` (or ends before any message) as a failed attempt
+    async fn open(
+        connector: &dyn Connector,
+        prompt: &str,
+    ) -> Result<mpsc::Receiver<ConnectorMessage>, String> {
+        let mut rx = connector.execute(prompt).await?;
+        let first = match rx.recv().await {
+            Some(ConnectorMessage::Error { message }) => return Err(message),
+            Some(first) => first,
+            None => return Err("Stream ended without output".to_string()),
+        };
+
+        // Hand back the message we looked at, followed by the rest of the stream
+        let (tx, relay) = mpsc::channel(DEFAULT_CHANNEL_CAPACITY);
+        tokio::spawn(async move {
+            let mut next = Some(first);
+            while let Some(msg) = next {
+                if tx.send(msg).await.is_err() {
+                    break;
+                }
+                next = rx.recv().await;
+            }
+        });
+        Ok(relay)
+    }
+
+    /// Update metrics after a request
+    async fn update_metrics(&self, success: bool, elapsed_ms: f64) {
+        let mut metrics = self.metrics.lock().await;
+        metrics.spawn_count += 1;
+        if success {
+            metrics.success_count += 1;
+        } else {
+            metrics.error_count += 1;
+        }
+
+        let n = metrics.spawn_count as f64;
+        metrics.avg_response_time_ms =
+            (metrics.avg_response_time_ms * (n - 1.0) + elapsed_ms) / n;
+    }
+}
+
+#[async_trait]
+impl Connector for FallbackConnector {
+    fn name(&self) -> &str {
+        "fallback"
+    }
+
+    async fn execute(&self, prompt: &str) -> Result<mpsc::Receiver<ConnectorMessage>, String> {
+        let start = Instant::now();
+        let mut errors = Vec::new();
+
+        for connector in self.in_try_order().await {
+            match Self::open(connector, prompt).await {
+                Ok(rx) => {
+                    let mut fallback = self.fallback_metrics.lock().await;
+                    *fallback.served_by.entry(connector.name().to_string()).or_insert(0) += 1;
+                    fallback.last_served_by = Some(connector.name().to_string());
+                    drop(fallback);
+
+                    self.update_metrics(true, start.elapsed().as_millis() as f64).await;
+                    return Ok(rx);
+                }
+                Err(e) => {
+                    warn!("Connector {} failed, trying next: {}", connector.name(), e);
+                    *self
+                        .fallback_metrics
+                        .lock()
+                        .await
+                        .failures
+                        .entry(connector.name().to_string())
+                        .or_insert(0) += 1;
+                    errors.push(format!("{}: {}", connector.name(), e));
+                }
+            }
+        }
+
+        self.update_metrics(false, start.elapsed().as_millis() as f64).await;
+        if errors.is_empty() {
+            Err("No connectors configured".to_string())
+        } else {
+            Err(format!("All connectors failed: {}", errors.join("; ")))
+        }
+    }
+
+    /// Healthy if any wrapped connector is healthy, otherwise the best available status
+    async fn health(&self) -> ConnectorHealth {
+        let mut best: Option<ConnectorHealth> = None;
+
+        for connector in &self.connectors {
+            match connector.health().await {
+                ConnectorHealth::Healthy => return ConnectorHealth::Healthy,
+                degraded @ ConnectorHealth::Degraded { .. } => {
+                    if !matches!(best, Some(ConnectorHealth::Degraded { .. })) {
+                        best = Some(degraded);
+                    }
+                }
+                unhealthy @ ConnectorHealth::Unhealthy { .. } => {
+                    if best.is_none() {
+                        best = Some(unhealthy);
+                    }
+                }
+            }
+        }
+
+        best.unwrap_or(ConnectorHealth::Unhealthy {
+            reason: "No connectors configured".to_string(),
+        })
+    }
+
+    async fn metrics(&self) -> ConnectorMetrics {
+        self.metrics.lock().await.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct FailingConnector;
+
+    #[async_trait]
+    impl Connector for FailingConnector {
+        fn name(&self) -> &str {
+            "failing"
+        }
+
+        async fn execute(&self, _prompt: &str) -> Result<mpsc::Receiver<ConnectorMessage>, String> {
+            Err("binary not found".to_string())
+        }
+
+        async fn health(&self) -> ConnectorHealth {
+            ConnectorHealth::Unhealthy {
+                reason: "binary not found".to_string(),
+            }
+        }
+
+        async fn metrics(&self) -> ConnectorMetrics {
+            ConnectorMetrics::default()
+        }
+    }
+
+    /// Healthy, and accepts the prompt only to report an error on the stream
+    struct ErrorStreamConnector;
+
+    #[async_trait]
+    impl Connector for ErrorStreamConnector {
+        fn name(&self) -> &str {
+            "error_stream"
+        }
+
+        async fn execute(&self, _prompt: &str) -> Result<mpsc::Receiver<ConnectorMessage>, String> {
+            let (tx, rx) = mpsc::channel(10);
+            tx.send(ConnectorMessage::Error {
+                message: "process exited with code 1".to_string(),
+            })
+            .await
+            .unwrap();
+            tx.send(ConnectorMessage::Done).await.unwrap();
+            Ok(rx)
+        }
+
+        async fn health(&self) -> ConnectorHealth {
+            ConnectorHealth::Healthy
+        }
+
+        async fn metrics(&self) -> ConnectorMetrics {
+            ConnectorMetrics::default()
+        }
+    }
+
+    struct CannedConnector;
+
+    #[async_trait]
+    impl Connector for CannedConnector {
+        fn name(&self) -> &str {
+            "canned"
+        }
+
+        async fn execute(&self, prompt: &str) -> Result<mpsc::Receiver<ConnectorMessage>, String> {
+            let (tx, rx) = mpsc::channel(10);
+            tx.send(ConnectorMessage::Content {
+                content: format!("echo: {}", prompt),
+            })
+            .await
+            .unwrap();
+            tx.send(ConnectorMessage::Done).await.unwrap();
+            Ok(rx)
+        }
+
+        async fn health(&self) -> ConnectorHealth {
+            ConnectorHealth::Healthy
+        }
+
+        async fn metrics(&self) -> ConnectorMetrics {
+            ConnectorMetrics::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fallback_uses_next_connector() {
+        let fallback = FallbackConnector::new(vec![
+            Box::new(FailingConnector),
+            Box::new(CannedConnector),
+        ]);
+        assert_eq!(fallback.connector_names(), vec!["failing", "canned"]);
+
+        let mut rx = fallback.execute("hi").await.unwrap();
+        assert_eq!(
+            rx.recv().await,
+            Some(ConnectorMessage::Content {
+                content: "echo: hi".to_string()
+            })
+        );
+        assert_eq!(rx.recv().await, Some(ConnectorMessage::Done));
+
+        // "failing" reports itself unhealthy, so the healthy "canned" goes first
+        let stats = fallback.fallback_metrics().await;
+        assert_eq!(stats.served_by.get("canned"), Some(&1));
+        assert_eq!(stats.failures.get("failing"), None);
+        assert_eq!(stats.last_served_by.as_deref(), Some("canned"));
+
+        let metrics = fallback.metrics().await;
+        assert_eq!(metrics.success_count, 1);
+        assert_eq!(fallback.health().await, ConnectorHealth::Healthy);
+    }
+
+    #[tokio::test]
+    async fn test_fallback_reports_all_failures() {
+        let fallback = FallbackConnector::new(vec![Box::new(FailingConnector)]);
+
+        let err = fallback.execute("hi").await.unwrap_err();
+        assert!(err.contains("failing: binary not found"));
+        assert_eq!(fallback.metrics().await.error_count, 1);
+        assert!(matches!(fallback.health().await, ConnectorHealth::Unhealthy { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_leading_stream_error_falls_back() {
+        let fallback = FallbackConnector::new(vec![
+            Box::new(ErrorStreamConnector),
+            Box::new(CannedConnector),
+        ]);
+
+        let mut rx = fallback.execute("hi").await.unwrap();
+        assert_eq!(
+            rx.recv().await,
+            Some(ConnectorMessage::Content {
+                content: "echo: hi".to_string()
+            })
+        );
+        assert_eq!(rx.recv().await, Some(ConnectorMessage::Done));
+        assert_eq!(rx.recv().await, None);
+
+        let stats = fallback.fallback_metrics().await;
+        assert_eq!(stats.failures.get("error_stream"), Some(&1));
+        assert_eq!(stats.last_served_by.as_deref(), Some("canned"));
+    }
+}
@@ -0,0 +1,138 @@
+use super::types::ConnectorHealth;
+use serde::{Deserialize, Serialize};
+
+/// Consecutive failures before a healthy connector is reported as failing
+pub const DEFAULT_FAILURES_TO_DEGRADE: u32 = 3;
+
+/// Consecutive successes before a failing connector is reported healthy again
+pub const DEFAULT_SUCCESSES_TO_RECOVER: u32 = 2;
+
+/// How many outcomes in a row it takes to change a connector's health
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HealthThresholds {
+    pub failures_to_degrade: u32,
+    pub successes_to_recover: u32,
+}
+
+impl Default for HealthThresholds {
+    fn default() -> Self {
+        Self {
+            failures_to_degrade: DEFAULT_FAILURES_TO_DEGRADE,
+            successes_to_recover: DEFAULT_SUCCESSES_TO_RECOVER,
+        }
+    }
+}
+
+/// Connector health with hysteresis, so one stray result doesn't flip it
+///
+/// Request outcomes go through `record_success` and `record_failure`; an
+/// explicit probe (warmup, health check) is authoritative and uses `set`.
+#[derive(Debug, Clone)]
+pub struct HealthTracker {
+    thresholds: HealthThresholds,
+    status: ConnectorHealth,
+    consecutive_failures: u32,
+    consecutive_successes: u32,
+}
+
+impl HealthTracker {
+    /// A healthy tracker; thresholds of 0 behave like 1
+    pub fn new(thresholds: HealthThresholds) -> Self {
+        Self {
+            thresholds,
+            status: ConnectorHealth::Healthy,
+            consecutive_failures: 0,
+            consecutive_successes: 0,
+        }
+    }
+
+    /// Current health
+    pub fn status(&self) -> ConnectorHealth {
+        self.status.clone()
+    }
+
+    /// Count a successful call; recovers after `successes_to_recover` in a row
+    pub fn record_success(&mut self) {
+        self.consecutive_failures = 0;
+        self.consecutive_successes = self.consecutive_successes.saturating_add(1);
+        if self.status != ConnectorHealth::Healthy
+            && self.consecutive_successes >= self.thresholds.successes_to_recover.max(1)
+        {
+            self.transition(ConnectorHealth::Healthy);
+        }
+    }
+
+    /// Count a failed call, reported as `failed` once `failures_to_degrade` happen in a row
+    pub fn record_failure(&mut self, failed: ConnectorHealth) {
+        self.consecutive_successes = 0;
+        self.consecutive_failures = self.consecutive_failures.saturating_add(1);
+        if self.consecutive_failures >= self.thresholds.failures_to_degrade.max(1) {
+            self.transition(failed);
+        }
+    }
+
+    /// Set health outright, e.g. from a probe, and restart both streaks
+    pub fn set(&mut self, health: ConnectorHealth) {
+        self.consecutive_failures = 0;
+        self.consecutive_successes = 0;
+        self.transition(health);
+    }
+
+    fn transition(&mut self, health: ConnectorHealth) {
+        if std::mem::discriminant(&self.status) != std::mem::discriminant(&health) {
+            tracing::info!(from = ?self.status, to = ?health, "Connector health changed");
+        }
+        self.status = health;
+    }
+}
+
+impl Default for HealthTracker {
+    fn default() -> Self {
+        Self::new(HealthThresholds::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn degraded() -> ConnectorHealth {
+        ConnectorHealth::Degraded {
+            reason: "call failed".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_health_hysteresis() {
+        let mut tracker = HealthTracker::new(HealthThresholds {
+            failures_to_degrade: 3,
+            successes_to_recover: 2,
+        });
+
+        // A single failure amid successes doesn't flip health
+        tracker.record_success();
+        tracker.record_failure(degraded());
+        tracker.record_success();
+        tracker.record_failure(degraded());
+        tracker.record_failure(degraded());
+        assert_eq!(tracker.status(), ConnectorHealth::Healthy);
+
+        // A sustained run of failures does
+        tracker.record_failure(degraded());
+        assert_eq!(tracker.status(), degraded());
+
+        // Recovery needs consecutive successes too
+        tracker.record_success();
+        tracker.record_failure(degraded());
+        tracker.record_success();
+        assert_eq!(tracker.status(), degraded());
+        tracker.record_success();
+        assert_eq!(tracker.status(), ConnectorHealth::Healthy);
+
+        // Probes are authoritative
+        tracker.set(ConnectorHealth::Unhealthy {
+            reason: "binary missing".to_string(),
+        });
+        assert!(matches!(tracker.status(), ConnectorHealth::Unhealthy { .. }));
+    }
+}
@@ -0,0 +1,138 @@
+/// Maximum bytes held while waiting for a split JSON object to complete
+const DEFAULT_MAX_PENDING_BYTES: usize = 64 * 1024;
+
+/// Reassembles JSON objects that a CLI flushed across several output lines.
+///
+/// Lines that are not the start of an incomplete JSON object pass straight
+/// through. Once a line opens an object that does not parse on its own,
+/// subsequent lines are accumulated until the joined text parses. If the
+/// buffer grows too large, a blank line arrives, or a new self-contained
+/// object starts, the pending lines are released unchanged.
+#[derive(Debug)]
+pub struct JsonLineBuffer {
+    pending: Vec<String>,
+    pending_bytes: usize,
+    max_pending_bytes: usize,
+}
+
+impl JsonLineBuffer {
+    /// Create a new buffer with the default size limit
+    pub fn new() -> Self {
+        Self::with_max_pending_bytes(DEFAULT_MAX_PENDING_BYTES)
+    }
+
+    /// Create a new buffer with a custom size limit
+    pub fn with_max_pending_bytes(max_pending_bytes: usize) -> Self {
+        Self {
+            pending: Vec::new(),
+            pending_bytes: 0,
+            max_pending_bytes,
+        }
+    }
+
+    /// Feed a line, returning any records that are ready to be parsed
+    pub fn push(&mut self, line: String) -> Vec<String> {
+        if self.pending.is_empty() {
+            if Self::is_incomplete_object(&line) {
+                self.pending_bytes = line.len();
+                self.pending.push(line);
+                return Vec::new();
+            }
+            return vec![line];
+        }
+
+        // A blank line or a new complete object ends the pending record
+        if line.trim().is_empty() || Self::is_complete_object(&line) {
+            let mut ready = self.flush();
+            ready.push(line);
+            return ready;
+        }
+
+        self.pending_bytes += line.len();
+        self.pending.push(line);
+
+        let joined = self.pending.concat();
+        if Self::is_complete_object(&joined) {
+            self.pending.clear();
+            self.pending_bytes = 0;
+            return vec![joined];
+        }
+
+        if self.pending_bytes > self.max_pending_bytes {
+            return self.flush();
+        }
+
+        Vec::new()
+    }
+
+    /// Release any pending lines unchanged (e.g. at end of stream)
+    pub fn flush(&mut self) -> Vec<String> {
+        self.pending_bytes = 0;
+        std::mem::take(&mut self.pending)
+    }
+
+    /// Whether a record is being accumulated
+    pub fn has_pending(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    fn is_incomplete_object(line: &str) -> bool {
+        line.trim_start().starts_with('{') && !Self::is_complete_object(line)
+    }
+
+    fn is_complete_object(text: &str) -> bool {
+        matches!(
+            serde_json::from_str::<serde_json::Value>(text),
+            Ok(serde_json::Value::Object(_))
+        )
+    }
+}
+
+impl Default for JsonLineBuffer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plain_lines_pass_through() {
+        let mut buffer = JsonLineBuffer::new();
+        assert_eq!(buffer.push("hello".to_string()), vec!["hello".to_string()]);
+        assert!(!buffer.has_pending());
+    }
+
+    #[test]
+    fn test_split_object_is_reassembled() {
+        let mut buffer = JsonLineBuffer::new();
+
+        assert!(buffer.push(r#"{"type":"content","#.to_string()).is_empty());
+        assert!(buffer.has_pending());
+
+        let ready = buffer.push(r#""content":"Hello world"}"#.to_string());
+        assert_eq!(ready, vec![r#"{"type":"content","content":"Hello world"}"#.to_string()]);
+        assert!(!buffer.has_pending());
+    }
+
+    #[test]
+    fn test_blank_line_releases_pending() {
+        let mut buffer = JsonLineBuffer::new();
+
+        buffer.push("{ not json".to_string());
+        let ready = buffer.push(String::new());
+        assert_eq!(ready, vec!["{ not json".to_string(), String::new()]);
+    }
+
+    #[test]
+    fn test_oversized_pending_is_released() {
+        let mut buffer = JsonLineBuffer::with_max_pending_bytes(16);
+
+        buffer.push("{ \"a\": \"".to_string());
+        let ready = buffer.push("this line is far too long".to_string());
+        assert_eq!(ready.len(), 2);
+        assert!(!buffer.has_pending());
+    }
+}
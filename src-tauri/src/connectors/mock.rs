@@ -0,0 +1,190 @@
+use super::connector::Connector;
+use super::health::{HealthThresholds, HealthTracker};
+use super::types::{ConnectorHealth, ConnectorMessage, ConnectorMetrics};
+use async_trait::async_trait;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{mpsc, Mutex};
+
+/// Connector that replays a scripted stream, for tests and UI development
+///
+/// Works on every platform without spawning processes or HTTP servers.
+pub struct MockConnector {
+    name: String,
+    model: Option<String>,
+    script: Vec<ConnectorMessage>,
+    delay: Duration,
+    fail_first: u32,
+    error_message: String,
+    calls: AtomicU32,
+    /// Every prompt received, in call order
+    prompts: Mutex<Vec<String>>,
+    metrics: Arc<Mutex<ConnectorMetrics>>,
+    health: Arc<Mutex<HealthTracker>>,
+}
+
+impl MockConnector {
+    /// Create a mock that replays `script` on every call
+    ///
+    /// A trailing `Done` is added if the script doesn't end with one.
+    pub fn new(script: Vec<ConnectorMessage>) -> Self {
+        Self {
+            name: "mock".to_string(),
+            model: None,
+            script,
+            delay: Duration::ZERO,
+            fail_first: 0,
+            error_message: "Injected failure".to_string(),
+            calls: AtomicU32::new(0),
+            prompts: Mutex::new(Vec::new()),
+            metrics: Arc::new(Mutex::new(ConnectorMetrics::default())),
+            health: Arc::new(Mutex::new(HealthTracker::default())),
+        }
+    }
+
+    /// Report a different connector name
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    /// Report `model` as the model prompts go to
+    pub fn with_model(mut self, model: impl Into<String>) -> Self {
+        self.model = Some(model.into());
+        self
+    }
+
+    /// Wait `delay` before each scripted message
+    pub fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// Fail the first `count` calls to `execute`
+    pub fn with_failures(mut self, count: u32) -> Self {
+        self.fail_first = count;
+        self
+    }
+
+    /// Require runs of failures or successes before health changes
+    pub fn with_health_thresholds(mut self, thresholds: HealthThresholds) -> Self {
+        self.health = Arc::new(Mutex::new(HealthTracker::new(thresholds)));
+        self
+    }
+
+    /// Error returned by injected failures
+    pub fn with_error_message(mut self, message: impl Into<String>) -> Self {
+        self.error_message = message.into();
+        self
+    }
+
+    /// Number of times `execute` has been called
+    pub fn call_count(&self) -> u32 {
+        self.calls.load(Ordering::SeqCst)
+    }
+
+    /// Prompts passed to `execute` so far, oldest first
+    pub async fn prompts(&self) -> Vec<String> {
+        self.prompts.lock().await.clone()
+    }
+}
+
+#[async_trait]
+impl Connector for MockConnector {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn execute(&self, prompt: &str) -> Result<mpsc::Receiver<ConnectorMessage>, String> {
+        let call = self.calls.fetch_add(1, Ordering::SeqCst);
+        self.prompts.lock().await.push(prompt.to_string());
+        let mut metrics = self.metrics.lock().await;
+        metrics.spawn_count += 1;
+
+        if call < self.fail_first {
+            metrics.error_count += 1;
+            self.health.lock().await.record_failure(ConnectorHealth::Degraded {
+                reason: self.error_message.clone(),
+            });
+            return Err(self.error_message.clone());
+        }
+
+        metrics.success_count += 1;
+        for message in &self.script {
+            if let ConnectorMessage::Usage { input_tokens, output_tokens } = message {
+                metrics.total_input_tokens += input_tokens;
+                metrics.total_output_tokens += output_tokens;
+            }
+        }
+        drop(metrics);
+        self.health.lock().await.record_success();
+
+        let mut script = self.script.clone();
+        if script.last() != Some(&ConnectorMessage::Done) {
+            script.push(ConnectorMessage::Done);
+        }
+
+        let (tx, rx) = mpsc::channel(script.len().max(1));
+        let delay = self.delay;
+        tokio::spawn(async move {
+            for message in script {
+                if !delay.is_zero() {
+                    tokio::time::sleep(delay).await;
+                }
+                if tx.send(message).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(rx)
+    }
+
+    async fn health(&self) -> ConnectorHealth {
+        self.health.lock().await.status()
+    }
+
+    async fn metrics(&self) -> ConnectorMetrics {
+        self.metrics.lock().await.clone()
+    }
+
+    async fn model(&self) -> Option<String> {
+        self.model.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_mock_replays_script_after_injected_failures() {
+        let mock = MockConnector::new(vec![
+            ConnectorMessage::Content {
+                content: "hello".to_string(),
+            },
+            ConnectorMessage::Usage {
+                input_tokens: 3,
+                output_tokens: 5,
+            },
+        ])
+        .with_failures(1);
+
+        assert_eq!(mock.execute("hi").await.unwrap_err(), "Injected failure");
+
+        let mut rx = mock.execute("hi").await.unwrap();
+        let mut messages = Vec::new();
+        while let Some(message) = rx.recv().await {
+            messages.push(message);
+        }
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages.last(), Some(&ConnectorMessage::Done));
+
+        let metrics = mock.metrics().await;
+        assert_eq!(mock.call_count(), 2);
+        assert_eq!(metrics.spawn_count, 2);
+        assert_eq!(metrics.error_count, 1);
+        assert_eq!(metrics.total_output_tokens, 5);
+    }
+}
@@ -1,7 +1,26 @@
 // Connector modules for different AI CLI tools
 pub mod claude_code;
 pub mod codex_cli;
+pub mod connector;
+pub mod context;
+pub mod error;
+pub mod fair_scheduler;
+pub mod fallback;
+pub mod health;
+pub mod line_buffer;
+pub mod mock;
 pub mod ollama;
+pub mod ollama_pool;
+pub mod preflight;
+pub mod stream;
 pub mod types;
 
+pub use connector::{cli_connector, Connector, EmbeddingConnector};
+pub use context::{ApproxTokenCounter, ContextLimitExceeded, ContextPolicy, TokenCounter};
+pub use error::ConnectorError;
+pub use fair_scheduler::FairScheduler;
+pub use fallback::{FallbackConnector, FallbackMetrics};
+pub use health::{HealthThresholds, HealthTracker};
+pub use mock::MockConnector;
+pub use ollama_pool::PooledOllamaConnector;
 pub use types::*;
@@ -1,9 +1,19 @@
-use super::types::{ConnectorHealth, ConnectorMessage, ConnectorMetrics};
+use super::context::{
+    default_token_counter, fit_prompt, ContextLimitExceeded, ContextPolicy, TokenCounter,
+};
+use super::error::ConnectorError;
+use super::health::{HealthThresholds, HealthTracker};
+use super::types::{
+    default_channel_capacity, ConnectorHealth, ConnectorMessage, ConnectorMetrics, ModelInfo,
+    DEFAULT_CHANNEL_CAPACITY,
+};
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::sync::{mpsc, Mutex};
-use tokio::time::timeout;
+use tracing::Instrument;
 
 /// Errors specific to Ollama connector
 #[derive(Debug, thiserror::Error)]
@@ -12,18 +22,48 @@ pub enum OllamaError {
     ConnectionError(String),
     #[error("HTTP request failed: {0}")]
     RequestError(String),
-    #[error("Timeout waiting for response")]
-    Timeout,
+    #[error("Ollama returned HTTP {0}")]
+    HttpStatus(u16),
+    #[error("Timed out connecting to Ollama")]
+    ConnectTimeout,
+    #[error("Timed out waiting for Ollama to respond")]
+    ReadTimeout,
     #[error("Failed to parse response: {0}")]
     ParseError(String),
-    #[error("Model not available: {0}")]
+    #[error("Model not available: {0} (pull it with `ollama pull {0}`)")]
     ModelNotAvailable(String),
     #[error("Max retries exceeded")]
     MaxRetriesExceeded,
+    #[error("{0}")]
+    ContextLimit(#[from] ContextLimitExceeded),
 }
 
 pub type Result<T> = std::result::Result<T, OllamaError>;
 
+impl OllamaError {
+    /// Whether the retry loop should try again after this error
+    pub fn is_retryable(&self) -> bool {
+        ConnectorError::from(self).is_retryable()
+    }
+}
+
+impl From<&OllamaError> for ConnectorError {
+    fn from(error: &OllamaError) -> Self {
+        match error {
+            OllamaError::HttpStatus(status) => ConnectorError::from_status(*status, error.to_string()),
+            OllamaError::ModelNotAvailable(model) => ConnectorError::NotFound(model.clone()),
+            OllamaError::ContextLimit(e) => ConnectorError::Rejected(e.to_string()),
+            OllamaError::ConnectTimeout | OllamaError::ReadTimeout => {
+                ConnectorError::Timeout(error.to_string())
+            }
+            OllamaError::ConnectionError(_)
+            | OllamaError::RequestError(_)
+            | OllamaError::ParseError(_)
+            | OllamaError::MaxRetriesExceeded => ConnectorError::Transient(error.to_string()),
+        }
+    }
+}
+
 /// Configuration for Ollama connector
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OllamaConfig {
@@ -31,14 +71,40 @@ pub struct OllamaConfig {
     pub host: String,
     /// Port (default: 11434)
     pub port: u16,
-    /// Timeout in milliseconds
+    /// Overall request timeout in milliseconds (including reading the response)
     pub timeout_ms: u64,
+    /// Timeout for establishing the connection, in milliseconds
+    #[serde(default = "default_connect_timeout_ms")]
+    pub connect_timeout_ms: u64,
     /// Maximum retries on failure
     pub max_retries: u32,
     /// Chat model to use (default: llama2)
     pub chat_model: String,
     /// Embedding model to use (default: nomic-embed-text)
     pub embedding_model: String,
+    /// Confirm `chat_model` is pulled before sending a chat request
+    #[serde(default)]
+    pub check_model_availability: bool,
+    /// Stream chat output chunk by chunk instead of waiting for the full reply
+    #[serde(default)]
+    pub stream: bool,
+    /// L2-normalize embeddings so cosine similarity reduces to a dot product
+    #[serde(default)]
+    pub normalize_embeddings: bool,
+    /// Chat messages buffered ahead of the reader; a full buffer pauses the
+    /// response stream until the reader catches up
+    #[serde(default = "default_channel_capacity")]
+    pub channel_capacity: usize,
+    /// Token budget for a single prompt (None = unlimited)
+    #[serde(default)]
+    pub max_context_tokens: Option<usize>,
+    /// What to do with a prompt over `max_context_tokens`
+    #[serde(default)]
+    pub context_policy: ContextPolicy,
+}
+
+fn default_connect_timeout_ms() -> u64 {
+    5000
 }
 
 impl Default for OllamaConfig {
@@ -47,9 +113,16 @@ impl Default for OllamaConfig {
             host: "http://localhost".to_string(),
             port: 11434,
             timeout_ms: 300000, // 5 minutes
+            connect_timeout_ms: default_connect_timeout_ms(),
             max_retries: 3,
             chat_model: "llama2".to_string(),
             embedding_model: "nomic-embed-text".to_string(),
+            check_model_availability: false,
+            stream: false,
+            normalize_embeddings: false,
+            channel_capacity: DEFAULT_CHANNEL_CAPACITY,
+            max_context_tokens: None,
+            context_policy: ContextPolicy::Reject,
         }
     }
 }
@@ -77,6 +150,13 @@ struct ChatResponse {
     eval_count: Option<u64>,
 }
 
+/// Token counts seen so far in a streamed chat response
+#[derive(Debug, Default, Clone, Copy)]
+struct StreamUsage {
+    input_tokens: u64,
+    output_tokens: u64,
+}
+
 /// Embedding request
 #[derive(Debug, Serialize)]
 struct EmbeddingRequest {
@@ -93,20 +173,44 @@ struct EmbeddingResponse {
 /// Model list response
 #[derive(Debug, Deserialize)]
 struct ModelListResponse {
-    models: Vec<ModelInfo>,
+    models: Vec<ModelTag>,
 }
 
 #[derive(Debug, Deserialize)]
-struct ModelInfo {
+struct ModelTag {
     name: String,
     size: u64,
 }
 
+/// Counts a running chat task for as long as it is alive, including when aborted
+struct InFlightGuard(Arc<AtomicUsize>);
+
+impl InFlightGuard {
+    fn new(counter: Arc<AtomicUsize>) -> Self {
+        counter.fetch_add(1, Ordering::SeqCst);
+        Self(counter)
+    }
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
 /// Ollama connector for chat and embeddings
+///
+/// Clones share metrics, health and the known-model cache.
+#[derive(Clone)]
 pub struct OllamaConnector {
     config: OllamaConfig,
     metrics: Arc<Mutex<ConnectorMetrics>>,
-    health: Arc<Mutex<ConnectorHealth>>,
+    health: Arc<Mutex<HealthTracker>>,
+    in_flight: Arc<AtomicUsize>,
+    /// Models seen by the last `list_models` call, reused by the chat precheck
+    known_models: Arc<Mutex<Option<HashSet<String>>>>,
+    /// Sizes prompts against `max_context_tokens`
+    token_counter: Arc<dyn TokenCounter>,
 }
 
 impl OllamaConnector {
@@ -115,13 +219,33 @@ impl OllamaConnector {
         Self {
             config,
             metrics: Arc::new(Mutex::new(ConnectorMetrics::default())),
-            health: Arc::new(Mutex::new(ConnectorHealth::Healthy)),
+            health: Arc::new(Mutex::new(HealthTracker::default())),
+            in_flight: Arc::new(AtomicUsize::new(0)),
+            known_models: Arc::new(Mutex::new(None)),
+            token_counter: default_token_counter(),
         }
     }
 
+    /// Count prompt tokens with the model's tokenizer instead of the estimate
+    pub fn with_token_counter(mut self, counter: Arc<dyn TokenCounter>) -> Self {
+        self.token_counter = counter;
+        self
+    }
+
+    /// Require runs of failures or successes before health changes
+    pub fn with_health_thresholds(mut self, thresholds: HealthThresholds) -> Self {
+        self.health = Arc::new(Mutex::new(HealthTracker::new(thresholds)));
+        self
+    }
+
+    /// Configuration this connector was created with
+    pub fn config(&self) -> &OllamaConfig {
+        &self.config
+    }
+
     /// Get current health status
     pub async fn health(&self) -> ConnectorHealth {
-        self.health.lock().await.clone()
+        self.health.lock().await.status()
     }
 
     /// Get current metrics
@@ -129,6 +253,16 @@ impl OllamaConnector {
         self.metrics.lock().await.clone()
     }
 
+    /// Zero the counters without touching health or config
+    pub async fn reset_metrics(&self) {
+        *self.metrics.lock().await = ConnectorMetrics::default();
+    }
+
+    /// Number of chat requests whose background task is still running
+    pub fn in_flight_chats(&self) -> usize {
+        self.in_flight.load(Ordering::SeqCst)
+    }
+
     /// Base URL for the Ollama API
     fn base_url(&self) -> String {
         format!("{}:{}", self.config.host, self.config.port)
@@ -152,26 +286,130 @@ impl OllamaConnector {
         }
     }
 
+    /// Confirm the server is reachable before the first real prompt
+    ///
+    /// Delegates to `check_health`, which records the outcome in the
+    /// connector's health status.
+    pub async fn warmup(&self) -> Result<()> {
+        if self.check_health().await? {
+            Ok(())
+        } else {
+            Err(OllamaError::ConnectionError(format!(
+                "Ollama server at {} is unreachable",
+                self.base_url()
+            )))
+        }
+    }
+
     /// List available models
     pub async fn list_models(&self) -> Result<Vec<String>> {
         let url = format!("{}/api/tags", self.base_url());
         let response = self.make_request::<(), ModelListResponse>(&url, None, "GET").await?;
-        Ok(response.models.into_iter().map(|m| m.name).collect())
+        let models: Vec<String> = response.models.into_iter().map(|m| m.name).collect();
+
+        *self.known_models.lock().await = Some(models.iter().cloned().collect());
+        Ok(models)
+    }
+
+    /// Pulled models for the model picker
+    ///
+    /// The configured chat model is appended as unavailable when it hasn't
+    /// been pulled, so the picker still shows what chat requests will ask for.
+    pub async fn available_models(&self) -> Result<Vec<ModelInfo>> {
+        let mut models: Vec<ModelInfo> = self
+            .list_models()
+            .await?
+            .into_iter()
+            .map(|name| ModelInfo {
+                display_name: name.clone(),
+                id: name,
+                available: true,
+            })
+            .collect();
+
+        let chat_model = &self.config.chat_model;
+        if !self.is_known_model(chat_model).await {
+            models.push(ModelInfo {
+                id: chat_model.clone(),
+                display_name: chat_model.clone(),
+                available: false,
+            });
+        }
+        Ok(models)
+    }
+
+    /// Forget the cached model list so the next precheck asks the server again
+    pub async fn clear_model_cache(&self) {
+        *self.known_models.lock().await = None;
+    }
+
+    /// Fail with `ModelNotAvailable` unless `model` has been pulled
+    ///
+    /// Uses the cached model list when it already contains the model, and
+    /// refreshes it otherwise in case the model was pulled since.
+    pub async fn ensure_model_available(&self, model: &str) -> Result<()> {
+        if self.is_known_model(model).await {
+            return Ok(());
+        }
+
+        self.list_models().await?;
+        if self.is_known_model(model).await {
+            Ok(())
+        } else {
+            Err(OllamaError::ModelNotAvailable(model.to_string()))
+        }
+    }
+
+    /// Whether the cached model list has `model`, treating a missing tag as `:latest`
+    async fn is_known_model(&self, model: &str) -> bool {
+        let known = self.known_models.lock().await;
+        known
+            .as_ref()
+            .map(|models| {
+                models.contains(model)
+                    || (!model.contains(':') && models.contains(&format!("{}:latest", model)))
+            })
+            .unwrap_or(false)
     }
 
     /// Execute a chat completion
+    ///
+    /// Dropping the returned receiver cancels the in-flight request.
     pub async fn chat(&self, prompt: &str) -> Result<mpsc::Receiver<ConnectorMessage>> {
-        let (tx, rx) = mpsc::channel(100);
+        let prompt = fit_prompt(
+            prompt,
+            self.config.max_context_tokens,
+            self.config.context_policy,
+            self.token_counter.as_ref(),
+        )?;
+
+        if self.config.check_model_availability {
+            self.ensure_model_available(&self.config.chat_model).await?;
+        }
 
-        let prompt = prompt.to_string();
+        let (tx, rx) = mpsc::channel(self.config.channel_capacity.max(1));
+
+        let prompt = prompt.into_owned();
         let config = self.config.clone();
         let metrics = self.metrics.clone();
         let health = self.health.clone();
+        let in_flight = InFlightGuard::new(self.in_flight.clone());
+        let span = tracing::info_span!("ollama_chat", connector = "ollama", model = %config.chat_model);
 
         tokio::spawn(async move {
+            let _in_flight = in_flight;
             let start = Instant::now();
 
-            match Self::execute_chat(&config, &prompt, tx.clone()).await {
+            // Nobody is listening any more, so stop instead of finishing the request
+            let outcome = tokio::select! {
+                outcome = Self::execute_chat(&config, &prompt, tx.clone()) => outcome,
+                _ = tx.closed() => {
+                    tracing::debug!("Chat receiver dropped, cancelling request");
+                    return;
+                }
+            };
+
+            match outcome {
                 Ok((input_tokens, output_tokens)) => {
                     let mut m = metrics.lock().await;
                     m.spawn_count += 1;
@@ -183,16 +421,16 @@ impl OllamaConnector {
                     let n = m.spawn_count as f64;
                     m.avg_response_time_ms = (m.avg_response_time_ms * (n - 1.0) + elapsed) / n;
 
-                    *health.lock().await = ConnectorHealth::Healthy;
+                    health.lock().await.record_success();
                 }
                 Err(e) => {
                     let mut m = metrics.lock().await;
                     m.spawn_count += 1;
                     m.error_count += 1;
 
-                    *health.lock().await = ConnectorHealth::Degraded {
+                    health.lock().await.record_failure(ConnectorHealth::Degraded {
                         reason: format!("Chat failed: {}", e),
-                    };
+                    });
 
                     let _ = tx.send(ConnectorMessage::Error {
                         message: format!("Chat error: {}", e),
@@ -201,7 +439,7 @@ impl OllamaConnector {
             }
 
             let _ = tx.send(ConnectorMessage::Done).await;
-        });
+        }.instrument(span));
 
         Ok(rx)
     }
@@ -217,28 +455,28 @@ impl OllamaConnector {
         let request = ChatRequest {
             model: config.chat_model.clone(),
             prompt: prompt.to_string(),
-            stream: false,
+            stream: config.stream,
         };
 
-        let client = reqwest::Client::new();
-        let response = timeout(
-            Duration::from_millis(config.timeout_ms),
-            client.post(&url).json(&request).send()
-        )
-        .await
-        .map_err(|_| OllamaError::Timeout)?
-        .map_err(|e| OllamaError::RequestError(e.to_string()))?;
+        let response = Self::http_client(config)?
+            .post(&url)
+            .json(&request)
+            .send()
+            .await
+            .map_err(Self::classify_error)?;
 
         if !response.status().is_success() {
-            return Err(OllamaError::RequestError(
-                format!("HTTP {}", response.status())
-            ));
+            return Err(OllamaError::HttpStatus(response.status().as_u16()));
+        }
+
+        if config.stream {
+            return Self::read_chat_stream(response, &tx).await;
         }
 
         let chat_response: ChatResponse = response
             .json()
             .await
-            .map_err(|e| OllamaError::ParseError(e.to_string()))?;
+            .map_err(Self::classify_error)?;
 
         // Send content
         let _ = tx.send(ConnectorMessage::Content {
@@ -259,6 +497,97 @@ impl OllamaConnector {
         Ok((input_tokens, output_tokens))
     }
 
+    /// Forward a newline-delimited stream of chat chunks
+    ///
+    /// Ollama only reports token counts in the final chunk, so each content
+    /// chunk is counted as one output token until then. Whatever was counted
+    /// is emitted as `Usage` even if the stream ends early.
+    async fn read_chat_stream(
+        mut response: reqwest::Response,
+        tx: &mpsc::Sender<ConnectorMessage>,
+    ) -> Result<(u64, u64)> {
+        let mut usage = StreamUsage::default();
+        let mut pending: Vec<u8> = Vec::new();
+        let mut done = false;
+
+        let outcome: Result<()> = async {
+            while !done {
+                let Some(bytes) = response
+                    .chunk()
+                    .await
+                    .map_err(Self::classify_error)?
+                else {
+                    break;
+                };
+                pending.extend_from_slice(&bytes);
+
+                while let Some(pos) = pending.iter().position(|b| *b == b'\n') {
+                    let line: Vec<u8> = pending.drain(..=pos).collect();
+                    done = Self::handle_stream_line(&line, &mut usage, tx).await?;
+                    if done {
+                        break;
+                    }
+                }
+            }
+
+            // The final chunk may not be newline-terminated
+            if !done && !pending.is_empty() {
+                done = Self::handle_stream_line(&pending, &mut usage, tx).await?;
+            }
+
+            if done {
+                Ok(())
+            } else {
+                Err(OllamaError::RequestError(
+                    "Stream ended before the response completed".to_string(),
+                ))
+            }
+        }
+        .await;
+
+        if usage.input_tokens > 0 || usage.output_tokens > 0 {
+            let _ = tx.send(ConnectorMessage::Usage {
+                input_tokens: usage.input_tokens,
+                output_tokens: usage.output_tokens,
+            }).await;
+        }
+
+        outcome.map(|_| (usage.input_tokens, usage.output_tokens))
+    }
+
+    /// Parse one streamed chunk, returning whether it was the last
+    async fn handle_stream_line(
+        line: &[u8],
+        usage: &mut StreamUsage,
+        tx: &mpsc::Sender<ConnectorMessage>,
+    ) -> Result<bool> {
+        let line = String::from_utf8_lossy(line);
+        let line = line.trim();
+        if line.is_empty() {
+            return Ok(false);
+        }
+
+        let chunk: ChatResponse = serde_json::from_str(line)
+            .map_err(|e| OllamaError::ParseError(e.to_string()))?;
+
+        if !chunk.response.is_empty() {
+            usage.output_tokens += 1;
+            let _ = tx.send(ConnectorMessage::Content {
+                content: chunk.response,
+            }).await;
+        }
+
+        // Reported counts replace the running estimate
+        if let Some(count) = chunk.prompt_eval_count {
+            usage.input_tokens = count;
+        }
+        if let Some(count) = chunk.eval_count {
+            usage.output_tokens = count;
+        }
+
+        Ok(chunk.done)
+    }
+
     /// Generate embeddings for text
     pub async fn embed(&self, text: &str) -> Result<Vec<f32>> {
         let url = format!("{}/api/embeddings", self.base_url());
@@ -284,9 +613,29 @@ impl OllamaConnector {
         let n = metrics.spawn_count as f64;
         metrics.avg_response_time_ms = (metrics.avg_response_time_ms * (n - 1.0) + elapsed) / n;
 
+        if self.config.normalize_embeddings {
+            return Self::normalize_embedding(response.embedding);
+        }
+
         Ok(response.embedding)
     }
 
+    /// Scale an embedding to unit length
+    pub fn normalize_embedding(mut embedding: Vec<f32>) -> Result<Vec<f32>> {
+        let norm = embedding.iter().map(|v| v * v).sum::<f32>().sqrt();
+        if !norm.is_finite() || norm <= 0.0 {
+            return Err(OllamaError::ParseError(format!(
+                "Cannot normalize embedding with norm {}",
+                norm
+            )));
+        }
+
+        for value in &mut embedding {
+            *value /= norm;
+        }
+        Ok(embedding)
+    }
+
     /// Generic HTTP request helper with retries
     async fn make_request<Req: Serialize, Res: for<'de> Deserialize<'de>>(
         &self,
@@ -299,13 +648,25 @@ impl OllamaConnector {
 
         loop {
             match self.try_request(url, body.as_ref(), method).await {
-                Ok(response) => return Ok(response),
+                Ok(response) => {
+                    self.health.lock().await.record_success();
+                    return Ok(response);
+                }
                 Err(e) => {
                     retries += 1;
+
+                    // A 4xx or missing model won't change on the next attempt
+                    if !e.is_retryable() {
+                        self.health.lock().await.record_failure(ConnectorHealth::Unhealthy {
+                            reason: e.to_string(),
+                        });
+                        return Err(e);
+                    }
+
                     if retries >= max_retries {
-                        self.update_health(ConnectorHealth::Unhealthy {
+                        self.health.lock().await.record_failure(ConnectorHealth::Unhealthy {
                             reason: format!("Max retries exceeded: {}", e),
-                        }).await;
+                        });
                         return Err(OllamaError::MaxRetriesExceeded);
                     }
 
@@ -324,7 +685,7 @@ impl OllamaConnector {
         body: Option<&Req>,
         method: &str,
     ) -> Result<Res> {
-        let client = reqwest::Client::new();
+        let client = Self::http_client(&self.config)?;
 
         let request = match method {
             "GET" => client.get(url),
@@ -338,29 +699,45 @@ impl OllamaConnector {
             _ => return Err(OllamaError::RequestError("Unsupported method".to_string())),
         };
 
-        let response = timeout(
-            Duration::from_millis(self.config.timeout_ms),
-            request.send()
-        )
-        .await
-        .map_err(|_| OllamaError::Timeout)?
-        .map_err(|e| OllamaError::ConnectionError(e.to_string()))?;
+        let response = request.send().await.map_err(Self::classify_error)?;
 
         if !response.status().is_success() {
-            return Err(OllamaError::RequestError(
-                format!("HTTP {}", response.status())
-            ));
+            return Err(OllamaError::HttpStatus(response.status().as_u16()));
         }
 
-        response
-            .json()
-            .await
-            .map_err(|e| OllamaError::ParseError(e.to_string()))
+        response.json().await.map_err(Self::classify_error)
+    }
+
+    /// HTTP client with separate connect and overall timeouts, so the two
+    /// failure modes can be told apart
+    fn http_client(config: &OllamaConfig) -> Result<reqwest::Client> {
+        reqwest::Client::builder()
+            .connect_timeout(Duration::from_millis(config.connect_timeout_ms))
+            .timeout(Duration::from_millis(config.timeout_ms))
+            .build()
+            .map_err(|e| OllamaError::RequestError(e.to_string()))
+    }
+
+    /// Map a reqwest error to the matching connector error
+    fn classify_error(e: reqwest::Error) -> OllamaError {
+        if e.is_timeout() {
+            if e.is_connect() {
+                OllamaError::ConnectTimeout
+            } else {
+                OllamaError::ReadTimeout
+            }
+        } else if e.is_connect() {
+            OllamaError::ConnectionError(e.to_string())
+        } else if e.is_decode() {
+            OllamaError::ParseError(e.to_string())
+        } else {
+            OllamaError::RequestError(e.to_string())
+        }
     }
 
     /// Update health status
     async fn update_health(&self, health: ConnectorHealth) {
-        *self.health.lock().await = health;
+        self.health.lock().await.set(health);
     }
 
     /// Validate embedding vector
@@ -432,4 +809,142 @@ mod tests {
         let health = connector.health().await;
         assert!(matches!(health, ConnectorHealth::Degraded { .. }));
     }
+
+    #[tokio::test]
+    async fn test_chat_stops_when_receiver_dropped() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/generate"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({
+                        "model": "llama2",
+                        "response": "too late",
+                        "done": true
+                    }))
+                    .set_delay(Duration::from_secs(30)),
+            )
+            .mount(&mock_server)
+            .await;
+
+        let address = mock_server.address();
+        let connector = OllamaConnector::new(OllamaConfig {
+            host: format!("http://{}", address.ip()),
+            port: address.port(),
+            ..OllamaConfig::default()
+        });
+
+        let rx = connector.chat("hello").await.unwrap();
+        assert_eq!(connector.in_flight_chats(), 1);
+        drop(rx);
+
+        tokio::time::timeout(Duration::from_secs(2), async {
+            while connector.in_flight_chats() > 0 {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("chat task kept running after the receiver was dropped");
+
+        // A cancelled chat is neither a success nor a failure
+        let metrics = connector.metrics().await;
+        assert_eq!(metrics.spawn_count, 0);
+    }
+
+    #[test]
+    fn test_normalize_embedding() {
+        let normalized = OllamaConnector::normalize_embedding(vec![3.0, 4.0]).unwrap();
+        assert!((normalized[0] - 0.6).abs() < 1e-6);
+        assert!((normalized[1] - 0.8).abs() < 1e-6);
+
+        assert!(OllamaConnector::normalize_embedding(vec![0.0, 0.0]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_embed_normalizes_only_when_enabled() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/embeddings"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "embedding": [1.0, 2.0, 2.0]
+            })))
+            .mount(&mock_server)
+            .await;
+
+        let address = mock_server.address();
+        let config = OllamaConfig {
+            host: format!("http://{}", address.ip()),
+            port: address.port(),
+            max_retries: 1,
+            ..OllamaConfig::default()
+        };
+
+        let raw = OllamaConnector::new(config.clone()).embed("text").await.unwrap();
+        assert_eq!(raw, vec![1.0, 2.0, 2.0]);
+
+        let normalized = OllamaConnector::new(OllamaConfig {
+            normalize_embeddings: true,
+            ..config
+        })
+        .embed("text")
+        .await
+        .unwrap();
+        let magnitude = normalized.iter().map(|v| v * v).sum::<f32>().sqrt();
+        assert!((magnitude - 1.0).abs() < 1e-6);
+        assert!((normalized[0] - 1.0 / 3.0).abs() < 1e-6);
+    }
+
+    #[tokio::test]
+    async fn test_chat_without_server_is_connection_error() {
+        // Reserve a port, then free it so nothing is listening there
+        let address = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+        let config = OllamaConfig {
+            host: format!("http://{}", address.ip()),
+            port: address.port(),
+            ..OllamaConfig::default()
+        };
+
+        let (tx, _rx) = mpsc::channel(10);
+        let err = OllamaConnector::execute_chat(&config, "hi", tx).await.unwrap_err();
+        assert!(matches!(err, OllamaError::ConnectionError(_)), "got {:?}", err);
+    }
+
+    #[tokio::test]
+    async fn test_slow_response_is_read_timeout() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/generate"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({
+                        "model": "llama2",
+                        "response": "slow",
+                        "done": true
+                    }))
+                    .set_delay(Duration::from_secs(5)),
+            )
+            .mount(&mock_server)
+            .await;
+
+        let address = mock_server.address();
+        let config = OllamaConfig {
+            host: format!("http://{}", address.ip()),
+            port: address.port(),
+            timeout_ms: 200,
+            ..OllamaConfig::default()
+        };
+
+        let (tx, _rx) = mpsc::channel(10);
+        let err = OllamaConnector::execute_chat(&config, "hi", tx).await.unwrap_err();
+        assert!(matches!(err, OllamaError::ReadTimeout), "got {:?}", err);
+    }
 }
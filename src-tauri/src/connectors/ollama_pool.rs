@@ -0,0 +1,227 @@
+use super::connector::Connector;
+use super::ollama::{OllamaConfig, OllamaConnector, OllamaError, Result};
+use super::types::{ConnectorHealth, ConnectorMessage, ConnectorMetrics};
+use async_trait::async_trait;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tokio::sync::mpsc;
+use tracing::warn;
+
+/// Spreads chat and embedding calls round-robin across several Ollama hosts
+///
+/// Hosts marked unhealthy (by `refresh_health` or a failed request) are
+/// skipped until a later health check brings them back.
+pub struct PooledOllamaConnector {
+    hosts: Vec<OllamaConnector>,
+    next: AtomicUsize,
+}
+
+impl PooledOllamaConnector {
+    /// Create a pool with one connector per host config
+    pub fn new(configs: Vec<OllamaConfig>) -> Self {
+        Self {
+            hosts: configs.into_iter().map(OllamaConnector::new).collect(),
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    /// Number of hosts in the pool
+    pub fn len(&self) -> usize {
+        self.hosts.len()
+    }
+
+    /// Check if the pool has no hosts
+    pub fn is_empty(&self) -> bool {
+        self.hosts.is_empty()
+    }
+
+    /// Get the connector for a host by index
+    pub fn host(&self, index: usize) -> Option<&OllamaConnector> {
+        self.hosts.get(index)
+    }
+
+    /// Run a health check against every host, returning how many are healthy
+    pub async fn refresh_health(&self) -> usize {
+        let mut healthy = 0;
+        for host in &self.hosts {
+            if host.check_health().await.unwrap_or(false) {
+                healthy += 1;
+            }
+        }
+        healthy
+    }
+
+    /// Zero the metrics of every host
+    pub async fn reset_metrics(&self) {
+        for host in &self.hosts {
+            host.reset_metrics().await;
+        }
+    }
+
+    /// Hosts to try for the next call: every usable host, starting at the
+    /// round-robin cursor
+    async fn candidates(&self) -> Vec<&OllamaConnector> {
+        if self.hosts.is_empty() {
+            return Vec::new();
+        }
+
+        let start = self.next.fetch_add(1, Ordering::Relaxed) % self.hosts.len();
+        let mut candidates = Vec::with_capacity(self.hosts.len());
+        for offset in 0..self.hosts.len() {
+            let host = &self.hosts[(start + offset) % self.hosts.len()];
+            if !matches!(host.health().await, ConnectorHealth::Unhealthy { .. }) {
+                candidates.push(host);
+            }
+        }
+        candidates
+    }
+
+    /// Execute a chat completion on the next healthy host
+    pub async fn chat(&self, prompt: &str) -> Result<mpsc::Receiver<ConnectorMessage>> {
+        let mut last_error = None;
+        for host in self.candidates().await {
+            match host.chat(prompt).await {
+                Ok(rx) => return Ok(rx),
+                Err(e) => {
+                    warn!("Ollama host failed chat, trying next: {}", e);
+                    last_error = Some(e);
+                }
+            }
+        }
+        Err(last_error.unwrap_or_else(Self::no_healthy_hosts))
+    }
+
+    /// Generate embeddings on the next healthy host, failing over on error
+    pub async fn embed(&self, text: &str) -> Result<Vec<f32>> {
+        let mut last_error = None;
+        for host in self.candidates().await {
+            match host.embed(text).await {
+                Ok(embedding) => return Ok(embedding),
+                Err(e) => {
+                    warn!("Ollama host failed embedding, trying next: {}", e);
+                    last_error = Some(e);
+                }
+            }
+        }
+        Err(last_error.unwrap_or_else(Self::no_healthy_hosts))
+    }
+
+    fn no_healthy_hosts() -> OllamaError {
+        OllamaError::ConnectionError("No healthy Ollama hosts in pool".to_string())
+    }
+}
+
+#[async_trait]
+impl Connector for PooledOllamaConnector {
+    fn name(&self) -> &str {
+        "ollama_pool"
+    }
+
+    async fn execute(&self, prompt: &str) -> std::result::Result<mpsc::Receiver<ConnectorMessage>, String> {
+        self.chat(prompt).await.map_err(|e| e.to_string())
+    }
+
+    /// Healthy while at least one host is usable
+    async fn health(&self) -> ConnectorHealth {
+        let mut usable = 0;
+        for host in &self.hosts {
+            if !matches!(host.health().await, ConnectorHealth::Unhealthy { .. }) {
+                usable += 1;
+            }
+        }
+
+        if usable == 0 {
+            ConnectorHealth::Unhealthy {
+                reason: "No healthy Ollama hosts in pool".to_string(),
+            }
+        } else if usable < self.hosts.len() {
+            ConnectorHealth::Degraded {
+                reason: format!("{} of {} Ollama hosts healthy", usable, self.hosts.len()),
+            }
+        } else {
+            ConnectorHealth::Healthy
+        }
+    }
+
+    /// Metrics summed across hosts
+    async fn metrics(&self) -> ConnectorMetrics {
+        let mut total = ConnectorMetrics::default();
+        let mut weighted_time = 0.0;
+        for host in &self.hosts {
+            let m = host.metrics().await;
+            total.spawn_count += m.spawn_count;
+            total.success_count += m.success_count;
+            total.error_count += m.error_count;
+            total.total_input_tokens += m.total_input_tokens;
+            total.total_output_tokens += m.total_output_tokens;
+            weighted_time += m.avg_response_time_ms * m.spawn_count as f64;
+        }
+        if total.spawn_count > 0 {
+            total.avg_response_time_ms = weighted_time / total.spawn_count as f64;
+        }
+        total
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    async fn ollama_server(expected_embeds: u64) -> MockServer {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/tags"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "models": [{"name": "nomic-embed-text", "size": 1}]
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/embeddings"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "embedding": [0.1, 0.2]
+            })))
+            .expect(expected_embeds)
+            .mount(&server)
+            .await;
+        server
+    }
+
+    fn config_for(address: std::net::SocketAddr) -> OllamaConfig {
+        OllamaConfig {
+            host: format!("http://{}", address.ip()),
+            port: address.port(),
+            timeout_ms: 1000,
+            max_retries: 1,
+            ..OllamaConfig::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_pool_round_robins_and_skips_down_host() {
+        let first = ollama_server(2).await;
+        let second = ollama_server(2).await;
+
+        // Reserve a port, then free it so nothing is listening there
+        let down = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+
+        let pool = PooledOllamaConnector::new(vec![
+            config_for(*first.address()),
+            config_for(down),
+            config_for(*second.address()),
+        ]);
+
+        assert_eq!(pool.refresh_health().await, 2);
+        assert!(matches!(pool.host(1).unwrap().health().await, ConnectorHealth::Unhealthy { .. }));
+        assert!(matches!(Connector::health(&pool).await, ConnectorHealth::Degraded { .. }));
+
+        for _ in 0..4 {
+            pool.embed("text").await.unwrap();
+        }
+
+        first.verify().await;
+        second.verify().await;
+        assert_eq!(pool.host(1).unwrap().metrics().await.spawn_count, 0);
+    }
+}
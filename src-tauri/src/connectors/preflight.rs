@@ -0,0 +1,61 @@
+use super::types::ConnectorConfig;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::process::Command;
+use tokio::time::timeout;
+
+/// Upper bound for a version probe, regardless of the connector timeout
+const PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Run `<cli_path> --version` to confirm the binary exists and starts
+///
+/// Returns the trimmed version string on success, or a human-readable
+/// reason suitable for `ConnectorHealth::Unhealthy`.
+pub async fn probe_cli_version(config: &ConnectorConfig) -> Result<String, String> {
+    let mut cmd = Command::new(&config.cli_path);
+    cmd.arg("--version")
+        .envs(&config.env)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+
+    let limit = config
+        .timeout_ms
+        .map(Duration::from_millis)
+        .map(|t| t.min(PROBE_TIMEOUT))
+        .unwrap_or(PROBE_TIMEOUT);
+
+    let output = timeout(limit, cmd.output())
+        .await
+        .map_err(|_| format!("{} --version timed out", config.cli_path))?
+        .map_err(|e| format!("Failed to run {}: {}", config.cli_path, e))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!(
+            "{} --version exited with {}: {}",
+            config.cli_path,
+            output.status,
+            stderr.trim()
+        ));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_probe_missing_binary() {
+        let config = ConnectorConfig {
+            cli_path: "/nonexistent/agent-cli".to_string(),
+            ..ConnectorConfig::default()
+        };
+
+        let err = probe_cli_version(&config).await.unwrap_err();
+        assert!(err.contains("/nonexistent/agent-cli"));
+    }
+}
@@ -0,0 +1,78 @@
+use super::types::ConnectorMessage;
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+
+/// Event name used when forwarding connector output to the frontend
+pub const CONNECTOR_MESSAGE_EVENT: &str = "connector://message";
+
+/// Payload emitted for each message of a connector stream
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ConnectorStreamEvent {
+    pub stream_id: String,
+    pub session_id: Option<String>,
+    pub message: ConnectorMessage,
+}
+
+/// Forward every message from `rx` to `emit`, tagged with the stream and session
+///
+/// Stops when the stream ends or `emit` returns `false` (e.g. the window is
+/// gone). Returns the number of events emitted.
+pub async fn forward_stream<F>(
+    stream_id: &str,
+    session_id: Option<&str>,
+    mut rx: mpsc::Receiver<ConnectorMessage>,
+    mut emit: F,
+) -> usize
+where
+    F: FnMut(ConnectorStreamEvent) -> bool,
+{
+    let mut emitted = 0;
+
+    while let Some(message) = rx.recv().await {
+        let event = ConnectorStreamEvent {
+            stream_id: stream_id.to_string(),
+            session_id: session_id.map(str::to_string),
+            message,
+        };
+        if !emit(event) {
+            break;
+        }
+        emitted += 1;
+    }
+
+    emitted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_forward_stream_payloads() {
+        let messages = vec![
+            ConnectorMessage::Content { content: "Hello".to_string() },
+            ConnectorMessage::Usage { input_tokens: 4, output_tokens: 1 },
+            ConnectorMessage::Done,
+        ];
+
+        let (tx, rx) = mpsc::channel(8);
+        for message in messages.clone() {
+            tx.send(message).await.unwrap();
+        }
+        drop(tx);
+
+        let mut emitted = Vec::new();
+        let count = forward_stream("stream-1", Some("session-1"), rx, |event| {
+            emitted.push(event);
+            true
+        })
+        .await;
+
+        assert_eq!(count, 3);
+        for (event, message) in emitted.iter().zip(messages) {
+            assert_eq!(event.stream_id, "stream-1");
+            assert_eq!(event.session_id.as_deref(), Some("session-1"));
+            assert_eq!(event.message, message);
+        }
+    }
+}
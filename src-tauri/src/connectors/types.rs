@@ -1,3 +1,4 @@
+use super::context::ContextPolicy;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -8,15 +9,86 @@ pub enum ConnectorMessage {
     /// Text content streamed from the model
     Content { content: String },
     /// Tool call or function invocation
-    ToolCall { name: String, args: String },
+    ToolCall { name: String, args: serde_json::Value },
     /// Error from the connector
     Error { message: String },
     /// Usage/token information
     Usage { input_tokens: u64, output_tokens: u64 },
+    /// CLI chatter such as banners and spinners, kept out of the transcript
+    SystemNotice { text: String },
     /// Stream completed
     Done,
 }
 
+/// Status-line openings CLIs print while starting up or working
+const NOTICE_PREFIXES: [&str; 6] = [
+    "Starting ",
+    "Loading ",
+    "Connecting ",
+    "Initializing ",
+    "Thinking",
+    "Working",
+];
+
+/// Longest status line, in words, still treated as a notice
+const MAX_NOTICE_WORDS: usize = 4;
+
+/// Frames of the braille spinners most CLIs animate with
+const SPINNER_FRAMES: [char; 10] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+
+/// Whether a plain-text output line is CLI noise rather than model output
+///
+/// Matches spinner frames, terminal control sequences, lines drawn only with
+/// box-drawing characters, and short "Starting..."-style status lines ending
+/// in an ellipsis. Anything else is treated as content.
+pub fn is_cli_notice(line: &str) -> bool {
+    let line = line.trim_end_matches(['\r', '\n']);
+    let trimmed = line.trim();
+    if trimmed.is_empty() {
+        return false;
+    }
+
+    if line.contains('\r') || trimmed.starts_with('\u{1b}') {
+        return true;
+    }
+    if trimmed.starts_with(SPINNER_FRAMES) {
+        return true;
+    }
+    if trimmed
+        .chars()
+        .all(|c| c.is_whitespace() || ('\u{2500}'..='\u{257F}').contains(&c))
+    {
+        return true;
+    }
+
+    let status_line = (trimmed.ends_with("...") || trimmed.ends_with('…'))
+        && trimmed.split_whitespace().count() <= MAX_NOTICE_WORDS;
+    status_line && NOTICE_PREFIXES.iter().any(|prefix| trimmed.starts_with(prefix))
+}
+
+impl ConnectorMessage {
+    /// Parse a tool call's string-encoded arguments into structured JSON
+    ///
+    /// CLIs often emit `args` as a JSON string rather than an object. Strings
+    /// holding a JSON object or array are decoded; anything else is kept
+    /// as a plain string value.
+    pub fn with_structured_args(self) -> Self {
+        match self {
+            ConnectorMessage::ToolCall {
+                name,
+                args: serde_json::Value::String(raw),
+            } => {
+                let args = match serde_json::from_str::<serde_json::Value>(&raw) {
+                    Ok(value) if value.is_object() || value.is_array() => value,
+                    _ => serde_json::Value::String(raw),
+                };
+                ConnectorMessage::ToolCall { name, args }
+            }
+            other => other,
+        }
+    }
+}
+
 /// Configuration for connector spawning
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ConnectorConfig {
@@ -30,6 +102,25 @@ pub struct ConnectorConfig {
     pub timeout_ms: Option<u64>,
     /// Maximum retries on failure
     pub max_retries: u32,
+    /// Messages buffered between the connector and its reader
+    ///
+    /// When the buffer is full the connector waits for the reader instead of
+    /// dropping output or growing memory.
+    #[serde(default = "default_channel_capacity")]
+    pub channel_capacity: usize,
+    /// Token budget for a single prompt (None = unlimited)
+    #[serde(default)]
+    pub max_context_tokens: Option<usize>,
+    /// What to do with a prompt over `max_context_tokens`
+    #[serde(default)]
+    pub context_policy: ContextPolicy,
+}
+
+/// Output messages buffered per connector call unless configured
+pub const DEFAULT_CHANNEL_CAPACITY: usize = 100;
+
+pub(crate) fn default_channel_capacity() -> usize {
+    DEFAULT_CHANNEL_CAPACITY
 }
 
 impl Default for ConnectorConfig {
@@ -40,6 +131,9 @@ impl Default for ConnectorConfig {
             env: HashMap::new(),
             timeout_ms: Some(300000), // 5 minutes default
             max_retries: 3,
+            channel_capacity: DEFAULT_CHANNEL_CAPACITY,
+            max_context_tokens: None,
+            context_policy: ContextPolicy::Reject,
         }
     }
 }
@@ -52,6 +146,16 @@ pub enum ConnectorHealth {
     Unhealthy { reason: String },
 }
 
+/// A model a connector can run, as shown in the model picker
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ModelInfo {
+    /// Identifier passed back to the connector, e.g. `gpt-5-codex` or `llama2:13b`
+    pub id: String,
+    pub display_name: String,
+    /// False when the model is configured but can't be used yet (e.g. not pulled)
+    pub available: bool,
+}
+
 /// Metrics for connector operations
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ConnectorMetrics {
@@ -62,3 +166,42 @@ pub struct ConnectorMetrics {
     pub total_output_tokens: u64,
     pub avg_response_time_ms: f64,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tool_call_string_args_are_decoded() {
+        let msg = ConnectorMessage::ToolCall {
+            name: "read_file".to_string(),
+            args: serde_json::Value::String(r#"{"path":"src/main.rs"}"#.to_string()),
+        };
+        assert_eq!(
+            msg.with_structured_args(),
+            ConnectorMessage::ToolCall {
+                name: "read_file".to_string(),
+                args: serde_json::json!({"path": "src/main.rs"}),
+            }
+        );
+
+        let plain = ConnectorMessage::ToolCall {
+            name: "shell".to_string(),
+            args: serde_json::Value::String("ls -la".to_string()),
+        };
+        assert_eq!(plain.clone().with_structured_args(), plain);
+    }
+
+    #[test]
+    fn test_cli_notice_heuristics() {
+        assert!(is_cli_notice("Starting Claude Code..."));
+        assert!(is_cli_notice("⠙ Thinking"));
+        assert!(is_cli_notice("╭──────────────╮"));
+        assert!(is_cli_notice("\u{1b}[2K"));
+
+        assert!(!is_cli_notice("Starting with the parser is easiest..."));
+        assert!(!is_cli_notice("Here is the fix:"));
+        assert!(!is_cli_notice("- remove the unused import"));
+        assert!(!is_cli_notice(""));
+    }
+}
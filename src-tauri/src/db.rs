@@ -1,68 +1,159 @@
+use crate::config::AppConfig;
 use crate::error::{AppError, AppResult};
-use sqlx::sqlite::{SqlitePool, SqlitePoolOptions, SqliteConnectOptions};
+use serde::Serialize;
+use sqlx::Executor;
+use sqlx::sqlite::{
+  SqliteConnectOptions, SqliteJournalMode, SqlitePool, SqlitePoolOptions, SqliteSynchronous,
+};
 use std::path::Path;
 use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
 use tracing::info;
 
+/// Schema migrations in order, numbered like their files in `migrations/`
+///
+/// `PRAGMA user_version` holds the number of the last one applied, so each
+/// runs exactly once per database.
+const MIGRATIONS: [(u32, &str); 6] = [
+  (2, include_str!("../migrations/002_session_schema.sql")),
+  (3, include_str!("../migrations/003_session_external_id.sql")),
+  (4, include_str!("../migrations/004_agents.sql")),
+  (5, include_str!("../migrations/005_session_templates.sql")),
+  (6, include_str!("../migrations/006_connector_metrics.sql")),
+  (7, include_str!("../migrations/007_orchestrator_metrics.sql")),
+];
+
+/// Apply every migration newer than the database's `user_version`
+async fn run_migrations(pool: &SqlitePool) -> AppResult<()> {
+  let start = std::time::Instant::now();
+  let current = schema_version(pool).await?;
+  info!("Running database migrations from version {}", current);
+
+  for (version, sql) in MIGRATIONS.iter().filter(|(version, _)| *version > current) {
+    let mut tx = pool.begin().await.map_err(|e| AppError::Database(e.to_string()))?;
+    tx.execute(*sql)
+      .await
+      .map_err(|e| AppError::Database(format!("Migration {:03} failed: {}", version, e)))?;
+    tx.execute(format!("PRAGMA user_version = {}", version).as_str())
+      .await
+      .map_err(|e| AppError::Database(format!("Migration {:03} failed: {}", version, e)))?;
+    tx.commit().await.map_err(|e| AppError::Database(e.to_string()))?;
+  }
+
+  info!("Database migrations completed in {:?}", start.elapsed());
+  Ok(())
+}
+
+/// Number of the last migration applied to the database behind `pool`
+async fn schema_version(pool: &SqlitePool) -> AppResult<u32> {
+  let (version,): (i64,) = sqlx::query_as("PRAGMA user_version")
+    .fetch_one(pool)
+    .await
+    .map_err(|e| AppError::Database(e.to_string()))?;
+  Ok(version as u32)
+}
+
 pub struct Database {
   pool: SqlitePool,
+  maintenance: Arc<Mutex<()>>,
+}
+
+/// Snapshot of connection pool usage
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct PoolStats {
+  pub size: u32,
+  pub idle: u32,
+  pub in_use: u32,
 }
 
 impl Database {
   pub async fn init(db_path: &Path) -> AppResult<Self> {
+    Self::init_with_config(db_path, &AppConfig::default()).await
+  }
+
+  pub async fn init_with_config(db_path: &Path, config: &AppConfig) -> AppResult<Self> {
     let db_url = format!("sqlite://{}", db_path.display());
-    
+    let pragmas = &config.database;
+
+    let journal_mode = SqliteJournalMode::from_str(&pragmas.journal_mode)
+      .map_err(|e| AppError::Config(format!("Invalid journal_mode: {}", e)))?;
+    let synchronous = SqliteSynchronous::from_str(&pragmas.synchronous)
+      .map_err(|e| AppError::Config(format!("Invalid synchronous mode: {}", e)))?;
+
     let connect_options = SqliteConnectOptions::from_str(&db_url)
       .map_err(|e| AppError::Database(e.to_string()))?
-      .create_if_missing(true);
+      .create_if_missing(true)
+      .journal_mode(journal_mode)
+      .synchronous(synchronous)
+      .busy_timeout(Duration::from_millis(pragmas.busy_timeout_ms))
+      .foreign_keys(pragmas.foreign_keys);
 
     let pool = SqlitePoolOptions::new()
-      .max_connections(5)
+      .max_connections(config.db_pool_size)
       .connect_with(connect_options)
       .await
       .map_err(|e| AppError::Database(e.to_string()))?;
 
-    let start = std::time::Instant::now();
-    
-    info!("Running database migrations");
-    sqlx::query(
-      r#"
-      CREATE TABLE IF NOT EXISTS sessions (
-        id TEXT PRIMARY KEY,
-        name TEXT NOT NULL,
-        created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
-        updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
-      );
-      "#
-    )
-    .execute(&pool)
-    .await
-    .map_err(|e| AppError::Database(format!("Failed to create sessions table: {}", e)))?;
-
-    sqlx::query(
-      r#"
-      CREATE TABLE IF NOT EXISTS panes (
-        id TEXT PRIMARY KEY,
-        session_id TEXT NOT NULL,
-        position INTEGER NOT NULL,
-        created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
-        FOREIGN KEY (session_id) REFERENCES sessions(id)
-      );
-      "#
-    )
-    .execute(&pool)
-    .await
-    .map_err(|e| AppError::Database(format!("Failed to create panes table: {}", e)))?;
-
-    let elapsed = start.elapsed();
-    info!("Database migrations completed in {:?}", elapsed);
+    run_migrations(&pool).await?;
 
-    Ok(Database { pool })
+    Ok(Database {
+      pool,
+      maintenance: Arc::new(Mutex::new(())),
+    })
   }
 
   pub fn pool(&self) -> &SqlitePool {
     &self.pool
   }
+
+  /// Number of the last migration applied, as recorded in `PRAGMA user_version`
+  pub async fn schema_version(&self) -> AppResult<u32> {
+    schema_version(&self.pool).await
+  }
+
+  /// Rebuild the database file to reclaim pages freed by deletes
+  pub async fn vacuum(&self) -> AppResult<()> {
+    self.run_maintenance("VACUUM").await
+  }
+
+  /// Refresh the query planner's table statistics
+  pub async fn analyze(&self) -> AppResult<()> {
+    self.run_maintenance("ANALYZE").await
+  }
+
+  /// Run a maintenance statement once no other connection is checked out
+  async fn run_maintenance(&self, statement: &str) -> AppResult<()> {
+    let _guard = self.maintenance.try_lock()
+      .map_err(|_| AppError::Database("Database maintenance already running".to_string()))?;
+
+    let in_use = self.pool_stats().in_use;
+    if in_use > 0 {
+      return Err(AppError::Database(format!(
+        "Cannot run {} while {} connection(s) are in use", statement, in_use
+      )));
+    }
+
+    let start = std::time::Instant::now();
+    sqlx::query(statement)
+      .execute(&self.pool)
+      .await
+      .map_err(|e| AppError::Database(format!("{} failed: {}", statement, e)))?;
+
+    info!("{} completed in {:?}", statement, start.elapsed());
+    Ok(())
+  }
+
+  pub fn pool_stats(&self) -> PoolStats {
+    let size = self.pool.size();
+    let idle = self.pool.num_idle() as u32;
+    PoolStats {
+      size,
+      idle,
+      in_use: size.saturating_sub(idle),
+    }
+  }
 }
 
 #[cfg(test)]
@@ -86,6 +177,150 @@ mod tests {
     assert_eq!(result.0, 1);
   }
 
+  #[tokio::test]
+  async fn test_migrations_run_once_and_record_version() {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("test.db");
+
+    let latest = MIGRATIONS[MIGRATIONS.len() - 1].0;
+    let db = Database::init(&db_path).await.unwrap();
+    assert_eq!(db.schema_version().await.unwrap(), latest);
+    for table in ["sessions", "messages", "progress_events", "agents", "orchestrator_metrics"] {
+      let (count,): (i64,) =
+        sqlx::query_as("SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = ?")
+          .bind(table)
+          .fetch_one(db.pool())
+          .await
+          .unwrap();
+      assert_eq!(count, 1, "missing table {}", table);
+    }
+    drop(db);
+
+    // Reopening finds nothing left to apply (003's ALTER TABLE would fail twice)
+    let db = Database::init(&db_path).await.unwrap();
+    assert_eq!(db.schema_version().await.unwrap(), latest);
+  }
+
+  #[tokio::test]
+  async fn test_pragmas_applied() {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("test.db");
+
+    let db = Database::init(&db_path).await.unwrap();
+
+    let journal_mode: (String,) = sqlx::query_as("PRAGMA journal_mode")
+      .fetch_one(db.pool())
+      .await
+      .unwrap();
+    assert_eq!(journal_mode.0.to_lowercase(), "wal");
+
+    let busy_timeout: (i64,) = sqlx::query_as("PRAGMA busy_timeout")
+      .fetch_one(db.pool())
+      .await
+      .unwrap();
+    assert_eq!(busy_timeout.0, 5000);
+  }
+
+  #[tokio::test]
+  async fn test_concurrent_writers_under_wal() {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("test.db");
+
+    // Two independent pools simulate separate writers contending for the file
+    let writer_a = Database::init(&db_path).await.unwrap();
+    let writer_b = Database::init(&db_path).await.unwrap();
+
+    let write = |pool: SqlitePool, prefix: &'static str| async move {
+      for i in 0..25 {
+        sqlx::query(
+          "INSERT INTO sessions (id, name, created_at, updated_at) VALUES (?, ?, datetime('now'), datetime('now'))"
+        )
+          .bind(format!("{}-{}", prefix, i))
+          .bind(format!("{}-{}", prefix, i))
+          .execute(&pool)
+          .await?;
+      }
+      Ok::<_, sqlx::Error>(())
+    };
+
+    let (a, b) = tokio::join!(
+      write(writer_a.pool().clone(), "a"),
+      write(writer_b.pool().clone(), "b")
+    );
+    a.unwrap();
+    b.unwrap();
+
+    let count: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM sessions")
+      .fetch_one(writer_a.pool())
+      .await
+      .unwrap();
+    assert_eq!(count.0, 50);
+  }
+
+  #[tokio::test]
+  async fn test_pool_stats_reflect_held_connections() {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("test.db");
+
+    let db = Database::init(&db_path).await.unwrap();
+
+    let first = db.pool().acquire().await.unwrap();
+    let second = db.pool().acquire().await.unwrap();
+
+    let stats = db.pool_stats();
+    assert_eq!(stats.in_use, 2);
+    assert_eq!(stats.size, stats.idle + stats.in_use);
+
+    drop(first);
+    drop(second);
+  }
+
+  #[tokio::test]
+  async fn test_vacuum_after_bulk_delete() {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("test.db");
+
+    let db = Database::init(&db_path).await.unwrap();
+
+    for i in 0..200 {
+      sqlx::query(
+        "INSERT INTO sessions (id, name, created_at, updated_at) VALUES (?, ?, datetime('now'), datetime('now'))"
+      )
+        .bind(format!("session-{}", i))
+        .bind(format!("{}-{}", i, "x".repeat(512)))
+        .execute(db.pool())
+        .await
+        .unwrap();
+    }
+    sqlx::query("DELETE FROM sessions WHERE id != 'session-0'")
+      .execute(db.pool())
+      .await
+      .unwrap();
+
+    db.vacuum().await.unwrap();
+    db.analyze().await.unwrap();
+
+    let remaining: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM sessions")
+      .fetch_one(db.pool())
+      .await
+      .unwrap();
+    assert_eq!(remaining.0, 1);
+  }
+
+  #[tokio::test]
+  async fn test_vacuum_refused_while_connection_held() {
+    let temp_dir = TempDir::new().unwrap();
+    let db_path = temp_dir.path().join("test.db");
+
+    let db = Database::init(&db_path).await.unwrap();
+    let held = db.pool().begin().await.unwrap();
+
+    assert!(db.vacuum().await.is_err());
+
+    held.rollback().await.unwrap();
+    assert!(db.vacuum().await.is_ok());
+  }
+
   #[tokio::test]
   async fn test_sessions_table_created() {
     let temp_dir = TempDir::new().unwrap();
@@ -1,3 +1,4 @@
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -11,6 +12,9 @@ pub enum AppError {
   #[error("Keychain error: {0}")]
   Keychain(String),
 
+  #[error("Validation error: {0}")]
+  Validation(String),
+
   #[error("IO error: {0}")]
   Io(#[from] std::io::Error),
 
@@ -18,4 +22,29 @@ pub enum AppError {
   Unknown(String),
 }
 
+/// Stable error category, for callers that need to branch on the kind of failure
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+  Database,
+  Config,
+  Keychain,
+  Io,
+  Validation,
+  Unknown,
+}
+
+impl AppError {
+  pub fn code(&self) -> ErrorCode {
+    match self {
+      AppError::Database(_) => ErrorCode::Database,
+      AppError::Config(_) => ErrorCode::Config,
+      AppError::Keychain(_) => ErrorCode::Keychain,
+      AppError::Io(_) => ErrorCode::Io,
+      AppError::Validation(_) => ErrorCode::Validation,
+      AppError::Unknown(_) => ErrorCode::Unknown,
+    }
+  }
+}
+
 pub type AppResult<T> = Result<T, AppError>;
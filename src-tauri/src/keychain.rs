@@ -1,5 +1,6 @@
 use crate::error::{AppError, AppResult};
 
+#[derive(Clone)]
 pub struct KeychainManager {
   service_name: String,
 }
@@ -31,15 +32,119 @@ impl KeychainManager {
       .delete_password()
       .map_err(|e| AppError::Keychain(e.to_string()))
   }
+
+  pub async fn store_secret_async(&self, key: &str, value: &str) -> AppResult<()> {
+    let (key, value) = (key.to_string(), value.to_string());
+    self.run_blocking(move |manager| manager.store_secret(&key, &value)).await
+  }
+
+  pub async fn retrieve_secret_async(&self, key: &str) -> AppResult<String> {
+    let key = key.to_string();
+    self.run_blocking(move |manager| manager.retrieve_secret(&key)).await
+  }
+
+  pub async fn delete_secret_async(&self, key: &str) -> AppResult<()> {
+    let key = key.to_string();
+    self.run_blocking(move |manager| manager.delete_secret(&key)).await
+  }
+
+  /// Run a keyring call on the blocking pool so the OS keychain never stalls the runtime
+  async fn run_blocking<T, F>(&self, f: F) -> AppResult<T>
+  where
+    T: Send + 'static,
+    F: FnOnce(&KeychainManager) -> AppResult<T> + Send + 'static,
+  {
+    let manager = self.clone();
+    tokio::task::spawn_blocking(move || f(&manager))
+      .await
+      .map_err(|e| AppError::Keychain(format!("Keychain task failed: {}", e)))?
+  }
 }
 
 #[cfg(test)]
 mod tests {
   use super::*;
+  use keyring::credential::{Credential, CredentialApi, CredentialBuilder, CredentialBuilderApi};
+  use std::any::Any;
+  use std::collections::HashMap;
+  use std::sync::{Arc, Mutex, Once};
+
+  type Store = Arc<Mutex<HashMap<(String, String), String>>>;
+
+  /// In-memory keyring backend whose entries are shared across `Entry::new` calls
+  #[derive(Debug)]
+  struct MemoryCredential {
+    store: Store,
+    id: (String, String),
+  }
+
+  impl CredentialApi for MemoryCredential {
+    fn set_password(&self, password: &str) -> keyring::Result<()> {
+      self.store.lock().unwrap().insert(self.id.clone(), password.to_string());
+      Ok(())
+    }
+
+    fn get_password(&self) -> keyring::Result<String> {
+      self.store.lock().unwrap().get(&self.id).cloned().ok_or(keyring::Error::NoEntry)
+    }
+
+    fn delete_password(&self) -> keyring::Result<()> {
+      self.store.lock().unwrap().remove(&self.id).map(|_| ()).ok_or(keyring::Error::NoEntry)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+      self
+    }
+  }
+
+  #[derive(Debug, Default)]
+  struct MemoryCredentialBuilder {
+    store: Store,
+  }
+
+  impl CredentialBuilderApi for MemoryCredentialBuilder {
+    fn build(&self, _target: Option<&str>, service: &str, user: &str) -> keyring::Result<Box<Credential>> {
+      Ok(Box::new(MemoryCredential {
+        store: self.store.clone(),
+        id: (service.to_string(), user.to_string()),
+      }))
+    }
+
+    fn as_any(&self) -> &dyn Any {
+      self
+    }
+  }
+
+  fn use_memory_backend() {
+    static INIT: Once = Once::new();
+    INIT.call_once(|| {
+      let builder: Box<CredentialBuilder> = Box::new(MemoryCredentialBuilder::default());
+      keyring::set_default_credential_builder(builder);
+    });
+  }
 
   #[test]
   fn test_keychain_manager_creation() {
     let manager = KeychainManager::new("test-service");
     assert_eq!(manager.service_name, "test-service");
   }
+
+  #[tokio::test]
+  async fn test_async_secret_round_trip() {
+    use_memory_backend();
+    let manager = KeychainManager::new("test-service-async");
+
+    manager.store_secret_async("api-key", "s3cret").await.unwrap();
+
+    // The executor stays free to run other tasks while the keyring call is in flight
+    let (secret, ticked) = tokio::join!(manager.retrieve_secret_async("api-key"), async {
+      tokio::task::yield_now().await;
+      true
+    });
+    assert_eq!(secret.unwrap(), "s3cret");
+    assert!(ticked);
+
+    manager.delete_secret_async("api-key").await.unwrap();
+    assert!(manager.retrieve_secret_async("api-key").await.is_err());
+  }
 }
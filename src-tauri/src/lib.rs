@@ -1,10 +1,13 @@
 pub mod db;
 pub mod config;
+pub mod logging;
 pub mod keychain;
 pub mod error;
+pub mod clock;
 pub mod connectors;
 pub mod commands;
 pub mod runtime;
 pub mod memory;
 pub mod session;
 pub mod api;
+pub mod shutdown;
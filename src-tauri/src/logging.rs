@@ -0,0 +1,57 @@
+use crate::config::{AppConfig, LogFormat};
+use tracing::Subscriber;
+use tracing_subscriber::EnvFilter;
+
+/// Build the tracing subscriber described by the app config.
+///
+/// `RUST_LOG` still takes precedence over `log_level` when set.
+pub fn build_subscriber(config: &AppConfig) -> Box<dyn Subscriber + Send + Sync> {
+  let env_filter = EnvFilter::try_from_default_env()
+    .unwrap_or_else(|_| EnvFilter::new(&config.log_level));
+
+  let builder = tracing_subscriber::fmt()
+    .with_env_filter(env_filter)
+    .with_target(false)
+    .with_level(true);
+
+  match config.log_format {
+    LogFormat::Text => Box::new(builder.finish()),
+    LogFormat::Json => Box::new(builder.json().finish()),
+  }
+}
+
+/// Install the configured subscriber as the global default
+pub fn init_logging(config: &AppConfig) {
+  if let Err(e) = tracing::subscriber::set_global_default(build_subscriber(config)) {
+    eprintln!("Failed to initialize logging: {}", e);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_build_text_subscriber() {
+    let config = AppConfig::default();
+    assert_eq!(config.log_format, LogFormat::Text);
+
+    let subscriber = build_subscriber(&config);
+    tracing::subscriber::with_default(subscriber, || {
+      tracing::info!("text output");
+    });
+  }
+
+  #[test]
+  fn test_build_json_subscriber() {
+    let config = AppConfig {
+      log_format: LogFormat::Json,
+      ..AppConfig::default()
+    };
+
+    let subscriber = build_subscriber(&config);
+    tracing::subscriber::with_default(subscriber, || {
+      tracing::info!(agent = "test", "json output");
+    });
+  }
+}
@@ -2,23 +2,63 @@
 #![cfg(feature = "gui")]
 
 use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use async_trait::async_trait;
+use tokio::sync::Mutex;
 use tracing::{info, warn};
-use tracing_subscriber::EnvFilter;
 use agent_manager::{
+    config::AppConfig,
     db::Database,
     error::AppResult,
     commands::connectors::ConnectorState,
+    commands::database::DatabaseState,
+    commands::memory::MemoryState,
     commands::runtime::RuntimeState,
     commands::session::SessionState,
+    commands::system::SystemState,
+    connectors::Connector,
+    runtime::Orchestrator,
     session::SessionService,
+    shutdown::{MetricsFlusher, MetricsSources, Runtime},
 };
 
-fn main() -> AppResult<()> {
-  init_logging();
-  
-  info!("Starting Agent Manager application");
+/// How often connector and orchestrator metrics are written to the database
+const METRICS_FLUSH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Longest the app waits for the runtime to come down on exit
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Whatever connectors and orchestrator the commands have set up so far
+struct AppMetricsSources {
+  connectors: ConnectorState,
+  orchestrator: Arc<Mutex<Option<Arc<Orchestrator>>>>,
+}
+
+#[async_trait]
+impl MetricsSources for AppMetricsSources {
+  async fn connectors(&self) -> Vec<Arc<dyn Connector>> {
+    self.connectors.installed().await
+  }
 
+  async fn orchestrator(&self) -> Option<Arc<Orchestrator>> {
+    self.orchestrator.lock().await.clone()
+  }
+}
+
+fn main() -> AppResult<()> {
   let app_data_dir = get_app_data_dir();
+  let config_path = app_data_dir.join("config.json");
+  let config = match AppConfig::load(&config_path) {
+    Ok(config) => config,
+    Err(e) => {
+      eprintln!("Failed to load {:?}, using the default config: {}", config_path, e);
+      AppConfig::default()
+    }
+  };
+  agent_manager::logging::init_logging(&config);
+
+  info!("Starting Agent Manager application");
   info!("App data directory: {:?}", app_data_dir);
 
   if let Err(e) = std::fs::create_dir_all(&app_data_dir) {
@@ -29,8 +69,8 @@ fn main() -> AppResult<()> {
   info!("Database path: {:?}", db_path);
 
   let rt = tokio::runtime::Runtime::new().expect("Failed to create tokio runtime");
-  let session_state = rt.block_on(async {
-    let db = match Database::init(&db_path).await {
+  let (db, schema_version, session_state, runtime_state) = rt.block_on(async {
+    let db = match Database::init_with_config(&db_path, &config).await {
       Ok(db) => {
         info!("Database initialized successfully");
         db
@@ -41,41 +81,104 @@ fn main() -> AppResult<()> {
       }
     };
 
-    let session_service = SessionService::new(db.pool().clone());
-    SessionState::new(session_service)
+    let schema_version = match db.schema_version().await {
+      Ok(version) => version,
+      Err(e) => {
+        eprintln!("Failed to read database schema version: {}", e);
+        std::process::exit(1);
+      }
+    };
+
+    let session_service =
+      SessionService::new(db.pool().clone()).with_max_metadata_bytes(config.max_metadata_bytes);
+    let runtime_state = match RuntimeState::from_config_persistent(&config, db.pool().clone()).await {
+      Ok(state) => state,
+      Err(e) => {
+        warn!("Failed to restore agents, starting with an empty registry: {}", e);
+        RuntimeState::from_config(&config)
+      }
+    };
+    (Arc::new(db), schema_version, SessionState::new(session_service), runtime_state)
+  });
+
+  let connector_state = ConnectorState::new();
+  let memory_state = MemoryState::with_manager(runtime_state.memory.clone());
+  let sources = Arc::new(AppMetricsSources {
+    connectors: connector_state.clone(),
+    orchestrator: runtime_state.orchestrator.clone(),
   });
+  let pool = db.pool().clone();
+  let flusher = {
+    let _runtime = rt.enter();
+    MetricsFlusher::new(pool.clone(), METRICS_FLUSH_INTERVAL)
+      .with_sources(sources.clone())
+      .spawn()
+  };
 
-  tauri::Builder::default()
-    .manage(ConnectorState::new())
-    .manage(RuntimeState::new())
+  let app = tauri::Builder::default()
+    .manage(connector_state)
+    .manage(runtime_state)
+    .manage(memory_state)
     .manage(session_state)
+    .manage(DatabaseState::new(db))
+    .manage(SystemState::new(&config, schema_version))
     .invoke_handler(tauri::generate_handler![
       agent_manager::commands::connectors::init_connector,
       agent_manager::commands::connectors::init_ollama,
+      agent_manager::commands::connectors::reinit_connector,
+      agent_manager::commands::connectors::warmup_connector,
       agent_manager::commands::connectors::get_connector_health,
       agent_manager::commands::connectors::get_connector_metrics,
+      agent_manager::commands::connectors::reset_connector_metrics,
       agent_manager::commands::connectors::switch_codex_model,
       agent_manager::commands::connectors::check_ollama_health,
       agent_manager::commands::connectors::list_ollama_models,
+      agent_manager::commands::connectors::get_available_models,
+      agent_manager::commands::connectors::execute_connector,
+      agent_manager::commands::connectors::cancel_connector_stream,
+      agent_manager::commands::connectors::test_connector,
       agent_manager::commands::runtime::register_agent,
+      agent_manager::commands::runtime::register_agents,
       agent_manager::commands::runtime::unregister_agent,
       agent_manager::commands::runtime::list_agents,
       agent_manager::commands::runtime::get_agent_metadata,
       agent_manager::commands::runtime::create_orchestrator,
       agent_manager::commands::runtime::start_orchestrator,
       agent_manager::commands::runtime::stop_orchestrator,
+      agent_manager::commands::runtime::pause_orchestrator,
+      agent_manager::commands::runtime::resume_orchestrator,
       agent_manager::commands::runtime::get_orchestrator_metrics,
+      agent_manager::commands::runtime::get_run_history,
+      agent_manager::commands::runtime::reset_agent_metrics,
       agent_manager::commands::runtime::get_queue_depth,
+      agent_manager::commands::runtime::get_agents_status,
+      agent_manager::commands::runtime::get_agent_topology,
+      agent_manager::commands::memory::dump_agent_memory,
+      agent_manager::commands::database::get_pool_stats,
+      agent_manager::commands::database::run_db_maintenance,
+      agent_manager::commands::system::get_backend_info,
       agent_manager::commands::session::create_session,
       agent_manager::commands::session::get_session,
       agent_manager::commands::session::list_sessions,
+      agent_manager::commands::session::list_sessions_by_activity,
       agent_manager::commands::session::update_session_status,
       agent_manager::commands::session::delete_session,
+      agent_manager::commands::session::save_session_template,
+      agent_manager::commands::session::list_session_templates,
+      agent_manager::commands::session::delete_session_template,
+      agent_manager::commands::session::create_session_from_template,
       agent_manager::commands::session::create_pane,
       agent_manager::commands::session::list_panes,
       agent_manager::commands::session::delete_pane,
+      agent_manager::commands::session::reorder_panes,
+      agent_manager::commands::session::set_pane_position,
+      agent_manager::commands::session::set_active_pane,
+      agent_manager::commands::session::get_active_pane,
       agent_manager::commands::session::add_message,
       agent_manager::commands::session::get_messages,
+      agent_manager::commands::session::get_thread,
+      agent_manager::commands::session::subscribe_session_messages,
+      agent_manager::commands::session::unsubscribe_session_messages,
       agent_manager::commands::session::get_pane_messages,
       agent_manager::commands::session::get_next_sequence_number,
       agent_manager::commands::session::create_block,
@@ -85,21 +188,30 @@ fn main() -> AppResult<()> {
       agent_manager::commands::session::get_block_attachments,
       agent_manager::commands::session::get_progress_timeline,
     ])
-    .run(tauri::generate_context!())
-    .expect("error while running tauri application");
+    .build(tauri::generate_context!())
+    .expect("error while building tauri application");
 
-  Ok(())
-}
-
-fn init_logging() {
-  let env_filter = EnvFilter::try_from_default_env()
-    .unwrap_or_else(|_| EnvFilter::new("info"));
+  let mut flusher = Some(flusher);
+  app.run(move |_, event| {
+    if !matches!(event, tauri::RunEvent::Exit) {
+      return;
+    }
+    let Some(flusher) = flusher.take() else {
+      return;
+    };
+    rt.block_on(async {
+      let mut runtime = Runtime::new().with_pool(pool.clone()).with_flusher(flusher);
+      for connector in sources.connectors().await {
+        runtime = runtime.with_connector(connector);
+      }
+      if let Some(orchestrator) = sources.orchestrator().await {
+        runtime = runtime.with_orchestrator(orchestrator);
+      }
+      runtime.shutdown(SHUTDOWN_TIMEOUT).await;
+    });
+  });
 
-  tracing_subscriber::fmt()
-    .with_env_filter(env_filter)
-    .with_target(false)
-    .with_level(true)
-    .init();
+  Ok(())
 }
 
 fn get_app_data_dir() -> PathBuf {
@@ -1,14 +1,97 @@
 use super::types::{BlackboardEntry, BlackboardStats, EntryId};
-use std::collections::HashMap;
+use crate::clock::{system_clock, Clock};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, VecDeque};
 use std::sync::Arc;
-use std::time::SystemTime;
-use tokio::sync::RwLock;
+use std::time::{Duration, SystemTime};
+use tokio::sync::{watch, Mutex, RwLock};
+use tokio::task::JoinHandle;
+
+/// Recall results cached unless configured
+pub const DEFAULT_RECALL_CACHE_CAPACITY: usize = 64;
+
+/// How long a cached recall result is reused unless configured
+pub const DEFAULT_RECALL_CACHE_TTL: Duration = Duration::from_secs(2);
+
+/// Cached recall key: query embedding hash, `top_k` and the score floor's bits
+type RecallKey = ([u8; 32], usize, u32);
+
+/// Recent recall results, dropped wholesale whenever the blackboard changes
+struct RecallCache {
+    capacity: usize,
+    ttl: Duration,
+    results: HashMap<RecallKey, (SystemTime, Vec<(f32, BlackboardEntry)>)>,
+    order: VecDeque<RecallKey>,
+}
+
+impl RecallCache {
+    fn new(capacity: usize, ttl: Duration) -> Self {
+        Self {
+            capacity,
+            ttl,
+            results: HashMap::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    fn key(query_embedding: &[f32], top_k: usize, min_score: f32) -> RecallKey {
+        let mut hasher = Sha256::new();
+        for value in query_embedding {
+            hasher.update(value.to_le_bytes());
+        }
+        (hasher.finalize().into(), top_k, min_score.to_bits())
+    }
+
+    fn get(&mut self, key: &RecallKey, now: SystemTime) -> Option<Vec<(f32, BlackboardEntry)>> {
+        let (cached_at, results) = self.results.get(key)?;
+        let fresh = now
+            .duration_since(*cached_at)
+            .map_or(true, |age| age < self.ttl);
+        if !fresh {
+            self.results.remove(key);
+            self.order.retain(|k| k != key);
+            return None;
+        }
+
+        let results = results.clone();
+        self.order.retain(|k| k != key);
+        self.order.push_back(*key);
+        Some(results)
+    }
+
+    fn insert(&mut self, key: RecallKey, now: SystemTime, results: Vec<(f32, BlackboardEntry)>) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        if self.results.insert(key, (now, results)).is_some() {
+            self.order.retain(|k| *k != key);
+        }
+        self.order.push_back(key);
+        while self.results.len() > self.capacity {
+            match self.order.pop_front() {
+                Some(lru) => {
+                    self.results.remove(&lru);
+                }
+                None => break,
+            }
+        }
+    }
+
+    fn clear(&mut self) {
+        self.results.clear();
+        self.order.clear();
+    }
+}
 
 /// Shared blackboard with TTL and LRU eviction
 pub struct Blackboard {
     entries: Arc<RwLock<HashMap<String, BlackboardEntry>>>,
     max_entries: usize,
     stats: Arc<RwLock<BlackboardStats>>,
+    watchers: Arc<RwLock<HashMap<String, watch::Sender<Option<BlackboardEntry>>>>>,
+    clock: Arc<dyn Clock>,
+    recall_cache: Arc<Mutex<RecallCache>>,
 }
 
 impl Blackboard {
@@ -18,27 +101,138 @@ impl Blackboard {
             entries: Arc::new(RwLock::new(HashMap::new())),
             max_entries,
             stats: Arc::new(RwLock::new(BlackboardStats::default())),
+            watchers: Arc::new(RwLock::new(HashMap::new())),
+            clock: system_clock(),
+            recall_cache: Arc::new(Mutex::new(RecallCache::new(
+                DEFAULT_RECALL_CACHE_CAPACITY,
+                DEFAULT_RECALL_CACHE_TTL,
+            ))),
+        }
+    }
+
+    /// Use `clock` for expiry and access times instead of the system clock
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Clock expiry and access times are measured on, for `BlackboardEntry::with_ttl`
+    pub fn clock(&self) -> &dyn Clock {
+        self.clock.as_ref()
+    }
+
+    /// Cache up to `capacity` recall results for `ttl` (0 disables caching)
+    pub fn with_recall_cache(mut self, capacity: usize, ttl: Duration) -> Self {
+        self.recall_cache = Arc::new(Mutex::new(RecallCache::new(capacity, ttl)));
+        self
+    }
+
+    /// Drop cached recall results; called with the entries lock held so a
+    /// concurrent recall can't cache a result from before the write
+    async fn invalidate_recalls(&self) {
+        self.recall_cache.lock().await.clear();
+    }
+
+    /// Watch a key, receiving its entry whenever it is put or removed
+    ///
+    /// Removal, eviction and expiry all send `None`. Expiry is noticed on the
+    /// next write, a `get` of the key, or a sweep (see `spawn_expiry_sweeper`).
+    pub async fn watch(&self, key: &str) -> watch::Receiver<Option<BlackboardEntry>> {
+        let now = self.clock.now();
+        let entries = self.entries.read().await;
+        let mut watchers = self.watchers.write().await;
+
+        if let Some(sender) = watchers.get(key) {
+            return sender.subscribe();
+        }
+
+        let current = entries.get(key).filter(|e| !e.is_expired_at(now)).cloned();
+        let (sender, receiver) = watch::channel(current);
+        watchers.insert(key.to_string(), sender);
+        receiver
+    }
+
+    /// Notify watchers of a key about its new state
+    async fn notify(&self, key: &str, entry: Option<BlackboardEntry>) {
+        let mut watchers = self.watchers.write().await;
+
+        if let Some(sender) = watchers.get(key) {
+            if sender.is_closed() {
+                watchers.remove(key);
+            } else {
+                sender.send_replace(entry);
+            }
         }
     }
 
     /// Put an entry in the blackboard
     pub async fn put(&self, mut entry: BlackboardEntry) {
         // Touch the entry
-        entry.touch();
+        entry.touch_at(self.clock.now());
 
         let mut entries = self.entries.write().await;
         let mut stats = self.stats.write().await;
 
         // Remove expired entries first
-        self.cleanup_expired(&mut entries, &mut stats).await;
+        let expired = self.cleanup_expired(&mut entries, &mut stats).await;
 
         // Check if we need to evict (LRU)
+        let mut evicted = None;
         if entries.len() >= self.max_entries && !entries.contains_key(&entry.key) {
-            self.evict_lru(&mut entries, &mut stats).await;
+            evicted = self.evict_lru(&mut entries, &mut stats).await;
         }
 
-        entries.insert(entry.key.clone(), entry);
+        let key = entry.key.clone();
+        entries.insert(key.clone(), entry.clone());
         stats.total_entries = entries.len();
+        self.invalidate_recalls().await;
+
+        for expired_key in expired.iter().filter(|k| **k != key) {
+            self.notify(expired_key, None).await;
+        }
+        if let Some(evicted_key) = evicted {
+            self.notify(&evicted_key, None).await;
+        }
+        self.notify(&key, Some(entry)).await;
+    }
+
+    /// Atomically set `key` to `new_value` only if its current value matches `expected`.
+    ///
+    /// `None` means the key must be absent (or expired). Returns whether the swap happened.
+    pub async fn compare_and_set(&self, key: &str, expected: Option<&str>, new_value: String) -> bool {
+        let mut entries = self.entries.write().await;
+        let mut stats = self.stats.write().await;
+
+        for expired_key in self.cleanup_expired(&mut entries, &mut stats).await {
+            self.notify(&expired_key, None).await;
+        }
+
+        let current = entries.get(key).map(|e| e.value.as_str());
+        if current != expected {
+            return false;
+        }
+
+        let updated = if let Some(entry) = entries.get_mut(key) {
+            entry.value = new_value;
+            entry.touch_at(self.clock.now());
+            entry.clone()
+        } else {
+            if entries.len() >= self.max_entries {
+                if let Some(evicted_key) = self.evict_lru(&mut entries, &mut stats).await {
+                    self.notify(&evicted_key, None).await;
+                }
+            }
+
+            let mut entry = BlackboardEntry::new(key.to_string(), new_value);
+            entry.touch_at(self.clock.now());
+            entries.insert(key.to_string(), entry.clone());
+            entry
+        };
+
+        stats.total_entries = entries.len();
+        self.invalidate_recalls().await;
+        self.notify(key, Some(updated)).await;
+        true
     }
 
     /// Get an entry by key
@@ -47,15 +241,16 @@ impl Blackboard {
         let mut stats = self.stats.write().await;
 
         if let Some(entry) = entries.get_mut(key) {
-            if entry.is_expired() {
+            if entry.is_expired_at(self.clock.now()) {
                 // Remove expired entry
                 entries.remove(key);
                 stats.expired_entries += 1;
                 stats.miss_count += 1;
+                self.notify(key, None).await;
                 None
             } else {
                 // Touch and return
-                entry.touch();
+                entry.touch_at(self.clock.now());
                 stats.total_accesses += 1;
                 stats.hit_count += 1;
                 Some(entry.clone())
@@ -66,6 +261,53 @@ impl Blackboard {
         }
     }
 
+    /// Export all live entries
+    pub async fn export(&self) -> Vec<BlackboardEntry> {
+        let now = self.clock.now();
+        self.entries
+            .read()
+            .await
+            .values()
+            .filter(|e| !e.is_expired_at(now))
+            .cloned()
+            .collect()
+    }
+
+    /// Import previously exported entries as-is, skipping expired ones
+    pub async fn import(&self, imported: Vec<BlackboardEntry>) {
+        let now = self.clock.now();
+        for entry in imported.into_iter().filter(|e| !e.is_expired_at(now)) {
+            let mut entries = self.entries.write().await;
+            let mut stats = self.stats.write().await;
+
+            let mut evicted = None;
+            if entries.len() >= self.max_entries && !entries.contains_key(&entry.key) {
+                evicted = self.evict_lru(&mut entries, &mut stats).await;
+            }
+
+            let key = entry.key.clone();
+            entries.insert(key.clone(), entry.clone());
+            stats.total_entries = entries.len();
+            self.invalidate_recalls().await;
+
+            if let Some(evicted_key) = evicted {
+                self.notify(&evicted_key, None).await;
+            }
+            self.notify(&key, Some(entry)).await;
+        }
+    }
+
+    /// Get a live entry without touching it or affecting stats
+    pub async fn peek(&self, key: &str) -> Option<BlackboardEntry> {
+        let now = self.clock.now();
+        self.entries
+            .read()
+            .await
+            .get(key)
+            .filter(|e| !e.is_expired_at(now))
+            .cloned()
+    }
+
     /// Remove an entry by key
     pub async fn remove(&self, key: &str) -> bool {
         let mut entries = self.entries.write().await;
@@ -73,6 +315,11 @@ impl Blackboard {
 
         let removed = entries.remove(key).is_some();
         stats.total_entries = entries.len();
+
+        if removed {
+            self.invalidate_recalls().await;
+            self.notify(key, None).await;
+        }
         removed
     }
 
@@ -86,14 +333,71 @@ impl Blackboard {
         self.entries.read().await.values().cloned().collect()
     }
 
+    /// Get all live entries whose key starts with `prefix`
+    pub async fn scan_prefix(&self, prefix: &str) -> Vec<BlackboardEntry> {
+        let now = self.clock.now();
+        self.entries
+            .read()
+            .await
+            .values()
+            .filter(|e| e.key.starts_with(prefix) && !e.is_expired_at(now))
+            .cloned()
+            .collect()
+    }
+
+    /// List all live keys that start with `prefix`
+    pub async fn keys_with_prefix(&self, prefix: &str) -> Vec<String> {
+        let now = self.clock.now();
+        self.entries
+            .read()
+            .await
+            .values()
+            .filter(|e| e.key.starts_with(prefix) && !e.is_expired_at(now))
+            .map(|e| e.key.clone())
+            .collect()
+    }
+
     /// Semantic recall - find entries similar to query using embeddings
     pub async fn recall(&self, query_embedding: &[f32], top_k: usize) -> Vec<BlackboardEntry> {
+        self.recall_scored(query_embedding, top_k)
+            .await
+            .into_iter()
+            .map(|(_, entry)| entry)
+            .collect()
+    }
+
+    /// Semantic recall returning `(similarity, entry)` pairs, highest similarity first
+    pub async fn recall_scored(&self, query_embedding: &[f32], top_k: usize) -> Vec<(f32, BlackboardEntry)> {
+        self.recall_scored_above(query_embedding, top_k, f32::MIN).await
+    }
+
+    /// Semantic recall keeping only entries whose similarity is at least `min_score`
+    ///
+    /// Repeating a query with no write in between is served from the recall
+    /// cache for up to its TTL instead of rescanning every entry.
+    pub async fn recall_scored_above(
+        &self,
+        query_embedding: &[f32],
+        top_k: usize,
+        min_score: f32,
+    ) -> Vec<(f32, BlackboardEntry)> {
+        let now = self.clock.now();
+        let key = RecallCache::key(query_embedding, top_k, min_score);
+        let cached = self.recall_cache.lock().await.get(&key, now);
+        if let Some(cached) = cached {
+            self.stats.write().await.recall_cache_hits += 1;
+            return cached
+                .into_iter()
+                .filter(|(_, e)| !e.is_expired_at(now))
+                .collect();
+        }
+
         let start = std::time::Instant::now();
 
         let entries = self.entries.read().await;
         let mut results: Vec<(f32, BlackboardEntry)> = entries
             .values()
-            .filter(|e| !e.is_expired() && e.embedding.is_some())
+            .filter(|e| !e.is_expired_at(now) && e.embedding.is_some())
             .map(|e| {
                 let similarity = cosine_similarity(
                     query_embedding,
@@ -101,21 +405,21 @@ impl Blackboard {
                 );
                 (similarity, e.clone())
             })
+            .filter(|(similarity, _)| *similarity >= min_score)
             .collect();
 
         // Sort by similarity (descending)
         results.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap());
 
         // Take top-k
-        let top_results: Vec<BlackboardEntry> = results
-            .into_iter()
-            .take(top_k)
-            .map(|(_, entry)| entry)
-            .collect();
+        results.truncate(top_k);
+        self.recall_cache.lock().await.insert(key, now, results.clone());
+        drop(entries);
 
         // Update stats
         let elapsed = start.elapsed().as_millis() as f64;
         let mut stats = self.stats.write().await;
+        stats.recall_scans += 1;
         let n = (stats.hit_count + stats.miss_count) as f64;
         if n > 0.0 {
             stats.avg_recall_latency_ms = (stats.avg_recall_latency_ms * (n - 1.0) + elapsed) / n;
@@ -123,7 +427,42 @@ impl Blackboard {
             stats.avg_recall_latency_ms = elapsed;
         }
 
-        top_results
+        results
+    }
+
+    /// Hybrid recall blending vector similarity with keyword overlap on the value.
+    ///
+    /// Each entry scores `alpha * cosine + (1 - alpha) * keyword`, so `alpha = 1.0`
+    /// is pure vector recall and `alpha = 0.0` is pure keyword matching.
+    pub async fn hybrid_recall(
+        &self,
+        query_text: &str,
+        query_embedding: &[f32],
+        top_k: usize,
+        alpha: f32,
+    ) -> Vec<(f32, BlackboardEntry)> {
+        let now = self.clock.now();
+        let alpha = alpha.clamp(0.0, 1.0);
+
+        let entries = self.entries.read().await;
+        let mut results: Vec<(f32, BlackboardEntry)> = entries
+            .values()
+            .filter(|e| !e.is_expired_at(now))
+            .map(|e| {
+                let vector_score = e
+                    .embedding
+                    .as_ref()
+                    .map(|emb| cosine_similarity(query_embedding, emb))
+                    .unwrap_or(0.0);
+                let text_score = keyword_score(query_text, &e.value);
+                (alpha * vector_score + (1.0 - alpha) * text_score, e.clone())
+            })
+            .filter(|(score, _)| *score > 0.0)
+            .collect();
+
+        results.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap());
+        results.truncate(top_k);
+        results
     }
 
     /// Clear all entries
@@ -133,56 +472,94 @@ impl Blackboard {
 
         entries.clear();
         stats.total_entries = 0;
+        self.invalidate_recalls().await;
+
+        let mut watchers = self.watchers.write().await;
+        watchers.retain(|_, sender| !sender.is_closed());
+        for sender in watchers.values() {
+            sender.send_replace(None);
+        }
     }
 
     /// Get statistics
     pub async fn stats(&self) -> BlackboardStats {
+        let now = self.clock.now();
         let mut stats = self.stats.write().await;
 
         // Update expired count
         let entries = self.entries.read().await;
-        stats.expired_entries = entries.values().filter(|e| e.is_expired()).count();
+        stats.expired_entries = entries.values().filter(|e| e.is_expired_at(now)).count();
 
         stats.clone()
     }
 
-    /// Cleanup expired entries
+    /// Remove expired entries now and tell their watchers, returning how many went
+    pub async fn sweep_expired(&self) -> usize {
+        let mut entries = self.entries.write().await;
+        let mut stats = self.stats.write().await;
+
+        let expired = self.cleanup_expired(&mut entries, &mut stats).await;
+        if !expired.is_empty() {
+            self.invalidate_recalls().await;
+        }
+        for key in &expired {
+            self.notify(key, None).await;
+        }
+        expired.len()
+    }
+
+    /// Every `interval`, sweep expired entries so watchers hear about expiry
+    /// without waiting for the next write
+    pub fn spawn_expiry_sweeper(self: Arc<Self>, interval: Duration) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+                self.sweep_expired().await;
+            }
+        })
+    }
+
+    /// Cleanup expired entries, returning their keys
     async fn cleanup_expired(
         &self,
         entries: &mut HashMap<String, BlackboardEntry>,
         stats: &mut BlackboardStats,
-    ) {
+    ) -> Vec<String> {
+        let now = self.clock.now();
         let expired_keys: Vec<String> = entries
             .iter()
-            .filter(|(_, e)| e.is_expired())
+            .filter(|(_, e)| e.is_expired_at(now))
             .map(|(k, _)| k.clone())
             .collect();
 
-        for key in expired_keys {
-            entries.remove(&key);
+        for key in &expired_keys {
+            entries.remove(key);
             stats.expired_entries += 1;
         }
 
         stats.total_entries = entries.len();
+        expired_keys
     }
 
-    /// Evict least recently used entry
+    /// Evict least recently used entry, returning its key
     async fn evict_lru(
         &self,
         entries: &mut HashMap<String, BlackboardEntry>,
         stats: &mut BlackboardStats,
-    ) {
-        if let Some((lru_key, _)) = entries
+    ) -> Option<String> {
+        let lru_key = entries
             .iter()
             .min_by_key(|(_, e)| e.last_accessed)
-        {
-            let lru_key = lru_key.clone();
-            entries.remove(&lru_key);
+            .map(|(k, _)| k.clone());
+
+        if let Some(lru_key) = &lru_key {
+            entries.remove(lru_key);
             stats.eviction_count += 1;
             tracing::debug!("Evicted LRU entry: {}", lru_key);
         }
 
         stats.total_entries = entries.len();
+        lru_key
     }
 }
 
@@ -203,9 +580,41 @@ fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
     }
 }
 
+/// Keyword score in [0, 1]: 1.0 for an exact substring match, otherwise the
+/// fraction of query tokens that appear in the text
+fn keyword_score(query: &str, text: &str) -> f32 {
+    let query = query.trim().to_lowercase();
+    if query.is_empty() {
+        return 0.0;
+    }
+
+    let text = text.to_lowercase();
+    if text.contains(&query) {
+        return 1.0;
+    }
+
+    let text_tokens: std::collections::HashSet<&str> = text
+        .split(|c: char| !c.is_alphanumeric() && c != '_')
+        .filter(|t| !t.is_empty())
+        .collect();
+    let query_tokens: Vec<&str> = query
+        .split(|c: char| !c.is_alphanumeric() && c != '_')
+        .filter(|t| !t.is_empty())
+        .collect();
+
+    if query_tokens.is_empty() {
+        return 0.0;
+    }
+
+    let matched = query_tokens.iter().filter(|t| text_tokens.contains(*t)).count();
+    matched as f32 / query_tokens.len() as f32
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::clock::MockClock;
+    use std::time::Duration;
 
     #[tokio::test]
     async fn test_blackboard_put_get() {
@@ -221,15 +630,16 @@ mod tests {
 
     #[tokio::test]
     async fn test_blackboard_ttl() {
-        let bb = Blackboard::new(10);
+        let clock = Arc::new(MockClock::new());
+        let bb = Blackboard::new(10).with_clock(clock.clone());
 
         let entry = BlackboardEntry::new("key1".to_string(), "value1".to_string())
-            .with_ttl(0); // Expires immediately
+            .with_ttl(1, clock.as_ref());
 
         bb.put(entry).await;
+        assert!(bb.peek("key1").await.is_some());
 
-        // Small delay to ensure expiration
-        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        clock.advance(Duration::from_secs(2));
 
         // Should return None due to expiration
         let retrieved = bb.get("key1").await;
@@ -239,20 +649,42 @@ mod tests {
         assert_eq!(stats.miss_count, 1);
     }
 
+    #[tokio::test]
+    async fn test_blackboard_sliding_ttl() {
+        let clock = Arc::new(MockClock::new());
+        let bb = Blackboard::new(10).with_clock(clock.clone());
+
+        let entry = BlackboardEntry::new("cached".to_string(), "value".to_string())
+            .with_sliding_ttl(Duration::from_millis(200), clock.as_ref());
+        bb.put(entry).await;
+
+        // Keep accessing well past the original 200ms deadline
+        for _ in 0..4 {
+            clock.advance(Duration::from_millis(100));
+            assert!(bb.get("cached").await.is_some());
+        }
+
+        // Once access stops, the entry expires
+        clock.advance(Duration::from_millis(300));
+        assert!(bb.get("cached").await.is_none());
+    }
+
     #[tokio::test]
     async fn test_blackboard_lru_eviction() {
-        let bb = Blackboard::new(3);
+        let clock = Arc::new(MockClock::new());
+        let bb = Blackboard::new(3).with_clock(clock.clone());
 
         // Add 3 entries
         for i in 0..3 {
             let entry = BlackboardEntry::new(format!("key{}", i), format!("value{}", i));
             bb.put(entry).await;
-            // Small delay to ensure different timestamps
-            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            // Ensure different timestamps
+            clock.advance(Duration::from_millis(10));
         }
 
         // Access key1 and key2 to update their last_accessed
         bb.get("key1").await;
+        clock.advance(Duration::from_millis(10));
         bb.get("key2").await;
 
         // Add a 4th entry, should evict key0 (least recently used)
@@ -290,6 +722,100 @@ mod tests {
         assert!(results.iter().any(|e| e.key == "doc1"));
     }
 
+    #[tokio::test]
+    async fn test_blackboard_recall_cache() {
+        let clock = Arc::new(MockClock::new());
+        let bb = Blackboard::new(10)
+            .with_clock(clock.clone())
+            .with_recall_cache(8, Duration::from_secs(2));
+        bb.put(
+            BlackboardEntry::new("doc1".to_string(), "about cats".to_string())
+                .with_embedding(vec![1.0, 0.0, 0.0]),
+        )
+        .await;
+
+        let query = vec![0.9, 0.1, 0.0];
+        let first = bb.recall(&query, 2).await;
+        let second = bb.recall(&query, 2).await;
+        assert_eq!(first.len(), 1);
+        assert_eq!(second[0].key, first[0].key);
+
+        let stats = bb.stats().await;
+        assert_eq!(stats.recall_scans, 1);
+        assert_eq!(stats.recall_cache_hits, 1);
+
+        // A different top_k is a different query
+        bb.recall(&query, 1).await;
+        assert_eq!(bb.stats().await.recall_scans, 2);
+
+        // Any write drops cached results
+        bb.put(
+            BlackboardEntry::new("doc2".to_string(), "about kittens".to_string())
+                .with_embedding(vec![0.8, 0.2, 0.0]),
+        )
+        .await;
+        assert_eq!(bb.recall(&query, 2).await.len(), 2);
+        assert_eq!(bb.stats().await.recall_scans, 3);
+
+        // So does the TTL running out
+        clock.advance(Duration::from_secs(3));
+        bb.recall(&query, 2).await;
+        assert_eq!(bb.stats().await.recall_scans, 4);
+    }
+
+    #[tokio::test]
+    async fn test_blackboard_recall_scored() {
+        let bb = Blackboard::new(10);
+
+        bb.put(BlackboardEntry::new("doc1".to_string(), "cats".to_string())
+            .with_embedding(vec![1.0, 0.0, 0.0])).await;
+        bb.put(BlackboardEntry::new("doc2".to_string(), "dogs".to_string())
+            .with_embedding(vec![0.0, 1.0, 0.0])).await;
+        bb.put(BlackboardEntry::new("doc3".to_string(), "both".to_string())
+            .with_embedding(vec![0.7, 0.7, 0.0])).await;
+
+        let query = vec![0.9, 0.1, 0.0];
+        let results = bb.recall_scored(&query, 3).await;
+
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].1.key, "doc1");
+        assert!(results.windows(2).all(|w| w[0].0 >= w[1].0));
+
+        let filtered = bb.recall_scored_above(&query, 3, 0.5).await;
+        assert_eq!(filtered.len(), 2);
+        assert!(filtered.iter().all(|(score, _)| *score >= 0.5));
+        assert!(!filtered.iter().any(|(_, e)| e.key == "doc2"));
+    }
+
+    #[tokio::test]
+    async fn test_blackboard_hybrid_recall_keyword_match() {
+        let bb = Blackboard::new(10);
+
+        bb.put(BlackboardEntry::new("doc1".to_string(), "notes on the parser".to_string())
+            .with_embedding(vec![1.0, 0.0, 0.0])).await;
+        bb.put(BlackboardEntry::new("doc2".to_string(), "parser design review".to_string())
+            .with_embedding(vec![0.9, 0.3, 0.0])).await;
+        bb.put(BlackboardEntry::new("doc3".to_string(), "crash with error E1234".to_string())
+            .with_embedding(vec![0.1, 0.0, 1.0])).await;
+
+        let query = vec![1.0, 0.1, 0.0];
+
+        // Pure vector recall misses the exact error code
+        let vector_only = bb.recall(&query, 2).await;
+        assert!(!vector_only.iter().any(|e| e.key == "doc3"));
+
+        let hybrid = bb.hybrid_recall("E1234", &query, 2, 0.5).await;
+        assert_eq!(hybrid.len(), 2);
+        assert!(hybrid.iter().any(|(_, e)| e.key == "doc3"));
+    }
+
+    #[test]
+    fn test_keyword_score() {
+        assert_eq!(keyword_score("E1234", "crash with error e1234"), 1.0);
+        assert_eq!(keyword_score("parser crash", "the crash happened"), 0.5);
+        assert_eq!(keyword_score("", "anything"), 0.0);
+    }
+
     #[tokio::test]
     async fn test_cosine_similarity() {
         let a = vec![1.0, 0.0, 0.0];
@@ -321,6 +847,116 @@ mod tests {
         assert_eq!(stats.total_entries, 1);
     }
 
+    #[tokio::test]
+    async fn test_blackboard_scan_prefix() {
+        let clock = Arc::new(MockClock::new());
+        let bb = Blackboard::new(10).with_clock(clock.clone());
+
+        bb.put(BlackboardEntry::new("task_a_result".to_string(), "a".to_string())).await;
+        bb.put(BlackboardEntry::new("task_b_result".to_string(), "b".to_string())).await;
+        bb.put(BlackboardEntry::new("notes".to_string(), "n".to_string())).await;
+        bb.put(
+            BlackboardEntry::new("task_c_result".to_string(), "c".to_string())
+                .with_ttl(0, clock.as_ref()),
+        ).await;
+
+        clock.advance(Duration::from_millis(10));
+
+        let mut values: Vec<String> = bb
+            .scan_prefix("task_")
+            .await
+            .into_iter()
+            .map(|e| e.value)
+            .collect();
+        values.sort();
+        assert_eq!(values, vec!["a".to_string(), "b".to_string()]);
+
+        let mut keys = bb.keys_with_prefix("task_").await;
+        keys.sort();
+        assert_eq!(keys, vec!["task_a_result".to_string(), "task_b_result".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_blackboard_compare_and_set() {
+        let bb = Blackboard::new(10);
+
+        assert!(bb.compare_and_set("task", None, "claimed".to_string()).await);
+        assert!(!bb.compare_and_set("task", None, "other".to_string()).await);
+        assert!(!bb.compare_and_set("task", Some("wrong"), "other".to_string()).await);
+        assert!(bb.compare_and_set("task", Some("claimed"), "done".to_string()).await);
+
+        assert_eq!(bb.get("task").await.unwrap().value, "done");
+    }
+
+    #[tokio::test]
+    async fn test_blackboard_compare_and_set_single_winner() {
+        let bb = Arc::new(Blackboard::new(10));
+
+        let handles: Vec<_> = (0..8)
+            .map(|i| {
+                let bb = bb.clone();
+                tokio::spawn(async move {
+                    bb.compare_and_set("task", None, format!("agent{}", i)).await
+                })
+            })
+            .collect();
+
+        let mut winners = 0;
+        for handle in handles {
+            if handle.await.unwrap() {
+                winners += 1;
+            }
+        }
+
+        assert_eq!(winners, 1);
+        assert!(bb.get("task").await.unwrap().value.starts_with("agent"));
+    }
+
+    #[tokio::test]
+    async fn test_blackboard_watch() {
+        let bb = Arc::new(Blackboard::new(10));
+
+        let mut rx = bb.watch("result").await;
+        assert!(rx.borrow().is_none());
+
+        let watcher = tokio::spawn(async move {
+            rx.changed().await.unwrap();
+            let entry = rx.borrow().clone();
+            entry.map(|e| e.value)
+        });
+
+        let producer = bb.clone();
+        tokio::spawn(async move {
+            producer
+                .put(BlackboardEntry::new("result".to_string(), "42".to_string()))
+                .await;
+        });
+
+        assert_eq!(watcher.await.unwrap(), Some("42".to_string()));
+
+        let mut rx = bb.watch("result").await;
+        bb.remove("result").await;
+        rx.changed().await.unwrap();
+        assert!(rx.borrow().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_watchers_are_told_about_expiry() {
+        let clock = Arc::new(MockClock::new());
+        let bb = Blackboard::new(10).with_clock(clock.clone());
+
+        bb.put(BlackboardEntry::new("lease".to_string(), "held".to_string()).with_ttl(1, clock.as_ref()))
+            .await;
+        let mut rx = bb.watch("lease").await;
+        assert!(rx.borrow().is_some());
+
+        clock.advance(Duration::from_secs(2));
+        assert_eq!(bb.sweep_expired().await, 1);
+        rx.changed().await.unwrap();
+        assert!(rx.borrow().is_none());
+        assert_eq!(bb.sweep_expired().await, 0);
+    }
+
     #[tokio::test]
     async fn test_blackboard_clear() {
         let bb = Blackboard::new(10);
@@ -0,0 +1,151 @@
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, VecDeque};
+
+/// SHA-256 digest of the embedded text
+type ContentHash = [u8; 32];
+
+/// LRU cache of embeddings keyed by content hash
+pub struct EmbeddingCache {
+    capacity: usize,
+    entries: HashMap<ContentHash, Vec<f32>>,
+    order: VecDeque<ContentHash>,
+    hit_count: u64,
+    miss_count: u64,
+}
+
+impl EmbeddingCache {
+    /// Create a new cache holding at most `capacity` embeddings (0 disables caching)
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: HashMap::new(),
+            order: VecDeque::new(),
+            hit_count: 0,
+            miss_count: 0,
+        }
+    }
+
+    /// Look up the embedding for a piece of text
+    pub fn get(&mut self, text: &str) -> Option<Vec<f32>> {
+        let hash = Self::hash(text);
+
+        match self.entries.get(&hash) {
+            Some(embedding) => {
+                let embedding = embedding.clone();
+                self.mark_used(hash);
+                self.hit_count += 1;
+                Some(embedding)
+            }
+            None => {
+                self.miss_count += 1;
+                None
+            }
+        }
+    }
+
+    /// Store the embedding for a piece of text, evicting the least recently used entry if full
+    pub fn insert(&mut self, text: &str, embedding: Vec<f32>) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let hash = Self::hash(text);
+        if self.entries.insert(hash, embedding).is_some() {
+            self.mark_used(hash);
+            return;
+        }
+
+        self.order.push_back(hash);
+        while self.entries.len() > self.capacity {
+            if let Some(lru) = self.order.pop_front() {
+                self.entries.remove(&lru);
+            } else {
+                break;
+            }
+        }
+    }
+
+    /// Number of cached embeddings
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Check if the cache is empty
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Maximum number of cached embeddings
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Number of lookups served from the cache
+    pub fn hit_count(&self) -> u64 {
+        self.hit_count
+    }
+
+    /// Number of lookups that missed the cache
+    pub fn miss_count(&self) -> u64 {
+        self.miss_count
+    }
+
+    /// Remove all cached embeddings
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.order.clear();
+    }
+
+    fn mark_used(&mut self, hash: ContentHash) {
+        if let Some(pos) = self.order.iter().position(|h| *h == hash) {
+            self.order.remove(pos);
+        }
+        self.order.push_back(hash);
+    }
+
+    fn hash(text: &str) -> ContentHash {
+        Sha256::digest(text.as_bytes()).into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache_hit_and_miss() {
+        let mut cache = EmbeddingCache::new(10);
+
+        assert!(cache.get("hello").is_none());
+        cache.insert("hello", vec![1.0, 2.0]);
+        assert_eq!(cache.get("hello"), Some(vec![1.0, 2.0]));
+
+        assert_eq!(cache.hit_count(), 1);
+        assert_eq!(cache.miss_count(), 1);
+    }
+
+    #[test]
+    fn test_cache_lru_eviction() {
+        let mut cache = EmbeddingCache::new(2);
+
+        cache.insert("a", vec![1.0]);
+        cache.insert("b", vec![2.0]);
+
+        // Touch "a" so "b" becomes least recently used
+        cache.get("a");
+        cache.insert("c", vec![3.0]);
+
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get("a").is_some());
+        assert!(cache.get("b").is_none());
+        assert!(cache.get("c").is_some());
+    }
+
+    #[test]
+    fn test_zero_capacity_disables_cache() {
+        let mut cache = EmbeddingCache::new(0);
+
+        cache.insert("a", vec![1.0]);
+        assert!(cache.is_empty());
+    }
+}
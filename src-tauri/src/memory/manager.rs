@@ -1,11 +1,15 @@
 use super::blackboard::Blackboard;
-use super::ring_buffer::RingBuffer;
-use super::types::{BlackboardEntry, BlackboardStats, MemoryEntry, MemoryStats};
+use super::embedding_cache::EmbeddingCache;
+use super::ring_buffer::{RingBuffer, DEFAULT_SUMMARIZATION_THRESHOLD};
+use super::types::{
+    AgentMemorySnapshot, BlackboardEntry, BlackboardStats, MemoryEntry, MemoryEvent, MemoryStats,
+};
 use crate::connectors::ollama::OllamaConnector;
+use crate::connectors::EmbeddingConnector;
 use crate::runtime::types::AgentId;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tokio::sync::{broadcast, Mutex, RwLock};
 
 /// Memory manager coordinates ring buffers and blackboard
 pub struct MemoryManager {
@@ -13,10 +17,24 @@ pub struct MemoryManager {
     agent_buffers: Arc<RwLock<HashMap<AgentId, Arc<RingBuffer>>>>,
     /// Shared blackboard
     blackboard: Arc<Blackboard>,
-    /// Ollama connector for embeddings
-    embeddings_connector: Option<Arc<OllamaConnector>>,
+    /// Connector for embeddings, separate from any chat connector
+    embeddings_connector: Option<Arc<dyn EmbeddingConnector>>,
+    /// Embeddings keyed by content hash, consulted before calling the connector
+    embedding_cache: Arc<Mutex<EmbeddingCache>>,
+    /// Blackboard keys each agent has read or written
+    blackboard_access: Arc<RwLock<HashMap<AgentId, HashSet<String>>>>,
+    /// Ring buffers shared by a team of agents, keyed by team ID
+    shared_buffers: Arc<RwLock<HashMap<String, Arc<RingBuffer>>>>,
+    /// Summarization progress, for callers that want to show activity
+    events: broadcast::Sender<MemoryEvent>,
 }
 
+/// Default number of embeddings kept in the cache
+const DEFAULT_EMBEDDING_CACHE_SIZE: usize = 1024;
+
+/// Number of memory events buffered for slow subscribers
+const EVENT_CHANNEL_CAPACITY: usize = 64;
+
 impl MemoryManager {
     /// Create a new memory manager
     pub fn new(blackboard_capacity: usize) -> Self {
@@ -24,18 +42,79 @@ impl MemoryManager {
             agent_buffers: Arc::new(RwLock::new(HashMap::new())),
             blackboard: Arc::new(Blackboard::new(blackboard_capacity)),
             embeddings_connector: None,
+            embedding_cache: Arc::new(Mutex::new(EmbeddingCache::new(DEFAULT_EMBEDDING_CACHE_SIZE))),
+            blackboard_access: Arc::new(RwLock::new(HashMap::new())),
+            shared_buffers: Arc::new(RwLock::new(HashMap::new())),
+            events: broadcast::channel(EVENT_CHANNEL_CAPACITY).0,
         }
     }
 
+    /// Subscribe to summarization progress events
+    pub fn subscribe(&self) -> broadcast::Receiver<MemoryEvent> {
+        self.events.subscribe()
+    }
+
     /// Set embeddings connector
     pub fn with_embeddings(mut self, connector: Arc<OllamaConnector>) -> Self {
         self.embeddings_connector = Some(connector);
         self
     }
 
+    /// Use a dedicated embeddings connector, e.g. another host or model than chat
+    ///
+    /// Fails if the connector's embedding model isn't available, so a
+    /// misconfigured endpoint shows up here rather than on the first recall.
+    pub async fn with_embedding_connector(
+        mut self,
+        connector: Arc<dyn EmbeddingConnector>,
+    ) -> Result<Self, String> {
+        connector.ensure_embedding_model().await.map_err(|e| {
+            format!(
+                "Embedding model {} is not available: {}",
+                connector.embedding_model(),
+                e
+            )
+        })?;
+        self.embeddings_connector = Some(connector);
+        Ok(self)
+    }
+
+    /// Set the maximum number of cached embeddings (0 disables caching)
+    pub fn with_embedding_cache_size(mut self, size: usize) -> Self {
+        self.embedding_cache = Arc::new(Mutex::new(EmbeddingCache::new(size)));
+        self
+    }
+
+    /// Embed text, reusing a cached embedding for identical content
+    async fn embed_cached(&self, connector: &dyn EmbeddingConnector, text: &str) -> Result<Vec<f32>, String> {
+        if let Some(embedding) = self.embedding_cache.lock().await.get(text) {
+            return Ok(embedding);
+        }
+
+        let embedding = connector.embed(text).await?;
+        self.embedding_cache.lock().await.insert(text, embedding.clone());
+        Ok(embedding)
+    }
+
+    /// Number of embeddings currently cached
+    pub async fn embedding_cache_len(&self) -> usize {
+        self.embedding_cache.lock().await.len()
+    }
+
     /// Create a ring buffer for an agent
     pub async fn create_agent_buffer(&self, agent_id: AgentId, capacity_tokens: u32) -> Arc<RingBuffer> {
-        let buffer = Arc::new(RingBuffer::new(capacity_tokens));
+        self.create_agent_buffer_with_threshold(agent_id, capacity_tokens, DEFAULT_SUMMARIZATION_THRESHOLD)
+            .await
+    }
+
+    /// Create a ring buffer for an agent that summarizes at `threshold` of capacity (0.0-1.0)
+    pub async fn create_agent_buffer_with_threshold(
+        &self,
+        agent_id: AgentId,
+        capacity_tokens: u32,
+        threshold: f32,
+    ) -> Arc<RingBuffer> {
+        let buffer = Arc::new(RingBuffer::new(capacity_tokens).with_threshold(threshold));
         self.agent_buffers.write().await.insert(agent_id, buffer.clone());
         buffer
     }
@@ -47,6 +126,7 @@ impl MemoryManager {
 
     /// Remove an agent's ring buffer
     pub async fn remove_agent_buffer(&self, agent_id: AgentId) -> bool {
+        self.blackboard_access.write().await.remove(&agent_id);
         self.agent_buffers.write().await.remove(&agent_id).is_some()
     }
 
@@ -61,21 +141,76 @@ impl MemoryManager {
 
         // Check if summarization is needed
         if buffer.should_summarize().await {
-            self.trigger_summarization(agent_id, buffer).await?;
+            self.trigger_summarization(&format!("agent {}", agent_id), buffer).await?;
         }
 
         Ok(())
     }
 
-    /// Trigger summarization for an agent's buffer
-    async fn trigger_summarization(&self, agent_id: AgentId, buffer: Arc<RingBuffer>) -> Result<(), String> {
-        tracing::info!("Triggering summarization for agent: {}", agent_id);
+    /// Create a ring buffer shared by a team of agents
+    pub async fn create_shared_buffer(&self, team_id: String, capacity_tokens: u32) -> Arc<RingBuffer> {
+        let buffer = Arc::new(RingBuffer::new(capacity_tokens));
+        self.shared_buffers.write().await.insert(team_id, buffer.clone());
+        buffer
+    }
 
-        let entries = buffer.get_all().await;
+    /// Get a team's shared ring buffer
+    pub async fn get_shared_buffer(&self, team_id: &str) -> Option<Arc<RingBuffer>> {
+        self.shared_buffers.read().await.get(team_id).cloned()
+    }
+
+    /// Remove a team's shared ring buffer
+    pub async fn remove_shared_buffer(&self, team_id: &str) -> bool {
+        self.shared_buffers.write().await.remove(team_id).is_some()
+    }
+
+    /// Add memory to a team's shared buffer, tagged with the writing agent
+    pub async fn add_to_shared(&self, team_id: &str, agent_id: AgentId, entry: MemoryEntry) -> Result<(), String> {
+        let buffer = self
+            .get_shared_buffer(team_id)
+            .await
+            .ok_or_else(|| format!("No shared buffer for team: {}", team_id))?;
+
+        buffer
+            .push(entry.with_metadata("agent_id".to_string(), agent_id.to_string()))
+            .await;
+
+        if buffer.should_summarize().await {
+            self.trigger_summarization(&format!("team {}", team_id), buffer).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Get all entries in a team's shared buffer
+    pub async fn get_shared(&self, team_id: &str) -> Option<Vec<MemoryEntry>> {
+        let buffer = self.get_shared_buffer(team_id).await?;
+        Some(buffer.get_all().await)
+    }
+
+    /// Trigger summarization for an agent's or team's buffer
+    async fn trigger_summarization(&self, owner: &str, buffer: Arc<RingBuffer>) -> Result<(), String> {
+        tracing::info!("Triggering summarization for {}", owner);
+
+        // Pinned entries survive summarization verbatim, so only the rest is compressed
+        let mut entries: Vec<MemoryEntry> = Vec::new();
+        buffer
+            .for_each(|e| {
+                if !e.is_pinned() {
+                    entries.push(e.clone());
+                }
+            })
+            .await;
         if entries.is_empty() {
             return Ok(());
         }
 
+        // No subscribers is fine, so send errors are ignored
+        let _ = self.events.send(MemoryEvent::SummarizationStarted {
+            owner: owner.to_string(),
+        });
+        let old_tokens = buffer.token_count().await;
+
         // Concatenate all content
         let full_content: String = entries
             .iter()
@@ -100,6 +235,12 @@ impl MemoryManager {
 
         buffer.summarize(summary, summary_tokens).await;
 
+        let _ = self.events.send(MemoryEvent::SummarizationCompleted {
+            owner: owner.to_string(),
+            old_tokens,
+            new_tokens: buffer.token_count().await,
+        });
+
         Ok(())
     }
 
@@ -108,8 +249,7 @@ impl MemoryManager {
         let embedding = if generate_embedding && self.embeddings_connector.is_some() {
             let connector = self.embeddings_connector.as_ref().unwrap();
             Some(
-                connector
-                    .embed(&value)
+                self.embed_cached(connector, &value)
                     .await
                     .map_err(|e| format!("Failed to generate embedding: {}", e))?,
             )
@@ -131,6 +271,79 @@ impl MemoryManager {
         self.blackboard.get(key).await
     }
 
+    /// Remove from blackboard, returning whether the key was present
+    pub async fn remove_from_blackboard(&self, key: &str) -> bool {
+        self.blackboard.remove(key).await
+    }
+
+    /// Add to blackboard on behalf of an agent, recording the access for snapshots
+    pub async fn add_to_blackboard_for_agent(
+        &self,
+        agent_id: AgentId,
+        key: String,
+        value: String,
+        generate_embedding: bool,
+    ) -> Result<(), String> {
+        self.record_blackboard_access(agent_id, &key).await;
+        self.add_to_blackboard(key, value, generate_embedding).await
+    }
+
+    /// Get from blackboard on behalf of an agent, recording the access for snapshots
+    pub async fn get_from_blackboard_for_agent(&self, agent_id: AgentId, key: &str) -> Option<BlackboardEntry> {
+        self.record_blackboard_access(agent_id, key).await;
+        self.blackboard.get(key).await
+    }
+
+    async fn record_blackboard_access(&self, agent_id: AgentId, key: &str) {
+        self.blackboard_access
+            .write()
+            .await
+            .entry(agent_id)
+            .or_default()
+            .insert(key.to_string());
+    }
+
+    /// Capture an agent's buffer, stats and accessed blackboard entries for debugging
+    pub async fn snapshot(&self, agent_id: AgentId) -> Option<AgentMemorySnapshot> {
+        let buffer = self.get_agent_buffer(agent_id).await?;
+        let entries = buffer.get_all().await;
+        let stats = buffer.stats().await;
+
+        let keys: Vec<String> = self
+            .blackboard_access
+            .read()
+            .await
+            .get(&agent_id)
+            .map(|keys| keys.iter().cloned().collect())
+            .unwrap_or_default();
+
+        let mut blackboard_entries = Vec::new();
+        for key in keys {
+            if let Some(entry) = self.blackboard.peek(&key).await {
+                blackboard_entries.push(entry);
+            }
+        }
+        blackboard_entries.sort_by(|a, b| a.key.cmp(&b.key));
+
+        Some(AgentMemorySnapshot {
+            agent_id,
+            entries,
+            stats,
+            blackboard_entries,
+            captured_at: std::time::SystemTime::now(),
+        })
+    }
+
+    /// Get all live blackboard entries whose key starts with `prefix`
+    pub async fn scan_blackboard_prefix(&self, prefix: &str) -> Vec<BlackboardEntry> {
+        self.blackboard.scan_prefix(prefix).await
+    }
+
+    /// List all live blackboard keys that start with `prefix`
+    pub async fn blackboard_keys_with_prefix(&self, prefix: &str) -> Vec<String> {
+        self.blackboard.keys_with_prefix(prefix).await
+    }
+
     /// Semantic recall from blackboard
     pub async fn recall(&self, query: &str, top_k: usize) -> Result<Vec<BlackboardEntry>, String> {
         if self.embeddings_connector.is_none() {
@@ -138,14 +351,45 @@ impl MemoryManager {
         }
 
         let connector = self.embeddings_connector.as_ref().unwrap();
-        let query_embedding = connector
-            .embed(query)
+        let query_embedding = self
+            .embed_cached(connector, query)
             .await
             .map_err(|e| format!("Failed to generate query embedding: {}", e))?;
 
         Ok(self.blackboard.recall(&query_embedding, top_k).await)
     }
 
+    /// Export an agent's ring buffer entries for warm-starting later
+    pub async fn export_agent(&self, agent_id: AgentId) -> Option<Vec<MemoryEntry>> {
+        let buffer = self.get_agent_buffer(agent_id).await?;
+        Some(buffer.get_all().await)
+    }
+
+    /// Replace an agent's ring buffer contents with exported entries, respecting capacity
+    pub async fn import_agent(&self, agent_id: AgentId, entries: Vec<MemoryEntry>) -> Result<(), String> {
+        let buffer = self
+            .get_agent_buffer(agent_id)
+            .await
+            .ok_or_else(|| format!("No buffer for agent: {}", agent_id))?;
+
+        buffer.clear().await;
+        for entry in entries {
+            buffer.push(entry).await;
+        }
+
+        Ok(())
+    }
+
+    /// Export all live blackboard entries
+    pub async fn export_blackboard(&self) -> Vec<BlackboardEntry> {
+        self.blackboard.export().await
+    }
+
+    /// Import previously exported blackboard entries
+    pub async fn import_blackboard(&self, entries: Vec<BlackboardEntry>) {
+        self.blackboard.import(entries).await;
+    }
+
     /// Get agent buffer stats
     pub async fn get_agent_stats(&self, agent_id: AgentId) -> Option<MemoryStats> {
         let buffer = self.get_agent_buffer(agent_id).await?;
@@ -259,4 +503,287 @@ mod tests {
         let stats = buffer.stats().await;
         assert!(stats.summarization_count > 0);
     }
+
+    #[tokio::test]
+    async fn test_summarization_preserves_pinned_entry() {
+        let manager = MemoryManager::new(100);
+        let agent_id = uuid::Uuid::new_v4();
+        let buffer = manager.create_agent_buffer(agent_id, 100).await;
+
+        let task = MemoryEntry::new("You are the reviewer agent".to_string(), 20).pinned();
+        let task_id = task.id;
+        manager.add_to_agent(agent_id, task).await.unwrap();
+
+        for i in 0..6 {
+            let entry = MemoryEntry::new(format!("chatter {} {}", i, "x".repeat(40)), 10);
+            manager.add_to_agent(agent_id, entry).await.unwrap();
+        }
+
+        assert_eq!(buffer.stats().await.summarization_count, 1);
+
+        let entries = buffer.get_all().await;
+        assert_eq!(entries[0].id, task_id);
+        assert_eq!(entries[0].content, "You are the reviewer agent");
+        assert_eq!(entries[0].token_count, 20);
+        assert!(!entries[1].content.contains("reviewer"));
+        assert!(entries[1].content.contains("..."));
+    }
+
+    #[tokio::test]
+    async fn test_agent_buffer_custom_threshold() {
+        let manager = MemoryManager::new(100);
+        let eager = uuid::Uuid::new_v4();
+        let default = uuid::Uuid::new_v4();
+
+        let eager_buffer = manager.create_agent_buffer_with_threshold(eager, 100, 0.5).await;
+        let default_buffer = manager.create_agent_buffer(default, 100).await;
+        assert_eq!(eager_buffer.threshold(), 0.5);
+
+        for agent_id in [eager, default] {
+            for i in 0..5 {
+                let entry = MemoryEntry::new(format!("entry{}", i), 10);
+                manager.add_to_agent(agent_id, entry).await.unwrap();
+            }
+        }
+
+        // 50 of 100 tokens reaches the 0.5 threshold but not the default 0.8
+        assert_eq!(eager_buffer.stats().await.summarization_count, 1);
+        assert_eq!(default_buffer.stats().await.summarization_count, 0);
+    }
+
+    #[tokio::test]
+    async fn test_summarization_events_bracket_summary() {
+        let manager = MemoryManager::new(100);
+        let agent_id = uuid::Uuid::new_v4();
+        let mut events = manager.subscribe();
+
+        manager.create_agent_buffer(agent_id, 50).await;
+        for i in 0..5 {
+            let entry = MemoryEntry::new(format!("entry {} with some content", i), 8);
+            manager.add_to_agent(agent_id, entry).await.unwrap();
+        }
+
+        let owner = format!("agent {}", agent_id);
+        assert_eq!(
+            events.try_recv().unwrap(),
+            MemoryEvent::SummarizationStarted { owner: owner.clone() }
+        );
+        match events.try_recv().unwrap() {
+            MemoryEvent::SummarizationCompleted { owner: completed_owner, old_tokens, new_tokens } => {
+                assert_eq!(completed_owner, owner);
+                assert_eq!(old_tokens, 40);
+                assert!(new_tokens < old_tokens);
+            }
+            other => panic!("Expected SummarizationCompleted, got {:?}", other),
+        }
+        assert!(events.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_memory_manager_shared_buffer() {
+        let manager = MemoryManager::new(100);
+        let agent1 = uuid::Uuid::new_v4();
+        let agent2 = uuid::Uuid::new_v4();
+
+        manager.create_agent_buffer(agent1, 100).await;
+        manager.create_agent_buffer(agent2, 100).await;
+        manager.create_shared_buffer("team".to_string(), 1000).await;
+
+        manager
+            .add_to_shared("team", agent1, MemoryEntry::new("from agent1".to_string(), 10))
+            .await
+            .unwrap();
+        manager
+            .add_to_shared("team", agent2, MemoryEntry::new("from agent2".to_string(), 10))
+            .await
+            .unwrap();
+        manager
+            .add_to_agent(agent1, MemoryEntry::new("private".to_string(), 5))
+            .await
+            .unwrap();
+
+        let shared = manager.get_shared("team").await.unwrap();
+        assert_eq!(shared.len(), 2);
+        assert_eq!(shared[0].metadata.get("agent_id"), Some(&agent1.to_string()));
+        assert_eq!(shared[1].metadata.get("agent_id"), Some(&agent2.to_string()));
+
+        assert_eq!(manager.get_agent_stats(agent1).await.unwrap().total_tokens, 5);
+        assert_eq!(manager.get_agent_stats(agent2).await.unwrap().total_tokens, 0);
+
+        assert!(manager
+            .add_to_shared("missing", agent1, MemoryEntry::new("x".to_string(), 1))
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_memory_manager_export_import_round_trip() {
+        let manager = MemoryManager::new(100);
+        let agent_id = uuid::Uuid::new_v4();
+
+        manager.create_agent_buffer(agent_id, 1000).await;
+        for i in 0..4 {
+            manager
+                .add_to_agent(agent_id, MemoryEntry::new(format!("entry{}", i), 10))
+                .await
+                .unwrap();
+        }
+        manager
+            .add_to_blackboard("plan".to_string(), "step 1".to_string(), false)
+            .await
+            .unwrap();
+
+        let exported = manager.export_agent(agent_id).await.unwrap();
+        let exported_blackboard = manager.export_blackboard().await;
+
+        let restored = MemoryManager::new(100);
+        restored.create_agent_buffer(agent_id, 1000).await;
+        restored.import_agent(agent_id, exported.clone()).await.unwrap();
+        restored.import_blackboard(exported_blackboard).await;
+
+        let entries = restored.export_agent(agent_id).await.unwrap();
+        assert_eq!(entries.len(), exported.len());
+        for (a, b) in entries.iter().zip(exported.iter()) {
+            assert_eq!(a.id, b.id);
+            assert_eq!(a.content, b.content);
+        }
+        assert_eq!(restored.get_agent_stats(agent_id).await.unwrap().total_tokens, 40);
+        assert_eq!(restored.get_from_blackboard("plan").await.unwrap().value, "step 1");
+    }
+
+    #[tokio::test]
+    async fn test_memory_manager_snapshot() {
+        let manager = MemoryManager::new(100);
+        let agent_id = uuid::Uuid::new_v4();
+
+        manager.create_agent_buffer(agent_id, 1000).await;
+        for i in 0..3 {
+            manager
+                .add_to_agent(agent_id, MemoryEntry::new(format!("entry{}", i), 10))
+                .await
+                .unwrap();
+        }
+
+        manager
+            .add_to_blackboard_for_agent(agent_id, "plan".to_string(), "step 1".to_string(), false)
+            .await
+            .unwrap();
+        manager
+            .add_to_blackboard("unrelated".to_string(), "other".to_string(), false)
+            .await
+            .unwrap();
+
+        let snapshot = manager.snapshot(agent_id).await.unwrap();
+        assert_eq!(snapshot.agent_id, agent_id);
+        assert_eq!(snapshot.entries.len(), 3);
+        assert_eq!(snapshot.entries[0].content, "entry0");
+        assert_eq!(snapshot.stats.total_tokens, 30);
+        assert_eq!(snapshot.stats.total_entries, 3);
+        assert_eq!(snapshot.blackboard_entries.len(), 1);
+        assert_eq!(snapshot.blackboard_entries[0].key, "plan");
+
+        let json = serde_json::to_string(&snapshot).unwrap();
+        assert!(json.contains("entry2"));
+
+        assert!(manager.snapshot(uuid::Uuid::new_v4()).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_embedding_cache_avoids_repeat_calls() {
+        use crate::connectors::ollama::OllamaConfig;
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/api/embeddings"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "embedding": [0.1, 0.2, 0.3]
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let address = mock_server.address();
+        let connector = OllamaConnector::new(OllamaConfig {
+            host: format!("http://{}", address.ip()),
+            port: address.port(),
+            max_retries: 1,
+            ..OllamaConfig::default()
+        });
+
+        let manager = MemoryManager::new(100).with_embeddings(Arc::new(connector));
+
+        manager
+            .add_to_blackboard("key1".to_string(), "same text".to_string(), true)
+            .await
+            .unwrap();
+        manager
+            .add_to_blackboard("key2".to_string(), "same text".to_string(), true)
+            .await
+            .unwrap();
+
+        assert_eq!(manager.embedding_cache_len().await, 1);
+        mock_server.verify().await;
+    }
+
+    #[tokio::test]
+    async fn test_dedicated_embedding_connector_used_for_recall() {
+        use crate::connectors::ollama::OllamaConfig;
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let embed_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/tags"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "models": [{"name": "mxbai-embed-large:latest", "size": 1}]
+            })))
+            .mount(&embed_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/embeddings"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "embedding": [1.0, 0.0]
+            })))
+            .expect(2)
+            .mount(&embed_server)
+            .await;
+
+        let address = embed_server.address();
+        let config = OllamaConfig {
+            host: format!("http://{}", address.ip()),
+            port: address.port(),
+            max_retries: 1,
+            embedding_model: "mxbai-embed-large".to_string(),
+            ..OllamaConfig::default()
+        };
+
+        let manager = MemoryManager::new(100)
+            .with_embedding_connector(Arc::new(OllamaConnector::new(config.clone())))
+            .await
+            .unwrap();
+        manager
+            .add_to_blackboard("plan".to_string(), "ship it".to_string(), true)
+            .await
+            .unwrap();
+
+        let recalled = manager.recall("what's the plan?", 1).await.unwrap();
+        assert_eq!(recalled.len(), 1);
+        assert_eq!(recalled[0].key, "plan");
+        embed_server.verify().await;
+
+        // A model the endpoint doesn't have is refused up front
+        let missing = OllamaConnector::new(OllamaConfig {
+            embedding_model: "not-pulled".to_string(),
+            ..config
+        });
+        let err = MemoryManager::new(100)
+            .with_embedding_connector(Arc::new(missing))
+            .await
+            .err()
+            .unwrap();
+        assert!(err.contains("not-pulled"), "{}", err);
+    }
 }
@@ -2,9 +2,11 @@
 pub mod types;
 pub mod ring_buffer;
 pub mod blackboard;
+pub mod embedding_cache;
 pub mod manager;
 
 pub use types::*;
-pub use ring_buffer::RingBuffer;
+pub use ring_buffer::{RingBuffer, DEFAULT_SUMMARIZATION_THRESHOLD};
 pub use blackboard::Blackboard;
+pub use embedding_cache::EmbeddingCache;
 pub use manager::MemoryManager;
@@ -3,6 +3,9 @@ use std::collections::VecDeque;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
+/// Fraction of capacity at which a buffer asks to be summarized
+pub const DEFAULT_SUMMARIZATION_THRESHOLD: f32 = 0.8;
+
 /// Ring buffer with token-based capacity
 pub struct RingBuffer {
     entries: Arc<Mutex<VecDeque<MemoryEntry>>>,
@@ -17,7 +20,7 @@ impl RingBuffer {
         Self {
             entries: Arc::new(Mutex::new(VecDeque::new())),
             capacity_tokens,
-            summarization_threshold: 0.8, // 80% of capacity
+            summarization_threshold: DEFAULT_SUMMARIZATION_THRESHOLD,
             stats: Arc::new(Mutex::new(MemoryStats {
                 capacity: capacity_tokens,
                 ..Default::default()
@@ -31,6 +34,11 @@ impl RingBuffer {
         self
     }
 
+    /// Get the summarization threshold
+    pub fn threshold(&self) -> f32 {
+        self.summarization_threshold
+    }
+
     /// Push a new entry, evicting old entries if necessary
     pub async fn push(&self, entry: MemoryEntry) {
         let mut entries = self.entries.lock().await;
@@ -41,9 +49,10 @@ impl RingBuffer {
         stats.total_tokens += entry.token_count;
         stats.total_entries = entries.len();
 
-        // Evict oldest entries if over capacity
+        // Evict least important (then oldest) entries if over capacity
         while stats.total_tokens > self.capacity_tokens {
-            if let Some(evicted) = entries.pop_front() {
+            let evicted = Self::eviction_candidate(&entries).and_then(|i| entries.remove(i));
+            if let Some(evicted) = evicted {
                 stats.total_tokens -= evicted.token_count;
                 stats.eviction_count += 1;
             } else {
@@ -54,6 +63,18 @@ impl RingBuffer {
         stats.total_entries = entries.len();
     }
 
+    /// Pick the entry to evict: lowest importance first, oldest among equals.
+    /// Pinned entries are only chosen once nothing else is left.
+    fn eviction_candidate(entries: &VecDeque<MemoryEntry>) -> Option<usize> {
+        entries
+            .iter()
+            .enumerate()
+            .filter(|(_, e)| !e.is_pinned())
+            .min_by_key(|(_, e)| e.importance)
+            .map(|(i, _)| i)
+            .or_else(|| if entries.is_empty() { None } else { Some(0) })
+    }
+
     /// Check if summarization should be triggered
     pub async fn should_summarize(&self) -> bool {
         let stats = self.stats.lock().await;
@@ -72,6 +93,20 @@ impl RingBuffer {
         entries.iter().rev().take(n).rev().cloned().collect()
     }
 
+    /// Visit every entry, oldest first, without cloning
+    ///
+    /// The buffer stays locked while `f` runs, so keep it short.
+    pub async fn for_each(&self, mut f: impl FnMut(&MemoryEntry)) {
+        for entry in self.entries.lock().await.iter() {
+            f(entry);
+        }
+    }
+
+    /// First entry, oldest first, matching `predicate`; only that entry is cloned
+    pub async fn find(&self, mut predicate: impl FnMut(&MemoryEntry) -> bool) -> Option<MemoryEntry> {
+        self.entries.lock().await.iter().find(|e| predicate(e)).cloned()
+    }
+
     /// Clear all entries
     pub async fn clear(&self) {
         let mut entries = self.entries.lock().await;
@@ -83,28 +118,32 @@ impl RingBuffer {
     }
 
     /// Summarize and compress the buffer
+    ///
+    /// Pinned entries are kept verbatim, in order, and the summary replaces
+    /// everything else after them.
     pub async fn summarize(&self, summary: String, summary_tokens: u32) {
         let mut entries = self.entries.lock().await;
         let mut stats = self.stats.lock().await;
 
-        // Clear old entries
+        // Drop everything except pinned entries
         let old_token_count = stats.total_tokens;
-        entries.clear();
+        entries.retain(|e| e.is_pinned());
+        let pinned_tokens: u32 = entries.iter().map(|e| e.token_count).sum();
 
         // Add summary as new entry
         let summary_entry = MemoryEntry::new(summary, summary_tokens);
         entries.push_back(summary_entry);
 
         // Update stats
-        stats.total_tokens = summary_tokens;
-        stats.total_entries = 1;
+        stats.total_tokens = pinned_tokens + summary_tokens;
+        stats.total_entries = entries.len();
         stats.summarization_count += 1;
 
         tracing::info!(
             "Buffer summarized: {} tokens → {} tokens ({:.1}% reduction)",
             old_token_count,
-            summary_tokens,
-            (1.0 - summary_tokens as f32 / old_token_count as f32) * 100.0
+            stats.total_tokens,
+            (1.0 - stats.total_tokens as f32 / old_token_count as f32) * 100.0
         );
     }
 
@@ -176,6 +215,38 @@ mod tests {
         assert!(!entries.iter().any(|e| e.content == "entry0"));
     }
 
+    #[tokio::test]
+    async fn test_ring_buffer_importance_eviction() {
+        let buffer = RingBuffer::new(50);
+
+        buffer.push(MemoryEntry::new("system prompt".to_string(), 10).pinned()).await;
+        buffer.push(MemoryEntry::new("key decision".to_string(), 10).with_importance(200)).await;
+
+        // Fill well past capacity with low-importance chatter
+        for i in 0..10 {
+            buffer.push(MemoryEntry::new(format!("chatter{}", i), 10).with_importance(10)).await;
+        }
+
+        let entries = buffer.get_all().await;
+        assert!(buffer.token_count().await <= 50);
+        assert!(entries.iter().any(|e| e.content == "system prompt"));
+        assert!(entries.iter().any(|e| e.content == "key decision"));
+        assert!(!entries.iter().any(|e| e.content == "chatter0"));
+        assert!(entries.iter().any(|e| e.content == "chatter9"));
+    }
+
+    #[tokio::test]
+    async fn test_ring_buffer_pinned_evicted_only_when_alone_over_capacity() {
+        let buffer = RingBuffer::new(15);
+
+        buffer.push(MemoryEntry::new("pinned1".to_string(), 10).pinned()).await;
+        buffer.push(MemoryEntry::new("pinned2".to_string(), 10).pinned()).await;
+
+        let entries = buffer.get_all().await;
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].content, "pinned2");
+    }
+
     #[tokio::test]
     async fn test_should_summarize() {
         let buffer = RingBuffer::new(100).with_threshold(0.8);
@@ -210,6 +281,25 @@ mod tests {
         assert_eq!(stats.summarization_count, 1);
     }
 
+    #[tokio::test]
+    async fn test_summarization_keeps_pinned_entries() {
+        let buffer = RingBuffer::new(100);
+
+        buffer.push(MemoryEntry::new("system prompt".to_string(), 10).pinned()).await;
+        for i in 0..5 {
+            buffer.push(MemoryEntry::new(format!("entry{}", i), 10)).await;
+        }
+
+        buffer.summarize("Summary of chatter".to_string(), 5).await;
+
+        let entries = buffer.get_all().await;
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].content, "system prompt");
+        assert!(entries[0].is_pinned());
+        assert_eq!(entries[1].content, "Summary of chatter");
+        assert_eq!(buffer.token_count().await, 15);
+    }
+
     #[tokio::test]
     async fn test_get_recent() {
         let buffer = RingBuffer::new(1000);
@@ -225,6 +315,23 @@ mod tests {
         assert_eq!(recent[2].content, "entry9");
     }
 
+    #[tokio::test]
+    async fn test_for_each_and_find_without_cloning() {
+        let buffer = RingBuffer::new(1000);
+
+        for i in 0..5 {
+            buffer.push(MemoryEntry::new(format!("entry{}", i), 10 + i)).await;
+        }
+
+        let mut summed = 0;
+        buffer.for_each(|entry| summed += entry.token_count).await;
+        assert_eq!(summed, buffer.stats().await.total_tokens);
+
+        let found = buffer.find(|entry| entry.token_count > 12).await.unwrap();
+        assert_eq!(found.content, "entry3");
+        assert!(buffer.find(|entry| entry.content == "missing").await.is_none());
+    }
+
     #[tokio::test]
     async fn test_usage_ratio() {
         let buffer = RingBuffer::new(100);
@@ -1,10 +1,22 @@
+use crate::clock::Clock;
+use crate::runtime::types::AgentId;
 use serde::{Deserialize, Serialize};
-use std::time::SystemTime;
+use std::time::{Duration, SystemTime};
 use uuid::Uuid;
 
 /// Memory entry identifier
 pub type EntryId = Uuid;
 
+/// Importance assigned to memory entries by default
+pub const DEFAULT_IMPORTANCE: u8 = 128;
+
+/// Entries at this importance are never summarized, and never evicted unless they alone exceed capacity
+pub const PINNED_IMPORTANCE: u8 = u8::MAX;
+
+fn default_importance() -> u8 {
+    DEFAULT_IMPORTANCE
+}
+
 /// Memory entry in a ring buffer
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MemoryEntry {
@@ -13,6 +25,9 @@ pub struct MemoryEntry {
     pub token_count: u32,
     pub timestamp: SystemTime,
     pub metadata: std::collections::HashMap<String, String>,
+    /// Lower-importance entries are evicted first
+    #[serde(default = "default_importance")]
+    pub importance: u8,
 }
 
 impl MemoryEntry {
@@ -23,6 +38,7 @@ impl MemoryEntry {
             token_count,
             timestamp: SystemTime::now(),
             metadata: std::collections::HashMap::new(),
+            importance: DEFAULT_IMPORTANCE,
         }
     }
 
@@ -30,6 +46,20 @@ impl MemoryEntry {
         self.metadata.insert(key, value);
         self
     }
+
+    pub fn with_importance(mut self, importance: u8) -> Self {
+        self.importance = importance;
+        self
+    }
+
+    /// Pin the entry: it is evicted last and kept verbatim through summarization
+    pub fn pinned(self) -> Self {
+        self.with_importance(PINNED_IMPORTANCE)
+    }
+
+    pub fn is_pinned(&self) -> bool {
+        self.importance == PINNED_IMPORTANCE
+    }
 }
 
 /// Blackboard entry with TTL
@@ -43,6 +73,9 @@ pub struct BlackboardEntry {
     pub expires_at: Option<SystemTime>,
     pub last_accessed: SystemTime,
     pub access_count: u64,
+    /// When set, each access pushes `expires_at` out by this window
+    #[serde(default)]
+    pub sliding_ttl: Option<Duration>,
 }
 
 impl BlackboardEntry {
@@ -57,13 +90,26 @@ impl BlackboardEntry {
             expires_at: None,
             last_accessed: now,
             access_count: 0,
+            sliding_ttl: None,
         }
     }
 
-    pub fn with_ttl(mut self, ttl_seconds: u64) -> Self {
-        self.expires_at = Some(
-            SystemTime::now() + std::time::Duration::from_secs(ttl_seconds)
-        );
+    /// Expire `ttl_seconds` after the current time on `clock`
+    ///
+    /// Pass the clock of the blackboard the entry is going into so the
+    /// deadline is measured on the same clock as expiry checks.
+    pub fn with_ttl(mut self, ttl_seconds: u64, clock: &dyn Clock) -> Self {
+        self.expires_at = Some(clock.now() + Duration::from_secs(ttl_seconds));
+        self
+    }
+
+    /// Expire after `ttl` without access, refreshing the deadline on every access
+    ///
+    /// The first deadline is `ttl` after the current time on `clock`, as with
+    /// `with_ttl`.
+    pub fn with_sliding_ttl(mut self, ttl: Duration, clock: &dyn Clock) -> Self {
+        self.sliding_ttl = Some(ttl);
+        self.expires_at = Some(clock.now() + ttl);
         self
     }
 
@@ -73,16 +119,30 @@ impl BlackboardEntry {
     }
 
     pub fn is_expired(&self) -> bool {
+        self.is_expired_at(SystemTime::now())
+    }
+
+    /// Whether the entry has expired as of `now`
+    pub fn is_expired_at(&self, now: SystemTime) -> bool {
         if let Some(expires_at) = self.expires_at {
-            SystemTime::now() > expires_at
+            now > expires_at
         } else {
             false
         }
     }
 
     pub fn touch(&mut self) {
-        self.last_accessed = SystemTime::now();
+        self.touch_at(SystemTime::now());
+    }
+
+    /// Record an access at `now`
+    pub fn touch_at(&mut self, now: SystemTime) {
+        self.last_accessed = now;
         self.access_count += 1;
+
+        if let Some(window) = self.sliding_ttl {
+            self.expires_at = Some(self.last_accessed + window);
+        }
     }
 }
 
@@ -106,4 +166,35 @@ pub struct BlackboardStats {
     pub hit_count: u64,
     pub miss_count: u64,
     pub avg_recall_latency_ms: f64,
+    /// Recalls that scanned the entries
+    #[serde(default)]
+    pub recall_scans: u64,
+    /// Recalls answered from the recall cache without a scan
+    #[serde(default)]
+    pub recall_cache_hits: u64,
+}
+
+/// Point-in-time view of everything an agent has in memory
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgentMemorySnapshot {
+    pub agent_id: AgentId,
+    pub entries: Vec<MemoryEntry>,
+    pub stats: MemoryStats,
+    /// Live blackboard entries the agent has read or written
+    pub blackboard_entries: Vec<BlackboardEntry>,
+    pub captured_at: SystemTime,
+}
+
+/// Progress events emitted while the memory manager compresses a buffer
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MemoryEvent {
+    /// Summarization of `owner`'s buffer has begun
+    SummarizationStarted { owner: String },
+    /// Summarization finished, shrinking the buffer from `old_tokens` to `new_tokens`
+    SummarizationCompleted {
+        owner: String,
+        old_tokens: u32,
+        new_tokens: u32,
+    },
 }
@@ -1,8 +1,38 @@
+use super::topology::MessageEdge;
 use super::types::{AgentId, AgentMessage, MessagePriority};
+use std::collections::hash_map::DefaultHasher;
 use std::collections::{BinaryHeap, HashMap};
+use std::hash::{Hash, Hasher};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::sync::{Mutex, RwLock};
 
+/// Errors returned when routing a message through the bus
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum BusError {
+    #[error("Mailbox not found for agent: {0}")]
+    MailboxNotFound(AgentId),
+
+    #[error("Mailbox for agent {agent_id} is full ({depth} messages queued)")]
+    Backpressure { agent_id: AgentId, depth: usize },
+
+    #[error("Validation error: message payload is {size} bytes (max {max})")]
+    PayloadTooLarge { size: usize, max: usize },
+
+    #[error("Validation error: message content is {size} bytes (max {max})")]
+    MessageTooLarge { size: usize, max: usize },
+}
+
+/// What the bus does with message content over `max_message_bytes`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OversizePolicy {
+    /// Refuse the message with `BusError::MessageTooLarge`
+    #[default]
+    Reject,
+    /// Cut the content to the limit and set `AgentMessage::truncated`
+    Truncate,
+}
+
 /// Message wrapper for priority queue
 #[derive(Debug, Clone)]
 struct PriorityMessage {
@@ -11,7 +41,7 @@ struct PriorityMessage {
 
 impl PartialEq for PriorityMessage {
     fn eq(&self, other: &Self) -> bool {
-        self.message.priority == other.message.priority
+        self.message.effective_priority() == other.message.effective_priority()
     }
 }
 
@@ -25,14 +55,31 @@ impl PartialOrd for PriorityMessage {
 
 impl Ord for PriorityMessage {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        self.message.priority.cmp(&other.message.priority)
+        self.message
+            .effective_priority()
+            .cmp(&other.message.effective_priority())
     }
 }
 
+/// Hash identifying a message for deduplication (sender, recipient and content)
+fn dedup_key(message: &AgentMessage) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    message.from.hash(&mut hasher);
+    message.to.hash(&mut hasher);
+    message.content.hash(&mut hasher);
+    message.payload.as_ref().map(|p| p.to_string()).hash(&mut hasher);
+    hasher.finish()
+}
+
 /// Mailbox for an individual agent
 pub struct Mailbox {
     agent_id: AgentId,
     messages: Arc<Mutex<BinaryHeap<PriorityMessage>>>,
+    dedup_window: Option<Duration>,
+    recent: Arc<Mutex<HashMap<u64, Instant>>>,
+    duplicates_dropped: Arc<Mutex<u64>>,
+    /// Sum of `AgentMessage::size_bytes` over queued messages
+    bytes: Arc<Mutex<usize>>,
 }
 
 impl Mailbox {
@@ -41,17 +88,62 @@ impl Mailbox {
         Self {
             agent_id,
             messages: Arc::new(Mutex::new(BinaryHeap::new())),
+            dedup_window: None,
+            recent: Arc::new(Mutex::new(HashMap::new())),
+            duplicates_dropped: Arc::new(Mutex::new(0)),
+            bytes: Arc::new(Mutex::new(0)),
         }
     }
 
+    /// Drop identical messages (same sender, recipient and content) pushed
+    /// within `window` of the first copy
+    pub fn with_dedup_window(mut self, window: Duration) -> Self {
+        self.dedup_window = Some(window);
+        self
+    }
+
     /// Push a message into the mailbox
-    pub async fn push(&self, message: AgentMessage) {
+    ///
+    /// Returns `false` if the message was dropped as a duplicate.
+    pub async fn push(&self, message: AgentMessage) -> bool {
+        if let Some(window) = self.dedup_window {
+            let key = dedup_key(&message);
+            let now = Instant::now();
+            let mut recent = self.recent.lock().await;
+            recent.retain(|_, seen| now.duration_since(*seen) < window);
+
+            if recent.contains_key(&key) {
+                *self.duplicates_dropped.lock().await += 1;
+                tracing::debug!(agent_id = %self.agent_id, message_id = %message.id, "Dropped duplicate message");
+                return false;
+            }
+            recent.insert(key, now);
+        }
+
+        *self.bytes.lock().await += message.size_bytes();
+        self.messages.lock().await.push(PriorityMessage { message });
+        true
+    }
+
+    /// Put a message back in the queue without deduplication, e.g. for a retry
+    ///
+    /// Bypasses the bus limits; go through `MessageBus::requeue` to apply them.
+    pub async fn requeue(&self, message: AgentMessage) {
+        *self.bytes.lock().await += message.size_bytes();
         self.messages.lock().await.push(PriorityMessage { message });
     }
 
+    /// Number of messages dropped as duplicates
+    pub async fn duplicates_dropped(&self) -> u64 {
+        *self.duplicates_dropped.lock().await
+    }
+
     /// Pop the highest priority message
     pub async fn pop(&self) -> Option<AgentMessage> {
-        self.messages.lock().await.pop().map(|pm| pm.message)
+        let message = self.messages.lock().await.pop()?.message;
+        let mut bytes = self.bytes.lock().await;
+        *bytes = bytes.saturating_sub(message.size_bytes());
+        Some(message)
     }
 
     /// Peek at the highest priority message without removing it
@@ -73,9 +165,15 @@ impl Mailbox {
         self.messages.lock().await.is_empty()
     }
 
+    /// Bytes held by queued messages (content plus payload)
+    pub async fn total_bytes(&self) -> usize {
+        *self.bytes.lock().await
+    }
+
     /// Clear all messages
     pub async fn clear(&self) {
         self.messages.lock().await.clear();
+        *self.bytes.lock().await = 0;
     }
 }
 
@@ -84,6 +182,13 @@ pub struct MessageBus {
     mailboxes: Arc<RwLock<HashMap<AgentId, Arc<Mailbox>>>>,
     total_sent: Arc<Mutex<u64>>,
     total_received: Arc<Mutex<u64>>,
+    max_queue_depth: Option<usize>,
+    max_payload_bytes: Option<usize>,
+    max_message_bytes: Option<usize>,
+    oversize_policy: OversizePolicy,
+    edges: Arc<Mutex<HashMap<(AgentId, AgentId), MessageEdge>>>,
+    /// Default priority for messages of a given kind
+    kind_priorities: HashMap<String, MessagePriority>,
 }
 
 impl MessageBus {
@@ -93,18 +198,146 @@ impl MessageBus {
             mailboxes: Arc::new(RwLock::new(HashMap::new())),
             total_sent: Arc::new(Mutex::new(0)),
             total_received: Arc::new(Mutex::new(0)),
+            max_queue_depth: None,
+            max_payload_bytes: None,
+            max_message_bytes: None,
+            oversize_policy: OversizePolicy::Reject,
+            edges: Arc::new(Mutex::new(HashMap::new())),
+            kind_priorities: HashMap::new(),
+        }
+    }
+
+    /// Give messages of `kind` this priority unless the sender set one
+    pub fn with_kind_priority(mut self, kind: impl Into<String>, priority: MessagePriority) -> Self {
+        self.kind_priorities.insert(kind.into(), priority);
+        self
+    }
+
+    /// Apply the kind's default priority to a message without an explicit one
+    fn apply_kind_priority(&self, message: &mut AgentMessage) {
+        if message.priority.is_some() {
+            return;
+        }
+        if let Some(priority) = message
+            .kind
+            .as_ref()
+            .and_then(|kind| self.kind_priorities.get(kind))
+        {
+            message.priority = Some(*priority);
+        }
+    }
+
+    /// Reject sends to any mailbox already holding `depth` messages
+    pub fn with_max_queue_depth(mut self, depth: usize) -> Self {
+        self.max_queue_depth = Some(depth);
+        self
+    }
+
+    /// Per-mailbox depth at which sends are rejected, if any
+    pub fn max_queue_depth(&self) -> Option<usize> {
+        self.max_queue_depth
+    }
+
+    /// Reject messages whose JSON `payload` serializes to more than `bytes`
+    pub fn with_max_payload_bytes(mut self, bytes: usize) -> Self {
+        self.max_payload_bytes = Some(bytes);
+        self
+    }
+
+    /// Cap message `content` at `bytes`, rejecting or truncating longer messages
+    pub fn with_max_message_bytes(mut self, bytes: usize, policy: OversizePolicy) -> Self {
+        self.max_message_bytes = Some(bytes);
+        self.oversize_policy = policy;
+        self
+    }
+
+    /// Apply the content size cap, truncating in place when the policy allows
+    fn check_content(&self, message: &mut AgentMessage) -> Result<(), BusError> {
+        let Some(max) = self.max_message_bytes else {
+            return Ok(());
+        };
+        let size = message.content.len();
+        if size <= max {
+            return Ok(());
+        }
+
+        match self.oversize_policy {
+            OversizePolicy::Reject => Err(BusError::MessageTooLarge { size, max }),
+            OversizePolicy::Truncate => {
+                let mut end = max;
+                while !message.content.is_char_boundary(end) {
+                    end -= 1;
+                }
+                message.content.truncate(end);
+                message.truncated = true;
+                tracing::warn!(message_id = %message.id, size, max, "Truncated oversized message");
+                Ok(())
+            }
         }
     }
 
+    /// Check a message's payload against the configured size cap
+    fn check_payload(&self, message: &AgentMessage) -> Result<(), BusError> {
+        let (Some(max), Some(payload)) = (self.max_payload_bytes, &message.payload) else {
+            return Ok(());
+        };
+        let size = serde_json::to_vec(payload).map_or(0, |bytes| bytes.len());
+        if size > max {
+            return Err(BusError::PayloadTooLarge { size, max });
+        }
+        Ok(())
+    }
+
+    /// Whether a mailbox has reached the configured depth limit
+    async fn is_full(&self, mailbox: &Mailbox) -> Option<usize> {
+        let max = self.max_queue_depth?;
+        let depth = mailbox.len().await;
+        (depth >= max).then_some(depth)
+    }
+
     /// Create a mailbox for an agent
     pub async fn create_mailbox(&self, agent_id: AgentId) -> Arc<Mailbox> {
-        let mailbox = Arc::new(Mailbox::new(agent_id));
-        self.mailboxes.write().await.insert(agent_id, mailbox.clone());
+        self.insert_mailbox(Mailbox::new(agent_id)).await
+    }
+
+    /// Create a mailbox that drops duplicate messages within `window`
+    pub async fn create_mailbox_with_dedup(
+        &self,
+        agent_id: AgentId,
+        window: Duration,
+    ) -> Arc<Mailbox> {
+        self.insert_mailbox(Mailbox::new(agent_id).with_dedup_window(window))
+            .await
+    }
+
+    /// Create mailboxes for several agents under a single lock
+    pub async fn create_mailboxes(&self, agent_ids: &[AgentId]) -> Vec<Arc<Mailbox>> {
+        let mut mailboxes = self.mailboxes.write().await;
+        agent_ids
+            .iter()
+            .map(|agent_id| {
+                let mailbox = Arc::new(Mailbox::new(*agent_id));
+                mailboxes.insert(*agent_id, mailbox.clone());
+                mailbox
+            })
+            .collect()
+    }
+
+    async fn insert_mailbox(&self, mailbox: Mailbox) -> Arc<Mailbox> {
+        let mailbox = Arc::new(mailbox);
+        self.mailboxes
+            .write()
+            .await
+            .insert(mailbox.agent_id, mailbox.clone());
         mailbox
     }
 
-    /// Remove a mailbox
+    /// Remove a mailbox and forget the agent's traffic edges
     pub async fn remove_mailbox(&self, agent_id: AgentId) -> bool {
+        self.edges
+            .lock()
+            .await
+            .retain(|(from, to), _| *from != agent_id && *to != agent_id);
         self.mailboxes.write().await.remove(&agent_id).is_some()
     }
 
@@ -114,28 +347,90 @@ impl MessageBus {
     }
 
     /// Send a message to an agent
-    pub async fn send(&self, message: AgentMessage) -> Result<(), String> {
+    ///
+    /// Returns `BusError::Backpressure` when the recipient's mailbox is at
+    /// the configured `max_queue_depth`, `BusError::PayloadTooLarge` when
+    /// the payload is over `max_payload_bytes`, and `BusError::MessageTooLarge`
+    /// when rejecting content over `max_message_bytes`; the message is not enqueued.
+    pub async fn send(&self, mut message: AgentMessage) -> Result<(), BusError> {
+        self.check_payload(&message)?;
+        self.check_content(&mut message)?;
+        self.apply_kind_priority(&mut message);
+
         let mailboxes = self.mailboxes.read().await;
-        if let Some(mailbox) = mailboxes.get(&message.to) {
-            mailbox.push(message).await;
+        let mailbox = mailboxes
+            .get(&message.to)
+            .ok_or(BusError::MailboxNotFound(message.to))?;
+
+        if let Some(depth) = self.is_full(mailbox).await {
+            tracing::warn!(agent_id = %message.to, depth, "Mailbox full, rejecting message");
+            return Err(BusError::Backpressure {
+                agent_id: message.to,
+                depth,
+            });
+        }
+
+        let (from, to) = (message.from, message.to);
+        if mailbox.push(message).await {
             *self.total_sent.lock().await += 1;
-            Ok(())
-        } else {
-            Err(format!("Mailbox not found for agent: {}", message.to))
+            self.record_edge(from, to).await;
+        }
+        Ok(())
+    }
+
+    /// Put a message back in its recipient's mailbox for another attempt
+    ///
+    /// The depth limit and size caps apply as they do for `send`, but the
+    /// message skips deduplication and keeps its priority.
+    pub async fn requeue(&self, mut message: AgentMessage) -> Result<(), BusError> {
+        self.check_payload(&message)?;
+        self.check_content(&mut message)?;
+
+        let mailboxes = self.mailboxes.read().await;
+        let mailbox = mailboxes
+            .get(&message.to)
+            .ok_or(BusError::MailboxNotFound(message.to))?;
+
+        if let Some(depth) = self.is_full(mailbox).await {
+            tracing::warn!(agent_id = %message.to, depth, "Mailbox full, not re-queueing message");
+            return Err(BusError::Backpressure {
+                agent_id: message.to,
+                depth,
+            });
         }
+
+        mailbox.requeue(message).await;
+        Ok(())
     }
 
     /// Broadcast a message to all agents except sender
-    pub async fn broadcast(&self, message: AgentMessage) -> usize {
+    ///
+    /// Mailboxes at the depth limit are skipped and not counted; a message
+    /// refused by the size caps is sent to nobody.
+    pub async fn broadcast(&self, mut message: AgentMessage) -> usize {
+        if let Err(e) = self
+            .check_payload(&message)
+            .and_then(|_| self.check_content(&mut message))
+        {
+            tracing::warn!(from = %message.from, "Not broadcasting message: {}", e);
+            return 0;
+        }
+        self.apply_kind_priority(&mut message);
+
         let mailboxes = self.mailboxes.read().await;
         let mut sent = 0;
 
         for (agent_id, mailbox) in mailboxes.iter() {
             if *agent_id != message.from {
+                if self.is_full(mailbox).await.is_some() {
+                    continue;
+                }
                 let mut broadcast_msg = message.clone();
                 broadcast_msg.to = *agent_id;
-                mailbox.push(broadcast_msg).await;
-                sent += 1;
+                if mailbox.push(broadcast_msg).await {
+                    sent += 1;
+                    self.record_edge(message.from, *agent_id).await;
+                }
             }
         }
 
@@ -143,6 +438,25 @@ impl MessageBus {
         sent
     }
 
+    /// Count a delivered message on the from→to edge
+    async fn record_edge(&self, from: AgentId, to: AgentId) {
+        let now = chrono::Utc::now();
+        let mut edges = self.edges.lock().await;
+        let edge = edges.entry((from, to)).or_insert(MessageEdge {
+            from,
+            to,
+            count: 0,
+            last_sent_at: now,
+        });
+        edge.count += 1;
+        edge.last_sent_at = now;
+    }
+
+    /// Traffic observed between agents since the bus was created
+    pub async fn message_edges(&self) -> Vec<MessageEdge> {
+        self.edges.lock().await.values().cloned().collect()
+    }
+
     /// Get total messages sent
     pub async fn total_sent(&self) -> u64 {
         *self.total_sent.lock().await
@@ -158,6 +472,16 @@ impl MessageBus {
         *self.total_received.lock().await += 1;
     }
 
+    /// Bytes queued across all mailboxes
+    pub async fn queue_bytes(&self) -> usize {
+        let mailboxes = self.mailboxes.read().await;
+        let mut total = 0;
+        for mailbox in mailboxes.values() {
+            total += mailbox.total_bytes().await;
+        }
+        total
+    }
+
     /// Get queue depth across all mailboxes
     pub async fn queue_depth(&self) -> usize {
         let mailboxes = self.mailboxes.read().await;
@@ -179,6 +503,32 @@ impl Default for MessageBus {
 mod tests {
     use super::*;
 
+    #[tokio::test]
+    async fn test_kind_priority_applies_when_unset() {
+        let bus = MessageBus::new().with_kind_priority("control", MessagePriority::High);
+        let agent_id = uuid::Uuid::new_v4();
+        let mailbox = bus.create_mailbox(agent_id).await;
+
+        bus.send(AgentMessage::new(agent_id, agent_id, "chatter".to_string()))
+            .await
+            .unwrap();
+        bus.send(AgentMessage::new(agent_id, agent_id, "shutdown".to_string()).with_kind("control"))
+            .await
+            .unwrap();
+        bus.send(
+            AgentMessage::new(agent_id, agent_id, "low control".to_string())
+                .with_kind("control")
+                .with_priority(MessagePriority::Low),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(mailbox.pop().await.unwrap().content, "shutdown");
+        assert_eq!(mailbox.pop().await.unwrap().content, "chatter");
+        // An explicit priority wins over the kind default
+        assert_eq!(mailbox.pop().await.unwrap().content, "low control");
+    }
+
     #[tokio::test]
     async fn test_mailbox_push_pop() {
         let agent_id = uuid::Uuid::new_v4();
@@ -216,6 +566,99 @@ mod tests {
         assert_eq!(mailbox.pop().await.unwrap().content, "low");
     }
 
+    #[tokio::test]
+    async fn test_mailbox_dedup_window() {
+        let agent_id = uuid::Uuid::new_v4();
+        let mailbox = Mailbox::new(agent_id).with_dedup_window(Duration::from_secs(60));
+        let msg = AgentMessage::new(uuid::Uuid::new_v4(), agent_id, "retry me".to_string());
+
+        assert!(mailbox.push(msg.clone()).await);
+        assert!(!mailbox.push(msg.clone()).await);
+        assert!(!mailbox.push(msg).await);
+
+        assert_eq!(mailbox.pop().await.unwrap().content, "retry me");
+        assert!(mailbox.pop().await.is_none());
+        assert_eq!(mailbox.duplicates_dropped().await, 2);
+    }
+
+    #[tokio::test]
+    async fn test_payload_survives_enqueue() {
+        let bus = MessageBus::new();
+        let agent_id = uuid::Uuid::new_v4();
+        bus.create_mailbox(agent_id).await;
+
+        let payload = serde_json::json!({
+            "tool": "read_file",
+            "args": { "path": "src/main.rs", "lines": [1, 20] }
+        });
+        let msg = AgentMessage::new(uuid::Uuid::new_v4(), agent_id, "Read main".to_string())
+            .with_payload(payload.clone());
+        bus.send(msg).await.unwrap();
+
+        let popped = bus.get_mailbox(agent_id).await.unwrap().pop().await.unwrap();
+        assert_eq!(popped.content, "Read main");
+        assert_eq!(popped.payload, Some(payload.clone()));
+        assert_eq!(popped.connector_input(), payload.to_string());
+    }
+
+    #[tokio::test]
+    async fn test_oversized_payload_rejected() {
+        let bus = MessageBus::new().with_max_payload_bytes(64);
+        let agent_id = uuid::Uuid::new_v4();
+        let sender = uuid::Uuid::new_v4();
+        bus.create_mailbox(agent_id).await;
+
+        let small = AgentMessage::new(sender, agent_id, "ok".to_string())
+            .with_payload(serde_json::json!({ "tool": "ls" }));
+        assert!(bus.send(small).await.is_ok());
+
+        let large = AgentMessage::new(sender, agent_id, "big".to_string())
+            .with_payload(serde_json::json!({ "blob": "x".repeat(100) }));
+        assert!(matches!(
+            bus.send(large.clone()).await,
+            Err(BusError::PayloadTooLarge { max: 64, .. })
+        ));
+        assert_eq!(bus.broadcast(large).await, 0);
+        assert_eq!(bus.queue_depth().await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_oversized_message_rejected_or_truncated() {
+        let agent = uuid::Uuid::new_v4();
+        let bus = MessageBus::new().with_max_message_bytes(16, OversizePolicy::Reject);
+        let mailbox = bus.create_mailbox(agent).await;
+
+        bus.send(AgentMessage::new(agent, agent, "short".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(mailbox.total_bytes().await, 5);
+
+        let huge = "x".repeat(100);
+        let err = bus
+            .send(AgentMessage::new(agent, agent, huge.clone()))
+            .await
+            .unwrap_err();
+        assert_eq!(err, BusError::MessageTooLarge { size: 100, max: 16 });
+        assert_eq!(
+            err.to_string(),
+            "Validation error: message content is 100 bytes (max 16)"
+        );
+        assert_eq!(mailbox.len().await, 1);
+
+        // Truncation keeps the message but flags it, and never splits a character
+        let bus = MessageBus::new().with_max_message_bytes(16, OversizePolicy::Truncate);
+        let mailbox = bus.create_mailbox(agent).await;
+        bus.send(AgentMessage::new(agent, agent, format!("{}é", "y".repeat(15))))
+            .await
+            .unwrap();
+        assert_eq!(bus.queue_bytes().await, 15);
+
+        let message = mailbox.pop().await.unwrap();
+        assert!(message.truncated);
+        assert_eq!(message.content, "y".repeat(15));
+        assert_eq!(mailbox.total_bytes().await, 0);
+    }
+
     #[tokio::test]
     async fn test_message_bus_send() {
         let bus = MessageBus::new();
@@ -249,6 +692,60 @@ mod tests {
         assert_eq!(bus.queue_depth().await, 2);
     }
 
+    #[tokio::test]
+    async fn test_message_bus_backpressure() {
+        let bus = MessageBus::new().with_max_queue_depth(2);
+        let agent_id = uuid::Uuid::new_v4();
+        let sender = uuid::Uuid::new_v4();
+        bus.create_mailbox(agent_id).await;
+
+        for i in 0..2 {
+            let msg = AgentMessage::new(sender, agent_id, format!("msg{}", i));
+            bus.send(msg).await.unwrap();
+        }
+
+        let msg = AgentMessage::new(sender, agent_id, "overflow".to_string());
+        let err = bus.send(msg).await.unwrap_err();
+        assert_eq!(err, BusError::Backpressure { agent_id, depth: 2 });
+        assert_eq!(bus.queue_depth().await, 2);
+        assert_eq!(bus.total_sent().await, 2);
+
+        // Draining the mailbox lifts the back-pressure
+        bus.get_mailbox(agent_id).await.unwrap().pop().await;
+        let msg = AgentMessage::new(sender, agent_id, "retry".to_string());
+        assert!(bus.send(msg).await.is_ok());
+
+        let missing = AgentMessage::new(sender, uuid::Uuid::new_v4(), "lost".to_string());
+        assert!(matches!(
+            bus.send(missing).await,
+            Err(BusError::MailboxNotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_requeue_respects_bus_limits() {
+        let bus = MessageBus::new()
+            .with_max_queue_depth(1)
+            .with_max_message_bytes(16, OversizePolicy::Reject);
+        let agent_id = uuid::Uuid::new_v4();
+        let mailbox = bus.create_mailbox(agent_id).await;
+
+        let retry = AgentMessage::new(agent_id, agent_id, "retry".to_string());
+        bus.requeue(retry.clone()).await.unwrap();
+        assert_eq!(
+            bus.requeue(retry).await.unwrap_err(),
+            BusError::Backpressure { agent_id, depth: 1 }
+        );
+
+        mailbox.pop().await;
+        let oversized = AgentMessage::new(agent_id, agent_id, "x".repeat(17));
+        assert!(matches!(
+            bus.requeue(oversized).await,
+            Err(BusError::MessageTooLarge { .. })
+        ));
+        assert!(mailbox.is_empty().await);
+    }
+
     #[tokio::test]
     async fn test_mailbox_clear() {
         let agent_id = uuid::Uuid::new_v4();
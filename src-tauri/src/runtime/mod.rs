@@ -3,8 +3,13 @@ pub mod types;
 pub mod registry;
 pub mod mailbox;
 pub mod orchestrator;
+pub mod topology;
 
 pub use types::*;
 pub use registry::AgentRegistry;
-pub use mailbox::{Mailbox, MessageBus};
-pub use orchestrator::{Orchestrator, LoopGuard, StopReason, OrchestratorMetrics};
+pub use mailbox::{BusError, Mailbox, MessageBus, OversizePolicy};
+pub use orchestrator::{
+    LoopGuard, Orchestrator, OrchestratorEvent, OrchestratorHandle, OrchestratorMetrics, RunRecord,
+    SessionBudget, StopReason,
+};
+pub use topology::{AgentNode, AgentStatusSummary, AgentTopology, MessageEdge};
@@ -1,12 +1,23 @@
-use super::mailbox::{Mailbox, MessageBus};
+use super::mailbox::MessageBus;
+use crate::api::RateLimiter;
+use crate::clock::{system_clock, Clock};
+use crate::connectors::{
+    cli_connector, ApproxTokenCounter, Connector, ConnectorConfig, ConnectorMessage, TokenCounter,
+};
+use crate::memory::{MemoryEntry, MemoryManager};
+use crate::session::{ProgressEvent, SessionService};
 use super::registry::AgentRegistry;
-use super::types::{AgentConfig, AgentId, AgentMessage, AgentStatus};
-use std::collections::HashMap;
+use super::types::{
+    AgentConfig, AgentId, AgentMessage, AgentMetadata, AgentStatus, ExecutionOrder, RoutingRule,
+};
+use futures::future::join_all;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::sync::{Mutex, RwLock};
+use tokio::sync::{broadcast, Mutex, RwLock, Semaphore};
 use tokio::time::timeout;
-use tracing::{debug, error, info, warn};
+use tracing::{debug, error, info, instrument, warn};
 
 /// Loop guard to prevent runaway execution
 #[derive(Debug, Clone)]
@@ -17,6 +28,8 @@ pub struct LoopGuard {
     pub max_messages_per_agent: u32,
     /// Maximum total execution time in milliseconds
     pub max_execution_time_ms: u64,
+    /// Maximum time for one pass over all agents, in milliseconds
+    pub max_iteration_time_ms: Option<u64>,
 }
 
 impl Default for LoopGuard {
@@ -25,36 +38,218 @@ impl Default for LoopGuard {
             max_iterations: 100,
             max_messages_per_agent: 50,
             max_execution_time_ms: 600000, // 10 minutes
+            max_iteration_time_ms: None,
         }
     }
 }
 
+/// Spending limit for a whole run, across every agent
+///
+/// Checked against the `Usage` reported by connectors after each message.
+/// Limits left as `None` are not enforced.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SessionBudget {
+    /// Maximum input plus output tokens
+    pub max_total_tokens: Option<u64>,
+    /// Maximum spend, priced with the per-million token rates below
+    pub max_cost_usd: Option<f64>,
+    #[serde(default)]
+    pub usd_per_million_input_tokens: f64,
+    #[serde(default)]
+    pub usd_per_million_output_tokens: f64,
+}
+
+impl SessionBudget {
+    /// Price `input_tokens` and `output_tokens` at this budget's rates
+    pub fn cost_usd(&self, input_tokens: u64, output_tokens: u64) -> f64 {
+        (input_tokens as f64 * self.usd_per_million_input_tokens
+            + output_tokens as f64 * self.usd_per_million_output_tokens)
+            / 1_000_000.0
+    }
+
+    /// Whether the usage so far crosses either limit
+    pub fn is_exceeded(&self, input_tokens: u64, output_tokens: u64) -> bool {
+        let over_tokens = self
+            .max_total_tokens
+            .is_some_and(|max| input_tokens + output_tokens > max);
+        let over_cost = self
+            .max_cost_usd
+            .is_some_and(|max| self.cost_usd(input_tokens, output_tokens) > max);
+        over_tokens || over_cost
+    }
+}
+
 /// Loop stoppage reason
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
 pub enum StopReason {
     /// Completed successfully
     Completed,
+    /// The registry had no agents to run
+    NoAgents,
     /// Max iterations reached
     MaxIterations,
     /// Max messages per agent reached
     MaxMessagesPerAgent { agent_id: AgentId, count: u32 },
     /// Max execution time reached
     MaxExecutionTime,
+    /// One loop iteration took longer than `max_iteration_time_ms`
+    IterationTimeout { iteration: u32 },
     /// Agent error
     AgentError { agent_id: AgentId, error: String },
+    /// Token or cost usage crossed the `SessionBudget`
+    BudgetExceeded { total_tokens: u64, cost_usd: f64 },
     /// Manual stop
     ManualStop,
 }
 
+impl std::fmt::Display for StopReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StopReason::Completed => write!(f, "completed"),
+            StopReason::NoAgents => write!(f, "no_agents"),
+            StopReason::MaxIterations => write!(f, "max_iterations"),
+            StopReason::MaxMessagesPerAgent { agent_id, count } => {
+                write!(f, "max_messages_per_agent:{}:{}", agent_id, count)
+            }
+            StopReason::MaxExecutionTime => write!(f, "max_execution_time"),
+            StopReason::IterationTimeout { iteration } => {
+                write!(f, "iteration_timeout:{}", iteration)
+            }
+            StopReason::AgentError { agent_id, error } => {
+                write!(f, "agent_error:{}:{}", agent_id, error)
+            }
+            StopReason::BudgetExceeded { total_tokens, cost_usd } => {
+                write!(f, "budget_exceeded:{}:{:.4}", total_tokens, cost_usd)
+            }
+            StopReason::ManualStop => write!(f, "manual_stop"),
+        }
+    }
+}
+
+/// Cheap, cloneable control for a running orchestrator
+///
+/// Lets another task (e.g. a UI command) stop or pause a run without holding
+/// the `Orchestrator` itself.
+#[derive(Debug, Clone)]
+pub struct OrchestratorHandle {
+    running: Arc<RwLock<bool>>,
+    paused: Arc<RwLock<bool>>,
+    events: broadcast::Sender<OrchestratorEvent>,
+}
+
+impl OrchestratorHandle {
+    /// Receive lifecycle events from now on
+    ///
+    /// A receiver that falls more than `EVENT_CHANNEL_CAPACITY` events behind
+    /// skips the oldest and gets `RecvError::Lagged`.
+    pub fn subscribe(&self) -> broadcast::Receiver<OrchestratorEvent> {
+        self.events.subscribe()
+    }
+
+    /// Stop the current run; it ends with `StopReason::ManualStop`
+    pub async fn stop(&self) {
+        *self.running.write().await = false;
+    }
+
+    /// Stop dispatching messages until `resume` is called
+    ///
+    /// Time limits keep counting while paused.
+    pub async fn pause(&self) {
+        *self.paused.write().await = true;
+    }
+
+    pub async fn resume(&self) {
+        *self.paused.write().await = false;
+    }
+
+    pub async fn is_running(&self) -> bool {
+        *self.running.read().await
+    }
+
+    pub async fn is_paused(&self) -> bool {
+        *self.paused.read().await
+    }
+}
+
 /// Orchestrator metrics
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct OrchestratorMetrics {
+    /// Run these metrics belong to (None before the first run)
+    pub run_id: Option<uuid::Uuid>,
     pub total_iterations: u32,
     pub total_messages: u64,
     pub messages_per_agent: HashMap<AgentId, u32>,
     pub retry_count: u64,
     pub error_count: u64,
     pub queue_depth: usize,
+    /// Tokens reported by connector `Usage` messages this run
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    /// Times an agent with mail was skipped for exceeding its rate limit
+    #[serde(default)]
+    pub throttled_count: u64,
+}
+
+/// Outcome of one `Orchestrator::start` call
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunRecord {
+    pub run_id: uuid::Uuid,
+    pub started_at: chrono::DateTime<chrono::Utc>,
+    pub finished_at: chrono::DateTime<chrono::Utc>,
+    /// None if the run ended with an error
+    pub stop_reason: Option<StopReason>,
+    pub metrics: OrchestratorMetrics,
+}
+
+/// Number of finished runs kept by default
+const DEFAULT_RUN_HISTORY: usize = 20;
+
+/// Pause between loop iterations unless overridden
+pub const DEFAULT_TICK_INTERVAL_MS: u64 = 10;
+
+/// How often a tick checks its clock for the end of the interval
+const TICK_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Progress event types written to a bound session's timeline
+pub const PROGRESS_AGENT_STARTED: &str = "agent_started";
+pub const PROGRESS_AGENT_COMPLETED: &str = "agent_completed";
+pub const PROGRESS_AGENT_FAILED: &str = "agent_failed";
+pub const PROGRESS_AGENT_RETRYING: &str = "agent_retrying";
+pub const PROGRESS_RUN_STOPPED: &str = "run_stopped";
+pub const PROGRESS_CONNECTOR_REQUEST: &str = "connector_request";
+
+/// Lifecycle events buffered per handle subscriber
+pub const EVENT_CHANNEL_CAPACITY: usize = 256;
+
+/// Token capacity of the memory buffer created for an agent on its first message
+pub const DEFAULT_AGENT_MEMORY_TOKENS: u32 = 8000;
+
+/// Lifecycle event broadcast to handle subscribers
+///
+/// Carries the same type, description and data as the progress event written
+/// to a bound session, but is sent whether or not a session is bound.
+#[derive(Debug, Clone, Serialize)]
+pub struct OrchestratorEvent {
+    /// One of the `PROGRESS_*` event types
+    pub event_type: String,
+    pub description: String,
+    pub data: serde_json::Value,
+}
+
+/// Session whose progress timeline records this orchestrator's lifecycle
+struct ProgressSink {
+    service: Arc<SessionService>,
+    session_id: String,
+}
+
+/// Outcome of one attempt at an agent's message
+#[derive(Debug)]
+enum Attempt {
+    /// The message was handled, with the agent's output
+    Done(String),
+    /// The attempt failed with this error and the message was re-queued to retry
+    Requeued(String),
 }
 
 /// Core orchestrator for managing agent execution
@@ -64,6 +259,41 @@ pub struct Orchestrator {
     loop_guard: LoopGuard,
     metrics: Arc<Mutex<OrchestratorMetrics>>,
     running: Arc<RwLock<bool>>,
+    /// Set while dispatch is paused through a handle
+    paused: Arc<RwLock<bool>>,
+    /// Re-queue failed messages one priority level higher instead of retrying in place
+    escalate_on_retry: bool,
+    /// Connectors keyed by the `connector_type` agents ask for
+    connectors: HashMap<String, Arc<dyn Connector>>,
+    /// CLI configs replacing the registered connector, keyed by agent or session ID
+    connector_overrides: HashMap<String, ConnectorConfig>,
+    /// Connectors built from overrides, keyed by (override key, connector type)
+    override_connectors: Arc<Mutex<HashMap<(String, String), Arc<dyn Connector>>>>,
+    /// Most recent finished runs, oldest first
+    run_history: Arc<Mutex<VecDeque<RunRecord>>>,
+    run_history_limit: usize,
+    /// Pause between loop iterations; 0 only yields to the runtime
+    tick_interval_ms: u64,
+    /// Measures the pause between loop iterations
+    clock: Arc<dyn Clock>,
+    /// Content-based forwarding of agent output
+    routing_rules: Vec<RoutingRule>,
+    /// Where lifecycle progress events go, if bound to a session
+    progress: Option<ProgressSink>,
+    /// Run-wide token and cost limit
+    budget: Option<SessionBudget>,
+    /// Held for the duration of a run, so callers can wait for it to end
+    run_guard: Arc<Mutex<()>>,
+    /// Agents processed concurrently per iteration; 1 keeps them sequential
+    max_parallel_agents: usize,
+    /// Per-agent dispatch limit, one bucket per agent ID
+    agent_rate_limiter: Option<Arc<RateLimiter>>,
+    /// Order agents are visited in each iteration
+    execution_order: ExecutionOrder,
+    /// Lifecycle events for handle subscribers
+    events: broadcast::Sender<OrchestratorEvent>,
+    /// Where each agent's handled messages and outputs are remembered
+    memory: Option<Arc<MemoryManager>>,
 }
 
 impl Orchestrator {
@@ -75,17 +305,192 @@ impl Orchestrator {
             loop_guard: LoopGuard::default(),
             metrics: Arc::new(Mutex::new(OrchestratorMetrics::default())),
             running: Arc::new(RwLock::new(false)),
+            paused: Arc::new(RwLock::new(false)),
+            escalate_on_retry: false,
+            connectors: HashMap::new(),
+            connector_overrides: HashMap::new(),
+            override_connectors: Arc::new(Mutex::new(HashMap::new())),
+            run_history: Arc::new(Mutex::new(VecDeque::new())),
+            run_history_limit: DEFAULT_RUN_HISTORY,
+            tick_interval_ms: DEFAULT_TICK_INTERVAL_MS,
+            clock: system_clock(),
+            routing_rules: Vec::new(),
+            progress: None,
+            budget: None,
+            run_guard: Arc::new(Mutex::new(())),
+            max_parallel_agents: 1,
+            agent_rate_limiter: None,
+            execution_order: ExecutionOrder::default(),
+            events: broadcast::channel(EVENT_CHANNEL_CAPACITY).0,
+            memory: None,
         }
     }
 
+    /// Visit agents in `order` each iteration (default: registration order)
+    pub fn with_execution_order(mut self, order: ExecutionOrder) -> Self {
+        self.execution_order = order;
+        self
+    }
+
+    /// Limit how often each agent's messages are dispatched, using one bucket per agent
+    ///
+    /// An agent over its rate keeps its mail and is skipped until its bucket
+    /// refills, while other agents carry on.
+    pub fn with_agent_rate_limit(mut self, limiter: Arc<RateLimiter>) -> Self {
+        self.agent_rate_limiter = Some(limiter);
+        self
+    }
+
+    /// Process up to `limit` agents' messages at once in each iteration
+    ///
+    /// Budget and iteration-time checks then run after the whole batch rather
+    /// than between agents. Values below 1 are treated as 1.
+    pub fn with_max_parallel_agents(mut self, limit: usize) -> Self {
+        self.max_parallel_agents = limit.max(1);
+        self
+    }
+
+    /// Stop runs with `StopReason::BudgetExceeded` once usage crosses `budget`
+    pub fn with_budget(mut self, budget: SessionBudget) -> Self {
+        self.budget = Some(budget);
+        self
+    }
+
+    /// Remember each message an agent handles, and its output, in `memory`
+    ///
+    /// An agent without a buffer gets one of `DEFAULT_AGENT_MEMORY_TOKENS`.
+    pub fn with_memory(mut self, memory: Arc<MemoryManager>) -> Self {
+        self.memory = Some(memory);
+        self
+    }
+
+    /// Record agent start/complete/error and run stop as progress events on `session_id`
+    pub fn with_session(mut self, service: Arc<SessionService>, session_id: impl Into<String>) -> Self {
+        self.progress = Some(ProgressSink {
+            service,
+            session_id: session_id.into(),
+        });
+        self
+    }
+
+    /// Forward output matching `rule` to an agent with the rule's target role
+    ///
+    /// Rules are checked in order and every matching rule sends its own
+    /// follow-up message.
+    pub fn with_routing_rule(mut self, rule: RoutingRule) -> Self {
+        self.routing_rules.push(rule);
+        self
+    }
+
+    /// Pause `ms` between loop iterations (default 10ms)
+    ///
+    /// Larger values trade latency for less CPU while agents are busy; 0 skips
+    /// the sleep and only yields to other tasks.
+    pub fn with_tick_interval_ms(mut self, ms: u64) -> Self {
+        self.tick_interval_ms = ms;
+        self
+    }
+
+    /// Use `clock` to measure the tick interval instead of the system clock
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Keep at most `limit` finished runs in the history
+    pub fn with_run_history_limit(mut self, limit: usize) -> Self {
+        self.run_history_limit = limit;
+        self
+    }
+
+    /// Route agents whose `connector_type` is `connector_type` to `connector`
+    ///
+    /// Agents without a registered connector fall back to the built-in stub.
+    pub fn with_connector(
+        mut self,
+        connector_type: impl Into<String>,
+        connector: Arc<dyn Connector>,
+    ) -> Self {
+        self.connectors.insert(connector_type.into(), connector);
+        self
+    }
+
+    /// Run CLI agents with `config` instead of their registered connector
+    ///
+    /// `key` is an agent ID, or the ID of the session bound with
+    /// `with_session` to cover every agent in the run. Agent overrides win
+    /// over the session's.
+    pub fn with_connector_override(mut self, key: impl Into<String>, config: ConnectorConfig) -> Self {
+        self.connector_overrides.insert(key.into(), config);
+        self
+    }
+
     /// Create with custom loop guard
     pub fn with_loop_guard(mut self, guard: LoopGuard) -> Self {
         self.loop_guard = guard;
         self
     }
 
+    /// Re-queue failed messages with escalated priority (up to `Critical`)
+    /// so persistently failing work jumps ahead of fresh traffic
+    pub fn with_priority_escalation(mut self, enabled: bool) -> Self {
+        self.escalate_on_retry = enabled;
+        self
+    }
+
     /// Start the orchestrator
+    ///
+    /// Each call is a new run with its own ID and fresh metrics; the finished
+    /// run is added to the history. Fails if a run is already in progress.
     pub async fn start(&self) -> Result<StopReason, String> {
+        let _run = self
+            .run_guard
+            .try_lock()
+            .map_err(|_| "Orchestrator is already running".to_string())?;
+        let run_id = uuid::Uuid::new_v4();
+        let started_at = chrono::Utc::now();
+        *self.metrics.lock().await = OrchestratorMetrics {
+            run_id: Some(run_id),
+            ..Default::default()
+        };
+
+        let result = self.run_loop(run_id).await;
+
+        let description = match &result {
+            Ok(reason) => format!("Run stopped: {}", reason),
+            Err(e) => format!("Run failed: {}", e),
+        };
+        self.record_progress(
+            PROGRESS_RUN_STOPPED,
+            description,
+            serde_json::json!({ "run_id": run_id }),
+        )
+        .await;
+
+        let record = RunRecord {
+            run_id,
+            started_at,
+            finished_at: chrono::Utc::now(),
+            stop_reason: result.as_ref().ok().cloned(),
+            metrics: self.metrics().await,
+        };
+        let mut history = self.run_history.lock().await;
+        history.push_back(record);
+        while history.len() > self.run_history_limit {
+            history.pop_front();
+        }
+
+        result
+    }
+
+    /// Finished runs, oldest first
+    pub async fn get_run_history(&self) -> Vec<RunRecord> {
+        self.run_history.lock().await.iter().cloned().collect()
+    }
+
+    /// Main dispatch loop for one run
+    #[instrument(skip_all, fields(run_id = %run_id))]
+    async fn run_loop(&self, run_id: uuid::Uuid) -> Result<StopReason, String> {
         *self.running.write().await = true;
 
         info!("Orchestrator starting...");
@@ -112,17 +517,44 @@ impl Orchestrator {
                 return Ok(StopReason::MaxExecutionTime);
             }
 
+            if *self.paused.read().await {
+                self.tick().await;
+                continue;
+            }
+
             // Process messages for all agents
-            let agents = self.registry.list_agents().await;
+            let agents = self.ordered_agents().await;
             if agents.is_empty() {
                 debug!("No agents registered, stopping");
-                return Ok(StopReason::Completed);
+                return Ok(StopReason::NoAgents);
             }
 
             let mut processed_any = false;
+            let mut dead_backlog = 0;
+            let iteration_start = std::time::Instant::now();
+            let throttled_before = self.metrics.lock().await.throttled_count;
+
+            let mut batch = Vec::new();
 
             for agent in agents {
-                // Check per-agent message limit
+                // Agents past their failure threshold are left alone
+                if matches!(agent.status, AgentStatus::Failed { .. }) {
+                    if let Some(mailbox) = self.message_bus.get_mailbox(agent.id).await {
+                        dead_backlog += mailbox.len().await;
+                    }
+                    continue;
+                }
+
+                // Check per-agent message limit (agents may only tighten the guard)
+                let max_messages = self
+                    .registry
+                    .get_config(agent.id)
+                    .await
+                    .and_then(|c| c.max_messages)
+                    .map_or(self.loop_guard.max_messages_per_agent, |limit| {
+                        limit.min(self.loop_guard.max_messages_per_agent)
+                    });
+
                 let agent_msg_count = self
                     .metrics
                     .lock()
@@ -132,7 +564,7 @@ impl Orchestrator {
                     .copied()
                     .unwrap_or(0);
 
-                if agent_msg_count >= self.loop_guard.max_messages_per_agent {
+                if agent_msg_count >= max_messages {
                     warn!(
                         "Agent {} reached max messages: {}",
                         agent.name, agent_msg_count
@@ -143,52 +575,231 @@ impl Orchestrator {
                     });
                 }
 
+                if self.max_parallel_agents > 1 {
+                    batch.push(agent);
+                    continue;
+                }
+
                 // Process one message for this agent
-                if let Some(result) = self.process_agent_message(agent.id).await {
-                    match result {
-                        Ok(_) => processed_any = true,
-                        Err(e) => {
-                            error!("Agent {} error: {}", agent.name, e);
-                            return Ok(StopReason::AgentError {
-                                agent_id: agent.id,
-                                error: e,
-                            });
-                        }
+                let result = self.process_agent_message(agent.id).await;
+                if let Some(reason) = self
+                    .settle_agent_message(&agent, result, &mut processed_any, iteration_start, iterations)
+                    .await
+                {
+                    return Ok(reason);
+                }
+            }
+
+            // One message each for the batched agents, at most `max_parallel_agents` at a time
+            if !batch.is_empty() {
+                let permits = Semaphore::new(self.max_parallel_agents);
+                let results = join_all(batch.iter().map(|agent| async {
+                    let _permit = permits.acquire().await.expect("semaphore is never closed");
+                    self.process_agent_message(agent.id).await
+                }))
+                .await;
+
+                for (agent, result) in batch.iter().zip(results) {
+                    if let Some(reason) = self
+                        .settle_agent_message(agent, result, &mut processed_any, iteration_start, iterations)
+                        .await
+                    {
+                        return Ok(reason);
                     }
                 }
             }
 
             // Update metrics
-            {
+            let throttled_only = {
                 let mut metrics = self.metrics.lock().await;
                 metrics.total_iterations = iterations;
                 metrics.queue_depth = self.message_bus.queue_depth().await;
-            }
+                !processed_any && metrics.throttled_count > throttled_before
+            };
 
-            iterations += 1;
+            // An iteration where every agent with mail was throttled did no
+            // work, so it doesn't count toward the iteration limit
+            if !throttled_only {
+                iterations += 1;
+            }
 
-            // If no messages were processed and queue is empty, we're done
-            if !processed_any && self.message_bus.queue_depth().await == 0 {
+            // If no messages were processed and only dead agents have mail, we're done
+            let live_depth = self.message_bus.queue_depth().await.saturating_sub(dead_backlog);
+            if !processed_any && live_depth == 0 {
                 info!("All messages processed, orchestrator completing");
                 return Ok(StopReason::Completed);
             }
 
             // Small delay to prevent busy loop
-            tokio::time::sleep(Duration::from_millis(10)).await;
+            self.tick().await;
+        }
+    }
+
+    /// Registered agents in the configured execution order
+    async fn ordered_agents(&self) -> Vec<AgentMetadata> {
+        let mut agents = self.registry.list_agents().await;
+        let mut priorities = HashMap::new();
+        if self.execution_order == ExecutionOrder::AgentPriority {
+            for agent in &agents {
+                if let Some(config) = self.registry.get_config(agent.id).await {
+                    priorities.insert(agent.id, config.priority);
+                }
+            }
+        }
+        self.execution_order
+            .sort(&mut agents, |id| priorities.get(&id).copied().unwrap_or(0));
+        agents
+    }
+
+    /// Act on one agent's processing result, returning a reason to stop the run
+    ///
+    /// An error only stops the run once the agent is past its failure
+    /// threshold; otherwise the message is retried on a later iteration.
+    async fn settle_agent_message(
+        &self,
+        agent: &AgentMetadata,
+        result: Option<Result<(), String>>,
+        processed_any: &mut bool,
+        iteration_start: std::time::Instant,
+        iteration: u32,
+    ) -> Option<StopReason> {
+        match result {
+            Some(Ok(())) => *processed_any = true,
+            Some(Err(e)) => {
+                let dead = self
+                    .registry
+                    .get_metadata(agent.id)
+                    .await
+                    .is_some_and(|m| matches!(m.status, AgentStatus::Failed { .. }));
+                if dead {
+                    error!("Agent {} error: {}", agent.name, e);
+                    return Some(StopReason::AgentError {
+                        agent_id: agent.id,
+                        error: e,
+                    });
+                }
+                warn!("Agent {} error, will retry: {}", agent.name, e);
+                *processed_any = true;
+            }
+            None => {}
+        }
+
+        if let Some(reason) = self.check_budget().await {
+            warn!("Session budget exceeded: {}", reason);
+            return Some(reason);
+        }
+
+        // Check per-iteration time, separately from the total budget
+        if let Some(limit_ms) = self.loop_guard.max_iteration_time_ms {
+            if iteration_start.elapsed().as_millis() as u64 > limit_ms {
+                warn!("Iteration {} exceeded {}ms", iteration, limit_ms);
+                return Some(StopReason::IterationTimeout { iteration });
+            }
+        }
+
+        None
+    }
+
+    /// `BudgetExceeded` if this run's usage has crossed the budget
+    async fn check_budget(&self) -> Option<StopReason> {
+        let budget = self.budget.as_ref()?;
+        let metrics = self.metrics.lock().await;
+        if !budget.is_exceeded(metrics.input_tokens, metrics.output_tokens) {
+            return None;
+        }
+        Some(StopReason::BudgetExceeded {
+            total_tokens: metrics.input_tokens + metrics.output_tokens,
+            cost_usd: budget.cost_usd(metrics.input_tokens, metrics.output_tokens),
+        })
+    }
+
+    /// Wait between loop iterations, until the clock has moved a full tick
+    ///
+    /// Returns early once the run is stopped, so a clock that stops moving
+    /// can't hold up a stop.
+    async fn tick(&self) {
+        if self.tick_interval_ms == 0 {
+            tokio::task::yield_now().await;
+            return;
+        }
+
+        let until = self.clock.now() + Duration::from_millis(self.tick_interval_ms);
+        while let Ok(remaining) = until.duration_since(self.clock.now()) {
+            if remaining.is_zero() || !*self.running.read().await {
+                return;
+            }
+            tokio::time::sleep(remaining.min(TICK_POLL_INTERVAL)).await;
+        }
+    }
+
+    /// Whether lifecycle events go anywhere: a bound session or a handle subscriber
+    fn is_observed(&self) -> bool {
+        self.progress.is_some() || self.events.receiver_count() > 0
+    }
+
+    /// Broadcast a lifecycle event to handle subscribers and write it to the
+    /// bound session, if any
+    ///
+    /// Failures are logged rather than stopping the run.
+    async fn record_progress(&self, event_type: &str, description: String, data: serde_json::Value) {
+        // No subscribers is not an error
+        let _ = self.events.send(OrchestratorEvent {
+            event_type: event_type.to_string(),
+            description: description.clone(),
+            data: data.clone(),
+        });
+
+        let Some(sink) = &self.progress else {
+            return;
+        };
+
+        let mut event = ProgressEvent::new(sink.session_id.clone(), event_type.to_string(), description);
+        event.data = Some(data.to_string());
+        if let Err(e) = sink.service.add_progress_event(event).await {
+            warn!("Failed to record {} progress event: {}", event_type, e);
+        }
+    }
+
+    /// Get a handle that can stop or pause this orchestrator from another task
+    pub fn handle(&self) -> OrchestratorHandle {
+        OrchestratorHandle {
+            running: self.running.clone(),
+            paused: self.paused.clone(),
+            events: self.events.clone(),
         }
     }
 
     /// Stop the orchestrator
     pub async fn stop(&self) {
-        *self.running.write().await = false;
+        self.handle().stop().await;
+    }
+
+    /// Wait until no run is in progress, returning at once if none is
+    pub async fn wait_until_finished(&self) {
+        drop(self.run_guard.lock().await);
     }
 
     /// Process a single message for an agent
+    #[instrument(skip_all, fields(agent_id = %agent_id, message_id = tracing::field::Empty))]
     async fn process_agent_message(&self, agent_id: AgentId) -> Option<Result<(), String>> {
         let mailbox = self.message_bus.get_mailbox(agent_id).await?;
 
+        // Leave the message queued if the agent is over its rate
+        if let Some(limiter) = &self.agent_rate_limiter {
+            if mailbox.is_empty().await {
+                return None;
+            }
+            if let Err(e) = limiter.check_rate_limit(&agent_id.to_string()).await {
+                debug!("Agent {} throttled: {}", agent_id, e);
+                self.metrics.lock().await.throttled_count += 1;
+                return None;
+            }
+        }
+
         // Get the next message
         let message = mailbox.pop().await?;
+        tracing::Span::current().record("message_id", tracing::field::display(message.id));
+        let input = message.content.clone();
 
         debug!(
             "Processing message {} for agent {}",
@@ -199,30 +810,76 @@ impl Orchestrator {
         self.registry
             .update_status(agent_id, AgentStatus::Processing)
             .await;
+        self.registry.heartbeat(agent_id).await;
 
         // Process with retry logic
         let config = self.registry.get_config(agent_id).await?;
-        let result = self.execute_with_retry(agent_id, message, &config).await;
+        let event_data = serde_json::json!({
+            "agent_id": agent_id,
+            "message_id": message.id,
+        });
+        self.record_progress(
+            PROGRESS_AGENT_STARTED,
+            format!("{} started processing", config.name),
+            event_data.clone(),
+        )
+        .await;
+
+        let attempt = if self.escalate_on_retry {
+            self.execute_or_requeue(agent_id, message, &config).await
+        } else {
+            self.execute_with_retry(agent_id, message, &config)
+                .await
+                .map(Attempt::Done)
+        };
+
+        // A re-queued retry isn't handled yet: it leaves the failure count
+        // alone and doesn't count toward the agent's message limit
+        let result = match attempt {
+            Ok(Attempt::Done(output)) => Ok(output),
+            Ok(Attempt::Requeued(e)) => {
+                self.registry.update_status(agent_id, AgentStatus::Idle).await;
+                self.record_progress(
+                    PROGRESS_AGENT_RETRYING,
+                    format!("{} failed, retry re-queued: {}", config.name, e),
+                    event_data,
+                )
+                .await;
+                self.registry.heartbeat(agent_id).await;
+                return Some(Ok(()));
+            }
+            Err(e) => Err(e),
+        };
+
+        if let Ok(output) = &result {
+            self.route_output(agent_id, output).await;
+            self.remember(agent_id, &input, output).await;
+        }
 
         // Update status based on result
         match &result {
             Ok(_) => {
-                self.registry
-                    .update_status(agent_id, AgentStatus::Idle)
-                    .await;
+                self.registry.record_success(agent_id).await;
+                self.record_progress(
+                    PROGRESS_AGENT_COMPLETED,
+                    format!("{} completed", config.name),
+                    event_data,
+                )
+                .await;
             }
-            Err(_) => {
-                self.registry
-                    .update_status(
-                        agent_id,
-                        AgentStatus::Failed {
-                            reason: "Processing failed".to_string(),
-                        },
-                    )
-                    .await;
+            Err(e) => {
+                self.record_progress(
+                    PROGRESS_AGENT_FAILED,
+                    format!("{} failed: {}", config.name, e),
+                    event_data,
+                )
+                .await;
+                self.registry.record_failure(agent_id, "Processing failed").await;
             }
         }
 
+        self.registry.heartbeat(agent_id).await;
+
         // Mark as received
         self.message_bus.mark_received().await;
 
@@ -233,7 +890,69 @@ impl Orchestrator {
             metrics.total_messages += 1;
         }
 
-        Some(result)
+        Some(result.map(|_| ()))
+    }
+
+    /// Add a handled message and its output to the agent's memory buffer
+    async fn remember(&self, agent_id: AgentId, input: &str, output: &str) {
+        let Some(memory) = &self.memory else {
+            return;
+        };
+        if memory.get_agent_buffer(agent_id).await.is_none() {
+            memory
+                .create_agent_buffer(agent_id, DEFAULT_AGENT_MEMORY_TOKENS)
+                .await;
+        }
+
+        for (kind, content) in [("input", input), ("output", output)] {
+            let tokens = u32::try_from(ApproxTokenCounter.count(content)).unwrap_or(u32::MAX);
+            let mut entry = MemoryEntry::new(content.to_string(), tokens);
+            entry.metadata.insert("kind".to_string(), kind.to_string());
+            if let Err(e) = memory.add_to_agent(agent_id, entry).await {
+                warn!("Failed to remember {} for agent {}: {}", kind, agent_id, e);
+            }
+        }
+    }
+
+    /// Send `output` on to agents picked by the routing rules
+    ///
+    /// Each matching rule goes to the least busy agent with its target role,
+    /// never back to the agent that produced the output.
+    async fn route_output(&self, from: AgentId, output: &str) {
+        for rule in self.routing_rules.iter().filter(|r| r.matches(output)) {
+            let mut target = None;
+            let mut target_depth = usize::MAX;
+            for agent in self.registry.list_by_role(rule.target_role.clone()).await {
+                if agent.id == from {
+                    continue;
+                }
+                let Some(mailbox) = self.message_bus.get_mailbox(agent.id).await else {
+                    continue;
+                };
+                let depth = mailbox.len().await;
+                if depth < target_depth {
+                    target = Some(agent.id);
+                    target_depth = depth;
+                }
+            }
+
+            let Some(to) = target else {
+                warn!(
+                    "No {:?} agent to route output matching '{}'",
+                    rule.target_role, rule.match_pattern
+                );
+                continue;
+            };
+
+            debug!("Routing output matching '{}' to agent {}", rule.match_pattern, to);
+            if let Err(e) = self
+                .message_bus
+                .send(AgentMessage::new(from, to, output.to_string()))
+                .await
+            {
+                warn!("Failed to route output to agent {}: {}", to, e);
+            }
+        }
     }
 
     /// Execute message processing with retry logic
@@ -242,12 +961,12 @@ impl Orchestrator {
         agent_id: AgentId,
         message: AgentMessage,
         config: &AgentConfig,
-    ) -> Result<(), String> {
+    ) -> Result<String, String> {
         let mut retries = 0;
 
         loop {
             match self.execute_message(agent_id, &message, config).await {
-                Ok(_) => return Ok(()),
+                Ok(output) => return Ok(output),
                 Err(e) => {
                     retries += 1;
 
@@ -270,24 +989,153 @@ impl Orchestrator {
         }
     }
 
-    /// Execute a message (stub implementation)
+    /// Execute a message once, putting it back in the mailbox at a higher
+    /// priority if it fails and has retries left
+    async fn execute_or_requeue(
+        &self,
+        agent_id: AgentId,
+        message: AgentMessage,
+        config: &AgentConfig,
+    ) -> Result<Attempt, String> {
+        match self.execute_message(agent_id, &message, config).await {
+            Ok(output) => Ok(Attempt::Done(output)),
+            Err(e) if message.retries + 1 < config.max_retries => {
+                self.metrics.lock().await.retry_count += 1;
+
+                let retry = message.escalated_retry();
+                warn!(
+                    "Retry {} for agent {}, re-queued at {:?} priority: {}",
+                    retry.retries, agent_id, retry.effective_priority(), e
+                );
+                if let Err(requeue_error) = self.message_bus.requeue(retry).await {
+                    self.metrics.lock().await.error_count += 1;
+                    return Err(format!("Retry not re-queued: {}: {}", requeue_error, e));
+                }
+                Ok(Attempt::Requeued(e))
+            }
+            Err(e) => {
+                self.metrics.lock().await.error_count += 1;
+                Err(format!("Max retries exceeded: {}", e))
+            }
+        }
+    }
+
+    /// Execute a message on the agent's connector, or the stub if none is registered,
+    /// returning the agent's text output
+    ///
+    /// Connector calls are recorded as `connector_request` progress events,
+    /// including ones cut off by the agent's timeout.
     async fn execute_message(
         &self,
-        _agent_id: AgentId,
+        agent_id: AgentId,
         message: &AgentMessage,
         config: &AgentConfig,
-    ) -> Result<(), String> {
-        // Simulate processing with timeout
-        let work = async {
-            // Stub: In a real implementation, this would call the connector
-            tokio::time::sleep(Duration::from_millis(10)).await;
-            debug!("Processed message: {}", message.content);
-            Ok(())
+    ) -> Result<String, String> {
+        let input = message.connector_input();
+        let limit = Duration::from_millis(config.timeout_ms);
+        let Some(connector) = self.resolve_connector(agent_id, config).await else {
+            // Stub: simulate processing when no connector is registered
+            let work = async {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+                debug!(
+                    structured = message.payload.is_some(),
+                    "Processed message: {}", input
+                );
+            };
+            timeout(limit, work).await.map_err(|_| "Timeout".to_string())?;
+            return Ok(String::new());
         };
 
-        timeout(Duration::from_millis(config.timeout_ms), work)
+        let started = std::time::Instant::now();
+        let mut usage = (0, 0);
+        let result = timeout(limit, self.run_connector(connector.as_ref(), &input, &mut usage))
             .await
-            .map_err(|_| "Timeout".to_string())?
+            .unwrap_or_else(|_| Err("Timeout".to_string()));
+
+        if self.is_observed() {
+            let latency_ms = started.elapsed().as_millis() as u64;
+            let model = connector.model().await;
+            self.record_progress(
+                PROGRESS_CONNECTOR_REQUEST,
+                format!(
+                    "{} request: {} tokens in {}ms",
+                    connector.name(),
+                    usage.0 + usage.1,
+                    latency_ms
+                ),
+                serde_json::json!({
+                    "agent_id": agent_id,
+                    "connector": connector.name(),
+                    "model": model,
+                    "prompt_len": input.len(),
+                    "input_tokens": usage.0,
+                    "output_tokens": usage.1,
+                    "latency_ms": latency_ms,
+                    "error": result.as_ref().err(),
+                }),
+            )
+            .await;
+        }
+
+        result
+    }
+
+    /// Connector for an agent: its own override, then the bound session's,
+    /// then the one registered for its `connector_type`
+    async fn resolve_connector(&self, agent_id: AgentId, config: &AgentConfig) -> Option<Arc<dyn Connector>> {
+        let agent_key = agent_id.to_string();
+        let session_key = self.progress.as_ref().map(|sink| sink.session_id.as_str());
+        let overridden = std::iter::once(agent_key.as_str())
+            .chain(session_key)
+            .find_map(|key| self.connector_overrides.get_key_value(key));
+
+        if let Some((key, override_config)) = overridden {
+            let cache_key = (key.clone(), config.connector_type.clone());
+            let mut built = self.override_connectors.lock().await;
+            if let Some(connector) = built.get(&cache_key) {
+                return Some(connector.clone());
+            }
+            match cli_connector(&config.connector_type, override_config.clone()) {
+                Ok(connector) => {
+                    built.insert(cache_key, connector.clone());
+                    return Some(connector);
+                }
+                Err(e) => warn!("Ignoring connector override {} for agent {}: {}", key, agent_id, e),
+            }
+        }
+
+        self.connectors.get(&config.connector_type).cloned()
+    }
+
+    /// Drain a connector's stream into its text output, failing on the first
+    /// error it reports
+    ///
+    /// Reported `Usage` is added to the run's token totals and to `usage` as it
+    /// arrives, so a call cut off part-way still reports what it used.
+    async fn run_connector(
+        &self,
+        connector: &dyn Connector,
+        input: &str,
+        usage: &mut (u64, u64),
+    ) -> Result<String, String> {
+        let mut rx = connector.execute(input).await?;
+        let mut output = String::new();
+        while let Some(msg) = rx.recv().await {
+            match msg {
+                ConnectorMessage::Content { content } => output.push_str(&content),
+                ConnectorMessage::Usage { input_tokens, output_tokens } => {
+                    usage.0 += input_tokens;
+                    usage.1 += output_tokens;
+                    let mut metrics = self.metrics.lock().await;
+                    metrics.input_tokens += input_tokens;
+                    metrics.output_tokens += output_tokens;
+                }
+                ConnectorMessage::Error { message } => return Err(message),
+                ConnectorMessage::Done => break,
+                _ => {}
+            }
+        }
+        Ok(output)
     }
 
     /// Get current metrics
@@ -295,16 +1143,26 @@ impl Orchestrator {
         self.metrics.lock().await.clone()
     }
 
-    /// Reset metrics
+    /// Forget one agent's message count, leaving other agents and run totals alone
+    pub async fn reset_agent_metrics(&self, agent_id: AgentId) {
+        self.metrics.lock().await.messages_per_agent.remove(&agent_id);
+    }
+
+    /// Reset metrics, keeping the current run ID
     pub async fn reset_metrics(&self) {
-        *self.metrics.lock().await = OrchestratorMetrics::default();
+        let mut metrics = self.metrics.lock().await;
+        *metrics = OrchestratorMetrics {
+            run_id: metrics.run_id,
+            ..Default::default()
+        };
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::runtime::types::AgentRole;
+    use crate::runtime::types::{AgentRole, MessagePriority};
+    use tracing_test::traced_test;
 
     #[tokio::test]
     async fn test_loop_guard_max_iterations() {
@@ -316,7 +1174,7 @@ mod tests {
             AgentRole::Worker,
             "claude_code".to_string(),
         );
-        let agent_id = registry.register(config).await;
+        let agent_id = registry.register(config).await.unwrap();
         bus.create_mailbox(agent_id).await;
 
         // Send many messages
@@ -329,6 +1187,7 @@ mod tests {
             max_iterations: 5,
             max_messages_per_agent: 100,
             max_execution_time_ms: 60000,
+            ..LoopGuard::default()
         });
 
         let result = orchestrator.start().await.unwrap();
@@ -345,7 +1204,7 @@ mod tests {
             AgentRole::Worker,
             "claude_code".to_string(),
         );
-        let agent_id = registry.register(config).await;
+        let agent_id = registry.register(config).await.unwrap();
         bus.create_mailbox(agent_id).await;
 
         // Send messages
@@ -358,6 +1217,7 @@ mod tests {
             max_iterations: 1000,
             max_messages_per_agent: 3,
             max_execution_time_ms: 60000,
+            ..LoopGuard::default()
         });
 
         let result = orchestrator.start().await.unwrap();
@@ -377,7 +1237,7 @@ mod tests {
             AgentRole::Worker,
             "claude_code".to_string(),
         );
-        let agent_id = registry.register(config).await;
+        let agent_id = registry.register(config).await.unwrap();
         bus.create_mailbox(agent_id).await;
 
         // Send just one message
@@ -393,6 +1253,42 @@ mod tests {
         assert_eq!(metrics.total_messages, 1);
     }
 
+    #[tokio::test]
+    async fn test_handled_messages_are_remembered() {
+        let registry = Arc::new(AgentRegistry::new());
+        let bus = Arc::new(MessageBus::new());
+        let agent_id = registry
+            .register(AgentConfig::new("worker".to_string(), AgentRole::Worker, "claude_code".to_string()))
+            .await
+            .unwrap();
+        bus.create_mailbox(agent_id).await;
+        bus.send(AgentMessage::new(agent_id, agent_id, "summarize the logs".to_string()))
+            .await
+            .unwrap();
+
+        let memory = Arc::new(MemoryManager::new(10));
+        let orchestrator = Orchestrator::new(registry, bus).with_memory(memory.clone());
+        orchestrator.start().await.unwrap();
+
+        let snapshot = memory.snapshot(agent_id).await.unwrap();
+        let kinds: Vec<&str> = snapshot
+            .entries
+            .iter()
+            .map(|e| e.metadata["kind"].as_str())
+            .collect();
+        assert_eq!(kinds, vec!["input", "output"]);
+        assert_eq!(snapshot.entries[0].content, "summarize the logs");
+    }
+
+    #[tokio::test]
+    async fn test_empty_registry_stops_with_no_agents() {
+        let orchestrator = Orchestrator::new(Arc::new(AgentRegistry::new()), Arc::new(MessageBus::new()));
+
+        let reason = orchestrator.start().await.unwrap();
+        assert!(matches!(reason, StopReason::NoAgents), "got {:?}", reason);
+        assert_eq!(reason.to_string(), "no_agents");
+    }
+
     #[tokio::test]
     async fn test_orchestrator_metrics() {
         let registry = Arc::new(AgentRegistry::new());
@@ -403,7 +1299,7 @@ mod tests {
             AgentRole::Worker,
             "claude_code".to_string(),
         );
-        let agent_id = registry.register(config).await;
+        let agent_id = registry.register(config).await.unwrap();
         bus.create_mailbox(agent_id).await;
 
         for i in 0..5 {
@@ -418,4 +1314,753 @@ mod tests {
         assert_eq!(metrics.total_messages, 5);
         assert!(metrics.total_iterations > 0);
     }
+
+    #[tokio::test]
+    async fn test_retry_escalates_priority_ahead_of_new_messages() {
+        let registry = Arc::new(AgentRegistry::new());
+        let bus = Arc::new(MessageBus::new());
+
+        let mut config = AgentConfig::new(
+            "test-agent".to_string(),
+            AgentRole::Worker,
+            "claude_code".to_string(),
+        );
+        // Shorter than the stub's processing time, so every attempt fails
+        config.timeout_ms = 1;
+        let agent_id = registry.register(config.clone()).await.unwrap();
+        let mailbox = bus.create_mailbox(agent_id).await;
+
+        let orchestrator = Orchestrator::new(registry, bus).with_priority_escalation(true);
+
+        let failing = AgentMessage::new(agent_id, agent_id, "flaky".to_string());
+        let failing_id = failing.id;
+        let attempt = orchestrator
+            .execute_or_requeue(agent_id, failing, &config)
+            .await
+            .unwrap();
+        assert!(matches!(attempt, Attempt::Requeued(_)), "got {:?}", attempt);
+
+        // Fresh normal-priority traffic arrives after the failure
+        for i in 0..3 {
+            mailbox
+                .push(AgentMessage::new(agent_id, agent_id, format!("fresh{}", i)))
+                .await;
+        }
+
+        let retried = mailbox.pop().await.unwrap();
+        assert_eq!(retried.id, failing_id);
+        assert_eq!(retried.retries, 1);
+        assert_eq!(retried.effective_priority(), MessagePriority::High);
+
+        // Second failure escalates again; the last allowed attempt gives up
+        let attempt = orchestrator
+            .execute_or_requeue(agent_id, retried, &config)
+            .await
+            .unwrap();
+        assert!(matches!(attempt, Attempt::Requeued(_)), "got {:?}", attempt);
+        let retried = mailbox.pop().await.unwrap();
+        assert_eq!(retried.effective_priority(), MessagePriority::Critical);
+        assert!(orchestrator
+            .execute_or_requeue(agent_id, retried, &config)
+            .await
+            .is_err());
+
+        let metrics = orchestrator.metrics().await;
+        assert_eq!(metrics.retry_count, 2);
+        assert_eq!(metrics.error_count, 1);
+        assert_eq!(mailbox.len().await, 3);
+    }
+
+    #[tokio::test]
+    async fn test_requeued_retries_are_not_handled_messages() {
+        let registry = Arc::new(AgentRegistry::new());
+        let bus = Arc::new(MessageBus::new());
+
+        let mut config = AgentConfig::new(
+            "flaky-agent".to_string(),
+            AgentRole::Worker,
+            "claude_code".to_string(),
+        );
+        // Shorter than the stub's processing time, so every attempt fails
+        config.timeout_ms = 1;
+        config.max_failures = 2;
+        let agent_id = registry.register(config).await.unwrap();
+        bus.create_mailbox(agent_id).await;
+        for i in 0..2 {
+            bus.send(AgentMessage::new(agent_id, agent_id, format!("flaky{}", i)))
+                .await
+                .unwrap();
+        }
+
+        let orchestrator = Orchestrator::new(registry.clone(), bus).with_priority_escalation(true);
+        let mut events = orchestrator.handle().subscribe();
+
+        // Re-queued attempts don't reset the failure count, so the second
+        // message to run out of retries reaches the threshold
+        let reason = orchestrator.start().await.unwrap();
+        assert!(
+            matches!(reason, StopReason::AgentError { agent_id: id, .. } if id == agent_id),
+            "got {:?}",
+            reason
+        );
+        assert_eq!(registry.get_metadata(agent_id).await.unwrap().failure_count, 2);
+
+        let metrics = orchestrator.metrics().await;
+        assert_eq!(metrics.total_messages, 2);
+        assert_eq!(metrics.messages_per_agent.get(&agent_id), Some(&2));
+        assert_eq!(metrics.retry_count, 4);
+
+        let mut types = Vec::new();
+        while let Ok(event) = events.try_recv() {
+            types.push(event.event_type);
+        }
+        let per_message = [
+            PROGRESS_AGENT_STARTED,
+            PROGRESS_AGENT_RETRYING,
+            PROGRESS_AGENT_STARTED,
+            PROGRESS_AGENT_RETRYING,
+            PROGRESS_AGENT_STARTED,
+            PROGRESS_AGENT_FAILED,
+        ];
+        let mut expected = [per_message, per_message].concat();
+        expected.push(PROGRESS_RUN_STOPPED);
+        assert_eq!(types, expected);
+    }
+
+    #[tokio::test]
+    async fn test_iteration_timeout_is_distinct_from_total() {
+        use crate::connectors::MockConnector;
+
+        let registry = Arc::new(AgentRegistry::new());
+        let bus = Arc::new(MessageBus::new());
+
+        let config = AgentConfig::new(
+            "slow-agent".to_string(),
+            AgentRole::Worker,
+            "mock".to_string(),
+        );
+        let agent_id = registry.register(config).await.unwrap();
+        bus.create_mailbox(agent_id).await;
+        bus.send(AgentMessage::new(agent_id, agent_id, "slow".to_string()))
+            .await
+            .unwrap();
+
+        let slow = MockConnector::new(vec![ConnectorMessage::Content {
+            content: "done".to_string(),
+        }])
+        .with_delay(Duration::from_millis(100));
+        let orchestrator = Orchestrator::new(registry, bus)
+            .with_connector("mock", Arc::new(slow))
+            .with_loop_guard(LoopGuard {
+                max_iteration_time_ms: Some(20),
+                ..LoopGuard::default()
+            });
+
+        let reason = orchestrator.start().await.unwrap();
+        assert!(matches!(reason, StopReason::IterationTimeout { iteration: 0 }));
+        assert_eq!(reason.to_string(), "iteration_timeout:0");
+    }
+
+    #[tokio::test]
+    async fn test_parallel_agents_run_concurrently() {
+        use crate::connectors::MockConnector;
+
+        let registry = Arc::new(AgentRegistry::new());
+        let bus = Arc::new(MessageBus::new());
+
+        let agents = 4;
+        for i in 0..agents {
+            let config = AgentConfig::new(format!("slow-{}", i), AgentRole::Worker, "mock".to_string());
+            let agent_id = registry.register(config).await.unwrap();
+            bus.create_mailbox(agent_id).await;
+            bus.send(AgentMessage::new(agent_id, agent_id, "work".to_string()))
+                .await
+                .unwrap();
+        }
+
+        let delay = Duration::from_millis(200);
+        let slow = MockConnector::new(vec![ConnectorMessage::Content {
+            content: "done".to_string(),
+        }])
+        .with_delay(delay);
+        let orchestrator = Orchestrator::new(registry, bus)
+            .with_connector("mock", Arc::new(slow))
+            .with_max_parallel_agents(agents);
+
+        let started = std::time::Instant::now();
+        let reason = orchestrator.start().await.unwrap();
+        let elapsed = started.elapsed();
+
+        assert!(matches!(reason, StopReason::Completed), "got {:?}", reason);
+        let metrics = orchestrator.metrics().await;
+        assert_eq!(metrics.total_messages, agents as u64);
+        assert_eq!(metrics.messages_per_agent.len(), agents);
+
+        // Each call streams two delayed messages (content, then done), so run
+        // one after another the agents would take at least `agents * 2 * delay`
+        assert!(elapsed < delay * agents as u32, "took {:?}", elapsed);
+    }
+
+    #[tokio::test]
+    async fn test_agent_rate_limit_throttles_only_the_busy_agent() {
+        use crate::api::rate_limit::RateLimitConfig;
+        use crate::clock::MockClock;
+
+        let registry = Arc::new(AgentRegistry::new());
+        let bus = Arc::new(MessageBus::new());
+
+        let mut agents = Vec::new();
+        for (name, burst) in [("chatty", 6), ("quiet", 2)] {
+            let agent_id = registry
+                .register(AgentConfig::new(name.to_string(), AgentRole::Worker, "stub".to_string()))
+                .await
+                .unwrap();
+            bus.create_mailbox(agent_id).await;
+            for i in 0..burst {
+                bus.send(AgentMessage::new(agent_id, agent_id, format!("msg {}", i)))
+                    .await
+                    .unwrap();
+            }
+            agents.push(agent_id);
+        }
+        let (chatty, quiet) = (agents[0], agents[1]);
+
+        // The clock never moves, so no bucket refills during the run
+        let limiter = RateLimiter::new(RateLimitConfig {
+            requests_per_second: 1,
+            burst_size: 2,
+        })
+        .with_clock(Arc::new(MockClock::new()));
+        let orchestrator = Orchestrator::new(registry, bus.clone())
+            .with_agent_rate_limit(Arc::new(limiter))
+            .with_tick_interval_ms(0)
+            .with_loop_guard(LoopGuard {
+                max_iterations: 10,
+                max_execution_time_ms: 200,
+                ..Default::default()
+            });
+
+        // Once only the throttled agent has mail, iterations stop counting
+        // and the run waits for tokens until its time runs out
+        let reason = orchestrator.start().await.unwrap();
+        assert!(matches!(reason, StopReason::MaxExecutionTime), "got {:?}", reason);
+
+        let metrics = orchestrator.metrics().await;
+        assert!(metrics.total_iterations < 10);
+        assert_eq!(metrics.messages_per_agent.get(&chatty), Some(&2));
+        assert_eq!(metrics.messages_per_agent.get(&quiet), Some(&2));
+        assert!(metrics.throttled_count > 0);
+        assert_eq!(bus.get_mailbox(chatty).await.unwrap().len().await, 4);
+        assert!(bus.get_mailbox(quiet).await.unwrap().is_empty().await);
+    }
+
+    #[tokio::test]
+    async fn test_role_priority_runs_coordinator_first() {
+        use crate::connectors::MockConnector;
+
+        let registry = Arc::new(AgentRegistry::new());
+        let bus = Arc::new(MessageBus::new());
+
+        // Registered last, so registration order would run it last
+        for (name, role) in [
+            ("worker-1", AgentRole::Worker),
+            ("worker-2", AgentRole::Worker),
+            ("coordinator", AgentRole::Coordinator),
+        ] {
+            let agent_id = registry
+                .register(AgentConfig::new(name.to_string(), role, "mock".to_string()))
+                .await
+                .unwrap();
+            bus.create_mailbox(agent_id).await;
+            bus.send(AgentMessage::new(agent_id, agent_id, name.to_string()))
+                .await
+                .unwrap();
+        }
+
+        // The mock records each agent's prompt in the order it was processed
+        let recorder = Arc::new(MockConnector::new(Vec::new()));
+        let orchestrator = Orchestrator::new(registry, bus)
+            .with_connector("mock", recorder.clone())
+            .with_execution_order(ExecutionOrder::RolePriority {
+                order: vec![AgentRole::Coordinator],
+            });
+        let reason = orchestrator.start().await.unwrap();
+        assert!(matches!(reason, StopReason::Completed), "got {:?}", reason);
+
+        let prompts = recorder.prompts().await;
+        assert_eq!(prompts.len(), 3);
+        assert_eq!(prompts[0], "coordinator");
+    }
+
+    #[tokio::test]
+    async fn test_lifecycle_is_recorded_on_bound_session() {
+        use crate::db::Database;
+        use tempfile::NamedTempFile;
+
+        let temp_file = NamedTempFile::new().unwrap();
+        let db = Database::init(temp_file.path()).await.unwrap();
+        let sessions = Arc::new(SessionService::new(db.pool().clone()));
+        let session = sessions.create_session("run".to_string()).await.unwrap();
+
+        let registry = Arc::new(AgentRegistry::new());
+        let bus = Arc::new(MessageBus::new());
+        let agent_id = registry
+            .register(AgentConfig::new(
+                "worker".to_string(),
+                AgentRole::Worker,
+                "claude_code".to_string(),
+            ))
+            .await
+            .unwrap();
+        bus.create_mailbox(agent_id).await;
+        bus.send(AgentMessage::new(agent_id, agent_id, "test".to_string()))
+            .await
+            .unwrap();
+
+        let orchestrator =
+            Orchestrator::new(registry, bus).with_session(sessions.clone(), session.id.clone());
+        let reason = orchestrator.start().await.unwrap();
+        assert!(matches!(reason, StopReason::Completed));
+
+        let timeline = sessions.get_progress_timeline(&session.id).await.unwrap();
+        let types: Vec<&str> = timeline.iter().map(|e| e.event_type.as_str()).collect();
+        assert_eq!(
+            types,
+            vec![PROGRESS_AGENT_STARTED, PROGRESS_AGENT_COMPLETED, PROGRESS_RUN_STOPPED]
+        );
+        assert_eq!(timeline[2].description, "Run stopped: completed");
+        assert!(timeline[0].data.as_deref().unwrap().contains(&agent_id.to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_connector_request_is_recorded_on_bound_session() {
+        use crate::connectors::MockConnector;
+        use crate::db::Database;
+        use tempfile::NamedTempFile;
+
+        let temp_file = NamedTempFile::new().unwrap();
+        let db = Database::init(temp_file.path()).await.unwrap();
+        let sessions = Arc::new(SessionService::new(db.pool().clone()));
+        let session = sessions.create_session("audit".to_string()).await.unwrap();
+
+        let registry = Arc::new(AgentRegistry::new());
+        let bus = Arc::new(MessageBus::new());
+        let agent_id = registry
+            .register(AgentConfig::new("worker".to_string(), AgentRole::Worker, "mock".to_string()))
+            .await
+            .unwrap();
+        bus.create_mailbox(agent_id).await;
+        bus.send(AgentMessage::new(agent_id, agent_id, "summarize".to_string()))
+            .await
+            .unwrap();
+
+        let mock = MockConnector::new(vec![
+            ConnectorMessage::Content {
+                content: "summary".to_string(),
+            },
+            ConnectorMessage::Usage {
+                input_tokens: 12,
+                output_tokens: 5,
+            },
+        ])
+        .with_model("mock-large");
+        let orchestrator = Orchestrator::new(registry, bus)
+            .with_connector("mock", Arc::new(mock))
+            .with_session(sessions.clone(), session.id.clone());
+        orchestrator.start().await.unwrap();
+
+        let timeline = sessions.get_progress_timeline(&session.id).await.unwrap();
+        let types: Vec<&str> = timeline.iter().map(|e| e.event_type.as_str()).collect();
+        assert_eq!(
+            types,
+            vec![
+                PROGRESS_AGENT_STARTED,
+                PROGRESS_CONNECTOR_REQUEST,
+                PROGRESS_AGENT_COMPLETED,
+                PROGRESS_RUN_STOPPED
+            ]
+        );
+
+        let data: serde_json::Value =
+            serde_json::from_str(timeline[1].data.as_deref().unwrap()).unwrap();
+        assert_eq!(data["agent_id"], agent_id.to_string());
+        assert_eq!(data["connector"], "mock");
+        assert_eq!(data["model"], "mock-large");
+        assert_eq!(data["prompt_len"], "summarize".len());
+        assert_eq!(data["input_tokens"], 12);
+        assert_eq!(data["output_tokens"], 5);
+        assert!(data["latency_ms"].is_u64());
+        assert!(data["error"].is_null());
+    }
+
+    #[tokio::test]
+    async fn test_timed_out_connector_request_is_recorded() {
+        use crate::connectors::MockConnector;
+        use crate::db::Database;
+        use tempfile::NamedTempFile;
+
+        let temp_file = NamedTempFile::new().unwrap();
+        let db = Database::init(temp_file.path()).await.unwrap();
+        let sessions = Arc::new(SessionService::new(db.pool().clone()));
+        let session = sessions.create_session("audit".to_string()).await.unwrap();
+
+        let registry = Arc::new(AgentRegistry::new());
+        let bus = Arc::new(MessageBus::new());
+        let mut config = AgentConfig::new("worker".to_string(), AgentRole::Worker, "mock".to_string());
+        config.timeout_ms = 20;
+        config.max_retries = 1;
+        let agent_id = registry.register(config.clone()).await.unwrap();
+        bus.create_mailbox(agent_id).await;
+
+        let slow = MockConnector::new(vec![ConnectorMessage::Content {
+            content: "too late".to_string(),
+        }])
+        .with_delay(Duration::from_millis(500));
+        let orchestrator = Orchestrator::new(registry, bus)
+            .with_connector("mock", Arc::new(slow))
+            .with_session(sessions.clone(), session.id.clone());
+
+        let message = AgentMessage::new(agent_id, agent_id, "summarize".to_string());
+        assert!(orchestrator
+            .execute_or_requeue(agent_id, message, &config)
+            .await
+            .is_err());
+
+        let timeline = sessions.get_progress_timeline(&session.id).await.unwrap();
+        assert_eq!(timeline.len(), 1);
+        assert_eq!(timeline[0].event_type, PROGRESS_CONNECTOR_REQUEST);
+        let data: serde_json::Value =
+            serde_json::from_str(timeline[0].data.as_deref().unwrap()).unwrap();
+        assert_eq!(data["error"], "Timeout");
+        assert!(data["latency_ms"].as_u64().unwrap() >= 20);
+    }
+
+    #[tokio::test]
+    async fn test_budget_stops_run_on_token_usage() {
+        use crate::connectors::MockConnector;
+
+        let registry = Arc::new(AgentRegistry::new());
+        let bus = Arc::new(MessageBus::new());
+        let agent_id = registry
+            .register(AgentConfig::new(
+                "spender".to_string(),
+                AgentRole::Worker,
+                "mock".to_string(),
+            ))
+            .await
+            .unwrap();
+        bus.create_mailbox(agent_id).await;
+        for i in 0..5 {
+            bus.send(AgentMessage::new(agent_id, agent_id, format!("task {}", i)))
+                .await
+                .unwrap();
+        }
+
+        let expensive = MockConnector::new(vec![ConnectorMessage::Usage {
+            input_tokens: 4_000,
+            output_tokens: 2_000,
+        }]);
+        let orchestrator = Orchestrator::new(registry, bus)
+            .with_connector("mock", Arc::new(expensive))
+            .with_budget(SessionBudget {
+                max_total_tokens: Some(10_000),
+                ..Default::default()
+            });
+
+        let reason = orchestrator.start().await.unwrap();
+        match reason {
+            StopReason::BudgetExceeded { total_tokens, .. } => assert_eq!(total_tokens, 12_000),
+            other => panic!("expected BudgetExceeded, got {:?}", other),
+        }
+
+        // The second message crossed the budget; the rest were never run
+        let metrics = orchestrator.metrics().await;
+        assert_eq!(metrics.total_messages, 2);
+        assert_eq!(metrics.input_tokens, 8_000);
+    }
+
+    #[test]
+    fn test_budget_cost_limit() {
+        let budget = SessionBudget {
+            max_cost_usd: Some(0.05),
+            usd_per_million_input_tokens: 3.0,
+            usd_per_million_output_tokens: 15.0,
+            ..Default::default()
+        };
+        // 10k in + 1k out = $0.045
+        assert!(!budget.is_exceeded(10_000, 1_000));
+        // 10k in + 2k out = $0.06
+        assert!(budget.is_exceeded(10_000, 2_000));
+    }
+
+    #[tokio::test]
+    async fn test_routing_rule_forwards_output_to_reviewer() {
+        use crate::connectors::MockConnector;
+
+        let registry = Arc::new(AgentRegistry::new());
+        let bus = Arc::new(MessageBus::new());
+
+        let worker = registry
+            .register(AgentConfig::new(
+                "worker".to_string(),
+                AgentRole::Worker,
+                "mock".to_string(),
+            ))
+            .await
+            .unwrap();
+        let reviewer = registry
+            .register(AgentConfig::new(
+                "reviewer".to_string(),
+                AgentRole::Reviewer,
+                "claude_code".to_string(),
+            ))
+            .await
+            .unwrap();
+        bus.create_mailbox(worker).await;
+        bus.create_mailbox(reviewer).await;
+        bus.send(AgentMessage::new(reviewer, worker, "implement it".to_string()))
+            .await
+            .unwrap();
+
+        let output = MockConnector::new(vec![ConnectorMessage::Content {
+            content: "Patch ready. REVIEW please".to_string(),
+        }]);
+        let orchestrator = Orchestrator::new(registry, bus.clone())
+            .with_connector("mock", Arc::new(output))
+            .with_routing_rule(RoutingRule::new("REVIEW", AgentRole::Reviewer));
+
+        let reason = orchestrator.start().await.unwrap();
+        assert!(matches!(reason, StopReason::Completed));
+
+        // The reviewer got the worker's output and processed it
+        let metrics = orchestrator.metrics().await;
+        assert_eq!(metrics.messages_per_agent.get(&worker), Some(&1));
+        assert_eq!(metrics.messages_per_agent.get(&reviewer), Some(&1));
+        assert_eq!(bus.total_sent().await, 2);
+    }
+
+    #[tokio::test]
+    async fn test_handle_pauses_and_stops_a_running_orchestrator() {
+        let registry = Arc::new(AgentRegistry::new());
+        let bus = Arc::new(MessageBus::new());
+
+        let config = AgentConfig::new(
+            "busy-agent".to_string(),
+            AgentRole::Worker,
+            "claude_code".to_string(),
+        );
+        let agent_id = registry.register(config).await.unwrap();
+        bus.create_mailbox(agent_id).await;
+        for i in 0..40 {
+            bus.send(AgentMessage::new(agent_id, agent_id, format!("msg{}", i)))
+                .await
+                .unwrap();
+        }
+
+        let orchestrator = Arc::new(Orchestrator::new(registry, bus));
+        let handle = orchestrator.handle();
+        let run = tokio::spawn({
+            let orchestrator = orchestrator.clone();
+            async move { orchestrator.start().await }
+        });
+
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(handle.is_running().await);
+        assert_eq!(
+            orchestrator.start().await.unwrap_err(),
+            "Orchestrator is already running"
+        );
+
+        handle.pause().await;
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        let paused_at = orchestrator.metrics().await.total_messages;
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(orchestrator.metrics().await.total_messages, paused_at);
+
+        handle.stop().await;
+        let reason = run.await.unwrap().unwrap();
+        assert!(matches!(reason, StopReason::ManualStop));
+        assert!(orchestrator.metrics().await.total_messages < 40);
+    }
+
+    #[tokio::test]
+    async fn test_handle_subscriber_receives_lifecycle_events() {
+        let registry = Arc::new(AgentRegistry::new());
+        let bus = Arc::new(MessageBus::new());
+        let agent_id = registry
+            .register(AgentConfig::new("worker".to_string(), AgentRole::Worker, "claude_code".to_string()))
+            .await
+            .unwrap();
+        bus.create_mailbox(agent_id).await;
+        bus.send(AgentMessage::new(agent_id, agent_id, "hello".to_string()))
+            .await
+            .unwrap();
+
+        // No session is bound; events still reach the handle
+        let orchestrator = Orchestrator::new(registry, bus);
+        let mut events = orchestrator.handle().subscribe();
+        orchestrator.start().await.unwrap();
+
+        let mut types = Vec::new();
+        while let Ok(event) = events.try_recv() {
+            types.push(event.event_type);
+        }
+        assert_eq!(
+            types,
+            vec![PROGRESS_AGENT_STARTED, PROGRESS_AGENT_COMPLETED, PROGRESS_RUN_STOPPED]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_tick_interval_is_honored() {
+        use crate::clock::MockClock;
+
+        async fn two_message_run(tick_ms: u64, clock: Arc<MockClock>) -> Arc<Orchestrator> {
+            let registry = Arc::new(AgentRegistry::new());
+            let bus = Arc::new(MessageBus::new());
+
+            let config = AgentConfig::new(
+                "test-agent".to_string(),
+                AgentRole::Worker,
+                "claude_code".to_string(),
+            );
+            let agent_id = registry.register(config).await.unwrap();
+            bus.create_mailbox(agent_id).await;
+            for i in 0..2 {
+                bus.send(AgentMessage::new(agent_id, agent_id, format!("msg{}", i)))
+                    .await
+                    .unwrap();
+            }
+
+            Arc::new(
+                Orchestrator::new(registry, bus)
+                    .with_tick_interval_ms(tick_ms)
+                    .with_clock(clock),
+            )
+        }
+
+        let clock = Arc::new(MockClock::new());
+        let orchestrator = two_message_run(150, clock.clone()).await;
+        let run = tokio::spawn({
+            let orchestrator = orchestrator.clone();
+            async move { orchestrator.start().await }
+        });
+
+        // One tick after each of the two processed messages, each held until
+        // the clock has moved 150ms
+        for handled in 1..=2 {
+            while orchestrator.metrics().await.total_messages < handled {
+                tokio::task::yield_now().await;
+            }
+            for _ in 0..10 {
+                tokio::task::yield_now().await;
+            }
+            assert_eq!(orchestrator.metrics().await.total_messages, handled);
+            assert!(!run.is_finished());
+            clock.advance(Duration::from_millis(150));
+        }
+        let reason = run.await.unwrap().unwrap();
+        assert!(matches!(reason, StopReason::Completed), "got {:?}", reason);
+
+        // Without a tick interval the clock never has to move
+        let orchestrator = two_message_run(0, Arc::new(MockClock::new())).await;
+        let reason = orchestrator.start().await.unwrap();
+        assert!(matches!(reason, StopReason::Completed), "got {:?}", reason);
+        assert_eq!(orchestrator.metrics().await.total_messages, 2);
+    }
+
+    #[tokio::test]
+    async fn test_reset_agent_metrics_leaves_other_agents() {
+        let registry = Arc::new(AgentRegistry::new());
+        let bus = Arc::new(MessageBus::new());
+
+        let mut agent_ids = Vec::new();
+        for name in ["noisy", "quiet"] {
+            let config = AgentConfig::new(
+                name.to_string(),
+                AgentRole::Worker,
+                "claude_code".to_string(),
+            );
+            let agent_id = registry.register(config).await.unwrap();
+            bus.create_mailbox(agent_id).await;
+            agent_ids.push(agent_id);
+        }
+        let (noisy, quiet) = (agent_ids[0], agent_ids[1]);
+
+        for i in 0..3 {
+            bus.send(AgentMessage::new(quiet, noisy, format!("noisy {}", i))).await.unwrap();
+        }
+        bus.send(AgentMessage::new(noisy, quiet, "quiet".to_string())).await.unwrap();
+
+        let orchestrator = Orchestrator::new(registry, bus);
+        orchestrator.start().await.unwrap();
+        orchestrator.reset_agent_metrics(noisy).await;
+
+        let metrics = orchestrator.metrics().await;
+        assert!(!metrics.messages_per_agent.contains_key(&noisy));
+        assert_eq!(metrics.messages_per_agent.get(&quiet), Some(&1));
+        assert_eq!(metrics.total_messages, 4);
+    }
+
+    #[tokio::test]
+    async fn test_each_run_is_recorded_separately() {
+        let registry = Arc::new(AgentRegistry::new());
+        let bus = Arc::new(MessageBus::new());
+
+        let config = AgentConfig::new(
+            "test-agent".to_string(),
+            AgentRole::Worker,
+            "claude_code".to_string(),
+        );
+        let agent_id = registry.register(config).await.unwrap();
+        bus.create_mailbox(agent_id).await;
+
+        let orchestrator = Orchestrator::new(registry, bus.clone());
+
+        for (run, count) in [2, 3].into_iter().enumerate() {
+            for i in 0..count {
+                let msg = AgentMessage::new(agent_id, agent_id, format!("run{} msg{}", run, i));
+                bus.send(msg).await.unwrap();
+            }
+            orchestrator.start().await.unwrap();
+        }
+
+        let history = orchestrator.get_run_history().await;
+        assert_eq!(history.len(), 2);
+        assert_ne!(history[0].run_id, history[1].run_id);
+        assert_eq!(history[0].metrics.run_id, Some(history[0].run_id));
+        assert_eq!(history[0].metrics.total_messages, 2);
+        assert_eq!(history[1].metrics.total_messages, 3);
+        assert!(matches!(history[1].stop_reason, Some(StopReason::Completed)));
+
+        // Current metrics belong to the latest run
+        assert_eq!(orchestrator.metrics().await.run_id, Some(history[1].run_id));
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn test_process_span_carries_agent_and_message_ids() {
+        let registry = Arc::new(AgentRegistry::new());
+        let bus = Arc::new(MessageBus::new());
+
+        let config = AgentConfig::new(
+            "test-agent".to_string(),
+            AgentRole::Worker,
+            "claude_code".to_string(),
+        );
+        let agent_id = registry.register(config).await.unwrap();
+        bus.create_mailbox(agent_id).await;
+
+        let msg = AgentMessage::new(agent_id, agent_id, "traced".to_string());
+        let message_id = msg.id;
+        bus.send(msg).await.unwrap();
+
+        let orchestrator = Orchestrator::new(registry, bus);
+        orchestrator.start().await.unwrap();
+
+        assert!(logs_contain("process_agent_message"));
+        assert!(logs_contain("run_id="));
+        assert!(logs_contain(&format!("agent_id={}", agent_id)));
+        assert!(logs_contain(&format!("message_id={}", message_id)));
+    }
 }
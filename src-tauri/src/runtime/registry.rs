@@ -1,12 +1,18 @@
-use super::types::{AgentConfig, AgentId, AgentMetadata, AgentRole, AgentStatus};
+use super::types::{AgentConfig, AgentId, AgentMetadata, AgentProfile, AgentRole, AgentStatus};
+use sqlx::SqlitePool;
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::{Duration, SystemTime};
 use tokio::sync::RwLock;
+use tracing::warn;
 
 /// Agent registry manages all active agents
 pub struct AgentRegistry {
     agents: Arc<RwLock<HashMap<AgentId, AgentMetadata>>>,
     configs: Arc<RwLock<HashMap<AgentId, AgentConfig>>>,
+    profiles: Arc<RwLock<HashMap<AgentRole, AgentProfile>>>,
+    /// When set, registrations are mirrored to the `agents` table
+    pool: Option<SqlitePool>,
 }
 
 impl AgentRegistry {
@@ -15,26 +21,155 @@ impl AgentRegistry {
         Self {
             agents: Arc::new(RwLock::new(HashMap::new())),
             configs: Arc::new(RwLock::new(HashMap::new())),
+            profiles: Arc::new(RwLock::new(HashMap::new())),
+            pool: None,
         }
     }
 
+    /// Create a registry backed by the `agents` table, reloading every stored agent
+    ///
+    /// Reloaded agents start `Idle` whatever they were doing before the restart.
+    pub async fn new_persistent(pool: SqlitePool) -> Result<Self, String> {
+        let rows: Vec<(String, String, String)> =
+            sqlx::query_as("SELECT id, config, created_at FROM agents ORDER BY created_at")
+                .fetch_all(&pool)
+                .await
+                .map_err(|e| format!("Failed to load agents: {}", e))?;
+
+        let mut agents = HashMap::new();
+        let mut configs = HashMap::new();
+        for (id, config, created_at) in rows {
+            let agent_id = id
+                .parse::<AgentId>()
+                .map_err(|e| format!("Invalid stored agent ID {}: {}", id, e))?;
+            let config: AgentConfig = serde_json::from_str(&config)
+                .map_err(|e| format!("Invalid stored config for agent {}: {}", id, e))?;
+            let created_at = chrono::DateTime::parse_from_rfc3339(&created_at)
+                .map_err(|e| format!("Invalid created_at for agent {}: {}", id, e))?
+                .with_timezone(&chrono::Utc);
+
+            let mut metadata = Self::metadata_for(agent_id, &config);
+            metadata.created_at = created_at;
+            agents.insert(agent_id, metadata);
+            configs.insert(agent_id, config);
+        }
+
+        Ok(Self {
+            agents: Arc::new(RwLock::new(agents)),
+            configs: Arc::new(RwLock::new(configs)),
+            profiles: Arc::new(RwLock::new(HashMap::new())),
+            pool: Some(pool),
+        })
+    }
+
+    /// Write an agent to the `agents` table
+    async fn persist<'c, E>(
+        executor: E,
+        metadata: &AgentMetadata,
+        config: &AgentConfig,
+    ) -> Result<(), String>
+    where
+        E: sqlx::Executor<'c, Database = sqlx::Sqlite>,
+    {
+        let config = serde_json::to_string(config)
+            .map_err(|e| format!("Failed to serialize agent config: {}", e))?;
+
+        sqlx::query("INSERT OR REPLACE INTO agents (id, config, created_at) VALUES (?, ?, ?)")
+            .bind(metadata.id.to_string())
+            .bind(config)
+            .bind(metadata.created_at.to_rfc3339())
+            .execute(executor)
+            .await
+            .map_err(|e| format!("Failed to persist agent {}: {}", metadata.id, e))?;
+
+        Ok(())
+    }
+
+    /// Override the profile applied to agents registered with a role
+    pub async fn set_profile(&self, role: AgentRole, profile: AgentProfile) {
+        self.profiles.write().await.insert(role, profile);
+    }
+
+    /// Get the profile applied to agents registered with a role
+    pub async fn profile_for(&self, role: &AgentRole) -> AgentProfile {
+        self.profiles
+            .read()
+            .await
+            .get(role)
+            .cloned()
+            .unwrap_or_else(|| AgentProfile::for_role(role))
+    }
+
     /// Register a new agent
-    pub async fn register(&self, config: AgentConfig) -> AgentId {
+    ///
+    /// With a database attached the agent is only registered once it has been
+    /// persisted, so a failed write leaves the registry unchanged.
+    pub async fn register(&self, config: AgentConfig) -> Result<AgentId, String> {
+        let profile = self.profile_for(&config.role).await;
+        let config = config.apply_profile(&profile);
         let agent_id = uuid::Uuid::new_v4();
+        let metadata = Self::metadata_for(agent_id, &config);
+
+        if let Some(pool) = &self.pool {
+            Self::persist(pool, &metadata, &config).await?;
+        }
 
-        let metadata = AgentMetadata {
+        self.agents.write().await.insert(agent_id, metadata);
+        self.configs.write().await.insert(agent_id, config);
+
+        Ok(agent_id)
+    }
+
+    /// Register a team at once, returning IDs in input order
+    ///
+    /// Every config is validated (after its role profile is applied) before
+    /// anything is inserted, and the team is persisted in one transaction, so
+    /// a single invalid config or failed write registers nobody.
+    pub async fn register_all(&self, configs: Vec<AgentConfig>) -> Result<Vec<AgentId>, String> {
+        let mut prepared = Vec::with_capacity(configs.len());
+        for config in configs {
+            let profile = self.profile_for(&config.role).await;
+            let config = config.apply_profile(&profile);
+            config.validate()?;
+            let agent_id = uuid::Uuid::new_v4();
+            prepared.push((Self::metadata_for(agent_id, &config), config));
+        }
+
+        if let Some(pool) = &self.pool {
+            let mut tx = pool
+                .begin()
+                .await
+                .map_err(|e| format!("Failed to begin transaction: {}", e))?;
+            for (metadata, config) in &prepared {
+                Self::persist(&mut *tx, metadata, config).await?;
+            }
+            tx.commit()
+                .await
+                .map_err(|e| format!("Failed to commit agents: {}", e))?;
+        }
+
+        let mut agents = self.agents.write().await;
+        let mut stored = self.configs.write().await;
+        let ids = prepared.iter().map(|(metadata, _)| metadata.id).collect();
+        for (metadata, config) in prepared {
+            stored.insert(metadata.id, config);
+            agents.insert(metadata.id, metadata);
+        }
+
+        Ok(ids)
+    }
+
+    fn metadata_for(agent_id: AgentId, config: &AgentConfig) -> AgentMetadata {
+        AgentMetadata {
             id: agent_id,
             name: config.name.clone(),
             role: config.role.clone(),
             status: AgentStatus::Idle,
             connector_type: config.connector_type.clone(),
             created_at: chrono::Utc::now(),
-        };
-
-        self.agents.write().await.insert(agent_id, metadata);
-        self.configs.write().await.insert(agent_id, config);
-
-        agent_id
+            last_heartbeat: SystemTime::now(),
+            failure_count: 0,
+        }
     }
 
     /// Unregister an agent
@@ -45,6 +180,16 @@ impl AgentRegistry {
         let removed_agent = agents.remove(&agent_id).is_some();
         configs.remove(&agent_id);
 
+        if let Some(pool) = &self.pool {
+            if let Err(e) = sqlx::query("DELETE FROM agents WHERE id = ?")
+                .bind(agent_id.to_string())
+                .execute(pool)
+                .await
+            {
+                warn!("Failed to delete agent {}: {}", agent_id, e);
+            }
+        }
+
         removed_agent
     }
 
@@ -69,6 +214,70 @@ impl AgentRegistry {
         }
     }
 
+    /// Count a failed message against an agent, returning its new status
+    ///
+    /// Below the agent's `max_failures` it goes back to `Idle` so it can be
+    /// retried; at the threshold it is marked `Failed` for good.
+    pub async fn record_failure(&self, agent_id: AgentId, reason: &str) -> Option<AgentStatus> {
+        let max_failures = self
+            .configs
+            .read()
+            .await
+            .get(&agent_id)
+            .map(|config| config.max_failures)?;
+
+        let mut agents = self.agents.write().await;
+        let metadata = agents.get_mut(&agent_id)?;
+        metadata.failure_count += 1;
+        metadata.status = if metadata.failure_count >= max_failures {
+            AgentStatus::Failed {
+                reason: format!("{} ({} consecutive failures)", reason, metadata.failure_count),
+            }
+        } else {
+            AgentStatus::Idle
+        };
+        Some(metadata.status.clone())
+    }
+
+    /// Clear an agent's failure count after a successful message
+    pub async fn record_success(&self, agent_id: AgentId) -> bool {
+        let mut agents = self.agents.write().await;
+        if let Some(metadata) = agents.get_mut(&agent_id) {
+            metadata.failure_count = 0;
+            metadata.status = AgentStatus::Idle;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Record that an agent made progress
+    pub async fn heartbeat(&self, agent_id: AgentId) -> bool {
+        let mut agents = self.agents.write().await;
+        if let Some(metadata) = agents.get_mut(&agent_id) {
+            metadata.last_heartbeat = SystemTime::now();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// List agents stuck in `Processing` with no heartbeat for longer than `max_idle`
+    pub async fn stale_agents(&self, max_idle: Duration) -> Vec<AgentMetadata> {
+        let now = SystemTime::now();
+        self.agents
+            .read()
+            .await
+            .values()
+            .filter(|m| m.status == AgentStatus::Processing)
+            .filter(|m| {
+                now.duration_since(m.last_heartbeat)
+                    .is_ok_and(|idle| idle > max_idle)
+            })
+            .cloned()
+            .collect()
+    }
+
     /// List all agents
     pub async fn list_agents(&self) -> Vec<AgentMetadata> {
         self.agents.read().await.values().cloned().collect()
@@ -100,6 +309,58 @@ impl Default for AgentRegistry {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::db::Database;
+    use tempfile::NamedTempFile;
+
+    #[tokio::test]
+    async fn test_persistent_registry_restores_agents() {
+        let temp_file = NamedTempFile::new().unwrap();
+        let db = Database::init(temp_file.path()).await.unwrap();
+
+        let registry = AgentRegistry::new_persistent(db.pool().clone()).await.unwrap();
+        let worker = registry
+            .register(AgentConfig::new(
+                "worker".to_string(),
+                AgentRole::Worker,
+                "codex_cli".to_string(),
+            ))
+            .await
+            .unwrap();
+        let reviewer = registry
+            .register(AgentConfig::new(
+                "reviewer".to_string(),
+                AgentRole::Reviewer,
+                "claude_code".to_string(),
+            ))
+            .await
+            .unwrap();
+        let dropped = registry
+            .register(AgentConfig::new(
+                "dropped".to_string(),
+                AgentRole::Worker,
+                "ollama".to_string(),
+            ))
+            .await
+            .unwrap();
+        registry.unregister(dropped).await;
+        registry.update_status(worker, AgentStatus::Processing).await;
+        let created_at = registry.get_metadata(worker).await.unwrap().created_at;
+        drop(registry);
+
+        let restored = AgentRegistry::new_persistent(db.pool().clone()).await.unwrap();
+        assert_eq!(restored.count().await, 2);
+        assert!(restored.get_metadata(dropped).await.is_none());
+
+        let metadata = restored.get_metadata(worker).await.unwrap();
+        assert_eq!(metadata.name, "worker");
+        assert_eq!(metadata.status, AgentStatus::Idle);
+        assert_eq!(metadata.created_at, created_at);
+        assert_eq!(restored.get_config(worker).await.unwrap().connector_type, "codex_cli");
+        assert_eq!(
+            restored.get_metadata(reviewer).await.unwrap().role,
+            AgentRole::Reviewer
+        );
+    }
 
     #[tokio::test]
     async fn test_register_agent() {
@@ -110,7 +371,7 @@ mod tests {
             "claude_code".to_string(),
         );
 
-        let agent_id = registry.register(config).await;
+        let agent_id = registry.register(config).await.unwrap();
 
         assert_eq!(registry.count().await, 1);
 
@@ -129,7 +390,7 @@ mod tests {
             "claude_code".to_string(),
         );
 
-        let agent_id = registry.register(config).await;
+        let agent_id = registry.register(config).await.unwrap();
         assert_eq!(registry.count().await, 1);
 
         let removed = registry.unregister(agent_id).await;
@@ -146,7 +407,7 @@ mod tests {
             "claude_code".to_string(),
         );
 
-        let agent_id = registry.register(config).await;
+        let agent_id = registry.register(config).await.unwrap();
 
         let updated = registry
             .update_status(agent_id, AgentStatus::Processing)
@@ -157,6 +418,184 @@ mod tests {
         assert_eq!(metadata.status, AgentStatus::Processing);
     }
 
+    #[tokio::test]
+    async fn test_failure_threshold_marks_agent_dead() {
+        let registry = AgentRegistry::new();
+        let mut config = AgentConfig::new(
+            "test-agent".to_string(),
+            AgentRole::Worker,
+            "claude_code".to_string(),
+        );
+        config.max_failures = 2;
+        let agent_id = registry.register(config).await.unwrap();
+
+        // Under the threshold the agent stays recoverable
+        let status = registry.record_failure(agent_id, "timeout").await;
+        assert_eq!(status, Some(AgentStatus::Idle));
+        assert_eq!(registry.get_metadata(agent_id).await.unwrap().failure_count, 1);
+
+        // A success clears the streak
+        registry.record_success(agent_id).await;
+        assert_eq!(registry.get_metadata(agent_id).await.unwrap().failure_count, 0);
+        registry.record_failure(agent_id, "timeout").await;
+
+        // Reaching the threshold marks it dead
+        let status = registry.record_failure(agent_id, "timeout").await;
+        assert!(matches!(status, Some(AgentStatus::Failed { .. })));
+        let metadata = registry.get_metadata(agent_id).await.unwrap();
+        assert_eq!(metadata.failure_count, 2);
+        assert!(matches!(metadata.status, AgentStatus::Failed { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_register_all_is_atomic() {
+        let registry = AgentRegistry::new();
+        let invalid = vec![
+            AgentConfig::new("ok".to_string(), AgentRole::Worker, String::new()),
+            AgentConfig::new(String::new(), AgentRole::Worker, String::new()),
+        ];
+
+        assert!(registry.register_all(invalid).await.is_err());
+        assert_eq!(registry.count().await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_failed_writes_register_nobody() {
+        let temp_file = NamedTempFile::new().unwrap();
+        let db = Database::init(temp_file.path()).await.unwrap();
+        let registry = AgentRegistry::new_persistent(db.pool().clone()).await.unwrap();
+
+        // Let the table take one agent, then fail every further insert
+        sqlx::query(
+            "CREATE TRIGGER agents_full BEFORE INSERT ON agents
+             WHEN (SELECT COUNT(*) FROM agents) >= 1
+             BEGIN SELECT RAISE(ABORT, 'agents table full'); END",
+        )
+        .execute(db.pool())
+        .await
+        .unwrap();
+
+        let worker = |name: &str| AgentConfig::new(name.to_string(), AgentRole::Worker, String::new());
+
+        // The first write of the team succeeds but is rolled back with the second
+        let err = registry
+            .register_all(vec![worker("a"), worker("b")])
+            .await
+            .unwrap_err();
+        assert!(err.contains("agents table full"));
+        assert_eq!(registry.count().await, 0);
+        let (stored,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM agents")
+            .fetch_one(db.pool())
+            .await
+            .unwrap();
+        assert_eq!(stored, 0);
+
+        registry.register(worker("c")).await.unwrap();
+        let err = registry.register(worker("d")).await.unwrap_err();
+        assert!(err.contains("agents table full"));
+        assert_eq!(registry.count().await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_stale_agents() {
+        let registry = AgentRegistry::new();
+        let config = AgentConfig::new(
+            "test-agent".to_string(),
+            AgentRole::Worker,
+            "claude_code".to_string(),
+        );
+
+        let agent_id = registry.register(config).await.unwrap();
+        registry
+            .update_status(agent_id, AgentStatus::Processing)
+            .await;
+        registry.heartbeat(agent_id).await;
+        assert!(registry.stale_agents(Duration::from_millis(20)).await.is_empty());
+
+        // Simulate a wedged execute: no heartbeat while processing
+        std::thread::sleep(Duration::from_millis(40));
+        let stale = registry.stale_agents(Duration::from_millis(20)).await;
+        assert_eq!(stale.len(), 1);
+        assert_eq!(stale[0].id, agent_id);
+
+        // Progress clears the stale flag
+        registry.heartbeat(agent_id).await;
+        assert!(registry.stale_agents(Duration::from_millis(20)).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_role_profile_defaults() {
+        let registry = AgentRegistry::new();
+
+        let coordinator = registry
+            .register(AgentConfig::new(
+                "coordinator".to_string(),
+                AgentRole::Coordinator,
+                String::new(),
+            ))
+            .await
+            .unwrap();
+        let worker = registry
+            .register(AgentConfig::new(
+                "worker".to_string(),
+                AgentRole::Worker,
+                String::new(),
+            ))
+            .await
+            .unwrap();
+        let reviewer = registry
+            .register(AgentConfig::new(
+                "reviewer".to_string(),
+                AgentRole::Reviewer,
+                String::new(),
+            ))
+            .await
+            .unwrap();
+
+        let coordinator_config = registry.get_config(coordinator).await.unwrap();
+        assert_eq!(coordinator_config.connector_type, "claude_code");
+        assert_eq!(coordinator_config.max_messages, None);
+
+        // Workers are only capped by the loop guard
+        let worker_config = registry.get_config(worker).await.unwrap();
+        assert_eq!(worker_config.max_messages, None);
+        assert_eq!(
+            registry.get_metadata(worker).await.unwrap().connector_type,
+            "claude_code"
+        );
+
+        let reviewer_config = registry.get_config(reviewer).await.unwrap();
+        assert_eq!(reviewer_config.connector_type, "codex_cli");
+        assert_eq!(reviewer_config.max_messages, Some(25));
+    }
+
+    #[tokio::test]
+    async fn test_role_profile_override_keeps_explicit_fields() {
+        let registry = AgentRegistry::new();
+        registry
+            .set_profile(
+                AgentRole::Worker,
+                AgentProfile {
+                    max_messages: Some(5),
+                    preferred_connector: Some("ollama".to_string()),
+                    tool_policies: Vec::new(),
+                },
+            )
+            .await;
+
+        let mut config = AgentConfig::new(
+            "worker".to_string(),
+            AgentRole::Worker,
+            "codex_cli".to_string(),
+        );
+        config.max_messages = Some(7);
+        let agent_id = registry.register(config).await.unwrap();
+
+        let config = registry.get_config(agent_id).await.unwrap();
+        assert_eq!(config.connector_type, "codex_cli");
+        assert_eq!(config.max_messages, Some(7));
+    }
+
     #[tokio::test]
     async fn test_list_by_role() {
         let registry = AgentRegistry::new();
@@ -177,9 +616,9 @@ mod tests {
             "ollama".to_string(),
         );
 
-        registry.register(config1).await;
-        registry.register(config2).await;
-        registry.register(config3).await;
+        registry.register(config1).await.unwrap();
+        registry.register(config2).await.unwrap();
+        registry.register(config3).await.unwrap();
 
         let workers = registry.list_by_role(AgentRole::Worker).await;
         assert_eq!(workers.len(), 2);
@@ -0,0 +1,211 @@
+use super::mailbox::MessageBus;
+use super::orchestrator::OrchestratorMetrics;
+use super::registry::AgentRegistry;
+use super::types::{AgentId, AgentRole, AgentStatus};
+use serde::{Deserialize, Serialize};
+
+/// An agent in the topology graph
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgentNode {
+    pub id: AgentId,
+    pub name: String,
+    pub role: AgentRole,
+    pub status: AgentStatus,
+    pub queue_depth: usize,
+}
+
+/// Observed traffic from one agent to another
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MessageEdge {
+    pub from: AgentId,
+    pub to: AgentId,
+    pub count: u64,
+    pub last_sent_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Snapshot of agents and the message flow between them
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgentTopology {
+    pub nodes: Vec<AgentNode>,
+    pub edges: Vec<MessageEdge>,
+}
+
+impl AgentTopology {
+    /// Capture the current topology from the registry and bus traffic
+    pub async fn capture(registry: &AgentRegistry, bus: &MessageBus) -> Self {
+        let mut nodes = Vec::new();
+        for agent in registry.list_agents().await {
+            let queue_depth = match bus.get_mailbox(agent.id).await {
+                Some(mailbox) => mailbox.len().await,
+                None => 0,
+            };
+            nodes.push(AgentNode {
+                id: agent.id,
+                name: agent.name,
+                role: agent.role,
+                status: agent.status,
+                queue_depth,
+            });
+        }
+
+        Self {
+            nodes,
+            edges: bus.message_edges().await,
+        }
+    }
+}
+
+/// One row of the dashboard's agent table
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgentStatusSummary {
+    pub id: AgentId,
+    pub name: String,
+    pub status: AgentStatus,
+    pub queue_len: usize,
+    /// Messages handled in the current or last run (0 without an orchestrator)
+    pub messages_processed: u32,
+}
+
+impl AgentStatusSummary {
+    /// Summarize every registered agent in one pass over the registry
+    pub async fn collect(
+        registry: &AgentRegistry,
+        bus: &MessageBus,
+        metrics: Option<&OrchestratorMetrics>,
+    ) -> Vec<Self> {
+        let mut summaries = Vec::new();
+        for agent in registry.list_agents().await {
+            let queue_len = match bus.get_mailbox(agent.id).await {
+                Some(mailbox) => mailbox.len().await,
+                None => 0,
+            };
+            let messages_processed = metrics
+                .and_then(|m| m.messages_per_agent.get(&agent.id))
+                .copied()
+                .unwrap_or(0);
+            summaries.push(Self {
+                id: agent.id,
+                name: agent.name,
+                status: agent.status,
+                queue_len,
+                messages_processed,
+            });
+        }
+        summaries.sort_by(|a, b| a.name.cmp(&b.name));
+        summaries
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::types::{AgentConfig, AgentMessage};
+
+    #[tokio::test]
+    async fn test_topology_reflects_traffic() {
+        let registry = AgentRegistry::new();
+        let bus = MessageBus::new();
+
+        let coordinator = registry
+            .register(AgentConfig::new(
+                "coordinator".to_string(),
+                AgentRole::Coordinator,
+                "claude_code".to_string(),
+            ))
+            .await
+            .unwrap();
+        let worker = registry
+            .register(AgentConfig::new(
+                "worker".to_string(),
+                AgentRole::Worker,
+                "claude_code".to_string(),
+            ))
+            .await
+            .unwrap();
+        bus.create_mailbox(coordinator).await;
+        bus.create_mailbox(worker).await;
+
+        for task in ["task 1", "task 2"] {
+            bus.send(AgentMessage::new(coordinator, worker, task.to_string()))
+                .await
+                .unwrap();
+        }
+        bus.send(AgentMessage::new(worker, coordinator, "done".to_string()))
+            .await
+            .unwrap();
+
+        let topology = AgentTopology::capture(&registry, &bus).await;
+        assert_eq!(topology.nodes.len(), 2);
+
+        let worker_node = topology.nodes.iter().find(|n| n.id == worker).unwrap();
+        assert_eq!(worker_node.queue_depth, 2);
+
+        assert_eq!(topology.edges.len(), 2);
+        let downstream = topology
+            .edges
+            .iter()
+            .find(|e| e.from == coordinator && e.to == worker)
+            .unwrap();
+        assert_eq!(downstream.count, 2);
+        let upstream = topology
+            .edges
+            .iter()
+            .find(|e| e.from == worker && e.to == coordinator)
+            .unwrap();
+        assert_eq!(upstream.count, 1);
+    }
+
+    #[tokio::test]
+    async fn test_agent_status_summary() {
+        let registry = AgentRegistry::new();
+        let bus = MessageBus::new();
+
+        let mut ids = Vec::new();
+        for name in ["alpha", "beta", "gamma"] {
+            let id = registry
+                .register(AgentConfig::new(
+                    name.to_string(),
+                    AgentRole::Worker,
+                    "claude_code".to_string(),
+                ))
+                .await
+                .unwrap();
+            bus.create_mailbox(id).await;
+            ids.push(id);
+        }
+        let (alpha, beta, gamma) = (ids[0], ids[1], ids[2]);
+
+        for task in ["task 1", "task 2", "task 3"] {
+            bus.send(AgentMessage::new(alpha, beta, task.to_string()))
+                .await
+                .unwrap();
+        }
+        registry.update_status(beta, AgentStatus::Processing).await;
+        registry
+            .update_status(
+                gamma,
+                AgentStatus::Failed {
+                    reason: "crashed".to_string(),
+                },
+            )
+            .await;
+
+        let mut metrics = OrchestratorMetrics::default();
+        metrics.messages_per_agent.insert(alpha, 4);
+
+        let summary = AgentStatusSummary::collect(&registry, &bus, Some(&metrics)).await;
+        let names: Vec<&str> = summary.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, vec!["alpha", "beta", "gamma"]);
+
+        assert_eq!(summary[0].status, AgentStatus::Idle);
+        assert_eq!(summary[0].queue_len, 0);
+        assert_eq!(summary[0].messages_processed, 4);
+        assert_eq!(summary[1].status, AgentStatus::Processing);
+        assert_eq!(summary[1].queue_len, 3);
+        assert_eq!(summary[1].messages_processed, 0);
+        assert!(matches!(summary[2].status, AgentStatus::Failed { .. }));
+
+        let without_run = AgentStatusSummary::collect(&registry, &bus, None).await;
+        assert!(without_run.iter().all(|s| s.messages_processed == 0));
+    }
+}
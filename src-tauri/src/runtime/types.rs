@@ -1,5 +1,6 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::time::SystemTime;
 use uuid::Uuid;
 
 /// Agent identifier
@@ -9,7 +10,7 @@ pub type AgentId = Uuid;
 pub type MessageId = Uuid;
 
 /// Agent role in the system
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub enum AgentRole {
     /// Coordinator agent that manages other agents
     Coordinator,
@@ -43,6 +44,11 @@ pub struct AgentMetadata {
     pub status: AgentStatus,
     pub connector_type: String,
     pub created_at: chrono::DateTime<chrono::Utc>,
+    /// Last time the agent made progress (registered, started or finished a message)
+    pub last_heartbeat: SystemTime,
+    /// Consecutive failed messages; reset on the next success
+    #[serde(default)]
+    pub failure_count: u32,
 }
 
 /// Message priority
@@ -60,6 +66,17 @@ impl Default for MessagePriority {
     }
 }
 
+impl MessagePriority {
+    /// Next priority up, saturating at `Critical`
+    pub fn escalated(self) -> Self {
+        match self {
+            MessagePriority::Low => MessagePriority::Normal,
+            MessagePriority::Normal => MessagePriority::High,
+            MessagePriority::High | MessagePriority::Critical => MessagePriority::Critical,
+        }
+    }
+}
+
 /// Message between agents
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AgentMessage {
@@ -67,9 +84,23 @@ pub struct AgentMessage {
     pub from: AgentId,
     pub to: AgentId,
     pub content: String,
-    pub priority: MessagePriority,
+    /// Priority chosen by the sender (or a retry); `None` lets the bus pick one
+    #[serde(default)]
+    pub priority: Option<MessagePriority>,
     pub created_at: chrono::DateTime<chrono::Utc>,
     pub metadata: HashMap<String, String>,
+    /// Structured input (tool requests, typed tasks) carried alongside `content`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub payload: Option<serde_json::Value>,
+    /// Failed attempts so far, when retries are re-queued through the mailbox
+    #[serde(default)]
+    pub retries: u32,
+    /// Category (e.g. "control") the bus can map to a default priority
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>,
+    /// Set when the bus cut `content` down to its size limit
+    #[serde(default)]
+    pub truncated: bool,
 }
 
 impl AgentMessage {
@@ -79,16 +110,63 @@ impl AgentMessage {
             from,
             to,
             content,
-            priority: MessagePriority::default(),
+            priority: None,
             created_at: chrono::Utc::now(),
             metadata: HashMap::new(),
+            payload: None,
+            retries: 0,
+            kind: None,
+            truncated: false,
         }
     }
 
     pub fn with_priority(mut self, priority: MessagePriority) -> Self {
-        self.priority = priority;
+        self.priority = Some(priority);
+        self
+    }
+
+    /// Tag the message with a kind; see `MessageBus::with_kind_priority`
+    pub fn with_kind(mut self, kind: impl Into<String>) -> Self {
+        self.kind = Some(kind.into());
         self
     }
+
+    /// Priority the message is delivered at, `Normal` unless one was set
+    pub fn effective_priority(&self) -> MessagePriority {
+        self.priority.unwrap_or_default()
+    }
+
+    pub fn with_payload(mut self, payload: serde_json::Value) -> Self {
+        self.payload = Some(payload);
+        self
+    }
+
+    /// Copy of this message for another attempt, one priority level higher
+    pub fn escalated_retry(&self) -> Self {
+        let mut retry = self.clone();
+        retry.retries += 1;
+        retry.priority = Some(self.effective_priority().escalated());
+        retry
+    }
+
+    /// Bytes this message holds in a mailbox: its content plus serialized payload
+    pub fn size_bytes(&self) -> usize {
+        let payload = self
+            .payload
+            .as_ref()
+            .and_then(|p| serde_json::to_vec(p).ok())
+            .map_or(0, |bytes| bytes.len());
+        self.content.len() + payload
+    }
+
+    /// Input handed to the agent's connector: the JSON payload when present,
+    /// otherwise the plain text content
+    pub fn connector_input(&self) -> String {
+        match &self.payload {
+            Some(payload) => payload.to_string(),
+            None => self.content.clone(),
+        }
+    }
 }
 
 /// Tool permission level
@@ -129,15 +207,87 @@ impl ToolPolicy {
     }
 }
 
+/// Forward agent output containing `match_pattern` to an agent with `target_role`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoutingRule {
+    /// Substring to look for in the agent's output
+    pub match_pattern: String,
+    pub target_role: AgentRole,
+}
+
+impl RoutingRule {
+    pub fn new(match_pattern: impl Into<String>, target_role: AgentRole) -> Self {
+        Self {
+            match_pattern: match_pattern.into(),
+            target_role,
+        }
+    }
+
+    /// Whether `output` should be routed by this rule
+    pub fn matches(&self, output: &str) -> bool {
+        !self.match_pattern.is_empty() && output.contains(&self.match_pattern)
+    }
+}
+
+/// Order the orchestrator visits agents in on each iteration
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ExecutionOrder {
+    /// Oldest registration first
+    #[default]
+    Registration,
+    /// Agents with a role in `order` first, in that order, then the rest
+    RolePriority { order: Vec<AgentRole> },
+    /// Highest `AgentConfig::priority` first
+    AgentPriority,
+}
+
+impl ExecutionOrder {
+    /// Sort `agents` in place; ties keep registration order
+    ///
+    /// `priority_of` is only consulted for `AgentPriority`.
+    pub fn sort(&self, agents: &mut [AgentMetadata], priority_of: impl Fn(AgentId) -> i32) {
+        agents.sort_by(|a, b| a.created_at.cmp(&b.created_at).then(a.id.cmp(&b.id)));
+        match self {
+            ExecutionOrder::Registration => {}
+            ExecutionOrder::RolePriority { order } => {
+                agents.sort_by_key(|agent| {
+                    order.iter().position(|role| *role == agent.role).unwrap_or(order.len())
+                });
+            }
+            ExecutionOrder::AgentPriority => {
+                agents.sort_by_key(|agent| std::cmp::Reverse(priority_of(agent.id)));
+            }
+        }
+    }
+}
+
 /// Agent configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AgentConfig {
     pub name: String,
     pub role: AgentRole,
+    /// Connector to use; left empty to take the role's preferred connector
     pub connector_type: String,
     pub max_retries: u32,
     pub timeout_ms: u64,
     pub tool_policies: Vec<ToolPolicy>,
+    /// Per-agent cap on processed messages, bounded by the loop guard
+    #[serde(default)]
+    pub max_messages: Option<u32>,
+    /// Consecutive failures before the agent is marked permanently `Failed`
+    #[serde(default = "default_max_failures")]
+    pub max_failures: u32,
+    /// Higher runs earlier under `ExecutionOrder::AgentPriority`
+    #[serde(default)]
+    pub priority: i32,
+}
+
+/// Consecutive failures tolerated before an agent is considered dead
+pub const DEFAULT_MAX_FAILURES: u32 = 3;
+
+fn default_max_failures() -> u32 {
+    DEFAULT_MAX_FAILURES
 }
 
 impl AgentConfig {
@@ -149,6 +299,80 @@ impl AgentConfig {
             max_retries: 3,
             timeout_ms: 300000, // 5 minutes
             tool_policies: Vec::new(),
+            max_messages: None,
+            max_failures: DEFAULT_MAX_FAILURES,
+            priority: 0,
+        }
+    }
+
+    /// Fill fields left unset from a role profile
+    pub fn apply_profile(mut self, profile: &AgentProfile) -> Self {
+        if self.connector_type.is_empty() {
+            if let Some(connector) = &profile.preferred_connector {
+                self.connector_type = connector.clone();
+            }
+        }
+        if self.max_messages.is_none() {
+            self.max_messages = profile.max_messages;
+        }
+        if self.tool_policies.is_empty() {
+            self.tool_policies = profile.tool_policies.clone();
+        }
+        self
+    }
+
+    /// Check the config is runnable
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("Agent name must not be empty".to_string());
+        }
+        if self.connector_type.is_empty() {
+            return Err(format!("Agent {} has no connector type", self.name));
+        }
+        if self.timeout_ms == 0 {
+            return Err(format!("Agent {} has a zero timeout", self.name));
+        }
+        if self.max_failures == 0 {
+            return Err(format!("Agent {} has a zero failure threshold", self.name));
+        }
+        Ok(())
+    }
+}
+
+/// Role-based defaults applied to agents at registration
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AgentProfile {
+    /// Per-agent cap on processed messages, bounded by the loop guard
+    pub max_messages: Option<u32>,
+    /// Connector used when the agent does not name one
+    pub preferred_connector: Option<String>,
+    /// Tool policies used when the agent does not define any
+    pub tool_policies: Vec<ToolPolicy>,
+}
+
+impl AgentProfile {
+    /// Built-in profile for a role
+    ///
+    /// Only reviewers get a message cap (25); coordinators and workers are
+    /// bounded by the loop guard alone.
+    pub fn for_role(role: &AgentRole) -> Self {
+        match role {
+            AgentRole::Coordinator => Self {
+                max_messages: None,
+                preferred_connector: Some("claude_code".to_string()),
+                tool_policies: Vec::new(),
+            },
+            AgentRole::Worker => Self {
+                max_messages: None,
+                preferred_connector: Some("claude_code".to_string()),
+                tool_policies: Vec::new(),
+            },
+            AgentRole::Reviewer => Self {
+                max_messages: Some(25),
+                preferred_connector: Some("codex_cli".to_string()),
+                tool_policies: Vec::new(),
+            },
+            AgentRole::Custom(_) => Self::default(),
         }
     }
 }
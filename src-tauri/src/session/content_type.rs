@@ -0,0 +1,228 @@
+use super::types::AttachmentType;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Attachment type and MIME type inferred for a stored file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DetectedContent {
+    pub attachment_type: AttachmentType,
+    pub content_type: Option<String>,
+}
+
+/// Infers attachment types from magic bytes, file extension and content
+///
+/// Magic bytes win for binary formats (so a PNG named `notes.txt` is still an
+/// image), then the extension table, then a unified-diff sniff of the text.
+#[derive(Debug, Clone)]
+pub struct ContentTypeDetector {
+    extensions: HashMap<String, (AttachmentType, String)>,
+}
+
+impl ContentTypeDetector {
+    /// A detector with no extension mappings; only magic bytes and diffs are recognised
+    pub fn empty() -> Self {
+        Self {
+            extensions: HashMap::new(),
+        }
+    }
+
+    /// Map a file extension (without the dot, case-insensitive) to a type
+    pub fn with_extension(
+        mut self,
+        extension: &str,
+        attachment_type: AttachmentType,
+        content_type: impl Into<String>,
+    ) -> Self {
+        self.extensions.insert(
+            extension.to_lowercase(),
+            (attachment_type, content_type.into()),
+        );
+        self
+    }
+
+    /// Detect the type of `bytes`, optionally stored under `filename`
+    pub fn detect(&self, filename: Option<&str>, bytes: &[u8]) -> DetectedContent {
+        let sniffed = infer::get(bytes);
+        if let Some(kind) = sniffed.filter(|k| k.matcher_type() == infer::MatcherType::Image) {
+            return DetectedContent {
+                attachment_type: AttachmentType::Image,
+                content_type: Some(kind.mime_type().to_string()),
+            };
+        }
+
+        let extension = filename
+            .and_then(|name| Path::new(name).extension())
+            .and_then(|ext| ext.to_str())
+            .map(str::to_lowercase);
+        if let Some((attachment_type, content_type)) =
+            extension.and_then(|ext| self.extensions.get(&ext))
+        {
+            return DetectedContent {
+                attachment_type: attachment_type.clone(),
+                content_type: Some(content_type.clone()),
+            };
+        }
+
+        if std::str::from_utf8(bytes).is_ok_and(is_unified_diff) {
+            return DetectedContent {
+                attachment_type: AttachmentType::Diff,
+                content_type: Some("text/x-diff".to_string()),
+            };
+        }
+
+        DetectedContent {
+            attachment_type: AttachmentType::File,
+            content_type: sniffed.map(|kind| kind.mime_type().to_string()),
+        }
+    }
+}
+
+impl Default for ContentTypeDetector {
+    fn default() -> Self {
+        let code = [
+            ("rs", "text/x-rust"),
+            ("py", "text/x-python"),
+            ("js", "text/javascript"),
+            ("ts", "text/x-typescript"),
+            ("tsx", "text/x-typescript"),
+            ("go", "text/x-go"),
+            ("java", "text/x-java"),
+            ("c", "text/x-c"),
+            ("h", "text/x-c"),
+            ("cpp", "text/x-c++"),
+            ("sh", "text/x-shellscript"),
+            ("json", "application/json"),
+            ("toml", "application/toml"),
+            ("yaml", "application/yaml"),
+            ("yml", "application/yaml"),
+        ];
+
+        let mut detector = Self::empty()
+            .with_extension("log", AttachmentType::Log, "text/plain")
+            .with_extension("diff", AttachmentType::Diff, "text/x-diff")
+            .with_extension("patch", AttachmentType::Diff, "text/x-diff");
+        for (extension, content_type) in code {
+            detector = detector.with_extension(extension, AttachmentType::Code, content_type);
+        }
+        detector
+    }
+}
+
+/// Whether `text` looks like a unified diff: file headers followed by a hunk
+pub fn is_unified_diff(text: &str) -> bool {
+    let mut saw_old = false;
+    let mut saw_new = false;
+    for line in text.lines() {
+        if line.starts_with("--- ") {
+            saw_old = true;
+        } else if line.starts_with("+++ ") && saw_old {
+            saw_new = true;
+        } else if line.starts_with("@@") && saw_new {
+            return true;
+        }
+    }
+    false
+}
+
+/// Line-change counts for a unified diff
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiffStats {
+    pub files_changed: u32,
+    pub lines_added: u32,
+    pub lines_removed: u32,
+}
+
+/// Count changed files and lines, `None` if `text` isn't a unified diff
+///
+/// Hunk headers are followed so removed lines that themselves start with
+/// `--` aren't mistaken for file headers.
+pub fn diff_stats(text: &str) -> Option<DiffStats> {
+    if !is_unified_diff(text) {
+        return None;
+    }
+
+    let mut stats = DiffStats::default();
+    let (mut old_left, mut new_left) = (0u32, 0u32);
+    for line in text.lines() {
+        if old_left > 0 || new_left > 0 {
+            match line.as_bytes().first() {
+                Some(b'+') => {
+                    stats.lines_added += 1;
+                    new_left = new_left.saturating_sub(1);
+                }
+                Some(b'-') => {
+                    stats.lines_removed += 1;
+                    old_left = old_left.saturating_sub(1);
+                }
+                // "\ No newline at end of file"
+                Some(b'\\') => {}
+                _ => {
+                    old_left = old_left.saturating_sub(1);
+                    new_left = new_left.saturating_sub(1);
+                }
+            }
+        } else if line.starts_with("@@") {
+            (old_left, new_left) = hunk_lengths(line).unwrap_or((0, 0));
+        } else if line.starts_with("+++ ") {
+            stats.files_changed += 1;
+        }
+    }
+    Some(stats)
+}
+
+/// Old and new line counts from a `@@ -a,b +c,d @@` header
+fn hunk_lengths(header: &str) -> Option<(u32, u32)> {
+    let mut ranges = header.split_whitespace().skip(1);
+    let old = ranges.next()?.strip_prefix('-')?;
+    let new = ranges.next()?.strip_prefix('+')?;
+    let length = |range: &str| match range.split_once(',') {
+        Some((_, count)) => count.parse().ok(),
+        None => Some(1),
+    };
+    Some((length(old)?, length(new)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIFF: &str = "diff --git a/src/lib.rs b/src/lib.rs\n\
+--- a/src/lib.rs\n\
++++ b/src/lib.rs\n\
+@@ -1,2 +1,2 @@\n\
+-fn old() {}\n\
++fn new() {}\n";
+
+    #[test]
+    fn test_detect_png_from_magic_bytes() {
+        let png = [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A, 0, 0, 0, 0x0D];
+        let detector = ContentTypeDetector::default();
+
+        // The magic bytes win over a misleading extension
+        let detected = detector.detect(Some("screenshot.txt"), &png);
+        assert_eq!(detected.attachment_type, AttachmentType::Image);
+        assert_eq!(detected.content_type.as_deref(), Some("image/png"));
+    }
+
+    #[test]
+    fn test_detect_source_file_and_diff() {
+        let detector = ContentTypeDetector::default();
+
+        let source = detector.detect(Some("src/Main.RS"), b"fn main() {}\n");
+        assert_eq!(source.attachment_type, AttachmentType::Code);
+        assert_eq!(source.content_type.as_deref(), Some("text/x-rust"));
+
+        // No extension to go on, so the diff is recognised from its content
+        let diff = detector.detect(Some("changes"), DIFF.as_bytes());
+        assert_eq!(diff.attachment_type, AttachmentType::Diff);
+        assert_eq!(diff.content_type.as_deref(), Some("text/x-diff"));
+
+        let plain = detector.detect(None, b"just some notes");
+        assert_eq!(plain.attachment_type, AttachmentType::File);
+        assert_eq!(plain.content_type, None);
+
+        let custom = ContentTypeDetector::empty().with_extension("txt", AttachmentType::Log, "text/plain");
+        assert_eq!(custom.detect(Some("run.txt"), b"ok").attachment_type, AttachmentType::Log);
+    }
+}
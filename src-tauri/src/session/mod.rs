@@ -1,6 +1,8 @@
 // Session management module
 pub mod types;
 pub mod service;
+pub mod content_type;
 
 pub use types::*;
 pub use service::SessionService;
+pub use content_type::{ContentTypeDetector, DetectedContent};
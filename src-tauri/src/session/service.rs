@@ -1,27 +1,117 @@
+use super::content_type::{diff_stats, ContentTypeDetector};
 use super::types::*;
-use sqlx::{Pool, Sqlite};
+use crate::config::DEFAULT_MAX_METADATA_BYTES;
+use crate::error::AppError;
+use crate::connectors::types::ConnectorMessage;
+use crate::connectors::Connector;
+use sqlx::{Pool, QueryBuilder, Sqlite, SqliteConnection};
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
 use std::path::Path;
+use tokio::sync::{broadcast, mpsc};
+use tracing::instrument;
+
+/// Rows per multi-row message INSERT (10 binds each, under SQLite's 999 parameter limit)
+const MESSAGE_INSERT_CHUNK: usize = 99;
+
+/// Buffered messages per subscriber before slow receivers start lagging
+const MESSAGE_CHANNEL_CAPACITY: usize = 256;
+
+/// `session_templates` row: id, name, then JSON panes, agents and blackboard, created_at
+type TemplateRow = (String, String, String, String, String, String);
 
 /// Session service for CRUD operations and event assembly
 pub struct SessionService {
     pool: Pool<Sqlite>,
+    message_tx: broadcast::Sender<Message>,
+    content_types: ContentTypeDetector,
+    max_metadata_bytes: usize,
 }
 
 impl SessionService {
     /// Create a new session service
     pub fn new(pool: Pool<Sqlite>) -> Self {
-        Self { pool }
+        let (message_tx, _) = broadcast::channel(MESSAGE_CHANNEL_CAPACITY);
+        Self {
+            pool,
+            message_tx,
+            content_types: ContentTypeDetector::default(),
+            max_metadata_bytes: DEFAULT_MAX_METADATA_BYTES,
+        }
+    }
+
+    /// Reject metadata and event data larger than `bytes` on write
+    pub fn with_max_metadata_bytes(mut self, bytes: usize) -> Self {
+        self.max_metadata_bytes = bytes;
+        self
+    }
+
+    /// Fail with a `Validation` error if a JSON column value is over the size cap
+    fn check_json_size(&self, field: &str, raw: Option<&str>) -> Result<(), sqlx::Error> {
+        let size = raw.map_or(0, str::len);
+        if size > self.max_metadata_bytes {
+            return Err(sqlx::Error::Encode(Box::new(AppError::Validation(format!(
+                "{} is {} bytes (max {})",
+                field, size, self.max_metadata_bytes
+            )))));
+        }
+        Ok(())
+    }
+
+    /// Check a model's metadata is well-formed JSON within the size cap
+    fn validate_json<T: JsonMetadata>(&self, item: &T) -> Result<(), sqlx::Error> {
+        self.check_json_size("Metadata", item.metadata())?;
+        item.validate_metadata()
+            .map_err(|e| sqlx::Error::Encode(Box::new(e)))
+    }
+
+    /// Use `detector` to type attachments passed to `store_attachment`
+    pub fn with_content_types(mut self, detector: ContentTypeDetector) -> Self {
+        self.content_types = detector;
+        self
+    }
+
+    /// Subscribe to every message persisted through this service
+    ///
+    /// Receivers see messages for all sessions; filter on `session_id`.
+    pub fn subscribe_messages(&self) -> broadcast::Receiver<Message> {
+        self.message_tx.subscribe()
+    }
+
+    /// Notify subscribers of a committed message (no-op without receivers)
+    fn publish_message(&self, message: &Message) {
+        let _ = self.message_tx.send(message.clone());
     }
 
     // ===== Session operations =====
 
     /// Create a new session
     pub async fn create_session(&self, name: String) -> Result<Session, sqlx::Error> {
-        let session = Session::new(name);
+        self.create_session_with_external_id(name, None).await
+    }
 
-        sqlx::query(
-            "INSERT INTO sessions (id, name, created_at, updated_at, status, metadata)
-             VALUES (?, ?, ?, ?, ?, ?)"
+    /// Create a session, or return the existing one if `external_id` was seen before
+    #[instrument(skip_all, fields(session_id = tracing::field::Empty))]
+    pub async fn create_session_with_external_id(
+        &self,
+        name: String,
+        external_id: Option<String>,
+    ) -> Result<Session, sqlx::Error> {
+        if let Some(external_id) = external_id.as_deref() {
+            if let Some(existing) = self.get_session_by_external_id(external_id).await? {
+                tracing::Span::current().record("session_id", existing.id.as_str());
+                return Ok(existing);
+            }
+        }
+
+        let mut session = Session::new(name);
+        session.external_id = external_id;
+        tracing::Span::current().record("session_id", session.id.as_str());
+
+        let inserted = sqlx::query(
+            "INSERT INTO sessions (id, name, created_at, updated_at, status, metadata, external_id)
+             VALUES (?, ?, ?, ?, ?, ?, ?)
+             ON CONFLICT(external_id) DO NOTHING"
         )
         .bind(&session.id)
         .bind(&session.name)
@@ -29,9 +119,20 @@ impl SessionService {
         .bind(&session.updated_at)
         .bind(&session.status)
         .bind(&session.metadata)
+        .bind(&session.external_id)
         .execute(&self.pool)
         .await?;
 
+        // A concurrent retry won the insert; hand back its session
+        if inserted.rows_affected() == 0 {
+            if let Some(external_id) = session.external_id.as_deref() {
+                return self
+                    .get_session_by_external_id(external_id)
+                    .await?
+                    .ok_or(sqlx::Error::RowNotFound);
+            }
+        }
+
         Ok(session)
     }
 
@@ -43,6 +144,17 @@ impl SessionService {
             .await
     }
 
+    /// Get session by client-supplied external ID
+    pub async fn get_session_by_external_id(
+        &self,
+        external_id: &str,
+    ) -> Result<Option<Session>, sqlx::Error> {
+        sqlx::query_as::<_, Session>("SELECT * FROM sessions WHERE external_id = ?")
+            .bind(external_id)
+            .fetch_optional(&self.pool)
+            .await
+    }
+
     /// List all sessions
     pub async fn list_sessions(&self) -> Result<Vec<Session>, sqlx::Error> {
         sqlx::query_as::<_, Session>("SELECT * FROM sessions ORDER BY created_at DESC")
@@ -50,7 +162,26 @@ impl SessionService {
             .await
     }
 
+    /// List sessions with the most recently active first
+    pub async fn list_sessions_by_activity(&self) -> Result<Vec<Session>, sqlx::Error> {
+        sqlx::query_as::<_, Session>("SELECT * FROM sessions ORDER BY updated_at DESC")
+            .fetch_all(&self.pool)
+            .await
+    }
+
+    /// Bump a session's `updated_at` after a write to one of its children
+    async fn touch_session(conn: &mut SqliteConnection, session_id: &str) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE sessions SET updated_at = ? WHERE id = ?")
+            .bind(chrono::Utc::now().to_rfc3339())
+            .bind(session_id)
+            .execute(conn)
+            .await?;
+
+        Ok(())
+    }
+
     /// Update session status
+    #[instrument(skip_all, fields(session_id = %id))]
     pub async fn update_session_status(
         &self,
         id: &str,
@@ -70,6 +201,7 @@ impl SessionService {
     }
 
     /// Delete session
+    #[instrument(skip_all, fields(session_id = %id))]
     pub async fn delete_session(&self, id: &str) -> Result<(), sqlx::Error> {
         sqlx::query("DELETE FROM sessions WHERE id = ?")
             .bind(id)
@@ -81,7 +213,8 @@ impl SessionService {
 
     // ===== Pane operations =====
 
-    /// Create a pane
+    /// Create a pane; it becomes the session's only active pane
+    #[instrument(skip_all, fields(session_id = %session_id))]
     pub async fn create_pane(
         &self,
         session_id: String,
@@ -90,6 +223,23 @@ impl SessionService {
     ) -> Result<Pane, sqlx::Error> {
         let pane = Pane::new(session_id, name, position);
 
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query(
+            "UPDATE panes SET active = 0, updated_at = ? WHERE session_id = ? AND active = 1"
+        )
+        .bind(&pane.created_at)
+        .bind(&pane.session_id)
+        .execute(&mut *tx)
+        .await?;
+        Self::insert_pane(&mut tx, &pane).await?;
+        Self::touch_session(&mut tx, &pane.session_id).await?;
+        tx.commit().await?;
+
+        Ok(pane)
+    }
+
+    async fn insert_pane(conn: &mut SqliteConnection, pane: &Pane) -> Result<(), sqlx::Error> {
         sqlx::query(
             "INSERT INTO panes (id, session_id, name, position, created_at, updated_at, active)
              VALUES (?, ?, ?, ?, ?, ?, ?)"
@@ -101,10 +251,10 @@ impl SessionService {
         .bind(&pane.created_at)
         .bind(&pane.updated_at)
         .bind(pane.active)
-        .execute(&self.pool)
+        .execute(conn)
         .await?;
 
-        Ok(pane)
+        Ok(())
     }
 
     /// List panes for a session
@@ -117,7 +267,141 @@ impl SessionService {
         .await
     }
 
+    /// Put a session's panes in the given order, numbering positions from 0
+    ///
+    /// Panes missing from `ordered_pane_ids` keep their relative order after the
+    /// listed ones; an ID that isn't a pane of the session is `RowNotFound`.
+    #[instrument(skip_all, fields(session_id = %session_id))]
+    pub async fn reorder_panes(
+        &self,
+        session_id: &str,
+        ordered_pane_ids: &[String],
+    ) -> Result<Vec<Pane>, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+
+        let current = Self::pane_ids_by_position(&mut tx, session_id).await?;
+        let known: HashSet<&str> = current.iter().map(String::as_str).collect();
+        if ordered_pane_ids.iter().any(|id| !known.contains(id.as_str())) {
+            return Err(sqlx::Error::RowNotFound);
+        }
+
+        let mut order: Vec<String> = Vec::with_capacity(current.len());
+        for id in ordered_pane_ids.iter().chain(current.iter()) {
+            if !order.contains(id) {
+                order.push(id.clone());
+            }
+        }
+
+        Self::write_pane_positions(&mut tx, &order).await?;
+        Self::touch_session(&mut tx, session_id).await?;
+        tx.commit().await?;
+
+        self.list_panes(session_id).await
+    }
+
+    /// Move one pane to `position`, shifting the session's other panes to make room
+    ///
+    /// Positions are renumbered from 0; a position past the end moves the pane last.
+    #[instrument(skip_all, fields(pane_id = %pane_id))]
+    pub async fn set_pane_position(
+        &self,
+        pane_id: &str,
+        position: i32,
+    ) -> Result<Vec<Pane>, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+
+        let (session_id,): (String,) =
+            sqlx::query_as("SELECT session_id FROM panes WHERE id = ?")
+                .bind(pane_id)
+                .fetch_optional(&mut *tx)
+                .await?
+                .ok_or(sqlx::Error::RowNotFound)?;
+
+        let mut order = Self::pane_ids_by_position(&mut tx, &session_id).await?;
+        order.retain(|id| id != pane_id);
+        let index = (position.max(0) as usize).min(order.len());
+        order.insert(index, pane_id.to_string());
+
+        Self::write_pane_positions(&mut tx, &order).await?;
+        Self::touch_session(&mut tx, &session_id).await?;
+        tx.commit().await?;
+
+        self.list_panes(&session_id).await
+    }
+
+    /// Make `pane_id` the session's only active pane
+    #[instrument(skip_all, fields(session_id = %session_id, pane_id = %pane_id))]
+    pub async fn set_active_pane(&self, session_id: &str, pane_id: &str) -> Result<(), sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+
+        let exists: Option<(String,)> =
+            sqlx::query_as("SELECT id FROM panes WHERE id = ? AND session_id = ?")
+                .bind(pane_id)
+                .bind(session_id)
+                .fetch_optional(&mut *tx)
+                .await?;
+        if exists.is_none() {
+            return Err(sqlx::Error::RowNotFound);
+        }
+
+        sqlx::query("UPDATE panes SET active = (id = ?), updated_at = ? WHERE session_id = ?")
+            .bind(pane_id)
+            .bind(chrono::Utc::now().to_rfc3339())
+            .bind(session_id)
+            .execute(&mut *tx)
+            .await?;
+
+        Self::touch_session(&mut tx, session_id).await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    /// Get the session's active pane (the first by position if several are active)
+    pub async fn get_active_pane(&self, session_id: &str) -> Result<Option<Pane>, sqlx::Error> {
+        sqlx::query_as::<_, Pane>(
+            "SELECT * FROM panes WHERE session_id = ? AND active = 1 ORDER BY position LIMIT 1"
+        )
+        .bind(session_id)
+        .fetch_optional(&self.pool)
+        .await
+    }
+
+    /// IDs of a session's panes in position order
+    async fn pane_ids_by_position(
+        conn: &mut SqliteConnection,
+        session_id: &str,
+    ) -> Result<Vec<String>, sqlx::Error> {
+        let rows: Vec<(String,)> = sqlx::query_as(
+            "SELECT id FROM panes WHERE session_id = ? ORDER BY position, created_at"
+        )
+        .bind(session_id)
+        .fetch_all(conn)
+        .await?;
+
+        Ok(rows.into_iter().map(|(id,)| id).collect())
+    }
+
+    /// Give each pane its index in `order` as its position
+    async fn write_pane_positions(
+        conn: &mut SqliteConnection,
+        order: &[String],
+    ) -> Result<(), sqlx::Error> {
+        let now = chrono::Utc::now().to_rfc3339();
+        for (position, id) in order.iter().enumerate() {
+            sqlx::query("UPDATE panes SET position = ?, updated_at = ? WHERE id = ?")
+                .bind(position as i32)
+                .bind(&now)
+                .bind(id)
+                .execute(&mut *conn)
+                .await?;
+        }
+
+        Ok(())
+    }
+
     /// Delete pane
+    #[instrument(skip_all, fields(pane_id = %id))]
     pub async fn delete_pane(&self, id: &str) -> Result<(), sqlx::Error> {
         sqlx::query("DELETE FROM panes WHERE id = ?")
             .bind(id)
@@ -127,10 +411,153 @@ impl SessionService {
         Ok(())
     }
 
+    // ===== Template operations =====
+
+    /// Save a session template, replacing any template with the same ID
+    #[instrument(skip_all, fields(template_id = %template.id))]
+    pub async fn save_template(&self, template: SessionTemplate) -> Result<SessionTemplate, sqlx::Error> {
+        let encode = |e: serde_json::Error| sqlx::Error::Encode(Box::new(e));
+        let panes = serde_json::to_string(&template.panes).map_err(encode)?;
+        let agents = serde_json::to_string(&template.agents).map_err(encode)?;
+        let blackboard = serde_json::to_string(&template.blackboard).map_err(encode)?;
+
+        sqlx::query(
+            "INSERT OR REPLACE INTO session_templates (id, name, panes, agents, blackboard, created_at)
+             VALUES (?, ?, ?, ?, ?, ?)"
+        )
+        .bind(&template.id)
+        .bind(&template.name)
+        .bind(panes)
+        .bind(agents)
+        .bind(blackboard)
+        .bind(&template.created_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(template)
+    }
+
+    /// Get session template by ID
+    pub async fn get_template(&self, id: &str) -> Result<Option<SessionTemplate>, sqlx::Error> {
+        let row: Option<TemplateRow> = sqlx::query_as(
+            "SELECT id, name, panes, agents, blackboard, created_at FROM session_templates WHERE id = ?"
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.map(Self::template_from_row).transpose()
+    }
+
+    /// List session templates by name
+    pub async fn list_templates(&self) -> Result<Vec<SessionTemplate>, sqlx::Error> {
+        let rows: Vec<TemplateRow> = sqlx::query_as(
+            "SELECT id, name, panes, agents, blackboard, created_at FROM session_templates ORDER BY name"
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter().map(Self::template_from_row).collect()
+    }
+
+    /// Delete session template
+    #[instrument(skip_all, fields(template_id = %id))]
+    pub async fn delete_template(&self, id: &str) -> Result<(), sqlx::Error> {
+        sqlx::query("DELETE FROM session_templates WHERE id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    fn template_from_row(row: TemplateRow) -> Result<SessionTemplate, sqlx::Error> {
+        let (id, name, panes, agents, blackboard, created_at) = row;
+        let decode = |e: serde_json::Error| sqlx::Error::Decode(Box::new(e));
+        Ok(SessionTemplate {
+            id,
+            name,
+            panes: serde_json::from_str(&panes).map_err(decode)?,
+            agents: serde_json::from_str(&agents).map_err(decode)?,
+            blackboard: serde_json::from_str(&blackboard).map_err(decode)?,
+            created_at,
+        })
+    }
+
+    /// Create a new session and its panes from a template in one transaction
+    ///
+    /// The session's metadata records the template ID. Only the first pane
+    /// starts active. Unknown templates are `RowNotFound`.
+    #[instrument(skip_all, fields(template_id = %template_id, session_id = tracing::field::Empty))]
+    pub async fn create_session_from_template(
+        &self,
+        template_id: &str,
+        name: String,
+    ) -> Result<TemplateInstance, sqlx::Error> {
+        let template = self
+            .get_template(template_id)
+            .await?
+            .ok_or(sqlx::Error::RowNotFound)?;
+
+        let mut session = Session::new(name);
+        session
+            .set_metadata(&serde_json::json!({ "template_id": template.id }))
+            .map_err(|e| sqlx::Error::Encode(Box::new(e)))?;
+        tracing::Span::current().record("session_id", session.id.as_str());
+
+        let panes: Vec<Pane> = template
+            .panes
+            .iter()
+            .enumerate()
+            .map(|(position, spec)| {
+                let mut pane = Pane::new(session.id.clone(), spec.name.clone(), position as i32);
+                pane.active = position == 0;
+                pane
+            })
+            .collect();
+
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query(
+            "INSERT INTO sessions (id, name, created_at, updated_at, status, metadata, external_id)
+             VALUES (?, ?, ?, ?, ?, ?, ?)"
+        )
+        .bind(&session.id)
+        .bind(&session.name)
+        .bind(&session.created_at)
+        .bind(&session.updated_at)
+        .bind(&session.status)
+        .bind(&session.metadata)
+        .bind(&session.external_id)
+        .execute(&mut *tx)
+        .await?;
+
+        for pane in &panes {
+            Self::insert_pane(&mut tx, pane).await?;
+        }
+        tx.commit().await?;
+
+        Ok(TemplateInstance {
+            session,
+            panes,
+            agents: template.agents,
+            blackboard: template.blackboard,
+        })
+    }
+
     // ===== Message operations =====
 
     /// Add a message
+    #[instrument(skip_all, fields(session_id = %message.session_id, message_id = %message.id))]
     pub async fn add_message(&self, message: Message) -> Result<Message, sqlx::Error> {
+        self.validate_json(&message)?;
+
+        let mut tx = self.pool.begin().await?;
+
+        if let Some(parent_id) = &message.parent_id {
+            Self::ensure_message_exists(&mut tx, parent_id).await?;
+        }
+
         sqlx::query(
             "INSERT INTO messages (id, session_id, pane_id, message_type, role, content, created_at, sequence_number, parent_id, metadata)
              VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
@@ -145,12 +572,254 @@ impl SessionService {
         .bind(message.sequence_number)
         .bind(&message.parent_id)
         .bind(&message.metadata)
-        .execute(&self.pool)
+        .execute(&mut *tx)
         .await?;
 
+        Self::touch_session(&mut tx, &message.session_id).await?;
+        tx.commit().await?;
+
+        self.publish_message(&message);
+
         Ok(message)
     }
 
+    /// Add many messages in one transaction using multi-row inserts
+    ///
+    /// With `assign_sequence`, each message is numbered after the current
+    /// highest sequence number of its session, in vector order.
+    #[instrument(skip_all, fields(count = messages.len()))]
+    pub async fn add_messages(
+        &self,
+        mut messages: Vec<Message>,
+        assign_sequence: bool,
+    ) -> Result<Vec<Message>, sqlx::Error> {
+        if messages.is_empty() {
+            return Ok(messages);
+        }
+        for message in &messages {
+            self.validate_json(message)?;
+        }
+
+        let mut tx = self.pool.begin().await?;
+
+        // Parents may be earlier messages of the same batch
+        let mut batch_ids: HashSet<&str> = HashSet::new();
+        for message in &messages {
+            if let Some(parent_id) = &message.parent_id {
+                if !batch_ids.contains(parent_id.as_str()) {
+                    Self::ensure_message_exists(&mut tx, parent_id).await?;
+                }
+            }
+            batch_ids.insert(&message.id);
+        }
+
+        if assign_sequence {
+            let mut next: HashMap<String, i32> = HashMap::new();
+            for message in messages.iter_mut() {
+                let sequence = match next.entry(message.session_id.clone()) {
+                    Entry::Occupied(entry) => entry.into_mut(),
+                    Entry::Vacant(entry) => {
+                        let start = Self::next_sequence_in(&mut tx, entry.key()).await?;
+                        entry.insert(start)
+                    }
+                };
+                message.sequence_number = *sequence;
+                *sequence += 1;
+            }
+        }
+
+        for mut statement in Self::insert_statements(&messages) {
+            statement.build().execute(&mut *tx).await?;
+        }
+
+        let sessions: HashSet<&str> = messages.iter().map(|m| m.session_id.as_str()).collect();
+        for session_id in sessions {
+            Self::touch_session(&mut tx, session_id).await?;
+        }
+        tx.commit().await?;
+
+        for message in &messages {
+            self.publish_message(message);
+        }
+
+        Ok(messages)
+    }
+
+    /// Multi-row inserts for `messages`, one per `MESSAGE_INSERT_CHUNK` rows
+    fn insert_statements(messages: &[Message]) -> Vec<QueryBuilder<'_, Sqlite>> {
+        messages
+            .chunks(MESSAGE_INSERT_CHUNK)
+            .map(|chunk| {
+                let mut builder = QueryBuilder::<Sqlite>::new(
+                    "INSERT INTO messages (id, session_id, pane_id, message_type, role, content, created_at, sequence_number, parent_id, metadata) "
+                );
+                builder.push_values(chunk, |mut row, message| {
+                    row.push_bind(&message.id)
+                        .push_bind(&message.session_id)
+                        .push_bind(&message.pane_id)
+                        .push_bind(&message.message_type)
+                        .push_bind(&message.role)
+                        .push_bind(&message.content)
+                        .push_bind(&message.created_at)
+                        .push_bind(message.sequence_number)
+                        .push_bind(&message.parent_id)
+                        .push_bind(&message.metadata);
+                });
+                builder
+            })
+            .collect()
+    }
+
+    /// Persist a connector's output stream as session messages
+    ///
+    /// `Content` chunks are joined into a single message carrying any `Usage`
+    /// in its metadata. Tool calls and errors become their own messages, and
+    /// pending content is flushed before them so sequence numbers follow the
+    /// stream order. Returns the messages written.
+    #[instrument(skip_all, fields(session_id = %session_id))]
+    pub async fn ingest_stream(
+        &self,
+        session_id: &str,
+        pane_id: Option<String>,
+        role: MessageRole,
+        mut rx: mpsc::Receiver<ConnectorMessage>,
+    ) -> Result<Vec<Message>, sqlx::Error> {
+        let mut sequence = self.get_next_sequence_number(session_id).await?;
+        let mut written = Vec::new();
+        let mut content = String::new();
+        let mut usage: Option<serde_json::Value> = None;
+
+        let new_message = |message_type: MessageType, role: MessageRole, text: String, sequence: i32| {
+            Message::new(session_id.to_string(), pane_id.clone(), message_type, role, text, sequence)
+        };
+
+        while let Some(event) = rx.recv().await {
+            let message = match event {
+                ConnectorMessage::Content { content: chunk } => {
+                    content.push_str(&chunk);
+                    continue;
+                }
+                ConnectorMessage::Usage { input_tokens, output_tokens } => {
+                    usage = Some(serde_json::json!({
+                        "input_tokens": input_tokens,
+                        "output_tokens": output_tokens,
+                    }));
+                    continue;
+                }
+                // CLI chatter is not part of the transcript
+                ConnectorMessage::SystemNotice { .. } => continue,
+                ConnectorMessage::Done => break,
+                ConnectorMessage::ToolCall { name, args } => {
+                    let text = match &args {
+                        serde_json::Value::String(raw) => raw.clone(),
+                        other => other.to_string(),
+                    };
+                    let mut message = new_message(MessageType::ToolCall, role.clone(), text, 0);
+                    message
+                        .set_metadata(&serde_json::json!({ "tool": name, "args": args }))
+                        .map_err(|e| sqlx::Error::Encode(Box::new(e)))?;
+                    message
+                }
+                ConnectorMessage::Error { message } => {
+                    new_message(MessageType::Error, MessageRole::System, message, 0)
+                }
+            };
+
+            if !content.is_empty() {
+                let output = new_message(
+                    MessageType::AgentOutput,
+                    role.clone(),
+                    std::mem::take(&mut content),
+                    sequence,
+                );
+                written.push(self.add_output_message(output, usage.take()).await?);
+                sequence += 1;
+            }
+
+            let mut message = message;
+            message.sequence_number = sequence;
+            written.push(self.add_message(message).await?);
+            sequence += 1;
+        }
+
+        if !content.is_empty() {
+            let output = new_message(MessageType::AgentOutput, role, content, sequence);
+            written.push(self.add_output_message(output, usage).await?);
+        }
+
+        Ok(written)
+    }
+
+    async fn add_output_message(
+        &self,
+        mut message: Message,
+        usage: Option<serde_json::Value>,
+    ) -> Result<Message, sqlx::Error> {
+        if let Some(usage) = usage {
+            message
+                .set_metadata(&serde_json::json!({ "usage": usage }))
+                .map_err(|e| sqlx::Error::Encode(Box::new(e)))?;
+        }
+        self.add_message(message).await
+    }
+
+    /// Re-run a recorded session's user messages through `connector`
+    ///
+    /// The prompts are copied in order into a new session, each followed by
+    /// whatever the connector streams back for it. A failed call is recorded
+    /// as an error message and the replay moves on to the next prompt.
+    /// Returns the new session.
+    #[instrument(skip_all, fields(session_id = %session_id, connector = connector.name()))]
+    pub async fn replay(
+        &self,
+        session_id: &str,
+        connector: &dyn Connector,
+    ) -> Result<Session, sqlx::Error> {
+        let source = self.get_session(session_id).await?.ok_or(sqlx::Error::RowNotFound)?;
+        let prompts: Vec<Message> = self
+            .get_messages(session_id)
+            .await?
+            .into_iter()
+            .filter(|m| m.role == "user")
+            .collect();
+
+        let replay = self.create_session(format!("{} (replay)", source.name)).await?;
+        for prompt in prompts {
+            let sequence = self.get_next_sequence_number(&replay.id).await?;
+            let mut input = Message::new(
+                replay.id.clone(),
+                None,
+                MessageType::UserInput,
+                MessageRole::User,
+                prompt.content.clone(),
+                sequence,
+            );
+            input.message_type = prompt.message_type;
+            self.add_message(input).await?;
+
+            match connector.execute(&prompt.content).await {
+                Ok(rx) => {
+                    self.ingest_stream(&replay.id, None, MessageRole::Assistant, rx).await?;
+                }
+                Err(reason) => {
+                    tracing::warn!(error = %reason, "Replayed prompt failed");
+                    let sequence = self.get_next_sequence_number(&replay.id).await?;
+                    self.add_message(Message::new(
+                        replay.id.clone(),
+                        None,
+                        MessageType::Error,
+                        MessageRole::System,
+                        reason,
+                        sequence,
+                    ))
+                    .await?;
+                }
+            }
+        }
+
+        Ok(replay)
+    }
+
     /// Get messages for a session
     pub async fn get_messages(&self, session_id: &str) -> Result<Vec<Message>, sqlx::Error> {
         sqlx::query_as::<_, Message>(
@@ -171,13 +840,59 @@ impl SessionService {
         .await
     }
 
+    /// Get the reply thread around a message
+    ///
+    /// Follows `parent_id` up to the root and down through every reply, so the
+    /// result holds the message, its ancestors and its descendants ordered by
+    /// sequence number.
+    #[instrument(skip_all, fields(message_id = %message_id))]
+    pub async fn get_thread(&self, message_id: &str) -> Result<Vec<Message>, sqlx::Error> {
+        let mut conn = self.pool.acquire().await?;
+        Self::ensure_message_exists(&mut conn, message_id).await?;
+
+        sqlx::query_as::<_, Message>(
+            "WITH RECURSIVE
+                ancestors(id, parent_id) AS (
+                    SELECT id, parent_id FROM messages WHERE id = ?
+                    UNION
+                    SELECT m.id, m.parent_id FROM messages m JOIN ancestors a ON m.id = a.parent_id
+                ),
+                descendants(id) AS (
+                    SELECT id FROM messages WHERE id = ?
+                    UNION
+                    SELECT m.id FROM messages m JOIN descendants d ON m.parent_id = d.id
+                )
+             SELECT * FROM messages
+             WHERE id IN (SELECT id FROM ancestors UNION SELECT id FROM descendants)
+             ORDER BY sequence_number, created_at"
+        )
+        .bind(message_id)
+        .bind(message_id)
+        .fetch_all(&mut *conn)
+        .await
+    }
+
+    async fn ensure_message_exists(conn: &mut SqliteConnection, message_id: &str) -> Result<(), sqlx::Error> {
+        sqlx::query("SELECT 1 FROM messages WHERE id = ?")
+            .bind(message_id)
+            .fetch_optional(&mut *conn)
+            .await?
+            .map(|_| ())
+            .ok_or(sqlx::Error::RowNotFound)
+    }
+
     /// Get next sequence number for session
     pub async fn get_next_sequence_number(&self, session_id: &str) -> Result<i32, sqlx::Error> {
-        let result: Option<(i32,)> = sqlx::query_as(
+        let mut conn = self.pool.acquire().await?;
+        Self::next_sequence_in(&mut conn, session_id).await
+    }
+
+    async fn next_sequence_in(conn: &mut SqliteConnection, session_id: &str) -> Result<i32, sqlx::Error> {
+        let result: Option<(Option<i32>,)> = sqlx::query_as(
             "SELECT MAX(sequence_number) FROM messages WHERE session_id = ?"
         )
         .bind(session_id)
-        .fetch_optional(&self.pool)
+        .fetch_optional(conn)
         .await?;
 
         Ok(result.and_then(|(n,)| n).unwrap_or(-1) + 1)
@@ -186,7 +901,12 @@ impl SessionService {
     // ===== Block operations =====
 
     /// Create a block
+    #[instrument(skip_all, fields(session_id = %block.session_id, block_id = %block.id))]
     pub async fn create_block(&self, block: Block) -> Result<Block, sqlx::Error> {
+        self.validate_json(&block)?;
+
+        let mut tx = self.pool.begin().await?;
+
         sqlx::query(
             "INSERT INTO blocks (id, session_id, pane_id, block_type, title, content, created_at, updated_at, sequence_number, bookmarked, metadata)
              VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
@@ -202,9 +922,12 @@ impl SessionService {
         .bind(block.sequence_number)
         .bind(block.bookmarked)
         .bind(&block.metadata)
-        .execute(&self.pool)
+        .execute(&mut *tx)
         .await?;
 
+        Self::touch_session(&mut tx, &block.session_id).await?;
+        tx.commit().await?;
+
         Ok(block)
     }
 
@@ -219,6 +942,7 @@ impl SessionService {
     }
 
     /// Toggle bookmark on a block
+    #[instrument(skip_all, fields(block_id = %block_id))]
     pub async fn toggle_bookmark(&self, block_id: &str) -> Result<(), sqlx::Error> {
         let now = chrono::Utc::now().to_rfc3339();
 
@@ -290,35 +1014,55 @@ impl SessionService {
         let first_msg = &messages[0];
         let pane_id = first_msg.pane_id.clone();
 
+        // Concatenate message contents
+        let content = messages
+            .iter()
+            .map(|m| m.content.as_str())
+            .collect::<Vec<&str>>()
+            .join("\n");
+
+        // Code changes become diff artifacts whatever message type carried them
+        let diff = diff_stats(&content);
+
         // Determine block type based on message type
         let block_type = match first_msg.message_type.as_str() {
+            _ if diff.is_some() => BlockType::Artifact,
             "userinput" => BlockType::Command,
             "agentoutput" => BlockType::Output,
             "systemmessage" => BlockType::Conversation,
             "toolcall" | "toolresult" => BlockType::Artifact,
+            "error" => BlockType::Error,
             _ => BlockType::Output,
         };
 
-        // Concatenate message contents
-        let content = messages
-            .iter()
-            .map(|m| m.content.as_str())
-            .collect::<Vec<&str>>()
-            .join("\n");
-
-        Some(Block::new(
+        let mut block = Block::new(
             session_id.to_string(),
             pane_id,
             block_type,
             content,
             sequence_number,
-        ))
+        );
+        if let Some(stats) = diff {
+            block.metadata = Some(
+                serde_json::json!({
+                    "attachment_type": format!("{:?}", AttachmentType::Diff).to_lowercase(),
+                    "files_changed": stats.files_changed,
+                    "lines_added": stats.lines_added,
+                    "lines_removed": stats.lines_removed,
+                })
+                .to_string(),
+            );
+        }
+        Some(block)
     }
 
     // ===== Attachment operations =====
 
     /// Create an attachment
+    #[instrument(skip_all, fields(attachment_id = %attachment.id))]
     pub async fn create_attachment(&self, attachment: Attachment) -> Result<Attachment, sqlx::Error> {
+        self.validate_json(&attachment)?;
+
         sqlx::query(
             "INSERT INTO attachments (id, block_id, message_id, attachment_type, filename, content_type, size_bytes, storage_path, created_at, metadata)
              VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
@@ -339,6 +1083,24 @@ impl SessionService {
         Ok(attachment)
     }
 
+    /// Create an attachment, inferring its type from the stored bytes
+    ///
+    /// `attachment_type` and `size_bytes` are always derived from `bytes`;
+    /// a caller-supplied `content_type` is kept.
+    pub async fn store_attachment(
+        &self,
+        mut attachment: Attachment,
+        bytes: &[u8],
+    ) -> Result<Attachment, sqlx::Error> {
+        let detected = self.content_types.detect(attachment.filename.as_deref(), bytes);
+        attachment.attachment_type = format!("{:?}", detected.attachment_type).to_lowercase();
+        attachment.size_bytes = bytes.len() as i64;
+        if attachment.content_type.is_none() {
+            attachment.content_type = detected.content_type;
+        }
+        self.create_attachment(attachment).await
+    }
+
     /// Get attachments for a block
     pub async fn get_block_attachments(&self, block_id: &str) -> Result<Vec<Attachment>, sqlx::Error> {
         sqlx::query_as::<_, Attachment>(
@@ -352,7 +1114,12 @@ impl SessionService {
     // ===== Progress events =====
 
     /// Add a progress event
+    #[instrument(skip_all, fields(session_id = %event.session_id))]
     pub async fn add_progress_event(&self, event: ProgressEvent) -> Result<ProgressEvent, sqlx::Error> {
+        self.check_json_size("Progress event data", event.data.as_deref())?;
+
+        let mut tx = self.pool.begin().await?;
+
         sqlx::query(
             "INSERT INTO progress_events (id, session_id, event_type, description, created_at, data)
              VALUES (?, ?, ?, ?, ?, ?)"
@@ -363,9 +1130,12 @@ impl SessionService {
         .bind(&event.description)
         .bind(&event.created_at)
         .bind(&event.data)
-        .execute(&self.pool)
+        .execute(&mut *tx)
         .await?;
 
+        Self::touch_session(&mut tx, &event.session_id).await?;
+        tx.commit().await?;
+
         Ok(event)
     }
 
@@ -384,6 +1154,7 @@ impl SessionService {
 mod tests {
     use super::*;
     use crate::db::Database;
+    use crate::runtime::{AgentConfig, AgentRole};
     use tempfile::NamedTempFile;
 
     async fn setup_test_db() -> SessionService {
@@ -404,6 +1175,24 @@ mod tests {
         assert_eq!(retrieved.unwrap().name, "test-session");
     }
 
+    #[tokio::test]
+    async fn test_create_session_idempotent_by_external_id() {
+        let service = setup_test_db().await;
+
+        let first = service
+            .create_session_with_external_id("retry".to_string(), Some("client-42".to_string()))
+            .await
+            .unwrap();
+        let second = service
+            .create_session_with_external_id("retry".to_string(), Some("client-42".to_string()))
+            .await
+            .unwrap();
+
+        assert_eq!(first.id, second.id);
+        assert_eq!(second.external_id.as_deref(), Some("client-42"));
+        assert_eq!(service.list_sessions().await.unwrap().len(), 1);
+    }
+
     #[tokio::test]
     async fn test_list_sessions() {
         let service = setup_test_db().await;
@@ -415,6 +1204,31 @@ mod tests {
         assert_eq!(sessions.len(), 2);
     }
 
+    #[tokio::test]
+    async fn test_message_bumps_session_activity() {
+        let service = setup_test_db().await;
+
+        let older = service.create_session("older".to_string()).await.unwrap();
+        let newer = service.create_session("newer".to_string()).await.unwrap();
+
+        let sessions = service.list_sessions_by_activity().await.unwrap();
+        assert_eq!(sessions[0].id, newer.id);
+
+        let message = Message::new(
+            older.id.clone(),
+            None,
+            MessageType::UserInput,
+            MessageRole::User,
+            "ping".to_string(),
+            0,
+        );
+        service.add_message(message).await.unwrap();
+
+        let sessions = service.list_sessions_by_activity().await.unwrap();
+        assert_eq!(sessions[0].id, older.id);
+        assert!(sessions[0].updated_at > older.updated_at);
+    }
+
     #[tokio::test]
     async fn test_create_pane() {
         let service = setup_test_db().await;
@@ -429,6 +1243,136 @@ mod tests {
         assert_eq!(panes.len(), 1);
     }
 
+    #[tokio::test]
+    async fn test_reorder_panes() {
+        let service = setup_test_db().await;
+
+        let session = service.create_session("test-session".to_string()).await.unwrap();
+        let mut ids = Vec::new();
+        for (i, name) in ["left", "middle", "right"].into_iter().enumerate() {
+            let pane = service
+                .create_pane(session.id.clone(), name.to_string(), i as i32)
+                .await
+                .unwrap();
+            ids.push(pane.id);
+        }
+
+        let reordered = vec![ids[2].clone(), ids[0].clone(), ids[1].clone()];
+        let panes = service.reorder_panes(&session.id, &reordered).await.unwrap();
+        let names: Vec<&str> = panes.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["right", "left", "middle"]);
+        assert_eq!(panes.iter().map(|p| p.position).collect::<Vec<_>>(), vec![0, 1, 2]);
+
+        // Move "middle" to the front; the others shift back
+        let panes = service.set_pane_position(&ids[1], 0).await.unwrap();
+        let names: Vec<&str> = panes.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["middle", "right", "left"]);
+
+        let unknown = vec!["not-a-pane".to_string()];
+        assert!(matches!(
+            service.reorder_panes(&session.id, &unknown).await,
+            Err(sqlx::Error::RowNotFound)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_set_active_pane() {
+        let service = setup_test_db().await;
+
+        let session = service.create_session("test-session".to_string()).await.unwrap();
+        let mut panes = Vec::new();
+        for i in 0..3 {
+            let pane = service
+                .create_pane(session.id.clone(), format!("pane{}", i), i)
+                .await
+                .unwrap();
+            panes.push(pane);
+        }
+
+        // The newest pane is the only active one
+        let active = service.get_active_pane(&session.id).await.unwrap().unwrap();
+        assert_eq!(active.id, panes[2].id);
+        let active_count = service
+            .list_panes(&session.id)
+            .await
+            .unwrap()
+            .into_iter()
+            .filter(|p| p.active)
+            .count();
+        assert_eq!(active_count, 1);
+
+        service.set_active_pane(&session.id, &panes[1].id).await.unwrap();
+
+        let active: Vec<String> = service
+            .list_panes(&session.id)
+            .await
+            .unwrap()
+            .into_iter()
+            .filter(|p| p.active)
+            .map(|p| p.id)
+            .collect();
+        assert_eq!(active, vec![panes[1].id.clone()]);
+        assert_eq!(
+            service.get_active_pane(&session.id).await.unwrap().unwrap().id,
+            panes[1].id
+        );
+
+        // A pane from another session can't be activated here
+        let other = service.create_session("other".to_string()).await.unwrap();
+        assert!(matches!(
+            service.set_active_pane(&other.id, &panes[0].id).await,
+            Err(sqlx::Error::RowNotFound)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_create_sessions_from_template() {
+        let service = setup_test_db().await;
+
+        let template = SessionTemplate::new("review-team".to_string())
+            .with_pane("coordinator")
+            .with_pane("worker")
+            .with_agent(AgentConfig::new(
+                "lead".to_string(),
+                AgentRole::Coordinator,
+                "claude-code".to_string(),
+            ))
+            .with_blackboard_entry("goal", "review the diff");
+        let template = service.save_template(template).await.unwrap();
+
+        let saved = service.get_template(&template.id).await.unwrap().unwrap();
+        assert_eq!(saved.panes, template.panes);
+        assert_eq!(saved.agents.len(), 1);
+        assert_eq!(saved.blackboard, template.blackboard);
+
+        let first = service
+            .create_session_from_template(&template.id, "first".to_string())
+            .await
+            .unwrap();
+        let second = service
+            .create_session_from_template(&template.id, "second".to_string())
+            .await
+            .unwrap();
+        assert_ne!(first.session.id, second.session.id);
+        assert_ne!(first.blackboard_key("goal"), second.blackboard_key("goal"));
+        assert_eq!(first.agents[0].name, "lead");
+
+        for instance in [&first, &second] {
+            let panes = service.list_panes(&instance.session.id).await.unwrap();
+            let names: Vec<&str> = panes.iter().map(|p| p.name.as_str()).collect();
+            assert_eq!(names, vec!["coordinator", "worker"]);
+            assert!(panes.iter().all(|p| p.session_id == instance.session.id));
+
+            let active = service.get_active_pane(&instance.session.id).await.unwrap().unwrap();
+            assert_eq!(active.name, "coordinator");
+        }
+
+        assert!(matches!(
+            service.create_session_from_template("missing", "x".to_string()).await,
+            Err(sqlx::Error::RowNotFound)
+        ));
+    }
+
     #[tokio::test]
     async fn test_add_message() {
         let service = setup_test_db().await;
@@ -450,6 +1394,266 @@ mod tests {
         assert_eq!(messages[0].content, "Hello");
     }
 
+    #[tokio::test]
+    async fn test_get_thread_follows_reply_chain() {
+        let service = setup_test_db().await;
+        let session = service.create_session("test-session".to_string()).await.unwrap();
+        let new_message = |content: &str, sequence: i32| {
+            Message::new(
+                session.id.clone(),
+                None,
+                MessageType::UserInput,
+                MessageRole::User,
+                content.to_string(),
+                sequence,
+            )
+        };
+
+        let root = service.add_message(new_message("question", 0)).await.unwrap();
+        let reply = service
+            .add_message(new_message("answer", 1).with_parent(&root.id))
+            .await
+            .unwrap();
+        let follow_up = service
+            .add_message(new_message("follow-up", 2).with_parent(&reply.id))
+            .await
+            .unwrap();
+        service.add_message(new_message("unrelated", 3)).await.unwrap();
+
+        let expected = vec![root.id.clone(), reply.id.clone(), follow_up.id.clone()];
+        for id in &expected {
+            let thread = service.get_thread(id).await.unwrap();
+            let ids: Vec<String> = thread.into_iter().map(|m| m.id).collect();
+            assert_eq!(ids, expected);
+        }
+
+        // Replies must point at an existing message
+        let orphan = new_message("orphan", 4).with_parent("missing");
+        assert!(matches!(
+            service.add_message(orphan).await,
+            Err(sqlx::Error::RowNotFound)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_add_messages_batch() {
+        let service = setup_test_db().await;
+        let batch_session = service.create_session("batch".to_string()).await.unwrap();
+
+        let batch: Vec<Message> = (0..1000)
+            .map(|i| {
+                Message::new(
+                    batch_session.id.clone(),
+                    None,
+                    MessageType::AgentOutput,
+                    MessageRole::Assistant,
+                    format!("line {}", i),
+                    0,
+                )
+            })
+            .collect();
+
+        // One insert per chunk instead of one per row
+        let statements = SessionService::insert_statements(&batch).len();
+        assert_eq!(
+            statements,
+            (batch.len() + MESSAGE_INSERT_CHUNK - 1) / MESSAGE_INSERT_CHUNK
+        );
+        assert!(statements < batch.len() / 10);
+
+        service.add_messages(batch, true).await.unwrap();
+
+        let messages = service.get_messages(&batch_session.id).await.unwrap();
+        assert_eq!(messages.len(), 1000);
+        for (i, message) in messages.iter().enumerate() {
+            assert_eq!(message.sequence_number, i as i32);
+            assert_eq!(message.content, format!("line {}", i));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_typed_message_metadata() {
+        #[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+        struct ToolMeta {
+            tool: String,
+            exit_code: i32,
+        }
+
+        let service = setup_test_db().await;
+        let session = service.create_session("test-session".to_string()).await.unwrap();
+
+        let mut message = Message::new(
+            session.id.clone(),
+            None,
+            MessageType::ToolResult,
+            MessageRole::Tool,
+            "ok".to_string(),
+            0,
+        );
+        let meta = ToolMeta { tool: "bash".to_string(), exit_code: 0 };
+        message.set_metadata(&meta).unwrap();
+        service.add_message(message).await.unwrap();
+
+        let stored = service.get_messages(&session.id).await.unwrap();
+        assert_eq!(stored[0].metadata_as::<ToolMeta>().unwrap(), Some(meta));
+
+        let mut invalid = Message::new(
+            session.id.clone(),
+            None,
+            MessageType::ToolResult,
+            MessageRole::Tool,
+            "bad".to_string(),
+            1,
+        );
+        invalid.metadata = Some("{not json".to_string());
+        assert!(service.add_message(invalid).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_oversized_metadata_rejected() {
+        let service = setup_test_db().await.with_max_metadata_bytes(1024);
+        let session = service.create_session("test-session".to_string()).await.unwrap();
+        let message = |content: &str, metadata: String| {
+            let mut message = Message::new(
+                session.id.clone(),
+                None,
+                MessageType::AgentOutput,
+                MessageRole::Assistant,
+                content.to_string(),
+                0,
+            );
+            message.metadata = Some(metadata);
+            message
+        };
+
+        let normal = serde_json::json!({ "usage": { "input_tokens": 10 } }).to_string();
+        assert!(service.add_message(message("small", normal)).await.is_ok());
+
+        let oversized = serde_json::json!({ "blob": "x".repeat(2048) }).to_string();
+        let err = service.add_message(message("huge", oversized)).await.unwrap_err();
+        match err {
+            sqlx::Error::Encode(e) => assert!(matches!(
+                e.downcast_ref::<AppError>(),
+                Some(AppError::Validation(_))
+            )),
+            other => panic!("expected a validation error, got {:?}", other),
+        }
+        assert_eq!(service.get_messages(&session.id).await.unwrap().len(), 1);
+
+        let mut event = ProgressEvent::new(session.id.clone(), "note".to_string(), "big".to_string());
+        event.data = Some("x".repeat(2048));
+        assert!(service.add_progress_event(event).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_messages_in_order() {
+        let service = setup_test_db().await;
+        let session = service.create_session("test-session".to_string()).await.unwrap();
+        let mut rx = service.subscribe_messages();
+
+        for (i, content) in ["first", "second"].iter().enumerate() {
+            let message = Message::new(
+                session.id.clone(),
+                None,
+                MessageType::AgentOutput,
+                MessageRole::Assistant,
+                content.to_string(),
+                i as i32,
+            );
+            service.add_message(message).await.unwrap();
+        }
+
+        let first = rx.recv().await.unwrap();
+        let second = rx.recv().await.unwrap();
+        assert_eq!(first.content, "first");
+        assert_eq!(second.content, "second");
+        assert_eq!(second.session_id, session.id);
+    }
+
+    #[tokio::test]
+    async fn test_ingest_connector_stream() {
+        let service = setup_test_db().await;
+        let session = service.create_session("test-session".to_string()).await.unwrap();
+
+        let (tx, rx) = mpsc::channel(16);
+        for event in [
+            ConnectorMessage::Content { content: "Hello, ".to_string() },
+            ConnectorMessage::Content { content: "world".to_string() },
+            ConnectorMessage::Usage { input_tokens: 12, output_tokens: 3 },
+            ConnectorMessage::Error { message: "rate limited".to_string() },
+            ConnectorMessage::Done,
+        ] {
+            tx.send(event).await.unwrap();
+        }
+        drop(tx);
+
+        let written = service
+            .ingest_stream(&session.id, None, MessageRole::Assistant, rx)
+            .await
+            .unwrap();
+        assert_eq!(written.len(), 2);
+
+        let messages = service.get_messages(&session.id).await.unwrap();
+        assert_eq!(messages.len(), 2);
+
+        assert_eq!(messages[0].content, "Hello, world");
+        assert_eq!(messages[0].message_type, "agentoutput");
+        assert_eq!(messages[0].sequence_number, 0);
+        let meta: serde_json::Value = messages[0].metadata_as().unwrap().unwrap();
+        assert_eq!(meta["usage"]["input_tokens"], 12);
+        assert_eq!(meta["usage"]["output_tokens"], 3);
+
+        assert_eq!(messages[1].content, "rate limited");
+        assert_eq!(messages[1].message_type, "error");
+        assert_eq!(messages[1].role, "system");
+        assert_eq!(messages[1].sequence_number, 1);
+    }
+
+    #[tokio::test]
+    async fn test_replay_session() {
+        use crate::connectors::MockConnector;
+
+        let service = setup_test_db().await;
+        let session = service.create_session("recorded".to_string()).await.unwrap();
+        for (i, (role, message_type, content)) in [
+            (MessageRole::User, MessageType::UserInput, "first prompt"),
+            (MessageRole::Assistant, MessageType::AgentOutput, "old answer"),
+            (MessageRole::User, MessageType::UserInput, "second prompt"),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let message = Message::new(
+                session.id.clone(),
+                None,
+                message_type,
+                role,
+                content.to_string(),
+                i as i32,
+            );
+            service.add_message(message).await.unwrap();
+        }
+
+        let connector = MockConnector::new(vec![ConnectorMessage::Content {
+            content: "new answer".to_string(),
+        }]);
+        let replay = service.replay(&session.id, &connector).await.unwrap();
+        assert_ne!(replay.id, session.id);
+        assert_eq!(replay.name, "recorded (replay)");
+        assert_eq!(connector.call_count(), 2);
+
+        let messages = service.get_messages(&replay.id).await.unwrap();
+        let contents: Vec<&str> = messages.iter().map(|m| m.content.as_str()).collect();
+        assert_eq!(contents, ["first prompt", "new answer", "second prompt", "new answer"]);
+        assert_eq!(messages[1].role, "assistant");
+
+        // The recording is left untouched
+        assert_eq!(service.get_messages(&session.id).await.unwrap().len(), 3);
+
+        let missing = service.replay("no-such-session", &connector).await;
+        assert!(matches!(missing, Err(sqlx::Error::RowNotFound)));
+    }
+
     #[tokio::test]
     async fn test_create_block() {
         let service = setup_test_db().await;
@@ -514,6 +1718,40 @@ mod tests {
         assert_eq!(blocks[2].block_type, "command");
     }
 
+    #[tokio::test]
+    async fn test_assemble_blocks_tags_diffs() {
+        let service = setup_test_db().await;
+        let session = service.create_session("test-session".to_string()).await.unwrap();
+
+        let diff = "Here is the fix:\n\
+--- a/src/lib.rs\n\
++++ b/src/lib.rs\n\
+@@ -1,3 +1,4 @@\n\
+ use std::fmt;\n\
+--- removed doc line\n\
++/// Added doc line\n\
++/// And another\n\
+ fn main() {}";
+        service.add_message(Message::new(
+            session.id.clone(),
+            None,
+            MessageType::AgentOutput,
+            MessageRole::Assistant,
+            diff.to_string(),
+            0,
+        )).await.unwrap();
+
+        let blocks = service.assemble_blocks(&session.id).await.unwrap();
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(blocks[0].block_type, "artifact");
+
+        let metadata: serde_json::Value = blocks[0].metadata_as().unwrap().unwrap();
+        assert_eq!(metadata["attachment_type"], "diff");
+        assert_eq!(metadata["files_changed"], 1);
+        assert_eq!(metadata["lines_added"], 2);
+        assert_eq!(metadata["lines_removed"], 1);
+    }
+
     #[tokio::test]
     async fn test_bookmark_block() {
         let service = setup_test_db().await;
@@ -1,3 +1,5 @@
+use crate::runtime::AgentConfig;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
@@ -37,6 +39,7 @@ pub enum MessageType {
     SystemMessage,
     ToolCall,
     ToolResult,
+    Error,
 }
 
 /// Message role
@@ -74,6 +77,32 @@ pub enum AttachmentType {
     Code,
 }
 
+/// Typed access to a model's JSON `metadata` column
+pub trait JsonMetadata {
+    fn metadata(&self) -> Option<&str>;
+
+    fn metadata_mut(&mut self) -> &mut Option<String>;
+
+    /// Serialize `value` into the metadata column
+    fn set_metadata<T: Serialize>(&mut self, value: &T) -> Result<(), serde_json::Error> {
+        *self.metadata_mut() = Some(serde_json::to_string(value)?);
+        Ok(())
+    }
+
+    /// Deserialize the metadata column, `None` if it is unset
+    fn metadata_as<T: DeserializeOwned>(&self) -> Result<Option<T>, serde_json::Error> {
+        self.metadata().map(serde_json::from_str).transpose()
+    }
+
+    /// Check that the raw metadata, if any, is well-formed JSON
+    fn validate_metadata(&self) -> Result<(), serde_json::Error> {
+        if let Some(raw) = self.metadata() {
+            serde_json::from_str::<serde_json::Value>(raw)?;
+        }
+        Ok(())
+    }
+}
+
 /// Session model
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
 pub struct Session {
@@ -83,6 +112,8 @@ pub struct Session {
     pub updated_at: String,
     pub status: String,
     pub metadata: Option<String>,
+    /// Client-supplied key used to make session creation idempotent
+    pub external_id: Option<String>,
 }
 
 impl Session {
@@ -95,8 +126,24 @@ impl Session {
             updated_at: now,
             status: "active".to_string(),
             metadata: None,
+            external_id: None,
         }
     }
+
+    pub fn with_external_id(mut self, external_id: String) -> Self {
+        self.external_id = Some(external_id);
+        self
+    }
+}
+
+impl JsonMetadata for Session {
+    fn metadata(&self) -> Option<&str> {
+        self.metadata.as_deref()
+    }
+
+    fn metadata_mut(&mut self) -> &mut Option<String> {
+        &mut self.metadata
+    }
 }
 
 /// Pane model
@@ -163,6 +210,22 @@ impl Message {
             metadata: None,
         }
     }
+
+    /// Mark this message as a reply to `parent_id`
+    pub fn with_parent(mut self, parent_id: impl Into<String>) -> Self {
+        self.parent_id = Some(parent_id.into());
+        self
+    }
+}
+
+impl JsonMetadata for Message {
+    fn metadata(&self) -> Option<&str> {
+        self.metadata.as_deref()
+    }
+
+    fn metadata_mut(&mut self) -> &mut Option<String> {
+        &mut self.metadata
+    }
 }
 
 /// Block model
@@ -206,6 +269,16 @@ impl Block {
     }
 }
 
+impl JsonMetadata for Block {
+    fn metadata(&self) -> Option<&str> {
+        self.metadata.as_deref()
+    }
+
+    fn metadata_mut(&mut self) -> &mut Option<String> {
+        &mut self.metadata
+    }
+}
+
 /// Attachment model
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
 pub struct Attachment {
@@ -242,6 +315,16 @@ impl Attachment {
     }
 }
 
+impl JsonMetadata for Attachment {
+    fn metadata(&self) -> Option<&str> {
+        self.metadata.as_deref()
+    }
+
+    fn metadata_mut(&mut self) -> &mut Option<String> {
+        &mut self.metadata
+    }
+}
+
 /// Progress event model
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
 pub struct ProgressEvent {
@@ -266,6 +349,84 @@ impl ProgressEvent {
     }
 }
 
+/// Pane created when a template is instantiated
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct PaneSpec {
+    pub name: String,
+}
+
+/// Blackboard entry seeded when a template is instantiated
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct BlackboardSeed {
+    pub key: String,
+    pub value: String,
+}
+
+/// Reusable session layout: panes, agent team and initial blackboard entries
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionTemplate {
+    pub id: String,
+    pub name: String,
+    /// Panes in position order; the first one starts active
+    #[serde(default)]
+    pub panes: Vec<PaneSpec>,
+    #[serde(default)]
+    pub agents: Vec<AgentConfig>,
+    #[serde(default)]
+    pub blackboard: Vec<BlackboardSeed>,
+    pub created_at: String,
+}
+
+impl SessionTemplate {
+    pub fn new(name: String) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            name,
+            panes: Vec::new(),
+            agents: Vec::new(),
+            blackboard: Vec::new(),
+            created_at: chrono::Utc::now().to_rfc3339(),
+        }
+    }
+
+    pub fn with_pane(mut self, name: impl Into<String>) -> Self {
+        self.panes.push(PaneSpec { name: name.into() });
+        self
+    }
+
+    pub fn with_agent(mut self, config: AgentConfig) -> Self {
+        self.agents.push(config);
+        self
+    }
+
+    pub fn with_blackboard_entry(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.blackboard.push(BlackboardSeed {
+            key: key.into(),
+            value: value.into(),
+        });
+        self
+    }
+}
+
+/// A session created from a template
+///
+/// The session and its panes are already persisted; `agents` and `blackboard`
+/// are left for the caller to register with the runtime and memory layers.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TemplateInstance {
+    pub session: Session,
+    pub panes: Vec<Pane>,
+    pub agents: Vec<AgentConfig>,
+    pub blackboard: Vec<BlackboardSeed>,
+}
+
+impl TemplateInstance {
+    /// Blackboard key for a seed, scoped to this session so instances don't collide
+    pub fn blackboard_key(&self, key: &str) -> String {
+        format!("{}:{}", self.session.id, key)
+    }
+}
+
 // Helper methods for string conversion
 impl MessageType {
     pub fn from_str(s: &str) -> Self {
@@ -275,6 +436,7 @@ impl MessageType {
             "systemmessage" => MessageType::SystemMessage,
             "toolcall" => MessageType::ToolCall,
             "toolresult" => MessageType::ToolResult,
+            "error" => MessageType::Error,
             _ => MessageType::SystemMessage, // Default fallback
         }
     }
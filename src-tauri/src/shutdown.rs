@@ -0,0 +1,488 @@
+use crate::api::websocket::WebSocketManager;
+use crate::clock::{system_clock, Clock};
+use crate::connectors::Connector;
+use crate::runtime::Orchestrator;
+use async_trait::async_trait;
+use serde::Serialize;
+use sqlx::SqlitePool;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+use tokio::time::timeout;
+use tracing::{info, warn};
+
+/// How often the flush task checks its clock for a due flush
+const FLUSH_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// How one subsystem came down
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum StepOutcome {
+  /// Shut down without errors
+  Clean,
+  /// Nothing was registered for this step
+  Skipped,
+  /// The shutdown deadline passed before the step finished
+  TimedOut,
+  Failed { error: String },
+}
+
+/// Result of `Runtime::shutdown`, one entry per subsystem in shutdown order
+#[derive(Debug, Clone, Serialize)]
+pub struct ShutdownReport {
+  pub orchestrator: StepOutcome,
+  pub connectors: StepOutcome,
+  pub websockets: StepOutcome,
+  pub database: StepOutcome,
+  pub elapsed_ms: u64,
+}
+
+impl ShutdownReport {
+  /// True if no step failed or timed out
+  pub fn is_clean(&self) -> bool {
+    [&self.orchestrator, &self.connectors, &self.websockets, &self.database]
+      .iter()
+      .all(|outcome| matches!(outcome, StepOutcome::Clean | StepOutcome::Skipped))
+  }
+}
+
+/// The long-lived pieces of the app that must be torn down together
+///
+/// `shutdown` stops them in dependency order: the orchestrator first so no new
+/// work starts, then connector metrics are written while the pool is still
+/// open, then WebSocket clients are dropped, and the pool is closed last.
+#[derive(Default)]
+pub struct Runtime {
+  orchestrator: Option<Arc<Orchestrator>>,
+  connectors: Vec<Arc<dyn Connector>>,
+  websockets: Option<Arc<WebSocketManager>>,
+  pool: Option<SqlitePool>,
+  flusher: Option<FlushHandle>,
+}
+
+impl Runtime {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn with_orchestrator(mut self, orchestrator: Arc<Orchestrator>) -> Self {
+    self.orchestrator = Some(orchestrator);
+    self
+  }
+
+  /// Flush this connector's metrics to the database on shutdown
+  pub fn with_connector(mut self, connector: Arc<dyn Connector>) -> Self {
+    self.connectors.push(connector);
+    self
+  }
+
+  pub fn with_websockets(mut self, websockets: Arc<WebSocketManager>) -> Self {
+    self.websockets = Some(websockets);
+    self
+  }
+
+  pub fn with_pool(mut self, pool: SqlitePool) -> Self {
+    self.pool = Some(pool);
+    self
+  }
+
+  /// Stop this periodic metrics flush once the orchestrator is down
+  pub fn with_flusher(mut self, flusher: FlushHandle) -> Self {
+    self.flusher = Some(flusher);
+    self
+  }
+
+  /// Tear everything down in order, giving all steps together at most `limit`
+  ///
+  /// A step that fails or runs out of time is reported and the remaining
+  /// steps still run.
+  pub async fn shutdown(self, limit: Duration) -> ShutdownReport {
+    let start = Instant::now();
+    let remaining = || limit.saturating_sub(start.elapsed());
+    info!("Shutting down runtime");
+
+    let orchestrator = match &self.orchestrator {
+      Some(orchestrator) => {
+        orchestrator.stop().await;
+        match timeout(remaining(), orchestrator.wait_until_finished()).await {
+          Ok(()) => StepOutcome::Clean,
+          Err(_) => StepOutcome::TimedOut,
+        }
+      }
+      None => StepOutcome::Skipped,
+    };
+
+    if let Some(flusher) = self.flusher {
+      flusher.stop().await;
+    }
+
+    let connectors = match (&self.pool, self.connectors.is_empty()) {
+      (_, true) => StepOutcome::Skipped,
+      (None, false) => StepOutcome::Failed {
+        error: "No database to flush connector metrics to".to_string(),
+      },
+      (Some(pool), false) => match timeout(remaining(), flush_metrics(pool, &self.connectors, &now())).await {
+        Ok(Ok(())) => StepOutcome::Clean,
+        Ok(Err(e)) => StepOutcome::Failed { error: e.to_string() },
+        Err(_) => StepOutcome::TimedOut,
+      },
+    };
+
+    let websockets = match &self.websockets {
+      Some(websockets) => {
+        let closed = websockets.close_all().await;
+        info!(closed, "Closed WebSocket connections");
+        StepOutcome::Clean
+      }
+      None => StepOutcome::Skipped,
+    };
+
+    let database = match &self.pool {
+      Some(pool) => match timeout(remaining(), pool.close()).await {
+        Ok(()) => StepOutcome::Clean,
+        Err(_) => StepOutcome::TimedOut,
+      },
+      None => StepOutcome::Skipped,
+    };
+
+    let report = ShutdownReport {
+      orchestrator,
+      connectors,
+      websockets,
+      database,
+      elapsed_ms: start.elapsed().as_millis() as u64,
+    };
+    if report.is_clean() {
+      info!("Runtime shut down in {}ms", report.elapsed_ms);
+    } else {
+      warn!("Runtime shut down with errors: {:?}", report);
+    }
+    report
+  }
+}
+
+fn now() -> String {
+  chrono::Utc::now().to_rfc3339()
+}
+
+/// Write a metrics snapshot row per connector in one transaction
+async fn flush_metrics(
+  pool: &SqlitePool,
+  connectors: &[Arc<dyn Connector>],
+  recorded_at: &str,
+) -> sqlx::Result<()> {
+  let mut tx = pool.begin().await?;
+  for connector in connectors {
+    let metrics = serde_json::to_string(&connector.metrics().await)
+      .map_err(|e| sqlx::Error::Encode(Box::new(e)))?;
+    sqlx::query("INSERT INTO connector_metrics (connector, metrics, recorded_at) VALUES (?, ?, ?)")
+      .bind(connector.name())
+      .bind(metrics)
+      .bind(recorded_at)
+      .execute(&mut *tx)
+      .await?;
+  }
+  tx.commit().await
+}
+
+/// Connectors and orchestrator looked up again at every flush
+///
+/// For apps that create and replace them while the flusher is running.
+#[async_trait]
+pub trait MetricsSources: Send + Sync {
+  async fn connectors(&self) -> Vec<Arc<dyn Connector>>;
+  async fn orchestrator(&self) -> Option<Arc<Orchestrator>>;
+}
+
+/// Writes connector and orchestrator metrics to the database on a timer
+///
+/// Without it metrics only reach the database at shutdown, so a crash loses
+/// everything since startup; with it at most one interval is lost.
+pub struct MetricsFlusher {
+  pool: SqlitePool,
+  interval: Duration,
+  connectors: Vec<Arc<dyn Connector>>,
+  orchestrator: Option<Arc<Orchestrator>>,
+  sources: Option<Arc<dyn MetricsSources>>,
+  clock: Arc<dyn Clock>,
+}
+
+impl MetricsFlusher {
+  pub fn new(pool: SqlitePool, interval: Duration) -> Self {
+    Self {
+      pool,
+      interval,
+      connectors: Vec::new(),
+      orchestrator: None,
+      sources: None,
+      clock: system_clock(),
+    }
+  }
+
+  pub fn with_connector(mut self, connector: Arc<dyn Connector>) -> Self {
+    self.connectors.push(connector);
+    self
+  }
+
+  pub fn with_orchestrator(mut self, orchestrator: Arc<Orchestrator>) -> Self {
+    self.orchestrator = Some(orchestrator);
+    self
+  }
+
+  /// Also flush whatever `sources` holds at the time of each flush
+  pub fn with_sources(mut self, sources: Arc<dyn MetricsSources>) -> Self {
+    self.sources = Some(sources);
+    self
+  }
+
+  /// Decide when flushes are due, and stamp them, with `clock`
+  pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+    self.clock = clock;
+    self
+  }
+
+  /// Write one snapshot of every registered source now
+  pub async fn flush(&self) -> sqlx::Result<()> {
+    let recorded_at = chrono::DateTime::<chrono::Utc>::from(self.clock.now()).to_rfc3339();
+    let mut connectors = self.connectors.clone();
+    let mut orchestrator = self.orchestrator.clone();
+    if let Some(sources) = &self.sources {
+      connectors.extend(sources.connectors().await);
+      if orchestrator.is_none() {
+        orchestrator = sources.orchestrator().await;
+      }
+    }
+    flush_metrics(&self.pool, &connectors, &recorded_at).await?;
+
+    if let Some(orchestrator) = &orchestrator {
+      let metrics = orchestrator.metrics().await;
+      let json = serde_json::to_string(&metrics).map_err(|e| sqlx::Error::Encode(Box::new(e)))?;
+      sqlx::query("INSERT INTO orchestrator_metrics (run_id, metrics, recorded_at) VALUES (?, ?, ?)")
+        .bind(metrics.run_id.map(|id| id.to_string()))
+        .bind(json)
+        .bind(&recorded_at)
+        .execute(&self.pool)
+        .await?;
+    }
+    Ok(())
+  }
+
+  /// Flush every `interval` in the background until the handle is stopped
+  ///
+  /// A failed flush is logged and retried at the next interval.
+  pub fn spawn(self) -> FlushHandle {
+    let (stop, mut stopped) = oneshot::channel();
+    let task = tokio::spawn(async move {
+      let mut next_flush = self.clock.now() + self.interval;
+      loop {
+        tokio::select! {
+          _ = &mut stopped => break,
+          _ = tokio::time::sleep(FLUSH_POLL_INTERVAL.min(self.interval)) => {}
+        }
+
+        let now = self.clock.now();
+        if now < next_flush {
+          continue;
+        }
+        next_flush = now + self.interval;
+        if let Err(e) = self.flush().await {
+          warn!("Periodic metrics flush failed: {}", e);
+        }
+      }
+    });
+    FlushHandle { stop, task }
+  }
+}
+
+/// Running periodic flush from `MetricsFlusher::spawn`
+pub struct FlushHandle {
+  stop: oneshot::Sender<()>,
+  task: JoinHandle<()>,
+}
+
+impl FlushHandle {
+  /// Cancel the flush task and wait for any flush in progress to finish
+  pub async fn stop(self) {
+    let _ = self.stop.send(());
+    let _ = self.task.await;
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::api::websocket::WebSocketConnection;
+  use crate::connectors::{ConnectorMessage, MockConnector};
+  use crate::db::Database;
+  use crate::runtime::{AgentConfig, AgentMessage, AgentRegistry, AgentRole, MessageBus, StopReason};
+  use tempfile::NamedTempFile;
+
+  #[tokio::test]
+  async fn test_shutdown_stops_every_subsystem_in_order() {
+    let temp_file = NamedTempFile::new().unwrap();
+    let db = Database::init(temp_file.path()).await.unwrap();
+    let pool = db.pool().clone();
+
+    // An orchestrator that would run for a while if left alone
+    let registry = Arc::new(AgentRegistry::new());
+    let bus = Arc::new(MessageBus::new());
+    let agent_id = registry
+      .register(AgentConfig::new("worker".to_string(), AgentRole::Worker, "claude_code".to_string()))
+      .await
+      .unwrap();
+    bus.create_mailbox(agent_id).await;
+    for i in 0..20 {
+      bus.send(AgentMessage::new(agent_id, agent_id, format!("task {}", i))).await.unwrap();
+    }
+    let orchestrator = Arc::new(Orchestrator::new(registry, bus));
+    let run = tokio::spawn({
+      let orchestrator = orchestrator.clone();
+      async move { orchestrator.start().await }
+    });
+    tokio::time::sleep(Duration::from_millis(30)).await;
+
+    let connector = MockConnector::new(vec![ConnectorMessage::Usage {
+      input_tokens: 3,
+      output_tokens: 4,
+    }]);
+    drop(connector.execute("hi").await.unwrap());
+
+    let websockets = Arc::new(WebSocketManager::new());
+    websockets
+      .add_connection(WebSocketConnection::new("conn1".to_string(), None))
+      .await;
+
+    let report = Runtime::new()
+      .with_orchestrator(orchestrator)
+      .with_connector(Arc::new(connector))
+      .with_websockets(websockets.clone())
+      .with_pool(pool.clone())
+      .shutdown(Duration::from_secs(5))
+      .await;
+
+    assert_eq!(report.orchestrator, StepOutcome::Clean);
+    assert_eq!(report.connectors, StepOutcome::Clean);
+    assert_eq!(report.websockets, StepOutcome::Clean);
+    assert_eq!(report.database, StepOutcome::Clean);
+    assert!(report.is_clean());
+
+    assert!(matches!(run.await.unwrap().unwrap(), StopReason::ManualStop));
+    assert_eq!(websockets.get_connection_count().await, 0);
+    assert!(pool.is_closed());
+
+    // The flushed metrics survived the pool closing
+    let reopened = Database::init(temp_file.path()).await.unwrap();
+    let (name, metrics): (String, String) =
+      sqlx::query_as("SELECT connector, metrics FROM connector_metrics")
+        .fetch_one(reopened.pool())
+        .await
+        .unwrap();
+    assert_eq!(name, "mock");
+    assert!(metrics.contains("\"total_output_tokens\":4"));
+  }
+
+  #[tokio::test]
+  async fn test_shutdown_with_nothing_registered_is_skipped() {
+    let report = Runtime::new().shutdown(Duration::from_secs(1)).await;
+    assert_eq!(report.orchestrator, StepOutcome::Skipped);
+    assert_eq!(report.database, StepOutcome::Skipped);
+    assert!(report.is_clean());
+  }
+
+  #[tokio::test]
+  async fn test_flush_reads_sources_at_flush_time() {
+    use tokio::sync::Mutex;
+
+    #[derive(Default)]
+    struct Slots(Mutex<Vec<Arc<dyn Connector>>>);
+
+    #[async_trait]
+    impl MetricsSources for Slots {
+      async fn connectors(&self) -> Vec<Arc<dyn Connector>> {
+        self.0.lock().await.clone()
+      }
+
+      async fn orchestrator(&self) -> Option<Arc<Orchestrator>> {
+        None
+      }
+    }
+
+    let temp_file = NamedTempFile::new().unwrap();
+    let db = Database::init(temp_file.path()).await.unwrap();
+    let pool = db.pool().clone();
+    let slots = Arc::new(Slots::default());
+    let flusher = MetricsFlusher::new(pool.clone(), Duration::from_secs(60)).with_sources(slots.clone());
+
+    // Installed after the flusher was built
+    slots.0.lock().await.push(Arc::new(MockConnector::new(Vec::new()).with_name("late")));
+    flusher.flush().await.unwrap();
+
+    let (name,): (String,) = sqlx::query_as("SELECT connector FROM connector_metrics")
+      .fetch_one(&pool)
+      .await
+      .unwrap();
+    assert_eq!(name, "late");
+  }
+
+  #[tokio::test]
+  async fn test_periodic_flush_follows_the_clock() {
+    use crate::clock::MockClock;
+
+    let temp_file = NamedTempFile::new().unwrap();
+    let db = Database::init(temp_file.path()).await.unwrap();
+    let pool = db.pool().clone();
+
+    let connector = MockConnector::new(vec![ConnectorMessage::Usage {
+      input_tokens: 5,
+      output_tokens: 6,
+    }]);
+    drop(connector.execute("hi").await.unwrap());
+    let orchestrator = Arc::new(Orchestrator::new(
+      Arc::new(AgentRegistry::new()),
+      Arc::new(MessageBus::new()),
+    ));
+
+    let clock = Arc::new(MockClock::new());
+    let flusher = MetricsFlusher::new(pool.clone(), Duration::from_secs(60))
+      .with_connector(Arc::new(connector))
+      .with_orchestrator(orchestrator)
+      .with_clock(clock.clone())
+      .spawn();
+
+    let count = |table: &'static str| {
+      let pool = pool.clone();
+      async move {
+        let (n,): (i64,) = sqlx::query_as(&format!("SELECT COUNT(*) FROM {}", table))
+          .fetch_one(&pool)
+          .await
+          .unwrap();
+        n
+      }
+    };
+
+    // Nothing is due until the clock reaches the interval
+    tokio::time::sleep(FLUSH_POLL_INTERVAL * 2).await;
+    assert_eq!(count("connector_metrics").await, 0);
+
+    clock.advance(Duration::from_secs(60));
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while count("orchestrator_metrics").await == 0 {
+      assert!(Instant::now() < deadline, "no flush after the clock advanced");
+      tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    flusher.stop().await;
+
+    let (metrics, recorded_at): (String, String) =
+      sqlx::query_as("SELECT metrics, recorded_at FROM connector_metrics")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert!(metrics.contains("\"total_output_tokens\":6"));
+    assert_eq!(
+      recorded_at,
+      chrono::DateTime::<chrono::Utc>::from(clock.now()).to_rfc3339()
+    );
+    assert_eq!(count("orchestrator_metrics").await, 1);
+  }
+}
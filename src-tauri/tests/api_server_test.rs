@@ -0,0 +1,109 @@
+use agent_manager::api::gateway::{ApiConfig, ApiGateway, SessionResponse};
+
+fn test_config() -> ApiConfig {
+    ApiConfig {
+        port: 0,
+        ..ApiConfig::default()
+    }
+}
+
+#[tokio::test]
+async fn test_post_sessions_over_http() {
+    let server = ApiGateway::new(test_config()).serve().await.unwrap();
+    let addr = server.addr;
+    let client = reqwest::Client::new();
+
+    let response = client
+        .post(format!("http://{}/sessions", addr))
+        .bearer_auth("dev-token-local")
+        .json(&serde_json::json!({ "name": "HTTP Session" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::CREATED);
+
+    let session: SessionResponse = response.json().await.unwrap();
+    assert_eq!(session.name, "HTTP Session");
+    assert_eq!(session.status, "active");
+
+    let fetched: SessionResponse = client
+        .get(format!("http://{}/sessions/{}", addr, session.id))
+        .bearer_auth("dev-token-local")
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(fetched.id, session.id);
+
+    server.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_requests_without_token_are_rejected() {
+    let server = ApiGateway::new(test_config()).serve().await.unwrap();
+    let addr = server.addr;
+
+    let response = reqwest::Client::new()
+        .post(format!("http://{}/sessions", addr))
+        .json(&serde_json::json!({ "name": "Anonymous" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::UNAUTHORIZED);
+
+    server.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_cors_allows_only_configured_origins() {
+    let config = ApiConfig {
+        enable_cors: true,
+        cors_allowed_origins: vec!["https://dashboard.local".to_string()],
+        ..test_config()
+    };
+    let server = ApiGateway::new(config).serve().await.unwrap();
+    let addr = server.addr;
+    let client = reqwest::Client::new();
+
+    let preflight = |origin: &'static str| {
+        client
+            .request(reqwest::Method::OPTIONS, format!("http://{}/sessions", addr))
+            .header("Origin", origin)
+            .header("Access-Control-Request-Method", "POST")
+            .send()
+    };
+
+    let allowed = preflight("https://dashboard.local").await.unwrap();
+    assert_eq!(
+        allowed.headers().get("access-control-allow-origin").unwrap(),
+        "https://dashboard.local"
+    );
+
+    let denied = preflight("https://evil.example").await.unwrap();
+    assert!(denied.headers().get("access-control-allow-origin").is_none());
+
+    server.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_oversized_body_is_rejected() {
+    let config = ApiConfig {
+        max_body_bytes: 64,
+        ..test_config()
+    };
+    let server = ApiGateway::new(config).serve().await.unwrap();
+    let addr = server.addr;
+
+    let response = reqwest::Client::new()
+        .post(format!("http://{}/sessions", addr))
+        .bearer_auth("dev-token-local")
+        .json(&serde_json::json!({ "name": "x".repeat(1024) }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::PAYLOAD_TOO_LARGE);
+
+    server.shutdown().await.unwrap();
+}
@@ -1,83 +1,48 @@
-use agent_manager::connectors::claude_code::ClaudeCodeConnector;
+use agent_manager::commands::connectors::ConnectorState;
+use agent_manager::connectors::claude_code::{ClaudeCodeConnector, ClaudeCodeError};
 use agent_manager::connectors::types::{ConnectorConfig, ConnectorMessage};
+use agent_manager::connectors::ContextPolicy;
+use common::{StubCli, StubCliBuilder};
 use std::collections::HashMap;
-use std::io::Write;
-use std::process::{Command, Stdio};
-use tempfile::NamedTempFile;
-
-/// Create a stub CLI script that simulates Claude Code output
-fn create_stub_cli() -> NamedTempFile {
-    let mut file = NamedTempFile::new().unwrap();
-
-    // Create a bash script that outputs test data
-    let script = r#"#!/bin/bash
-echo "Starting Claude Code..."
-echo '{"type":"content","content":"Hello from Claude"}'
-echo "Some plain text output"
-echo "input: 50 tokens, output: 25 tokens"
-echo '{"type":"done"}'
-exit 0
-"#;
-
-    file.write_all(script.as_bytes()).unwrap();
-    file.flush().unwrap();
-
-    // Make it executable
-    #[cfg(unix)]
-    {
-        use std::os::unix::fs::PermissionsExt;
-        let mut perms = std::fs::metadata(file.path()).unwrap().permissions();
-        perms.set_mode(0o755);
-        std::fs::set_permissions(file.path(), perms).unwrap();
-    }
 
-    file
+mod common;
+
+/// Create a stub CLI that simulates Claude Code output
+fn create_stub_cli() -> StubCli {
+    StubCliBuilder::new()
+        .stdout("Starting Claude Code...")
+        .stdout(r#"{"type":"content","content":"Hello from Claude"}"#)
+        .stdout("Some plain text output")
+        .stdout("input: 50 tokens, output: 25 tokens")
+        .stdout(r#"{"type":"done"}"#)
+        .build()
 }
 
 /// Create a stub CLI that fails
-fn create_failing_stub_cli() -> NamedTempFile {
-    let mut file = NamedTempFile::new().unwrap();
-
-    let script = r#"#!/bin/bash
-echo "Error: Something went wrong" >&2
-exit 1
-"#;
-
-    file.write_all(script.as_bytes()).unwrap();
-    file.flush().unwrap();
-
-    #[cfg(unix)]
-    {
-        use std::os::unix::fs::PermissionsExt;
-        let mut perms = std::fs::metadata(file.path()).unwrap().permissions();
-        perms.set_mode(0o755);
-        std::fs::set_permissions(file.path(), perms).unwrap();
-    }
-
-    file
+fn create_failing_stub_cli() -> StubCli {
+    StubCliBuilder::new()
+        .stderr("Error: Something went wrong")
+        .exit_code(1)
+        .build()
 }
 
 /// Create a stub CLI that times out (sleeps for a long time)
-fn create_timeout_stub_cli() -> NamedTempFile {
-    let mut file = NamedTempFile::new().unwrap();
-
-    let script = r#"#!/bin/bash
-sleep 10
-exit 0
-"#;
-
-    file.write_all(script.as_bytes()).unwrap();
-    file.flush().unwrap();
-
-    #[cfg(unix)]
-    {
-        use std::os::unix::fs::PermissionsExt;
-        let mut perms = std::fs::metadata(file.path()).unwrap().permissions();
-        perms.set_mode(0o755);
-        std::fs::set_permissions(file.path(), perms).unwrap();
+fn create_timeout_stub_cli() -> StubCli {
+    StubCliBuilder::new().sleep(10).build()
+}
+
+/// Read a stream to the end
+async fn drain(mut rx: tokio::sync::mpsc::Receiver<ConnectorMessage>) -> Vec<ConnectorMessage> {
+    let mut messages = Vec::new();
+    while let Some(msg) = rx.recv().await {
+        messages.push(msg);
     }
+    messages
+}
 
-    file
+/// Whether the stream ended on an error rather than `Done`
+fn ended_in_error(messages: &[ConnectorMessage]) -> bool {
+    matches!(messages.last(), Some(ConnectorMessage::Error { .. }))
 }
 
 #[tokio::test]
@@ -89,6 +54,8 @@ async fn test_connector_spawn_and_stream() {
         env: HashMap::new(),
         timeout_ms: Some(5000),
         max_retries: 1,
+        channel_capacity: 100,
+        ..Default::default()
     };
 
     let connector = ClaudeCodeConnector::new(config);
@@ -119,13 +86,15 @@ async fn test_connector_timeout() {
         env: HashMap::new(),
         timeout_ms: Some(500), // 500ms timeout
         max_retries: 1,
+        channel_capacity: 100,
+        ..Default::default()
     };
 
     let connector = ClaudeCodeConnector::new(config);
-    let result = connector.execute("test prompt").await;
+    let messages = drain(connector.execute("test prompt").await.unwrap()).await;
 
-    // Should timeout
-    assert!(result.is_err());
+    // Should time out, well before the stub's 10s sleep ends
+    assert!(ended_in_error(&messages));
 }
 
 #[tokio::test]
@@ -137,19 +106,80 @@ async fn test_connector_retry_logic() {
         env: HashMap::new(),
         timeout_ms: Some(5000),
         max_retries: 3,
+        channel_capacity: 100,
+        ..Default::default()
     };
 
     let connector = ClaudeCodeConnector::new(config);
-    let result = connector.execute("test prompt").await;
+    let messages = drain(connector.execute("test prompt").await.unwrap()).await;
 
-    // Should fail after retries
-    assert!(result.is_err());
+    // Should fail; the stderr line already reached the reader, so it isn't retried
+    assert!(ended_in_error(&messages));
+    assert_eq!(connector.metrics().await.spawn_count, 1);
 
     // Check metrics were updated
     let metrics = connector.metrics().await;
     assert!(metrics.error_count > 0);
 }
 
+#[tokio::test]
+async fn test_missing_binary_fails_without_retrying() {
+    let connector = ClaudeCodeConnector::new(ConnectorConfig {
+        cli_path: "/nonexistent/claude".to_string(),
+        max_retries: 3,
+        ..Default::default()
+    });
+
+    let err = connector.execute("test prompt").await.unwrap_err();
+    assert!(matches!(err, ClaudeCodeError::NotFound(ref path) if path == "/nonexistent/claude"));
+    assert!(!err.is_retryable());
+    assert_eq!(connector.metrics().await.spawn_count, 1);
+}
+
+#[tokio::test]
+async fn test_timeout_is_retried() {
+    let stub = create_timeout_stub_cli();
+    let connector = ClaudeCodeConnector::new(ConnectorConfig {
+        cli_path: stub.path().to_str().unwrap().to_string(),
+        timeout_ms: Some(200),
+        max_retries: 2,
+        ..Default::default()
+    });
+
+    let messages = drain(connector.execute("test prompt").await.unwrap()).await;
+    assert_eq!(
+        messages.last(),
+        Some(&ConnectorMessage::Error {
+            message: ClaudeCodeError::MaxRetriesExceeded.to_string(),
+        })
+    );
+    assert_eq!(connector.metrics().await.spawn_count, 2);
+}
+
+#[tokio::test]
+async fn test_single_slot_channel_streams_long_output() {
+    let mut builder = StubCliBuilder::new();
+    for i in 0..20 {
+        builder = builder.stdout(&format!(r#"{{"type":"content","content":"line {}"}}"#, i));
+    }
+    let stub = builder.build();
+    let connector = ClaudeCodeConnector::new(ConnectorConfig {
+        cli_path: stub.path().to_str().unwrap().to_string(),
+        timeout_ms: Some(5000),
+        max_retries: 1,
+        channel_capacity: 1,
+        ..Default::default()
+    });
+
+    let messages = drain(connector.execute("test prompt").await.unwrap()).await;
+    let contents = messages
+        .iter()
+        .filter(|m| matches!(m, ConnectorMessage::Content { .. }))
+        .count();
+    assert_eq!(contents, 20);
+    assert_eq!(messages.last(), Some(&ConnectorMessage::Done));
+}
+
 #[tokio::test]
 async fn test_connector_cancellation() {
     let stub = create_stub_cli();
@@ -159,6 +189,8 @@ async fn test_connector_cancellation() {
         env: HashMap::new(),
         timeout_ms: Some(5000),
         max_retries: 1,
+        channel_capacity: 100,
+        ..Default::default()
     };
 
     let connector = ClaudeCodeConnector::new(config);
@@ -180,6 +212,8 @@ async fn test_usage_tracking() {
         env: HashMap::new(),
         timeout_ms: Some(5000),
         max_retries: 1,
+        channel_capacity: 100,
+        ..Default::default()
     };
 
     let connector = ClaudeCodeConnector::new(config);
@@ -193,6 +227,40 @@ async fn test_usage_tracking() {
     assert_eq!(metrics.total_output_tokens, 125);
 }
 
+#[tokio::test]
+async fn test_context_limit_policies() {
+    let stub = create_stub_cli();
+    let config = ConnectorConfig {
+        cli_path: stub.path().to_str().unwrap().to_string(),
+        timeout_ms: Some(5000),
+        max_retries: 1,
+        max_context_tokens: Some(4),
+        ..Default::default()
+    };
+    let long_prompt = "a prompt that is well over sixteen characters long";
+
+    // Rejected before the CLI is ever spawned
+    let connector = ClaudeCodeConnector::new(config.clone());
+    let err = connector.execute(long_prompt).await.unwrap_err();
+    assert!(matches!(
+        err,
+        ClaudeCodeError::ContextLimit(ref e) if e.limit == 4
+    ));
+    assert_eq!(connector.metrics().await.spawn_count, 0);
+
+    // Truncated prompts still go through
+    let connector = ClaudeCodeConnector::new(ConnectorConfig {
+        context_policy: ContextPolicy::TruncateTail,
+        ..config
+    });
+    let mut rx = connector.execute(long_prompt).await.unwrap();
+    let mut got_done = false;
+    while let Some(msg) = rx.recv().await {
+        got_done |= msg == ConnectorMessage::Done;
+    }
+    assert!(got_done);
+}
+
 #[tokio::test]
 async fn test_health_status() {
     let config = ConnectorConfig::default();
@@ -201,3 +269,52 @@ async fn test_health_status() {
     let health = connector.health().await;
     assert!(matches!(health, agent_manager::connectors::types::ConnectorHealth::Healthy));
 }
+
+#[tokio::test]
+async fn test_connector_round_trip_reports_stub_output() {
+    let stub = StubCliBuilder::new()
+        .stdout(r#"{"type":"content","content":"Hello "}"#)
+        .stdout(r#"{"type":"content","content":"from stub"}"#)
+        .stdout(r#"{"type":"usage","input_tokens":50,"output_tokens":25}"#)
+        .stdout(r#"{"type":"done"}"#)
+        .build();
+    let state = ConnectorState::new();
+    state
+        .install(
+            "claude_code",
+            ConnectorConfig {
+                cli_path: stub.path().to_str().unwrap().to_string(),
+                timeout_ms: Some(5000),
+                max_retries: 1,
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+
+    let result = state.test_connector("claude_code", "ping").await;
+    assert!(result.success, "unexpected error: {:?}", result.error);
+    assert_eq!(result.content, "Hello from stub");
+    assert_eq!(result.tokens, 75);
+    assert!(result.error.is_none());
+
+    let failing = create_failing_stub_cli();
+    state
+        .install(
+            "claude_code",
+            ConnectorConfig {
+                cli_path: failing.path().to_str().unwrap().to_string(),
+                timeout_ms: Some(5000),
+                max_retries: 1,
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+    let result = state.test_connector("claude_code", "ping").await;
+    assert!(!result.success);
+    assert!(result.error.is_some());
+
+    let uninitialized = state.test_connector("codex_cli", "ping").await;
+    assert_eq!(uninitialized.error.as_deref(), Some("Connector not initialized"));
+}
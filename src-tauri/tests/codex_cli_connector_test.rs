@@ -1,88 +1,49 @@
 use agent_manager::connectors::codex_cli::{CodexCliConnector, GptModel};
 use agent_manager::connectors::types::{ConnectorConfig, ConnectorMessage};
+use common::{StubCli, StubCliBuilder};
 use std::collections::HashMap;
-use std::io::Write;
-use tempfile::NamedTempFile;
-
-/// Create a stub CLI script that simulates Codex CLI output
-fn create_stub_cli() -> NamedTempFile {
-    let mut file = NamedTempFile::new().unwrap();
-
-    // Create a bash script that outputs test data
-    let script = r#"#!/bin/bash
-# Read model command
-read -r line
-
-# Read prompt
-read -r prompt
-
-echo "Model switched to: ${line#/model }"
-echo '{"type":"content","content":"Hello from GPT-5"}'
-echo "Some code output"
-echo '{"usage":{"prompt_tokens":75,"completion_tokens":30,"total_tokens":105}}'
-echo '{"type":"done"}'
-exit 0
-"#;
-
-    file.write_all(script.as_bytes()).unwrap();
-    file.flush().unwrap();
-
-    // Make it executable
-    #[cfg(unix)]
-    {
-        use std::os::unix::fs::PermissionsExt;
-        let mut perms = std::fs::metadata(file.path()).unwrap().permissions();
-        perms.set_mode(0o755);
-        std::fs::set_permissions(file.path(), perms).unwrap();
-    }
 
-    file
+mod common;
+
+/// Create a stub CLI that simulates Codex CLI output
+fn create_stub_cli() -> StubCli {
+    StubCliBuilder::new()
+        // Model command, then the prompt
+        .read_line()
+        .read_line()
+        .stdout("Model switched")
+        .stdout(r#"{"type":"content","content":"Hello from GPT-5"}"#)
+        .stdout("Some code output")
+        .stdout(r#"{"usage":{"prompt_tokens":75,"completion_tokens":30,"total_tokens":105}}"#)
+        .stdout(r#"{"type":"done"}"#)
+        .build()
 }
 
 /// Create a stub CLI that fails
-fn create_failing_stub_cli() -> NamedTempFile {
-    let mut file = NamedTempFile::new().unwrap();
-
-    let script = r#"#!/bin/bash
-echo "Error: Model not found" >&2
-exit 1
-"#;
-
-    file.write_all(script.as_bytes()).unwrap();
-    file.flush().unwrap();
-
-    #[cfg(unix)]
-    {
-        use std::os::unix::fs::PermissionsExt;
-        let mut perms = std::fs::metadata(file.path()).unwrap().permissions();
-        perms.set_mode(0o755);
-        std::fs::set_permissions(file.path(), perms).unwrap();
-    }
-
-    file
+fn create_failing_stub_cli() -> StubCli {
+    StubCliBuilder::new()
+        .stderr("Error: Model not found")
+        .exit_code(1)
+        .build()
 }
 
 /// Create a stub CLI that times out
-fn create_timeout_stub_cli() -> NamedTempFile {
-    let mut file = NamedTempFile::new().unwrap();
-
-    let script = r#"#!/bin/bash
-sleep 10
-exit 0
-"#;
-
-    file.write_all(script.as_bytes()).unwrap();
-    file.flush().unwrap();
-
-    #[cfg(unix)]
-    {
-        use std::os::unix::fs::PermissionsExt;
-        let mut perms = std::fs::metadata(file.path()).unwrap().permissions();
-        perms.set_mode(0o755);
-        std::fs::set_permissions(file.path(), perms).unwrap();
+fn create_timeout_stub_cli() -> StubCli {
+    StubCliBuilder::new().sleep(10).build()
+}
+
+/// Read a stream to the end
+async fn drain(mut rx: tokio::sync::mpsc::Receiver<ConnectorMessage>) -> Vec<ConnectorMessage> {
+    let mut messages = Vec::new();
+    while let Some(msg) = rx.recv().await {
+        messages.push(msg);
     }
+    messages
+}
 
-    file
+/// Whether the stream ended on an error rather than `Done`
+fn ended_in_error(messages: &[ConnectorMessage]) -> bool {
+    matches!(messages.last(), Some(ConnectorMessage::Error { .. }))
 }
 
 #[tokio::test]
@@ -94,6 +55,8 @@ async fn test_connector_spawn_and_stream() {
         env: HashMap::new(),
         timeout_ms: Some(5000),
         max_retries: 1,
+        channel_capacity: 100,
+        ..Default::default()
     };
 
     let connector = CodexCliConnector::new(config);
@@ -143,13 +106,15 @@ async fn test_connector_timeout() {
         env: HashMap::new(),
         timeout_ms: Some(500), // 500ms timeout
         max_retries: 1,
+        channel_capacity: 100,
+        ..Default::default()
     };
 
     let connector = CodexCliConnector::new(config);
-    let result = connector.execute("test prompt").await;
+    let messages = drain(connector.execute("test prompt").await.unwrap()).await;
 
-    // Should timeout
-    assert!(result.is_err());
+    // Should time out, well before the stub's 10s sleep ends
+    assert!(ended_in_error(&messages));
 }
 
 #[tokio::test]
@@ -161,19 +126,45 @@ async fn test_connector_retry_logic() {
         env: HashMap::new(),
         timeout_ms: Some(5000),
         max_retries: 3,
+        channel_capacity: 100,
+        ..Default::default()
     };
 
     let connector = CodexCliConnector::new(config);
-    let result = connector.execute("test prompt").await;
+    let messages = drain(connector.execute("test prompt").await.unwrap()).await;
 
     // Should fail after retries
-    assert!(result.is_err());
+    assert!(ended_in_error(&messages));
 
     // Check metrics were updated
     let metrics = connector.metrics().await;
     assert!(metrics.error_count > 0);
 }
 
+#[tokio::test]
+async fn test_single_slot_channel_streams_long_output() {
+    let mut builder = StubCliBuilder::new().read_line().read_line();
+    for i in 0..20 {
+        builder = builder.stdout(&format!(r#"{{"type":"content","content":"line {}"}}"#, i));
+    }
+    let stub = builder.build();
+    let connector = CodexCliConnector::new(ConnectorConfig {
+        cli_path: stub.path().to_str().unwrap().to_string(),
+        timeout_ms: Some(5000),
+        max_retries: 1,
+        channel_capacity: 1,
+        ..Default::default()
+    });
+
+    let messages = drain(connector.execute("test prompt").await.unwrap()).await;
+    let contents = messages
+        .iter()
+        .filter(|m| matches!(m, ConnectorMessage::Content { .. }))
+        .count();
+    assert_eq!(contents, 20);
+    assert_eq!(messages.last(), Some(&ConnectorMessage::Done));
+}
+
 #[tokio::test]
 async fn test_usage_tracking() {
     let stub = create_stub_cli();
@@ -183,6 +174,8 @@ async fn test_usage_tracking() {
         env: HashMap::new(),
         timeout_ms: Some(5000),
         max_retries: 1,
+        channel_capacity: 100,
+        ..Default::default()
     };
 
     let connector = CodexCliConnector::new(config);
@@ -205,6 +198,8 @@ async fn test_openai_usage_parsing() {
         env: HashMap::new(),
         timeout_ms: Some(5000),
         max_retries: 1,
+        channel_capacity: 100,
+        ..Default::default()
     };
 
     let connector = CodexCliConnector::new(config);
@@ -241,6 +236,8 @@ async fn test_graceful_shutdown() {
         env: HashMap::new(),
         timeout_ms: Some(5000),
         max_retries: 1,
+        channel_capacity: 100,
+        ..Default::default()
     };
 
     let connector = CodexCliConnector::new(config);
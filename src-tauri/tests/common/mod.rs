@@ -0,0 +1,157 @@
+//! Shared helpers for integration tests
+#![allow(dead_code)]
+
+use std::io::Write;
+use std::path::Path;
+use tempfile::TempPath;
+
+/// One action performed by a stub CLI
+enum Step {
+    Stdout(String),
+    Stderr(String),
+    ReadLine,
+    Sleep(u64),
+}
+
+/// Builds a throwaway CLI that prints scripted output
+///
+/// On Unix this is a bash script; on Windows it's a `.cmd` batch file, so the
+/// same connector tests run on both.
+#[derive(Default)]
+pub struct StubCliBuilder {
+    steps: Vec<Step>,
+    exit_code: i32,
+}
+
+impl StubCliBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Print a line to stdout
+    pub fn stdout(mut self, line: &str) -> Self {
+        self.steps.push(Step::Stdout(line.to_string()));
+        self
+    }
+
+    /// Print a line to stderr
+    pub fn stderr(mut self, line: &str) -> Self {
+        self.steps.push(Step::Stderr(line.to_string()));
+        self
+    }
+
+    /// Read and discard one line of stdin
+    pub fn read_line(mut self) -> Self {
+        self.steps.push(Step::ReadLine);
+        self
+    }
+
+    /// Sleep for `seconds`
+    pub fn sleep(mut self, seconds: u64) -> Self {
+        self.steps.push(Step::Sleep(seconds));
+        self
+    }
+
+    /// Exit with `code` after the last step
+    pub fn exit_code(mut self, code: i32) -> Self {
+        self.exit_code = code;
+        self
+    }
+
+    /// Write the stub to a temporary file and make it executable
+    pub fn build(self) -> StubCli {
+        let mut file = tempfile::Builder::new()
+            .prefix("stub-cli")
+            .suffix(SCRIPT_SUFFIX)
+            .tempfile()
+            .unwrap();
+        file.write_all(self.render().as_bytes()).unwrap();
+        file.flush().unwrap();
+
+        // Close the handle first: Linux refuses to exec a file open for writing
+        let path = file.into_temp_path();
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mut perms = std::fs::metadata(&path).unwrap().permissions();
+            perms.set_mode(0o755);
+            std::fs::set_permissions(&path, perms).unwrap();
+        }
+
+        StubCli { path }
+    }
+
+    #[cfg(unix)]
+    fn render(&self) -> String {
+        let mut script = String::from("#!/bin/bash\n");
+        for step in &self.steps {
+            match step {
+                Step::Stdout(line) => script.push_str(&format!("printf '%s\\n' {}\n", sh_quote(line))),
+                Step::Stderr(line) => script.push_str(&format!("printf '%s\\n' {} >&2\n", sh_quote(line))),
+                Step::ReadLine => script.push_str("read -r line\n"),
+                Step::Sleep(seconds) => script.push_str(&format!("sleep {}\n", seconds)),
+            }
+        }
+        script.push_str(&format!("exit {}\n", self.exit_code));
+        script
+    }
+
+    #[cfg(windows)]
+    fn render(&self) -> String {
+        let mut script = String::from("@echo off\r\n");
+        for step in &self.steps {
+            match step {
+                Step::Stdout(line) => script.push_str(&format!("echo({}\r\n", cmd_escape(line))),
+                Step::Stderr(line) => script.push_str(&format!("echo({} 1>&2\r\n", cmd_escape(line))),
+                Step::ReadLine => script.push_str("set /p line=\r\n"),
+                // `timeout` needs a console, so ping localhost once per second instead
+                Step::Sleep(seconds) => {
+                    script.push_str(&format!("ping -n {} 127.0.0.1 >nul\r\n", seconds + 1))
+                }
+            }
+        }
+        script.push_str(&format!("exit /b {}\r\n", self.exit_code));
+        script
+    }
+}
+
+#[cfg(unix)]
+const SCRIPT_SUFFIX: &str = ".sh";
+
+#[cfg(windows)]
+const SCRIPT_SUFFIX: &str = ".cmd";
+
+/// Quote a string for a bash command line
+#[cfg(unix)]
+fn sh_quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', r"'\''"))
+}
+
+/// Escape cmd.exe metacharacters so `echo` prints the text literally
+#[cfg(windows)]
+fn cmd_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '^' | '&' | '|' | '<' | '>' | '(' | ')' => {
+                escaped.push('^');
+                escaped.push(c);
+            }
+            '%' => escaped.push_str("%%"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// A stub CLI on disk, deleted when dropped
+pub struct StubCli {
+    path: TempPath,
+}
+
+impl StubCli {
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
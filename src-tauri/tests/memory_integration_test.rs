@@ -1,5 +1,7 @@
-use agent_manager::memory::{BlackboardEntry, MemoryEntry, MemoryManager};
+use agent_manager::clock::MockClock;
+use agent_manager::memory::{Blackboard, BlackboardEntry, MemoryEntry, MemoryManager};
 use std::sync::Arc;
+use std::time::Duration;
 
 #[tokio::test]
 async fn test_two_agents_sharing_blackboard() {
@@ -74,27 +76,20 @@ async fn test_memory_trimmed_when_near_cap() {
 
 #[tokio::test]
 async fn test_blackboard_ttl_eviction() {
-    let manager = MemoryManager::new(10);
+    let clock = Arc::new(MockClock::new());
+    let blackboard = Blackboard::new(10).with_clock(clock.clone());
 
     // Add entry with 1 second TTL
-    let mut entry = BlackboardEntry::new("temp_key".to_string(), "temp_value".to_string());
-    entry.expires_at = Some(
-        std::time::SystemTime::now() + std::time::Duration::from_secs(1)
-    );
-
-    // Manually add to blackboard
-    manager.add_to_blackboard("temp_key".to_string(), "temp_value".to_string(), false)
-        .await
-        .unwrap();
+    let entry = BlackboardEntry::new("temp_key".to_string(), "temp_value".to_string())
+        .with_ttl(1, clock.as_ref());
+    blackboard.put(entry).await;
 
     // Should exist now
-    assert!(manager.get_from_blackboard("temp_key").await.is_some());
-
-    // Wait for expiration
-    tokio::time::sleep(std::time::Duration::from_secs(2)).await;
+    assert!(blackboard.get("temp_key").await.is_some());
 
-    // Should be expired (but test might be flaky due to timing)
-    // In real blackboard implementation, expired entries are removed on next access
+    // Expired entries are removed on next access
+    clock.advance(Duration::from_secs(2));
+    assert!(blackboard.get("temp_key").await.is_none());
 }
 
 #[tokio::test]
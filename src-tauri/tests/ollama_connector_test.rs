@@ -1,4 +1,4 @@
-use agent_manager::connectors::ollama::{OllamaConfig, OllamaConnector};
+use agent_manager::connectors::ollama::{OllamaConfig, OllamaConnector, OllamaError};
 use agent_manager::connectors::types::ConnectorMessage;
 use wiremock::matchers::{method, path};
 use wiremock::{Mock, MockServer, ResponseTemplate};
@@ -22,9 +22,15 @@ async fn test_ollama_health_check() {
         host: mock_server.uri(),
         port: 80,
         timeout_ms: 5000,
+        connect_timeout_ms: 5000,
         max_retries: 1,
         chat_model: "llama2".to_string(),
         embedding_model: "nomic-embed-text".to_string(),
+        check_model_availability: false,
+        stream: false,
+        normalize_embeddings: false,
+        channel_capacity: 100,
+        ..Default::default()
     };
 
     let connector = OllamaConnector::new(config);
@@ -53,9 +59,15 @@ async fn test_ollama_list_models() {
         host: mock_server.uri(),
         port: 80,
         timeout_ms: 5000,
+        connect_timeout_ms: 5000,
         max_retries: 1,
         chat_model: "llama2".to_string(),
         embedding_model: "nomic-embed-text".to_string(),
+        check_model_availability: false,
+        stream: false,
+        normalize_embeddings: false,
+        channel_capacity: 100,
+        ..Default::default()
     };
 
     let connector = OllamaConnector::new(config);
@@ -88,9 +100,15 @@ async fn test_ollama_chat() {
         host: mock_server.uri(),
         port: 80,
         timeout_ms: 5000,
+        connect_timeout_ms: 5000,
         max_retries: 1,
         chat_model: "llama2".to_string(),
         embedding_model: "nomic-embed-text".to_string(),
+        check_model_availability: false,
+        stream: false,
+        normalize_embeddings: false,
+        channel_capacity: 100,
+        ..Default::default()
     };
 
     let connector = OllamaConnector::new(config);
@@ -135,9 +153,15 @@ async fn test_ollama_embeddings() {
         host: mock_server.uri(),
         port: 80,
         timeout_ms: 5000,
+        connect_timeout_ms: 5000,
         max_retries: 1,
         chat_model: "llama2".to_string(),
         embedding_model: "nomic-embed-text".to_string(),
+        check_model_availability: false,
+        stream: false,
+        normalize_embeddings: false,
+        channel_capacity: 100,
+        ..Default::default()
     };
 
     let connector = OllamaConnector::new(config);
@@ -163,9 +187,15 @@ async fn test_ollama_error_handling() {
         host: mock_server.uri(),
         port: 80,
         timeout_ms: 5000,
+        connect_timeout_ms: 5000,
         max_retries: 2,
         chat_model: "llama2".to_string(),
         embedding_model: "nomic-embed-text".to_string(),
+        check_model_availability: false,
+        stream: false,
+        normalize_embeddings: false,
+        channel_capacity: 100,
+        ..Default::default()
     };
 
     let connector = OllamaConnector::new(config);
@@ -217,9 +247,15 @@ async fn test_connector_metrics() {
         host: mock_server.uri(),
         port: 80,
         timeout_ms: 5000,
+        connect_timeout_ms: 5000,
         max_retries: 1,
         chat_model: "llama2".to_string(),
         embedding_model: "nomic-embed-text".to_string(),
+        check_model_availability: false,
+        stream: false,
+        normalize_embeddings: false,
+        channel_capacity: 100,
+        ..Default::default()
     };
 
     let connector = OllamaConnector::new(config);
@@ -256,9 +292,15 @@ async fn test_health_status_transitions() {
         host: mock_server.uri(),
         port: 80,
         timeout_ms: 5000,
+        connect_timeout_ms: 5000,
         max_retries: 1,
         chat_model: "llama2".to_string(),
         embedding_model: "nomic-embed-text".to_string(),
+        check_model_availability: false,
+        stream: false,
+        normalize_embeddings: false,
+        channel_capacity: 100,
+        ..Default::default()
     };
 
     let connector = OllamaConnector::new(config);
@@ -272,3 +314,232 @@ async fn test_health_status_transitions() {
     let health = connector.health().await;
     assert!(matches!(health, agent_manager::connectors::types::ConnectorHealth::Healthy));
 }
+
+#[tokio::test]
+async fn test_ollama_chat_rejects_unpulled_model() {
+    let mock_server = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .and(path("/api/tags"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "models": [
+                {"name": "mistral:latest", "size": 1000000}
+            ]
+        })))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/api/generate"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&mock_server)
+        .await;
+
+    let address = mock_server.address();
+    let connector = OllamaConnector::new(OllamaConfig {
+        host: format!("http://{}", address.ip()),
+        port: address.port(),
+        max_retries: 1,
+        chat_model: "llama2".to_string(),
+        check_model_availability: true,
+        ..OllamaConfig::default()
+    });
+
+    let err = connector.chat("Hello").await.unwrap_err();
+    assert!(matches!(err, OllamaError::ModelNotAvailable(ref model) if model == "llama2"));
+    assert!(err.to_string().contains("ollama pull llama2"));
+
+    mock_server.verify().await;
+}
+
+#[tokio::test]
+async fn test_ollama_model_precheck_is_cached() {
+    let mock_server = MockServer::start().await;
+
+    Mock::given(method("GET"))
+        .and(path("/api/tags"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "models": [
+                {"name": "llama2:latest", "size": 1000000}
+            ]
+        })))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/api/generate"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "model": "llama2",
+            "response": "Hi",
+            "done": true
+        })))
+        .expect(2)
+        .mount(&mock_server)
+        .await;
+
+    let address = mock_server.address();
+    let connector = OllamaConnector::new(OllamaConfig {
+        host: format!("http://{}", address.ip()),
+        port: address.port(),
+        max_retries: 1,
+        chat_model: "llama2".to_string(),
+        check_model_availability: true,
+        ..OllamaConfig::default()
+    });
+
+    for _ in 0..2 {
+        let mut rx = connector.chat("Hello").await.unwrap();
+        while let Some(msg) = rx.recv().await {
+            if matches!(msg, ConnectorMessage::Done) {
+                break;
+            }
+        }
+    }
+
+    mock_server.verify().await;
+}
+
+#[tokio::test]
+async fn test_ollama_stream_cut_off_reports_partial_usage() {
+    let mock_server = MockServer::start().await;
+
+    // Two chunks arrive, then the stream breaks off mid-object without `done`
+    let body = concat!(
+        "{\"model\":\"llama2\",\"response\":\"Hel\",\"done\":false}\n",
+        "{\"model\":\"llama2\",\"response\":\"lo\",\"done\":false}\n",
+        "{\"model\":\"llama2\",\"resp",
+    );
+
+    Mock::given(method("POST"))
+        .and(path("/api/generate"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(body, "application/x-ndjson"))
+        .mount(&mock_server)
+        .await;
+
+    let address = mock_server.address();
+    let connector = OllamaConnector::new(OllamaConfig {
+        host: format!("http://{}", address.ip()),
+        port: address.port(),
+        max_retries: 1,
+        stream: true,
+        ..OllamaConfig::default()
+    });
+
+    let mut rx = connector.chat("Hello").await.unwrap();
+    let mut messages = Vec::new();
+    while let Some(msg) = rx.recv().await {
+        messages.push(msg);
+    }
+
+    let content: Vec<&str> = messages
+        .iter()
+        .filter_map(|m| match m {
+            ConnectorMessage::Content { content } => Some(content.as_str()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(content, vec!["Hel", "lo"]);
+
+    assert!(messages.iter().any(|m| matches!(
+        m,
+        ConnectorMessage::Usage { input_tokens: 0, output_tokens: 2 }
+    )));
+    assert!(messages.iter().any(|m| matches!(m, ConnectorMessage::Error { .. })));
+    assert!(matches!(messages.last(), Some(ConnectorMessage::Done)));
+}
+
+#[tokio::test]
+async fn test_ollama_stream_uses_reported_usage() {
+    let mock_server = MockServer::start().await;
+
+    let body = concat!(
+        "{\"model\":\"llama2\",\"response\":\"Hi\",\"done\":false}\n",
+        "{\"model\":\"llama2\",\"response\":\"\",\"done\":true,\"prompt_eval_count\":7,\"eval_count\":3}\n",
+    );
+
+    Mock::given(method("POST"))
+        .and(path("/api/generate"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(body, "application/x-ndjson"))
+        .mount(&mock_server)
+        .await;
+
+    let address = mock_server.address();
+    let connector = OllamaConnector::new(OllamaConfig {
+        host: format!("http://{}", address.ip()),
+        port: address.port(),
+        max_retries: 1,
+        stream: true,
+        ..OllamaConfig::default()
+    });
+
+    let mut rx = connector.chat("Hello").await.unwrap();
+    let mut messages = Vec::new();
+    while let Some(msg) = rx.recv().await {
+        messages.push(msg);
+    }
+
+    assert!(messages.iter().any(|m| matches!(
+        m,
+        ConnectorMessage::Usage { input_tokens: 7, output_tokens: 3 }
+    )));
+    assert!(!messages.iter().any(|m| matches!(m, ConnectorMessage::Error { .. })));
+
+    let metrics = connector.metrics().await;
+    assert_eq!(metrics.total_input_tokens, 7);
+    assert_eq!(metrics.total_output_tokens, 3);
+}
+
+#[tokio::test]
+async fn test_ollama_tiny_channel_back_pressures_without_dropping() {
+    let mock_server = MockServer::start().await;
+
+    let mut body = String::new();
+    for i in 0..50 {
+        body.push_str(&format!(
+            "{{\"model\":\"llama2\",\"response\":\"{} \",\"done\":false}}\n",
+            i
+        ));
+    }
+    body.push_str("{\"model\":\"llama2\",\"response\":\"\",\"done\":true,\"prompt_eval_count\":5,\"eval_count\":50}\n");
+
+    Mock::given(method("POST"))
+        .and(path("/api/generate"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(body, "application/x-ndjson"))
+        .mount(&mock_server)
+        .await;
+
+    let address = mock_server.address();
+    let connector = OllamaConnector::new(OllamaConfig {
+        host: format!("http://{}", address.ip()),
+        port: address.port(),
+        max_retries: 1,
+        stream: true,
+        channel_capacity: 1,
+        ..OllamaConfig::default()
+    });
+
+    let mut rx = connector.chat("Hello").await.unwrap();
+
+    // With nobody reading, the producer is parked on the full buffer
+    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    assert_eq!(connector.in_flight_chats(), 1);
+
+    let mut content = String::new();
+    let mut done = false;
+    while let Some(msg) = rx.recv().await {
+        match msg {
+            ConnectorMessage::Content { content: chunk } => content.push_str(&chunk),
+            ConnectorMessage::Error { message } => panic!("unexpected error: {}", message),
+            ConnectorMessage::Done => done = true,
+            _ => {}
+        }
+    }
+
+    let expected: String = (0..50).map(|i| format!("{} ", i)).collect();
+    assert_eq!(content, expected);
+    assert!(done);
+    assert_eq!(connector.in_flight_chats(), 0);
+}
@@ -2,8 +2,34 @@ use agent_manager::runtime::{
     AgentConfig, AgentMessage, AgentRegistry, AgentRole, LoopGuard, MessageBus, Orchestrator,
     StopReason,
 };
+use agent_manager::connectors::claude_code::ClaudeCodeConnector;
+use agent_manager::connectors::{Connector, ConnectorConfig, ConnectorMessage, MockConnector};
+use common::{StubCli, StubCliBuilder};
 use std::sync::Arc;
 
+mod common;
+
+/// A Claude stub that answers with `content` and reports `input_tokens`
+fn usage_stub(content: &str, input_tokens: u64) -> StubCli {
+    StubCliBuilder::new()
+        .stdout(&format!(r#"{{"type":"content","content":"{}"}}"#, content))
+        .stdout(&format!(
+            r#"{{"type":"usage","input_tokens":{},"output_tokens":1}}"#,
+            input_tokens
+        ))
+        .stdout(r#"{"type":"done"}"#)
+        .build()
+}
+
+fn stub_config(stub: &StubCli) -> ConnectorConfig {
+    ConnectorConfig {
+        cli_path: stub.path().to_str().unwrap().to_string(),
+        timeout_ms: Some(5000),
+        max_retries: 1,
+        ..Default::default()
+    }
+}
+
 #[tokio::test]
 async fn test_two_agent_message_exchange() {
     // Setup
@@ -22,8 +48,8 @@ async fn test_two_agent_message_exchange() {
         "codex_cli".to_string(),
     );
 
-    let agent1 = registry.register(config1).await;
-    let agent2 = registry.register(config2).await;
+    let agent1 = registry.register(config1).await.unwrap();
+    let agent2 = registry.register(config2).await.unwrap();
 
     // Create mailboxes
     bus.create_mailbox(agent1).await;
@@ -41,6 +67,7 @@ async fn test_two_agent_message_exchange() {
         max_iterations: 100,
         max_messages_per_agent: 10,
         max_execution_time_ms: 5000,
+        ..LoopGuard::default()
     });
 
     // Run orchestrator
@@ -73,7 +100,7 @@ async fn test_orchestrator_prevents_runaway_loop() {
         AgentRole::Worker,
         "claude_code".to_string(),
     );
-    let agent_id = registry.register(config).await;
+    let agent_id = registry.register(config).await.unwrap();
     bus.create_mailbox(agent_id).await;
 
     // Send many messages to simulate runaway condition
@@ -87,6 +114,7 @@ async fn test_orchestrator_prevents_runaway_loop() {
         max_iterations: 10,
         max_messages_per_agent: 5,
         max_execution_time_ms: 5000,
+        ..LoopGuard::default()
     });
 
     let result = orchestrator.start().await.unwrap();
@@ -108,7 +136,7 @@ async fn test_orchestrator_retry_logic() {
         AgentRole::Worker,
         "claude_code".to_string(),
     );
-    let agent_id = registry.register(config).await;
+    let agent_id = registry.register(config).await.unwrap();
     bus.create_mailbox(agent_id).await;
 
     // Send a message
@@ -135,7 +163,7 @@ async fn test_orchestrator_queue_depth_tracking() {
         AgentRole::Worker,
         "claude_code".to_string(),
     );
-    let agent_id = registry.register(config).await;
+    let agent_id = registry.register(config).await.unwrap();
     bus.create_mailbox(agent_id).await;
 
     // Send multiple messages
@@ -176,9 +204,9 @@ async fn test_orchestrator_multi_agent_coordination() {
         "ollama".to_string(),
     );
 
-    let coordinator_id = registry.register(coordinator_config).await;
-    let worker1_id = registry.register(worker1_config).await;
-    let worker2_id = registry.register(worker2_config).await;
+    let coordinator_id = registry.register(coordinator_config).await.unwrap();
+    let worker1_id = registry.register(worker1_config).await.unwrap();
+    let worker2_id = registry.register(worker2_config).await.unwrap();
 
     bus.create_mailbox(coordinator_id).await;
     bus.create_mailbox(worker1_id).await;
@@ -222,7 +250,7 @@ async fn test_orchestrator_metrics_reset() {
         AgentRole::Worker,
         "claude_code".to_string(),
     );
-    let agent_id = registry.register(config).await;
+    let agent_id = registry.register(config).await.unwrap();
     bus.create_mailbox(agent_id).await;
 
     let msg = AgentMessage::new(agent_id, agent_id, "test".to_string());
@@ -242,3 +270,123 @@ async fn test_orchestrator_metrics_reset() {
     assert_eq!(metrics.total_messages, 0);
     assert_eq!(metrics.total_iterations, 0);
 }
+
+#[tokio::test]
+async fn test_register_team_in_one_call() {
+    let registry = Arc::new(AgentRegistry::new());
+    let bus = Arc::new(MessageBus::new());
+
+    let team = vec![
+        AgentConfig::new("lead".to_string(), AgentRole::Coordinator, String::new()),
+        AgentConfig::new("builder".to_string(), AgentRole::Worker, String::new()),
+        AgentConfig::new("critic".to_string(), AgentRole::Reviewer, String::new()),
+    ];
+
+    let ids = registry.register_all(team).await.unwrap();
+    bus.create_mailboxes(&ids).await;
+
+    assert_eq!(ids.len(), 3);
+    assert_eq!(registry.count().await, 3);
+
+    for (id, name) in ids.iter().zip(["lead", "builder", "critic"]) {
+        assert_eq!(registry.get_metadata(*id).await.unwrap().name, name);
+        assert!(bus.get_mailbox(*id).await.is_some());
+    }
+}
+
+#[tokio::test]
+async fn test_orchestrator_with_mock_connector() {
+    let registry = Arc::new(AgentRegistry::new());
+    let bus = Arc::new(MessageBus::new());
+
+    let agent = registry
+        .register(AgentConfig::new(
+            "mocked".to_string(),
+            AgentRole::Worker,
+            "mock".to_string(),
+        ))
+        .await
+        .unwrap();
+    bus.create_mailbox(agent).await;
+
+    for i in 0..3 {
+        bus.send(AgentMessage::new(agent, agent, format!("task {}", i)))
+            .await
+            .unwrap();
+    }
+
+    // The first call fails once, so exactly one retry is expected
+    let mock = Arc::new(
+        MockConnector::new(vec![
+            ConnectorMessage::Content {
+                content: "done".to_string(),
+            },
+            ConnectorMessage::Usage {
+                input_tokens: 10,
+                output_tokens: 4,
+            },
+        ])
+        .with_failures(1),
+    );
+
+    let orchestrator = Orchestrator::new(registry, bus).with_connector("mock", mock.clone());
+    let result = orchestrator.start().await.unwrap();
+    assert!(matches!(result, StopReason::Completed));
+
+    let metrics = orchestrator.metrics().await;
+    assert_eq!(metrics.total_messages, 3);
+    assert_eq!(metrics.retry_count, 1);
+    assert_eq!(metrics.error_count, 0);
+
+    let connector_metrics = mock.metrics().await;
+    assert_eq!(mock.call_count(), 4);
+    assert_eq!(connector_metrics.success_count, 3);
+    assert_eq!(connector_metrics.error_count, 1);
+    assert_eq!(connector_metrics.total_input_tokens, 30);
+    assert_eq!(connector_metrics.total_output_tokens, 12);
+}
+
+#[tokio::test]
+async fn test_session_connector_override() {
+    use agent_manager::db::Database;
+    use agent_manager::session::SessionService;
+    use tempfile::NamedTempFile;
+
+    let temp_file = NamedTempFile::new().unwrap();
+    let db = Database::init(temp_file.path()).await.unwrap();
+    let sessions = Arc::new(SessionService::new(db.pool().clone()));
+
+    let registry = Arc::new(AgentRegistry::new());
+    let bus = Arc::new(MessageBus::new());
+    let agent = registry
+        .register(AgentConfig::new(
+            "writer".to_string(),
+            AgentRole::Worker,
+            "claude_code".to_string(),
+        ))
+        .await
+        .unwrap();
+    bus.create_mailbox(agent).await;
+    bus.send(AgentMessage::new(agent, agent, "draft".to_string()))
+        .await
+        .unwrap();
+
+    // The global connector and the session's cheaper one report different usage
+    let global = usage_stub("global", 1000);
+    let scratch = usage_stub("scratch", 7);
+
+    let orchestrator = Orchestrator::new(registry, bus)
+        .with_connector(
+            "claude_code",
+            Arc::new(ClaudeCodeConnector::new(stub_config(&global))),
+        )
+        .with_session(sessions, "scratch-session")
+        .with_connector_override("scratch-session", stub_config(&scratch));
+
+    let result = orchestrator.start().await.unwrap();
+    assert!(matches!(result, StopReason::Completed), "got {:?}", result);
+
+    let metrics = orchestrator.metrics().await;
+    assert_eq!(metrics.error_count, 0);
+    assert_eq!(metrics.input_tokens, 7);
+}